@@ -0,0 +1,25 @@
+use std::fmt;
+
+/******************************************************************************************/
+/*                                 BANDWIDTH ERROR                                        */
+/******************************************************************************************/
+
+#[derive(Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum BandwidthError {
+    InvalidRateError,
+    InvalidScheduleError,
+}
+
+impl fmt::Display for BandwidthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BandwidthError::InvalidRateError => {
+                write!(f, "El limite de ancho de banda es invalido")
+            }
+            BandwidthError::InvalidScheduleError => {
+                write!(f, "El horario de ancho de banda es invalido")
+            }
+        }
+    }
+}
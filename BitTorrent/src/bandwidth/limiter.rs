@@ -0,0 +1,219 @@
+use super::schedule::{Rates, Schedule};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/******************************************************************************************/
+/*                                 BANDWIDTH LIMITER                                      */
+/******************************************************************************************/
+
+/// Cantidad maxima de bytes que se reservan de una vez. Al acotar cada reserva, las
+/// conexiones que comparten un limite se turnan y ninguna acapara el ancho de banda.
+pub const QUANTUM: usize = 16384;
+
+/// Limite compartido por todos los torrents del proceso.
+static GLOBAL: OnceLock<BandwidthLimiter> = OnceLock::new();
+
+/// Devuelve el limitador global, que comienza sin limites.
+pub fn global() -> &'static BandwidthLimiter {
+    GLOBAL.get_or_init(BandwidthLimiter::unlimited)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Download,
+    Upload,
+}
+
+/// Token bucket con capacidad para un segundo de trafico. Las reservas que superan los
+/// tokens disponibles quedan en deuda, y quien reserva espera hasta que se salde.
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn new(now: Instant) -> Self {
+        TokenBucket {
+            tokens: 0.0,
+            last: now,
+        }
+    }
+
+    /// Reserva `amount` bytes a `rate` bytes por segundo y devuelve cuanto hay que esperar
+    /// antes de usarlos.
+    fn reserve(&mut self, rate: u64, amount: usize, now: Instant) -> Duration {
+        let rate = rate as f64;
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        let capacity = rate.max(QUANTUM as f64);
+        self.tokens = (self.tokens + elapsed * rate).min(capacity);
+        self.last = now;
+        self.tokens -= amount as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / rate)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    rates: Rates,
+    schedule: Schedule,
+    download: TokenBucket,
+    upload: TokenBucket,
+}
+
+/// Limita la descarga y la subida de un conjunto de conexiones. Los limites y el horario
+/// pueden cambiarse mientras se usa.
+#[derive(Debug)]
+pub struct BandwidthLimiter {
+    state: Mutex<LimiterState>,
+}
+
+impl BandwidthLimiter {
+    pub fn new(rates: Rates, schedule: Schedule) -> Self {
+        let now = Instant::now();
+        BandwidthLimiter {
+            state: Mutex::new(LimiterState {
+                rates,
+                schedule,
+                download: TokenBucket::new(now),
+                upload: TokenBucket::new(now),
+            }),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(Rates::default(), Schedule::default())
+    }
+
+    /// Cambia los limites generales, que rigen fuera de las franjas del horario.
+    pub fn set_rates(&self, rates: Rates) {
+        if let Ok(mut state) = self.state.lock() {
+            state.rates = rates;
+        }
+    }
+
+    pub fn set_schedule(&self, schedule: Schedule) {
+        if let Ok(mut state) = self.state.lock() {
+            state.schedule = schedule;
+        }
+    }
+
+    /// Devuelve los limites que rigen en el minuto del dia indicado.
+    pub fn rates_at(&self, minute: u32) -> Rates {
+        match self.state.lock() {
+            Ok(state) => state.schedule.rates_at(minute).unwrap_or(state.rates),
+            Err(_) => Rates::default(),
+        }
+    }
+
+    /// Reserva `amount` bytes en la direccion indicada y devuelve cuanto hay que esperar
+    /// antes de transferirlos.
+    pub fn reserve(&self, direction: Direction, amount: usize) -> Duration {
+        self.reserve_at(
+            direction,
+            amount,
+            Instant::now(),
+            Schedule::current_minute(),
+        )
+    }
+
+    fn reserve_at(
+        &self,
+        direction: Direction,
+        amount: usize,
+        now: Instant,
+        minute: u32,
+    ) -> Duration {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return Duration::ZERO,
+        };
+        let rates = state.schedule.rates_at(minute).unwrap_or(state.rates);
+        let (rate, bucket) = match direction {
+            Direction::Download => (rates.download, &mut state.download),
+            Direction::Upload => (rates.upload, &mut state.upload),
+        };
+        match rate {
+            Some(rate) => bucket.reserve(rate, amount, now),
+            None => {
+                bucket.tokens = 0.0;
+                bucket.last = now;
+                Duration::ZERO
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod bandwidth_limiter_should {
+    use super::*;
+
+    const RATE: u64 = 32768;
+
+    fn limiter() -> BandwidthLimiter {
+        BandwidthLimiter::new(
+            Rates {
+                download: Some(RATE),
+                upload: None,
+            },
+            Schedule::default(),
+        )
+    }
+
+    #[test]
+    fn make_transfers_wait_for_the_rate() {
+        let start = Instant::now();
+        let limiter = limiter();
+
+        let first = limiter.reserve_at(Direction::Download, QUANTUM, start, 0);
+        let second = limiter.reserve_at(Direction::Download, QUANTUM, start, 0);
+        assert_eq!(first, Duration::from_millis(500));
+        assert_eq!(second, Duration::from_secs(1));
+
+        let later = start + Duration::from_secs(3);
+        assert_eq!(
+            limiter.reserve_at(Direction::Download, QUANTUM, later, 0),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn not_limit_unlimited_directions() {
+        let start = Instant::now();
+        let limiter = limiter();
+
+        for _ in 0..10 {
+            assert_eq!(
+                limiter.reserve_at(Direction::Upload, QUANTUM, start, 0),
+                Duration::ZERO
+            );
+        }
+    }
+
+    #[test]
+    fn apply_rate_changes_and_schedules() {
+        let start = Instant::now();
+        let limiter = limiter();
+        limiter.set_rates(Rates::default());
+        assert_eq!(
+            limiter.reserve_at(Direction::Download, QUANTUM, start, 0),
+            Duration::ZERO
+        );
+
+        limiter.set_schedule(Schedule::parse("00:00-01:00=32/0").unwrap());
+        assert_eq!(limiter.rates_at(30), Rates::from_kib(32, 0));
+        assert_eq!(limiter.rates_at(90), Rates::default());
+        assert_eq!(
+            limiter.reserve_at(Direction::Download, QUANTUM, start, 30),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            limiter.reserve_at(Direction::Download, QUANTUM, start, 90),
+            Duration::ZERO
+        );
+    }
+}
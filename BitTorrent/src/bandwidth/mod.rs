@@ -0,0 +1,4 @@
+pub(crate) mod errors;
+pub mod limiter;
+pub mod schedule;
+pub mod throttle;
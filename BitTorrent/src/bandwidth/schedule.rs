@@ -0,0 +1,162 @@
+use super::errors::BandwidthError;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/******************************************************************************************/
+/*                                BANDWIDTH SCHEDULE                                      */
+/******************************************************************************************/
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+const KIB: u64 = 1024;
+
+/// Limites de descarga y subida en bytes por segundo. `None` indica que no hay limite.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rates {
+    pub download: Option<u64>,
+    pub upload: Option<u64>,
+}
+
+impl Rates {
+    /// Genera los limites a partir de valores en KiB/s, donde 0 indica que no hay limite.
+    pub fn from_kib(download: u64, upload: u64) -> Self {
+        let rate = |kib: u64| (kib > 0).then_some(kib * KIB);
+        Rates {
+            download: rate(download),
+            upload: rate(upload),
+        }
+    }
+
+    /// Interpreta un par `descarga/subida` en KiB/s, por ejemplo `500/100`.
+    fn parse(text: &str) -> Result<Self, BandwidthError> {
+        let (download, upload) = text
+            .split_once('/')
+            .ok_or(BandwidthError::InvalidRateError)?;
+        let parse = |value: &str| {
+            value
+                .trim()
+                .parse::<u64>()
+                .or(Err(BandwidthError::InvalidRateError))
+        };
+        Ok(Self::from_kib(parse(download)?, parse(upload)?))
+    }
+}
+
+/// Franja horaria con sus propios limites. Si `start` es mayor que `end` la franja
+/// atraviesa la medianoche.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rule {
+    start: u32,
+    end: u32,
+    rates: Rates,
+}
+
+impl Rule {
+    fn contains(&self, minute: u32) -> bool {
+        if self.start <= self.end {
+            self.start <= minute && minute < self.end
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+}
+
+/// Limites que dependen de la hora del dia (UTC). Fuera de las franjas configuradas
+/// rigen los limites generales.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schedule {
+    rules: Vec<Rule>,
+}
+
+impl Schedule {
+    /// Interpreta franjas separadas por coma con el formato `HH:MM-HH:MM=descarga/subida`,
+    /// con los limites en KiB/s. Por ejemplo `08:00-18:00=500/100,18:00-08:00=0/0`.
+    pub fn parse(text: &str) -> Result<Self, BandwidthError> {
+        let mut rules = vec![];
+        for rule in text.split(',').filter(|rule| !rule.trim().is_empty()) {
+            let (range, rates) = rule
+                .split_once('=')
+                .ok_or(BandwidthError::InvalidScheduleError)?;
+            let (start, end) = range
+                .split_once('-')
+                .ok_or(BandwidthError::InvalidScheduleError)?;
+            rules.push(Rule {
+                start: Self::parse_time(start)?,
+                end: Self::parse_time(end)?,
+                rates: Rates::parse(rates)?,
+            });
+        }
+        Ok(Schedule { rules })
+    }
+
+    /// Convierte un horario `HH:MM` en minutos desde la medianoche.
+    fn parse_time(text: &str) -> Result<u32, BandwidthError> {
+        let (hours, minutes) = text
+            .trim()
+            .split_once(':')
+            .ok_or(BandwidthError::InvalidScheduleError)?;
+        let hours = hours
+            .parse::<u32>()
+            .or(Err(BandwidthError::InvalidScheduleError))?;
+        let minutes = minutes
+            .parse::<u32>()
+            .or(Err(BandwidthError::InvalidScheduleError))?;
+        if hours > 24 || minutes > 59 || (hours == 24 && minutes > 0) {
+            return Err(BandwidthError::InvalidScheduleError);
+        }
+        Ok(hours * 60 + minutes)
+    }
+
+    /// Devuelve los limites de la primera franja que incluye el minuto del dia, si hay.
+    pub fn rates_at(&self, minute: u32) -> Option<Rates> {
+        self.rules
+            .iter()
+            .find(|rule| rule.contains(minute))
+            .map(|rule| rule.rates)
+    }
+
+    /// Minuto del dia actual en UTC.
+    pub fn current_minute() -> u32 {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        ((seconds / 60) % MINUTES_PER_DAY as u64) as u32
+    }
+}
+
+#[cfg(test)]
+mod schedule_should {
+    use super::*;
+
+    #[test]
+    fn parse_rates_in_kib() {
+        assert_eq!(
+            Rates::parse("500/0"),
+            Ok(Rates {
+                download: Some(500 * KIB),
+                upload: None
+            })
+        );
+        assert_eq!(Rates::parse("500"), Err(BandwidthError::InvalidRateError));
+        assert_eq!(Rates::parse("-1/3"), Err(BandwidthError::InvalidRateError));
+    }
+
+    #[test]
+    fn find_the_rule_for_the_time_of_day() {
+        let schedule = Schedule::parse("08:00-18:00=500/100, 22:30-06:00=0/50").unwrap();
+
+        assert_eq!(schedule.rates_at(8 * 60), Some(Rates::from_kib(500, 100)));
+        assert_eq!(schedule.rates_at(18 * 60), None);
+        assert_eq!(schedule.rates_at(23 * 60), Some(Rates::from_kib(0, 50)));
+        assert_eq!(schedule.rates_at(5 * 60 + 59), Some(Rates::from_kib(0, 50)));
+        assert_eq!(schedule.rates_at(7 * 60), None);
+    }
+
+    #[test]
+    fn fail_with_invalid_schedules() {
+        assert!(Schedule::parse("").unwrap().rates_at(0).is_none());
+        assert!(Schedule::parse("08:00=1/1").is_err());
+        assert!(Schedule::parse("08:00-25:00=1/1").is_err());
+        assert!(Schedule::parse("8-18=1/1").is_err());
+        assert!(Schedule::parse("08:00-18:00").is_err());
+    }
+}
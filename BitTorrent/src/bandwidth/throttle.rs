@@ -0,0 +1,91 @@
+use super::limiter::{self, BandwidthLimiter, Direction, QUANTUM};
+use std::io::{Read, Result, Write};
+use std::thread;
+use std::time::Duration;
+
+/******************************************************************************************/
+/*                                     THROTTLED                                          */
+/******************************************************************************************/
+
+/// Envuelve una conexion para que sus lecturas y escrituras respeten los limites de
+/// ancho de banda. Cada operacion transfiere a lo sumo `QUANTUM` bytes.
+pub struct Throttled<'a, S> {
+    stream: &'a mut S,
+    limiters: Vec<&'a BandwidthLimiter>,
+}
+
+impl<'a, S> Throttled<'a, S> {
+    /// Aplica el limite del torrent y el global.
+    pub fn new(stream: &'a mut S, torrent: &'a BandwidthLimiter) -> Self {
+        Self::with_limiters(stream, vec![torrent, limiter::global()])
+    }
+
+    pub fn with_limiters(stream: &'a mut S, limiters: Vec<&'a BandwidthLimiter>) -> Self {
+        Throttled { stream, limiters }
+    }
+
+    /// Reserva los bytes en todos los limites y espera lo que pida el mas restrictivo.
+    fn wait(&self, direction: Direction, amount: usize) {
+        let wait = self
+            .limiters
+            .iter()
+            .map(|limiter| limiter.reserve(direction, amount))
+            .max()
+            .unwrap_or(Duration::ZERO);
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+}
+
+impl<S: Read> Read for Throttled<'_, S> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let len = buf.len().min(QUANTUM);
+        let read = self.stream.read(&mut buf[..len])?;
+        self.wait(Direction::Download, read);
+        Ok(read)
+    }
+}
+
+impl<S: Write> Write for Throttled<'_, S> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let len = buf.len().min(QUANTUM);
+        self.wait(Direction::Upload, len);
+        self.stream.write(&buf[..len])
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.stream.flush()
+    }
+}
+
+#[cfg(test)]
+mod throttled_should {
+    use super::*;
+    use crate::bandwidth::schedule::{Rates, Schedule};
+    use std::time::Instant;
+
+    #[test]
+    fn limit_writes_to_the_configured_rate() {
+        let limiter = BandwidthLimiter::new(Rates::from_kib(0, 64), Schedule::default());
+        let mut output: Vec<u8> = vec![];
+        let start = Instant::now();
+
+        let mut throttled = Throttled::with_limiters(&mut output, vec![&limiter]);
+        throttled.write_all(&[7; 2 * QUANTUM]).unwrap();
+
+        assert_eq!(output, vec![7; 2 * QUANTUM]);
+        assert!(start.elapsed() >= Duration::from_millis(450));
+    }
+
+    #[test]
+    fn read_in_quantums() {
+        let unlimited = BandwidthLimiter::unlimited();
+        let input = vec![1; 3 * QUANTUM];
+        let mut stream = input.as_slice();
+        let mut buffer = vec![0; 4 * QUANTUM];
+
+        let mut throttled = Throttled::with_limiters(&mut stream, vec![&unlimited]);
+        assert_eq!(throttled.read(&mut buffer).unwrap(), QUANTUM);
+    }
+}
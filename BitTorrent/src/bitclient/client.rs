@@ -1,19 +1,24 @@
+use crate::bandwidth::limiter::{self, BandwidthLimiter};
+use crate::bandwidth::schedule::{Rates, Schedule};
 use crate::bitclient::errors::ClientError;
+use crate::bitclient::manager::{ConnectionManager, DEFAULT_MAX_GLOBAL_PEERS, DEFAULT_MAX_PEERS};
 use crate::bitclient::server;
 use crate::downloads::downloader::Downloader;
 use crate::downloads::errors::DownloaderError;
 use crate::log::logger::Logger;
-use crate::peer_connection::connection::Connection;
+use crate::peers::ban::{BanList, Contributor};
 use crate::peers::peer::Peer;
 use crate::pieces::errors::PiecesError;
-use crate::pieces::piece::Piece;
-use crate::torrent_file::errors::MetaInfoError;
-use crate::torrent_file::metainfo::MetaInfo;
-use crate::tracker::errors::TrackerError;
+use crate::pieces::piece::{Piece, Verification};
+use crate::streaming::sequential::{SequentialWindow, DEFAULT_DEADLINE, DEFAULT_WINDOW_SIZE};
+use crate::streaming::stream_server;
+use protocol::torrent_file::errors::MetaInfoError;
+use protocol::torrent_file::metainfo::MetaInfo;
 use crate::tracker::tracker_request::TrackerRequest;
 use crate::tracker::tracker_response::TrackerResponse;
 use gtk4::glib::Sender as gtkSender;
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
@@ -21,6 +26,7 @@ use std::sync::mpsc::{self, Sender};
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::thread;
+use std::time::{Duration, Instant};
 
 /******************************************************************************************/
 /*                                      BITCLIENT                                         */
@@ -53,6 +59,16 @@ pub struct BitClient {
     pub peer_id: String,
     pub peer: Peer, //Representa al cliente como peer
     pub pieces: Vec<Piece>,
+    pub sequential: SequentialWindow,
+    pub streaming_port: Option<String>,
+    pub max_peers: usize,
+    pub max_global_peers: usize,
+    /// Ids de los peers con los que hay una conexion abierta.
+    pub connected_peers: HashSet<String>,
+    /// Peers bloqueados por enviar piezas corruptas.
+    pub bans: BanList,
+    /// Limite de ancho de banda del torrent. Puede modificarse durante la descarga.
+    pub bandwidth: Arc<BandwidthLimiter>,
 }
 
 type Result<T> = std::result::Result<T, ClientError>;
@@ -60,7 +76,21 @@ type Result<T> = std::result::Result<T, ClientError>;
 #[allow(dead_code)]
 impl BitClient {
     pub fn new(configuration_path: &str, torrent_path: &str) -> Result<BitClient> {
-        let config_parameters = Self::read_configuration_file(configuration_path)?;
+        let config = Self::read_configuration_file(configuration_path)?;
+        let port_to_peers = Self::get_parameter(&config, "PORT")?;
+        let log_path = Self::get_parameter(&config, "LOGS_URL")?;
+        let downloads_path = Self::get_parameter(&config, "DOWNLOADS_URL")?;
+        let sequential = Self::generate_sequential_window(&config)?;
+        let streaming_port = config.get("STREAMING_PORT").cloned();
+        let max_peers = Self::get_count_parameter(&config, "MAX_PEERS", DEFAULT_MAX_PEERS)?;
+        let max_global_peers =
+            Self::get_count_parameter(&config, "MAX_GLOBAL_PEERS", DEFAULT_MAX_GLOBAL_PEERS)?;
+        let (rates, schedule) = Self::generate_bandwidth_limits(&config, "")?;
+        let (global_rates, global_schedule) = Self::generate_bandwidth_limits(&config, "GLOBAL_")?;
+        if config.keys().any(|key| key.starts_with("GLOBAL_")) {
+            limiter::global().set_rates(global_rates);
+            limiter::global().set_schedule(global_schedule);
+        }
         let id: String = Self::generate_id();
         let (log, _rx) = mpsc::channel();
         let (null_sender, _null_receiver) =
@@ -69,7 +99,7 @@ impl BitClient {
             MetaInfoError::DecodingError,
         )))?;
         let downloader = Downloader::new(
-            &config_parameters[2],
+            &downloads_path,
             &metainfo.info.name,
             metainfo.info.length,
         )
@@ -78,11 +108,11 @@ impl BitClient {
         )))?;
 
         let pieces = Self::generate_pieces(&metainfo);
-        let mut peer = Peer::new(id.clone(), String::from(""), config_parameters[0].clone());
+        let mut peer = Peer::new(id.clone(), String::from(""), port_to_peers.clone());
         peer.bitfield = vec![false; metainfo.info.num_pieces];
         let client: BitClient = BitClient {
-            port_to_peers: config_parameters[0].clone(),
-            log_path: config_parameters[1].clone(),
+            port_to_peers,
+            log_path,
             log,
             downloader,
             peer_id: id,
@@ -90,6 +120,13 @@ impl BitClient {
             metainfo,
             pieces,
             event_bus: null_sender,
+            sequential,
+            streaming_port,
+            max_peers,
+            max_global_peers,
+            connected_peers: HashSet::new(),
+            bans: BanList::new(),
+            bandwidth: Arc::new(BandwidthLimiter::new(rates, schedule)),
         };
         Ok(client)
     }
@@ -104,7 +141,7 @@ impl BitClient {
         println!("EL tamaño del archivo es {:?}", metainfo.info.length);
         let mut pieces: Vec<Piece> = vec![];
         let n = metainfo.info.pieces.len();
-        let last_piece_dont_fix = size % piece_length != 0;
+        let last_piece_dont_fix = !size.is_multiple_of(piece_length);
 
         for i in 0..n {
             let length = {
@@ -119,7 +156,7 @@ impl BitClient {
                 length,
                 i as u32,
                 piece_length,
-                metainfo.info.pieces[i as usize].clone(),
+                metainfo.info.pieces[i].clone(),
                 BLOCK_SIZE,
             );
             pieces.push(piece);
@@ -138,52 +175,172 @@ impl BitClient {
         id
     }
 
-    /// Lee el archivo de configuracion y almacena las variables en el cliente.
-    fn read_configuration_file(path: &str) -> Result<Vec<String>> {
+    /// Lee el archivo de configuracion, cada linea tiene el formato CLAVE:valor.
+    fn read_configuration_file(path: &str) -> Result<HashMap<String, String>> {
         let file = File::open(path).or(Err(ClientError::OpenFileError))?;
         let reader = BufReader::new(file);
-        let mut lineas: Vec<String> = Vec::new();
+        let mut parameters: HashMap<String, String> = HashMap::new();
         for line in reader.lines() {
             let line = line.or(Err(ClientError::ReadFileError))?;
-            let split_line: Vec<&str> = line.split(':').collect();
-            lineas.push(split_line[1].to_string());
+            if let Some((key, value)) = line.split_once(':') {
+                parameters.insert(key.trim().to_string(), value.trim().to_string());
+            }
         }
-        Ok(lineas)
+        Ok(parameters)
     }
 
-    /// Funcion que se encarga de anunciarse al tracker
-    pub fn announce_to_tracker(&mut self) -> Result<TrackerResponse> {
-        let mut tracker_request = TrackerRequest::new(
+    /// Devuelve un parametro obligatorio del archivo de configuracion.
+    fn get_parameter(config: &HashMap<String, String>, key: &str) -> Result<String> {
+        config
+            .get(key)
+            .cloned()
+            .ok_or(ClientError::InvalidConfigurationError)
+    }
+
+    /// Devuelve un parametro numerico opcional del archivo de configuracion, o el valor por defecto.
+    fn get_numeric_parameter(
+        config: &HashMap<String, String>,
+        key: &str,
+        default: u64,
+    ) -> Result<u64> {
+        match config.get(key) {
+            Some(value) => value
+                .parse::<u64>()
+                .or(Err(ClientError::InvalidConfigurationError)),
+            None => Ok(default),
+        }
+    }
+
+    /// Devuelve un parametro opcional que debe ser una cantidad positiva, o el valor por defecto.
+    fn get_count_parameter(
+        config: &HashMap<String, String>,
+        key: &str,
+        default: usize,
+    ) -> Result<usize> {
+        let count = Self::get_numeric_parameter(config, key, default as u64)?;
+        match usize::try_from(count) {
+            Ok(count) if count > 0 => Ok(count),
+            _ => Err(ClientError::InvalidConfigurationError),
+        }
+    }
+
+    /// Inicializa la ventana de descarga secuencial con los parametros SEQUENTIAL,
+    /// SEQUENTIAL_WINDOW y SEQUENTIAL_DEADLINE (en segundos).
+    fn generate_sequential_window(config: &HashMap<String, String>) -> Result<SequentialWindow> {
+        let enabled = match config.get("SEQUENTIAL").map(|value| value.as_str()) {
+            Some("true") => true,
+            Some("false") | None => false,
+            Some(_) => return Err(ClientError::InvalidConfigurationError),
+        };
+        let size = Self::get_numeric_parameter(config, "SEQUENTIAL_WINDOW", DEFAULT_WINDOW_SIZE as u64)?
+            .try_into()
+            .or(Err(ClientError::InvalidConfigurationError))?;
+        let deadline = Self::get_numeric_parameter(config, "SEQUENTIAL_DEADLINE", DEFAULT_DEADLINE)?;
+        Ok(SequentialWindow::new(
+            enabled,
+            size,
+            Duration::from_secs(deadline),
+        ))
+    }
+
+    /// Lee los limites de ancho de banda MAX_DOWNLOAD_RATE y MAX_UPLOAD_RATE (en KiB/s, 0 es
+    /// sin limite) y el horario BANDWIDTH_SCHEDULE, con el prefijo indicado.
+    fn generate_bandwidth_limits(
+        config: &HashMap<String, String>,
+        prefix: &str,
+    ) -> Result<(Rates, Schedule)> {
+        let download_key = prefix.to_owned() + "MAX_DOWNLOAD_RATE";
+        let upload_key = prefix.to_owned() + "MAX_UPLOAD_RATE";
+        let download = Self::get_numeric_parameter(config, &download_key, 0)?;
+        let upload = Self::get_numeric_parameter(config, &upload_key, 0)?;
+        let schedule = match config.get(&(prefix.to_owned() + "BANDWIDTH_SCHEDULE")) {
+            Some(schedule) => {
+                Schedule::parse(schedule).or(Err(ClientError::InvalidConfigurationError))?
+            }
+            None => Schedule::default(),
+        };
+        Ok((Rates::from_kib(download, upload), schedule))
+    }
+
+    /// Genera el pedido de announce al tracker del torrent. Permite anunciarse sin tener
+    /// tomado el lock del cliente durante la comunicacion con el tracker.
+    pub fn tracker_request(&self) -> TrackerRequest {
+        TrackerRequest::new(
             self.metainfo.info_hash.clone(),
             self.peer_id.clone(),
             self.port_to_peers.clone(),
             self.metainfo.announce.clone(),
-        );
+        )
+    }
+
+    /// Funcion que se encarga de anunciarse al tracker
+    pub fn announce_to_tracker(&mut self) -> Result<TrackerResponse> {
+        let mut tracker_request = self.tracker_request();
         let response = tracker_request
             .announce()
-            .or(Err(ClientError::TrackerError(
-                TrackerError::FailToConnectError,
-            )))?;
+            .map_err(ClientError::TrackerError)?;
 
         Ok(response)
     }
 
     /// Funcion que se encarga de almacenar la data de un bloque especifico de una pieza en el vector de piezas
-    pub fn store(&mut self, piece_index: u32, block_index: u32, data: Vec<u8>) -> Result<bool> {
-        self.pieces[piece_index as usize]
+    /// Si la pieza ya estaba completa se descarta la data, ya que puede llegar repetida
+    /// cuando un bloque vencido de la ventana secuencial se pide a mas de un peer.
+    pub fn store(
+        &mut self,
+        piece_index: u32,
+        block_index: u32,
+        data: Vec<u8>,
+        source: Contributor,
+    ) -> Result<bool> {
+        self.sequential.mark_as_received(piece_index, block_index);
+        if self.pieces[piece_index as usize].is_complete {
+            return Ok(self.is_complete());
+        }
+        let verification = self.pieces[piece_index as usize]
             .store(
                 &mut self.downloader,
                 block_index,
                 data,
+                source,
                 self.log.clone(),
                 self.event_bus.clone(),
             )
             .or(Err(ClientError::StorageError(
                 PiecesError::DownloadingError,
             )))?;
+        let now = Instant::now();
+        let banned = match verification {
+            Verification::Incomplete => vec![],
+            Verification::Valid(sources) => self.bans.valid_piece(piece_index, &sources, now),
+            Verification::Corrupt(sources) => self.bans.corrupt_piece(piece_index, sources, now),
+        };
+        for contributor in banned {
+            println!(
+                "[CLIENTE] Bloqueo al peer {} por enviar piezas corruptas",
+                contributor.ip
+            );
+            let message = "- [INFO] Se bloqueo al peer ".to_owned()
+                + &contributor.ip
+                + " por enviar piezas corruptas";
+            self.log.send(message).or(Err(ClientError::WriteLogError))?;
+        }
+        if self.pieces[piece_index as usize].is_complete {
+            self.sequential.mark_as_complete(piece_index);
+        }
         Ok(self.is_complete())
     }
 
+    /// Indica si el peer esta bloqueado por haber enviado piezas corruptas.
+    pub fn is_banned(&self, peer: &Peer) -> bool {
+        self.bans.is_banned(&peer.ip, &peer.id, Instant::now())
+    }
+
+    /// Indica si todas las piezas del torrent estan completas, sin informarlo.
+    pub fn download_finished(&self) -> bool {
+        self.pieces.iter().all(|piece| piece.is_complete)
+    }
+
     /// Verifica si se completo la descarga del torrent, es decir si todas las piezas estan completas
     fn is_complete(&self) -> bool {
         for piece in self.pieces.iter() {
@@ -198,13 +355,31 @@ impl BitClient {
         true
     }
 
-    /// Busca en el vector de piezas cual es el siguiente bloque que debe descargar
+    /// Busca en el vector de piezas cual es el siguiente bloque que debe descargar.
+    /// Primero recorre las piezas urgentes de la ventana secuencial, donde tambien se vuelven a pedir
+    /// los bloques que superaron el deadline, y luego el resto de las piezas.
     pub fn next_block_to_request(&self, peer_bitfield: &[bool]) -> Option<(u32, u32, u32)> {
-        for piece in self.pieces.iter() {
+        let urgent = self.sequential.urgent_pieces(&self.pieces);
+        for index in urgent.iter() {
+            let piece = &self.pieces[*index as usize];
             if peer_bitfield[piece.index as usize] {
                 if let Some(block) = piece.next_block_to_request() {
                     return Some((piece.index, block.index, block.length));
                 }
+                for block in piece.blocks.iter() {
+                    if block.data.is_empty() && self.sequential.is_overdue(piece.index, block.index)
+                    {
+                        return Some((piece.index, block.index, block.length));
+                    }
+                }
+            }
+        }
+        for index in self.sequential.pieces_order(self.pieces.len() as u32) {
+            let piece = &self.pieces[index as usize];
+            if !urgent.contains(&index) && peer_bitfield[piece.index as usize] {
+                if let Some(block) = piece.next_block_to_request() {
+                    return Some((piece.index, block.index, block.length));
+                }
             }
         }
         None
@@ -212,7 +387,6 @@ impl BitClient {
 
     /// Funcion que se llama desde el main, se encarga de inicializar el cliente, comunicarse con el tracker.
     /// Dispara un thread para el logger, un thread para el servidor y uno para la conexion por cada peer.
-
     pub fn download_torrent(
         configuration_path: &str,
         torrent_path: &str,
@@ -278,42 +452,25 @@ impl BitClient {
                 //Inicio el server
                 let server = server::start(mutex.clone())?;
 
-                //Disparo un thread por conexion
-                let mut connections = vec![];
+                //Inicio el servidor de streaming, si esta configurado
+                let streaming = stream_server::start(mutex.clone())?;
+
+                //Mantengo las conexiones con los peers
                 println!(
                     "[CLIENTE] Recibi {} peers del tracker",
                     response.peers.len()
                 );
-                for (id, peer) in response.peers.into_iter().enumerate() {
-                    //println!("[CLIENTE] Por conectarme al peer {}, Peer: {:?} ",id, peer);
-                    let mutex_clone = mutex.clone();
-                    connections.push(thread::spawn(move || {
-                        Connection::connect(id, peer, mutex_clone)
-                    }));
-                }
+                let mut manager = ConnectionManager::new(mutex, &response)?;
+                manager.run()?;
 
-                for (id, connection) in connections.into_iter().enumerate() {
-                    let result = connection.join();
-                    match result {
-                        Ok(result_connection) => {
-                            if let Err(err) = result_connection {
-                                println!("[ERROR] Conexión {}: {}", id, err);
-                                let message = "- [ERROR] Conexión ".to_owned()
-                                    + &id.to_string()
-                                    + " : "
-                                    + &err.to_string();
-                                tx.send(message).or(Err(ClientError::WriteLogError))?;
-                                //return Err(ClientError::ReadConnectionError(err));
-
-                                //id conexion fallo, ya no esta activa
-                            }
-                        }
-                        Err(_) => return Err(ClientError::FailToJoinThreadError),
-                    }
-                }
                 if let Err(err) = server.join() {
                     println!("[Error] Fallo al joinear el server: {:?}", err)
                 }
+                if let Some(streaming) = streaming {
+                    if let Err(err) = streaming.join() {
+                        println!("[Error] Fallo al joinear el servidor de streaming: {:?}", err)
+                    }
+                }
             }
         }
 
@@ -332,6 +489,13 @@ impl BitClient {
 
     pub fn mark_as_requested(&mut self, piece_index: u32, block_index: u32) {
         self.pieces[piece_index as usize].mark_as_requested(block_index);
+        self.sequential.mark_as_requested(piece_index, block_index);
+    }
+
+    /// Vuelve a dejar disponible un bloque que fue pedido a un peer y nunca llego.
+    pub fn requeue_block(&mut self, piece_index: u32, block_index: u32) {
+        self.pieces[piece_index as usize].mark_as_not_requested(block_index);
+        self.sequential.cancel_request(piece_index, block_index);
     }
 }
 
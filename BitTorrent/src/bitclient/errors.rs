@@ -1,8 +1,9 @@
 use crate::downloads::errors::DownloaderError;
 use crate::peer_connection::errors::ConnectionError;
 use crate::pieces::errors::PiecesError;
-use crate::torrent_file::errors::MetaInfoError;
+use crate::streaming::errors::StreamingError;
 use crate::tracker::errors::TrackerError;
+use protocol::torrent_file::errors::MetaInfoError;
 use std::fmt;
 
 /******************************************************************************************/
@@ -34,6 +35,8 @@ pub enum ClientError {
     WriteConnectionAsServerError,
     UploadError,
     InvalidServerMessageError,
+    InvalidConfigurationError,
+    StreamingError(StreamingError),
 }
 
 #[allow(dead_code)]
@@ -102,6 +105,12 @@ impl fmt::Display for ClientError {
             ClientError::InvalidServerMessageError => {
                 write!(f, "El servidor recibio un mensaje que no corresponde")
             }
+            ClientError::InvalidConfigurationError => {
+                write!(f, "El archivo de configuracion es invalido")
+            }
+            ClientError::StreamingError(streaming_error) => {
+                write!(f, "{}", streaming_error)
+            }
         }
     }
 }
@@ -0,0 +1,375 @@
+use crate::bitclient::client::{BitClient, Event};
+use crate::bitclient::errors::ClientError;
+use crate::peer_connection::connection::Connection;
+use crate::peer_connection::errors::ConnectionError;
+use crate::peers::peer::Peer;
+use crate::peers::pool::PeerPool;
+use crate::tracker::tracker_response::TrackerResponse;
+use gtk4::glib::Sender as gtkSender;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/******************************************************************************************/
+/*                                CONNECTION MANAGER                                      */
+/******************************************************************************************/
+
+/// Cantidad de conexiones activas por torrent si no se configura MAX_PEERS.
+pub const DEFAULT_MAX_PEERS: usize = 30;
+/// Cantidad de conexiones activas entre todos los torrents si no se configura MAX_GLOBAL_PEERS.
+pub const DEFAULT_MAX_GLOBAL_PEERS: usize = 80;
+/// Cada cuanto se revisan las conexiones terminadas y se abren nuevas.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Intervalos de announce si el tracker no informo ninguno.
+const DEFAULT_ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1800);
+const DEFAULT_MIN_ANNOUNCE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Conexiones abiertas por todos los torrents que se descargan en el proceso.
+static GLOBAL_CONNECTIONS: ConnectionSlots = ConnectionSlots::new();
+
+/// Contador de conexiones abiertas con un limite maximo.
+#[derive(Debug)]
+struct ConnectionSlots {
+    used: AtomicUsize,
+}
+
+impl ConnectionSlots {
+    const fn new() -> Self {
+        ConnectionSlots {
+            used: AtomicUsize::new(0),
+        }
+    }
+
+    /// Ocupa un lugar si hay menos de `max` conexiones abiertas. Devuelve true si lo ocupo.
+    fn acquire(&self, max: usize) -> bool {
+        self.used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                (used < max).then_some(used + 1)
+            })
+            .is_ok()
+    }
+
+    fn release(&self) {
+        let _ = self
+            .used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                used.checked_sub(1)
+            });
+    }
+}
+
+/// Duplica la espera entre announces, sin bajar de `min` ni superar `max`.
+fn next_retry_interval(current: Duration, min: Duration, max: Duration) -> Duration {
+    (current * 2).min(max).max(min)
+}
+
+/// Como anunciarse al tracker en cada vuelta de `ConnectionManager::run`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AnnouncePolicy {
+    /// La descarga se completo y no hace falta conseguir peers.
+    Skip,
+    /// No quedan conexiones ni peers en el pool: se reintenta con backoff.
+    Retry,
+    /// Se anuncia cada `announce_interval`, aunque los peers del pool esten en backoff.
+    Regular,
+}
+
+fn announce_policy(completed: bool, active: usize, pool: &PeerPool) -> AnnouncePolicy {
+    if completed {
+        AnnouncePolicy::Skip
+    } else if active == 0 && pool.is_empty() {
+        AnnouncePolicy::Retry
+    } else {
+        AnnouncePolicy::Regular
+    }
+}
+
+/// Conexion en curso con un peer.
+struct ActiveConnection {
+    address: String,
+    handle: JoinHandle<()>,
+}
+
+type ConnectionResult = (usize, std::result::Result<bool, ConnectionError>);
+
+/// Mantiene las conexiones con los peers de un torrent. Toma los peers de un `PeerPool`,
+/// abre conexiones hasta llegar al limite del torrent y al global, reintenta los peers
+/// que fallaron y vuelve a anunciarse al tracker para conseguir peers nuevos.
+pub struct ConnectionManager {
+    client: Arc<Mutex<BitClient>>,
+    pool: PeerPool,
+    max_peers: usize,
+    max_global_peers: usize,
+    active: HashMap<usize, ActiveConnection>,
+    next_id: usize,
+    sender: Sender<ConnectionResult>,
+    receiver: Receiver<ConnectionResult>,
+    announce_interval: Duration,
+    min_announce_interval: Duration,
+    /// Espera entre announces cuando no quedan peers. Se duplica con cada announce que no
+    /// consigue peers nuevos, hasta `announce_interval`.
+    retry_interval: Duration,
+    last_announce: Instant,
+    log: Sender<String>,
+    event_bus: gtkSender<Event>,
+    completed: bool,
+}
+
+type Result<T> = std::result::Result<T, ClientError>;
+
+impl ConnectionManager {
+    /// Se inicializa con el cliente y la respuesta del primer announce al tracker.
+    pub fn new(client: Arc<Mutex<BitClient>>, response: &TrackerResponse) -> Result<Self> {
+        let lock = client.lock().or(Err(ClientError::MutexLockError))?;
+        let mut pool = PeerPool::new(lock.peer_id.clone(), lock.port_to_peers.clone());
+        let max_peers = lock.max_peers;
+        let max_global_peers = lock.max_global_peers;
+        let log = lock.log.clone();
+        let event_bus = lock.event_bus.clone();
+        drop(lock);
+
+        for peer in response.peers.iter() {
+            pool.add(peer.clone());
+        }
+        let (sender, receiver) = mpsc::channel();
+        let min_announce_interval = response
+            .min_interval()
+            .unwrap_or(DEFAULT_MIN_ANNOUNCE_INTERVAL);
+        Ok(ConnectionManager {
+            client,
+            pool,
+            max_peers,
+            max_global_peers,
+            active: HashMap::new(),
+            next_id: 0,
+            sender,
+            receiver,
+            announce_interval: response.interval().unwrap_or(DEFAULT_ANNOUNCE_INTERVAL),
+            min_announce_interval,
+            retry_interval: min_announce_interval,
+            last_announce: Instant::now(),
+            log,
+            event_bus,
+            completed: false,
+        })
+    }
+
+    /// Mantiene las conexiones hasta completar la descarga. Se anuncia al tracker cada
+    /// `announce_interval` y, si se queda sin peers, sigue anunciandose con backoff hasta
+    /// conseguir nuevos.
+    pub fn run(&mut self) -> Result<()> {
+        loop {
+            if !self.completed {
+                self.open_connections();
+            }
+            self.wait_results()?;
+
+            if self.completed && self.active.is_empty() {
+                return Ok(());
+            }
+            match announce_policy(self.completed, self.active.len(), &self.pool) {
+                AnnouncePolicy::Skip => {}
+                AnnouncePolicy::Retry => self.retry_announce()?,
+                AnnouncePolicy::Regular => {
+                    self.reannounce(self.announce_interval)?;
+                }
+            }
+        }
+    }
+
+    /// Abre conexiones con los peers listos hasta llegar a los limites configurados.
+    fn open_connections(&mut self) {
+        while self.active.len() < self.max_peers {
+            if !GLOBAL_CONNECTIONS.acquire(self.max_global_peers) {
+                return;
+            }
+            let peer = match self.pool.next_ready(Instant::now()) {
+                Some(peer) => peer,
+                None => {
+                    GLOBAL_CONNECTIONS.release();
+                    return;
+                }
+            };
+            self.spawn(peer);
+        }
+    }
+
+    fn spawn(&mut self, peer: Peer) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let address = PeerPool::address(&peer);
+        let client = self.client.clone();
+        let sender = self.sender.clone();
+        let handle = thread::spawn(move || {
+            let result = Connection::connect(id, peer, client);
+            let _ = sender.send((id, result));
+        });
+        self.active.insert(id, ActiveConnection { address, handle });
+    }
+
+    /// Espera a que terminen conexiones y actualiza el pool segun su resultado.
+    fn wait_results(&mut self) -> Result<()> {
+        if let Ok(result) = self.receiver.recv_timeout(POLL_INTERVAL) {
+            self.finish(result)?;
+        }
+        // Los threads informan su resultado antes de terminar, por lo que los que ya
+        // terminaron y siguen activos luego de leer el canal no lo informaron
+        let finished: Vec<usize> = self
+            .active
+            .iter()
+            .filter(|(_id, connection)| connection.handle.is_finished())
+            .map(|(id, _connection)| *id)
+            .collect();
+        while let Ok(result) = self.receiver.try_recv() {
+            self.finish(result)?;
+        }
+        for id in finished {
+            self.finish((id, Err(ConnectionError::FailToConnectError)))?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, (id, result): ConnectionResult) -> Result<()> {
+        let connection = match self.active.remove(&id) {
+            Some(connection) => connection,
+            None => return Ok(()),
+        };
+        GLOBAL_CONNECTIONS.release();
+        if connection.handle.join().is_err() {
+            println!("[ERROR] Fallo al joinear la conexion {}", id);
+        }
+        match result {
+            Ok(true) => self.completed = true,
+            Ok(false) => self.pool.finished(&connection.address),
+            Err(err) => {
+                match err {
+                    ConnectionError::SelfConnectionError
+                    | ConnectionError::DuplicatePeerError
+                    | ConnectionError::BadPeerResponseError => self.pool.ban(&connection.address),
+                    _ => self.pool.failed(&connection.address, Instant::now()),
+                }
+                println!("[ERROR] Conexión {}: {}", id, err);
+                let message =
+                    "- [ERROR] Conexión ".to_owned() + &id.to_string() + " : " + &err.to_string();
+                self.log.send(message).or(Err(ClientError::WriteLogError))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Se anuncia al tracker cuando no quedan peers. Si el tracker falla o no devuelve peers
+    /// nuevos, la espera hasta el proximo intento se duplica.
+    fn retry_announce(&mut self) -> Result<()> {
+        match self.reannounce(self.retry_interval)? {
+            Some(true) => self.retry_interval = self.min_announce_interval,
+            Some(false) => {
+                self.retry_interval = next_retry_interval(
+                    self.retry_interval,
+                    self.min_announce_interval,
+                    self.announce_interval,
+                );
+                println!(
+                    "[CLIENTE] No hay peers disponibles, reintento en {} segundos",
+                    self.retry_interval.as_secs()
+                );
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Se anuncia al tracker si paso `interval` desde el ultimo announce y agrega los
+    /// peers nuevos al pool. Devuelve si se agrego algun peer, o None si no se anuncio.
+    fn reannounce(&mut self, interval: Duration) -> Result<Option<bool>> {
+        if self.last_announce.elapsed() < interval {
+            return Ok(None);
+        }
+        self.last_announce = Instant::now();
+        let lock = self.client.lock().or(Err(ClientError::MutexLockError))?;
+        let mut tracker_request = lock.tracker_request();
+        drop(lock);
+
+        let response = match tracker_request.announce() {
+            Ok(response) => response,
+            Err(error) => {
+                let message = "- [ERROR] ".to_owned() + &error.to_string();
+                self.log.send(message).or(Err(ClientError::WriteLogError))?;
+                return Ok(Some(false));
+            }
+        };
+        if let Some(interval) = response.interval() {
+            self.announce_interval = interval;
+        }
+        if let Some(interval) = response.min_interval() {
+            self.min_announce_interval = interval;
+        }
+        self.event_bus
+            .send(Event::UpdatePeerList(response.peers.clone()))
+            .or(Err(ClientError::WriteLogError))?;
+        let mut added = false;
+        for peer in response.peers {
+            added |= self.pool.add(peer);
+        }
+        Ok(Some(added))
+    }
+}
+
+#[cfg(test)]
+mod connection_manager_should {
+    use super::*;
+
+    #[test]
+    fn limit_open_connections() {
+        let slots = ConnectionSlots::new();
+
+        assert!(slots.acquire(2));
+        assert!(slots.acquire(2));
+        assert!(!slots.acquire(2));
+        slots.release();
+        assert!(slots.acquire(2));
+        slots.release();
+        slots.release();
+        slots.release();
+        assert!(slots.acquire(1));
+        assert!(!slots.acquire(1));
+    }
+
+    #[test]
+    fn keep_announcing_while_every_peer_is_backed_off() {
+        let mut pool = PeerPool::new("ourselves-0000000000".to_string(), "6881".to_string());
+        let start = Instant::now();
+        for ip in ["10.0.0.1", "10.0.0.2"] {
+            pool.add(Peer::new(String::new(), ip.to_string(), "6881".to_string()));
+        }
+        while let Some(peer) = pool.next_ready(start) {
+            pool.failed(&PeerPool::address(&peer), start);
+        }
+
+        assert!(pool.next_ready(start).is_none());
+        assert!(!pool.is_empty());
+        assert_eq!(announce_policy(false, 0, &pool), AnnouncePolicy::Regular);
+        assert_eq!(announce_policy(true, 0, &pool), AnnouncePolicy::Skip);
+        assert_eq!(
+            announce_policy(false, 0, &PeerPool::new(String::new(), String::new())),
+            AnnouncePolicy::Retry
+        );
+    }
+
+    #[test]
+    fn double_the_retry_interval_up_to_the_announce_interval() {
+        let min = Duration::from_secs(60);
+        let max = Duration::from_secs(300);
+
+        let second = next_retry_interval(min, min, max);
+        assert_eq!(second, Duration::from_secs(120));
+        assert_eq!(
+            next_retry_interval(second, min, max),
+            Duration::from_secs(240)
+        );
+        assert_eq!(next_retry_interval(Duration::from_secs(240), min, max), max);
+        assert_eq!(next_retry_interval(Duration::ZERO, min, max), min);
+    }
+}
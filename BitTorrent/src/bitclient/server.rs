@@ -1,9 +1,15 @@
+use crate::bandwidth::limiter::BandwidthLimiter;
+use crate::bandwidth::throttle::Throttled;
 use crate::bitclient::client::BitClient;
 use crate::bitclient::errors::ClientError;
 use crate::peer_connection::errors::ConnectionError;
-use crate::peer_protocol::handshake::Handshake;
-use crate::peer_protocol::messages::{Message, MessageId};
+use crate::peer_connection::timers::{
+    PeerTimeouts, PeerTimers, HANDSHAKE_TIMEOUT, TICK, WRITE_TIMEOUT,
+};
 use crate::peers::peer::Peer;
+use protocol::peer_protocol::codec::{CodecLimits, PeerCodec};
+use protocol::peer_protocol::handshake::Handshake;
+use protocol::peer_protocol::messages::{Message, MessageId};
 use std::io::Read;
 use std::io::Write;
 use std::net::IpAddr;
@@ -12,10 +18,10 @@ use std::net::{Shutdown, TcpStream};
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 const HANDSHAKE_LEN: usize = 68;
 
 const HOST: &str = "127.0.0.1";
-const LEN: usize = 4;
 use std::thread::JoinHandle;
 type Result<T> = std::result::Result<T, ClientError>;
 #[allow(dead_code)]
@@ -26,6 +32,7 @@ pub struct Server {
     connections: Vec<ServerConnection>,
     listener: TcpListener,
     log: Sender<String>,
+    bandwidth: Arc<BandwidthLimiter>,
 }
 #[allow(dead_code)]
 impl Server {
@@ -33,6 +40,7 @@ impl Server {
         let client = mutex.lock().or(Err(ClientError::MutexLockError))?;
         let port = client.port_to_peers.clone();
         let sender = client.log.clone();
+        let bandwidth = client.bandwidth.clone();
         drop(client);
 
         let addr = HOST.to_owned() + ":" + &port;
@@ -49,18 +57,17 @@ impl Server {
             log: sender,
             listener,
             connections: vec![],
+            bandwidth,
         })
     }
 
-    /// Envia el bitfield por la conexion
-    fn send_bitfield(&mut self) -> Result<Vec<u8>> {
+    /// Genera el mensaje bitfield a enviar por la conexion
+    fn send_bitfield(&mut self) -> Result<MessageId> {
         let client = self.client.lock().or(Err(ClientError::MutexLockError))?;
         let bitfield = client.peer.bitfield.clone();
         drop(client);
-        let mut bytes = Peer::bytes_from_bitmap(bitfield);
-        let message =
-            Message::send_bitfield(&mut bytes).or(Err(ClientError::InvalidMessageError))?;
-        Ok(message)
+        let bytes = Peer::bytes_from_bitmap(bitfield);
+        Ok(MessageId::Bitfield(bytes))
     }
 
     /// Realiza el handshake con el otro peer, en caso de que no coincidan los info hash de ambos devuelve false
@@ -107,7 +114,18 @@ impl Server {
                 let peer_id = id.to_string();
                 let peer = Peer::new(peer_id, peer_ip, peer_port);
 
-                self.connections.push(ServerConnection { stream, peer, id });
+                let client = self.client.lock().or(Err(ClientError::MutexLockError))?;
+                let num_pieces = client.metainfo.info.num_pieces;
+                drop(client);
+                Self::set_timeouts(&stream, HANDSHAKE_TIMEOUT)?;
+                let codec = PeerCodec::new(CodecLimits::for_pieces(num_pieces));
+                self.connections.push(ServerConnection {
+                    stream,
+                    peer,
+                    id,
+                    codec,
+                    timers: PeerTimers::new(PeerTimeouts::default(), Instant::now()),
+                });
                 let valid = self.attempt_handshake(id)?;
                 if !valid {
                     println!(
@@ -123,13 +141,13 @@ impl Server {
                     return Ok(());
                 }
                 println!("[SERVER] El handshake con la conexion {} , es valido", id);
+                Self::set_timeouts(&self.connections[id].stream, TICK)?;
                 let bitfield = self.send_bitfield()?;
-                self.write_messages(bitfield, id)?;
+                self.write_message(&bitfield, id)?;
                 println!("[SERVER] envie el bitfield a la conexion {}", id);
 
                 let have = self.return_have()?;
-                let message = Message::send_have(have as u32);
-                self.write_messages(message, id)?;
+                self.write_message(&MessageId::Have(have as u32), id)?;
                 println!(
                     "[SERVER] envie have de la pieza {} a la conexion {}",
                     have, id
@@ -137,18 +155,18 @@ impl Server {
 
                 let mut done = false;
                 while !done {
-                    let messages =
-                        self.read_stream(id)
-                            .or(Err(ClientError::ReadConnectionError(
-                                ConnectionError::ReadConnectionError,
-                            )))?;
-                    match self.handle_server_message(messages, id) {
+                    match self.read_stream(id) {
+                        Ok(messages) => {
+                            self.connections[id].timers.message_received(Instant::now());
+                            done = self.handle_server_message(messages, id)?;
+                        }
+                        Err(ClientError::ReadConnectionError(ConnectionError::TimeoutError)) => {}
                         Err(err) => {
                             return Err(err);
                         }
-                        Ok(result) => {
-                            done = result;
-                        }
+                    }
+                    if !done {
+                        done = self.check_timers(id)?;
                     }
                 }
                 if let Err(err) = self.end(id) {
@@ -160,51 +178,50 @@ impl Server {
         Ok(())
     }
 
-    /// Funcion encargada de leer desde la conexion y transformarlo en mensaje
-    pub fn read_stream(&mut self, id: usize) -> Result<Message> {
-        let len: usize = self.read_size(id)?;
-        let mut byte_message: Vec<u8>;
-        match len {
-            0 => {
-                byte_message = vec![];
-            }
-            _ => {
-                //println!("[CONEXION {}] Por leer el mensaje",self.id);
-                byte_message = vec![0; len];
-                self.connections[id]
-                    .stream
-                    .read_exact(&mut byte_message)
-                    .or(Err(ClientError::ReadConnectionError(
-                        ConnectionError::ReadConnectionError,
-                    )))?;
-            }
+    /// Configura el timeout de lectura de la conexion y el de escritura.
+    fn set_timeouts(stream: &TcpStream, read_timeout: Duration) -> Result<()> {
+        stream
+            .set_read_timeout(Some(read_timeout))
+            .and(stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+            .or(Err(ClientError::FailToConnectError(
+                ConnectionError::FailToConnectError,
+            )))
+    }
+
+    /// Envia un keep alive si hace tiempo que no se le envia nada al peer. Devuelve true si
+    /// el peer estuvo en silencio demasiado tiempo y hay que cerrar la conexion.
+    fn check_timers(&mut self, id: usize) -> Result<bool> {
+        let now = Instant::now();
+        if self.connections[id].timers.is_idle(now) {
+            println!("[SERVER] La conexion {} dejo de responder", id);
+            return Ok(true);
         }
-        let len = len.try_into().or(Err(ClientError::InvalidMessageError))?;
-        let message = Message::new(len, byte_message).or(Err(ClientError::InvalidMessageError))?;
-        Ok(message)
+        if self.connections[id].timers.keep_alive_due(now) {
+            self.write_message(&MessageId::KeepAlive, id)?;
+        }
+        Ok(false)
     }
 
-    /// Lee el largo del mensaje, es decir los primeros 4 bytes
-    fn read_size(&mut self, id: usize) -> Result<usize> {
-        //println!("[CONEXION {}] Por leer el tamaño de del mensaje",self.id);
-        let mut byte_len: [u8; LEN] = [0; LEN];
-        self.connections[id]
-            .stream
-            .read_exact(&mut byte_len)
-            .or(Err(ClientError::ReadConnectionError(
-                ConnectionError::ReadConnectionError,
-            )))?;
-        let len: usize = u32::from_be_bytes(byte_len)
-            .try_into()
-            .or(Err(ClientError::InvalidMessageError))?;
-        Ok(len)
+    /// Funcion encargada de leer desde la conexion y transformarlo en mensaje
+    pub fn read_stream(&mut self, id: usize) -> Result<Message> {
+        let connection = &mut self.connections[id];
+        connection
+            .codec
+            .read_message(&mut Throttled::new(&mut connection.stream, &self.bandwidth))
+            .map_err(|error| ClientError::ReadConnectionError(error.into()))
     }
 
     /// Escribe un mensaje por la conexion
-    pub fn write_messages(&mut self, bytes: Vec<u8>, id: usize) -> Result<()> {
-        self.connections[id].stream.write_all(&bytes).or(Err(
-            ClientError::WriteConnectionError(ConnectionError::WriteConnectionError),
-        ))?;
+    pub fn write_message(&mut self, message: &MessageId, id: usize) -> Result<()> {
+        let connection = &mut self.connections[id];
+        connection
+            .codec
+            .write_message(
+                &mut Throttled::new(&mut connection.stream, &self.bandwidth),
+                message,
+            )
+            .map_err(|error| ClientError::WriteConnectionError(error.into()))?;
+        connection.timers.message_sent(Instant::now());
         Ok(())
     }
 
@@ -235,13 +252,11 @@ impl Server {
                 drop(client);
                 self.connections[id].peer.store_bitmap(bytes, num_pieces);
                 let have = self.return_have()?;
-                let message = Message::send_have(have as u32);
-                self.write_messages(message, id)?;
+                self.write_message(&MessageId::Have(have as u32), id)?;
             }
             MessageId::Unchoke => {
                 println! {"[SERVER] Recibi un Unchoke de la conexion {}!",id};
-                let choke = Message::send_choke();
-                self.write_messages(choke, id)?;
+                self.write_message(&MessageId::Choke, id)?;
                 return Ok(true);
             }
 
@@ -250,20 +265,17 @@ impl Server {
                 let mut client = self.client.lock().or(Err(ClientError::MutexLockError))?;
                 let piece_length = client.metainfo.info.piece_length;
                 let offset = piece_index * piece_length + begin;
-                let mut block = client
+                let block = client
                     .downloader
                     .upload(offset as u64, length as u64)
                     .or(Err(ClientError::UploadError))?;
                 drop(client);
-                let message = Message::send_piece(piece_index, begin, &mut block)
-                    .or(Err(ClientError::InvalidMessageError))?;
-                self.write_messages(message, id)?;
+                self.write_message(&MessageId::Piece(piece_index, begin, block), id)?;
             }
 
             MessageId::Cancel(_piece_index, _begin, _length) => {
                 println! {"[SERVER] Recibi un Cancel de la conexion {}!",id};
-                let choke = Message::send_choke();
-                self.write_messages(choke, id)?;
+                self.write_message(&MessageId::Choke, id)?;
                 return Ok(true);
             }
             MessageId::Choke => {
@@ -274,15 +286,13 @@ impl Server {
             MessageId::NotInterested => {
                 println! {"[SERVER] Recibi un NotInerested de la conexion {}!",id};
                 self.connections[id].peer.interested = true;
-                let choke = Message::send_choke();
-                self.write_messages(choke, id)?;
+                self.write_message(&MessageId::Choke, id)?;
                 return Ok(true);
             }
             MessageId::Interested => {
                 println! {"[SERVER] Recibi un Interested de la conexion {}!",id};
                 self.connections[id].peer.interested = false;
-                let unchoke = Message::send_unchoke();
-                self.write_messages(unchoke, id)?;
+                self.write_message(&MessageId::Unchoke, id)?;
             }
             _ => {
                 return Err(ClientError::InvalidMessageError);
@@ -309,6 +319,8 @@ pub struct ServerConnection {
     stream: TcpStream,
     peer: Peer,
     id: usize,
+    codec: PeerCodec,
+    timers: PeerTimers,
 }
 
 /// Funcion disparada desde un thread, establece un bind y escucha una a una las peticiones.
@@ -346,6 +358,8 @@ mod server_should {
     use crate::log::logger::Logger;
     use std::sync::mpsc;
 
+    const LEN: usize = 4;
+
     pub fn read_stream(stream: &mut TcpStream) -> Message {
         let len: usize = read_size(stream);
         let mut byte_message: Vec<u8>;
@@ -1,4 +0,0 @@
-pub mod bencode_encoder;
-pub mod bencode_parser;
-mod errors;
-pub mod url_encoder;
@@ -1,11 +1,11 @@
+pub mod bandwidth;
 pub mod bitclient;
 pub mod bitfield;
 pub mod downloads;
-pub mod encoder;
 pub mod log;
 pub mod peer_connection;
-pub mod peer_protocol;
 pub mod peers;
 pub mod pieces;
+pub mod streaming;
 pub mod torrent_file;
 pub mod tracker;
@@ -1,4 +1,5 @@
 use bittorrent::bitclient::client::{BitClient, Event};
+use bittorrent::torrent_file::creator::TorrentCreator;
 use gtk4::glib;
 use gtk4::glib::{MainContext, Receiver, Sender};
 use std::env::args;
@@ -13,8 +14,64 @@ fn descargar_torrent(config: String, torrent: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Crea un archivo .torrent a partir de los argumentos:
+/// create <archivo|directorio> <announce> [--output f] [--piece-length n] [--tier url,url]
+/// [--comment c] [--web-seed url] [--private]
+fn crear_torrent(args: &[String]) -> Result<(), String> {
+    if args.len() < 2 {
+        return Err(String::from("Cantidad de argumentos inválido"));
+    }
+    let path = args[0].trim_end_matches('/').to_string();
+    let mut creator = TorrentCreator::new(&args[1]);
+    let mut output = None;
+    let mut i = 2;
+    while i < args.len() {
+        let value = args.get(i + 1).cloned();
+        match (args[i].as_str(), value) {
+            ("--private", _) => {
+                creator.private = true;
+                i += 1;
+                continue;
+            }
+            ("--output", Some(value)) => output = Some(value),
+            ("--piece-length", Some(value)) => {
+                let length = value
+                    .parse::<u32>()
+                    .or(Err(format!("Tamaño de pieza inválido: {}", value)))?;
+                creator.piece_length = Some(length);
+            }
+            ("--tier", Some(value)) => creator
+                .announce_list
+                .push(value.split(',').map(String::from).collect()),
+            ("--comment", Some(value)) => creator.comment = Some(value),
+            ("--web-seed", Some(value)) => creator.url_list.push(value),
+            (arg, _) => return Err(format!("Argumento inválido: {}", arg)),
+        }
+        i += 2;
+    }
+    let output = output.unwrap_or_else(|| {
+        let name = path.rsplit('/').next().unwrap_or("contenido");
+        format!("{}.torrent", name)
+    });
+    let info_hash = creator
+        .create_file(&path, &output)
+        .map_err(|error| error.to_string())?;
+    println!(
+        "[INFO] Torrent creado en {} con info hash {}",
+        output,
+        hex::encode(info_hash)
+    );
+    Ok(())
+}
+
 fn main() {
     let args = args().collect::<Vec<String>>();
+    if args.len() > 1 && args[1] == "create" {
+        if let Err(error) = crear_torrent(&args[2..]) {
+            println!("[ERROR] {}", error);
+        }
+        return;
+    }
     if args.len() < 3 {
         println!("[ERROR] Cantidad de argumentos inválido");
         return;
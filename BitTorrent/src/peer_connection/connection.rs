@@ -1,20 +1,27 @@
+use crate::bandwidth::limiter::BandwidthLimiter;
+use crate::bandwidth::throttle::Throttled;
 use crate::bitclient::client::{BitClient, Event};
 use crate::peer_connection::errors::ConnectionError;
-use crate::peer_protocol::handshake::Handshake;
-use crate::peer_protocol::messages::{Message, MessageId};
+use crate::peer_connection::timers::{
+    PeerTimeouts, PeerTimers, CONNECT_TIMEOUT, HANDSHAKE_TIMEOUT, TICK, WRITE_TIMEOUT,
+};
+use crate::peers::ban::Contributor;
 use crate::peers::peer::Peer;
 use gtk4::glib::Sender as gtkSender;
+use protocol::peer_protocol::codec::{CodecLimits, PeerCodec};
+use protocol::peer_protocol::handshake::Handshake;
+use protocol::peer_protocol::messages::{Message, MessageId};
 use std::fmt::Debug;
 use std::io::Read;
 use std::io::Write;
-use std::net::{Shutdown, TcpStream};
+use std::net::{IpAddr, Shutdown, SocketAddr, TcpStream, ToSocketAddrs};
 use std::sync::mpsc::Sender;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Instant;
 
 static BLOCK_SIZE: u32 = 16384; // 2^14
 const HANDSHAKE_LEN: usize = 68;
-const LEN: usize = 4;
 /******************************************************************************************/
 /*                                 CONNECTION                                             */
 /******************************************************************************************/
@@ -33,23 +40,45 @@ pub struct Connection {
     pub event_bus: gtkSender<Event>,
     pub num_pieces: usize,
     pub bitfield: bool,
+    pub codec: PeerCodec,
+    pub timers: PeerTimers,
+    pub choked: bool,
+    pub bandwidth: Arc<BandwidthLimiter>,
 }
 
 #[allow(dead_code)]
 impl Connection {
     /// Se inicializa con un id, el peer y una referencia mutable a un lock del cliente.
     /// Ademas se encarga de realizar el handshake entre estos.
-    /// En caso de que el info hash no sea igual, cierra la conexion
-    pub fn new(id: usize, peer: Peer, client: Arc<Mutex<BitClient>>) -> Result<Connection> {
+    /// En caso de que el info hash no sea igual, cierra la conexion. Tambien la cierra si el
+    /// peer resulta ser el propio cliente, si ya hay otra conexion con el mismo peer o si el
+    /// peer esta bloqueado.
+    pub fn new(id: usize, mut peer: Peer, client: Arc<Mutex<BitClient>>) -> Result<Connection> {
         let lock = client.lock().or(Err(ConnectionError::MutexLockError))?;
+        if lock.is_banned(&peer) {
+            return Err(ConnectionError::BannedPeerError);
+        }
         let client_id = lock.peer_id.clone();
         let info_hash = lock.metainfo.info_hash.clone();
         let num_pieces = lock.metainfo.info.num_pieces;
         let log = lock.log.clone();
         let event_bus = lock.event_bus.clone();
+        let bandwidth = lock.bandwidth.clone();
         drop(lock);
 
-        let stream = Self::attempt_handshake(id, client_id, info_hash, &peer)?;
+        let (stream, peer_id) = Self::attempt_handshake(id, client_id, info_hash, &peer)?;
+        stream
+            .set_read_timeout(Some(TICK))
+            .or(Err(ConnectionError::FailToConnectError))?;
+        peer.id = peer_id;
+        let mut lock = client.lock().or(Err(ConnectionError::MutexLockError))?;
+        if lock.is_banned(&peer) {
+            return Err(ConnectionError::BannedPeerError);
+        }
+        if !lock.connected_peers.insert(peer.id.clone()) {
+            return Err(ConnectionError::DuplicatePeerError);
+        }
+        drop(lock);
         println!("[CONEXION {}] Conexion establecida!", id);
 
         Ok(Connection {
@@ -61,24 +90,44 @@ impl Connection {
             num_pieces,
             event_bus,
             bitfield: false,
+            codec: PeerCodec::new(CodecLimits::for_pieces(num_pieces)),
+            timers: PeerTimers::new(PeerTimeouts::default(), Instant::now()),
+            choked: true,
+            bandwidth,
         })
     }
 
-    /// Se connecta al peer con un tcpstream.
+    /// Se connecta al peer con un tcpstream. Las ips se parsean para soportar IPv6,
+    /// que no puede concatenarse directamente con el puerto. Si el peer no responde dentro
+    /// de `CONNECT_TIMEOUT` se abandona la conexion.
     fn connect_to_peer(peer: &Peer) -> Result<TcpStream> {
-        let message = peer.ip.clone() + ":" + &peer.port;
-        let stream = TcpStream::connect(message).or(Err(ConnectionError::FailToConnectError))?;
+        let addr = match (peer.ip.parse::<IpAddr>(), peer.port.parse::<u16>()) {
+            (Ok(ip), Ok(port)) => Some(SocketAddr::new(ip, port)),
+            _ => (peer.ip.clone() + ":" + &peer.port)
+                .to_socket_addrs()
+                .ok()
+                .and_then(|mut addrs| addrs.next()),
+        };
+        let addr = addr.ok_or(ConnectionError::FailToConnectError)?;
+        let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+            .or(Err(ConnectionError::FailToConnectError))?;
+        stream
+            .set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+            .or(Err(ConnectionError::FailToConnectError))?;
+        stream
+            .set_write_timeout(Some(WRITE_TIMEOUT))
+            .or(Err(ConnectionError::FailToConnectError))?;
         Ok(stream)
     }
 
-    /// Realiza el handshake con el otro peer .
+    /// Realiza el handshake con el otro peer. Devuelve la conexion y el id del peer.
     fn attempt_handshake(
         _id: usize,
         client_id: String,
         info_hash: Vec<u8>,
         peer: &Peer,
-    ) -> Result<TcpStream> {
-        let handshake = Handshake::new(info_hash, client_id);
+    ) -> Result<(TcpStream, String)> {
+        let handshake = Handshake::new(info_hash, client_id.clone());
         let request = handshake.as_bytes();
         let mut stream = Self::connect_to_peer(peer)?;
         stream
@@ -90,8 +139,11 @@ impl Connection {
             .or(Err(ConnectionError::ReadConnectionError))?;
         let handshake_response = Handshake::from_bytes(buffer.to_vec())
             .or(Err(ConnectionError::InvalidUTF8HandshakeError))?;
+        if handshake_response.peer_id == client_id {
+            return Err(ConnectionError::SelfConnectionError);
+        }
         if handshake_response.info_hash == handshake.info_hash {
-            Ok(stream)
+            Ok((stream, handshake_response.peer_id))
         } else {
             Err(ConnectionError::BadPeerResponseError)
         }
@@ -99,58 +151,120 @@ impl Connection {
 
     /// Lee desde la conexion y lo traduce en un mensaje.
     pub fn read_stream(&mut self) -> Result<Message> {
-        let len: usize = self.read_size()?;
-        let mut byte_message: Vec<u8>;
-        match len {
-            0 => {
-                byte_message = vec![];
-            }
-            _ => {
-                //println!("[CONEXION {}] Por leer el mensaje",self.id);
-                byte_message = vec![0; len];
-                self.stream
-                    .read_exact(&mut byte_message)
-                    .or(Err(ConnectionError::ReadConnectionError))?;
-            }
-        }
-        let len = len.try_into().or(Err(ConnectionError::UsizeFromU32Error))?;
-        let message =
-            Message::new(len, byte_message).or(Err(ConnectionError::InvalidMessageError))?;
+        let message = self
+            .codec
+            .read_message(&mut Throttled::new(&mut self.stream, &self.bandwidth))?;
         Ok(message)
     }
 
-    /// Lee el tamaño del mensaje desde la conexion.
-    fn read_size(&mut self) -> Result<usize> {
-        //println!("[CONEXION {}] Por leer el tamaño de del mensaje",self.id);
-        let mut byte_len: [u8; LEN] = [0; LEN];
-        self.stream
-            .read_exact(&mut byte_len)
-            .or(Err(ConnectionError::ReadConnectionError))?;
-        let len: usize = u32::from_be_bytes(byte_len)
-            .try_into()
-            .or(Err(ConnectionError::UsizeFromU32Error))?;
-        Ok(len)
-    }
-
-    /// Escribe en la conexion.
-    pub fn write_messages(&mut self, bytes: Vec<u8>) -> Result<()> {
-        self.stream
-            .write_all(&bytes)
-            .or(Err(ConnectionError::WriteConnectionError))?;
+    /// Escribe un mensaje en la conexion.
+    pub fn write_message(&mut self, message: &MessageId) -> Result<()> {
+        self.codec.write_message(
+            &mut Throttled::new(&mut self.stream, &self.bandwidth),
+            message,
+        )?;
+        self.timers.message_sent(Instant::now());
         Ok(())
     }
 
     /// Funcion llamada desde afuera cuando se dispara un thread.
-    /// Inicializa la conexion y se queda leyendo.
+    /// Inicializa la conexion y se queda leyendo. Al terminar, por el motivo que sea, los
+    /// bloques pedidos al peer que no llegaron vuelven a quedar disponibles.
     pub fn connect(id: usize, peer: Peer, client: Arc<Mutex<BitClient>>) -> Result<bool> {
         let mut connection = Connection::new(id, peer, client)?;
-        let mut done = false;
-        while !done {
-            let messages = connection.read_stream()?;
-            done = connection.handle_message(messages)?;
+        let result = connection.listen();
+        connection.release()?;
+        result
+    }
+
+    /// Devuelve los bloques pendientes y libera al peer para que pueda volver a conectarse.
+    fn release(&mut self) -> Result<()> {
+        let pending = self.timers.pending_requests();
+        self.requeue(pending)?;
+        let mut client = self
+            .client
+            .lock()
+            .or(Err(ConnectionError::MutexLockError))?;
+        client.connected_peers.remove(&self.peer.id);
+        drop(client);
+        Ok(())
+    }
+
+    /// Lee y atiende los mensajes del peer hasta terminar la descarga. Cada vez que llega
+    /// un mensaje o pasa un `TICK` sin recibir nada se revisan los timers de la conexion.
+    fn listen(&mut self) -> Result<bool> {
+        loop {
+            match self.read_stream() {
+                Ok(message) => {
+                    self.timers.message_received(Instant::now());
+                    if self.handle_message(message)? {
+                        return Ok(true);
+                    }
+                }
+                Err(ConnectionError::TimeoutError) => {}
+                Err(error) => return Err(error),
+            }
+            if self.check_timers()? {
+                return Ok(true);
+            }
         }
+    }
 
-        Ok(true)
+    /// Envia un keep alive si hace tiempo que no se envia nada, cierra la conexion si el
+    /// peer esta en silencio hace demasiado y vuelve a encolar los bloques vencidos.
+    /// Devuelve true si, mientras tanto, otras conexiones completaron la descarga.
+    fn check_timers(&mut self) -> Result<bool> {
+        let client = self
+            .client
+            .lock()
+            .or(Err(ConnectionError::MutexLockError))?;
+        let finished = client.download_finished();
+        let banned = client.is_banned(&self.peer);
+        drop(client);
+        if finished {
+            return Ok(true);
+        }
+        if banned {
+            return Err(ConnectionError::BannedPeerError);
+        }
+        let now = Instant::now();
+        if self.timers.is_idle(now) {
+            println!("[CONEXION {}] El peer dejo de responder", self.id);
+            return Err(ConnectionError::IdlePeerError);
+        }
+        let expired = self.timers.expired_requests(now);
+        if !expired.is_empty() {
+            println!(
+                "[CONEXION {}] Vencieron {} pedidos, vuelven a la cola",
+                self.id,
+                expired.len()
+            );
+            self.requeue(expired)?;
+            if !self.choked {
+                self.request_next_block()?;
+            }
+        }
+        if self.timers.keep_alive_due(now) {
+            self.write_message(&MessageId::KeepAlive)?;
+        }
+        Ok(false)
+    }
+
+    /// Devuelve al cliente los bloques pedidos que el peer no entrego, para que puedan
+    /// pedirse de nuevo a este u otro peer.
+    fn requeue(&mut self, blocks: Vec<(u32, u32)>) -> Result<()> {
+        if blocks.is_empty() {
+            return Ok(());
+        }
+        let mut client = self
+            .client
+            .lock()
+            .or(Err(ConnectionError::MutexLockError))?;
+        for (piece_index, block_index) in blocks {
+            client.requeue_block(piece_index, block_index);
+        }
+        drop(client);
+        Ok(())
     }
 
     /// Maneja los mensajes que recibe del cliente y le responde en base a nuestros intereses.
@@ -202,13 +316,14 @@ impl Connection {
             Some((piece_index, block_index, block_length)) => {
                 let offset = block_index * BLOCK_SIZE;
                 client.mark_as_requested(piece_index, block_index);
+                self.timers
+                    .block_requested(piece_index, block_index, Instant::now());
                 println!(
                     "[CONEXION {}] Estoy pidiendo el bloque {} de la pieza {}",
                     self.id, block_index, piece_index
                 );
-                let message = Message::send_request(piece_index, offset, block_length);
                 drop(client);
-                self.write_messages(message)?;
+                self.write_message(&MessageId::Request(piece_index, offset, block_length))?;
             }
             None => {
                 println!(
@@ -225,8 +340,7 @@ impl Connection {
         println! {"[CONEXION {}] Bitfield!",self.id};
         self.bitfield = true;
         self.peer.store_bitmap(bytes, self.num_pieces);
-        let message = Message::send_interested();
-        self.write_messages(message)?;
+        self.write_message(&MessageId::Interested)?;
         Ok(())
     }
 
@@ -242,14 +356,14 @@ impl Connection {
             .or(Err(ConnectionError::MutexLockError))?;
         client.peer.bitfield[index as usize] = true;
         drop(client);
-        let message = Message::send_interested();
-        self.write_messages(message)?;
+        self.write_message(&MessageId::Interested)?;
         Ok(())
     }
 
     /// Maneja el mensaje en caso de recibir un unchoke.
     fn handle_unchoke(&mut self) -> Result<()> {
         println! {"[CONEXION {}] Unchoke!",self.id};
+        self.choked = false;
         if !self.bitfield {
             self.peer.bitfield = vec![true; self.num_pieces];
         }
@@ -268,7 +382,7 @@ impl Connection {
     }
 
     /// Maneja el mensaje en caso de recibir un piece.
-    fn handle_piece(&self, piece_index: u32, offset: u32, data: Vec<u8>) -> Result<bool> {
+    fn handle_piece(&mut self, piece_index: u32, offset: u32, data: Vec<u8>) -> Result<bool> {
         println!(
             "[CONEXION {}] Recibi una pieza: {}, offset: {}",
             self.id, piece_index, offset
@@ -278,16 +392,30 @@ impl Connection {
             .lock()
             .or(Err(ConnectionError::MutexLockError))?;
         let block_index = offset / BLOCK_SIZE;
+        self.timers.block_received(piece_index, block_index);
+        let source = Contributor::new(self.peer.ip.clone(), self.peer.id.clone());
         let completed = client
-            .store(piece_index, block_index, data)
+            .store(piece_index, block_index, data, source)
             .or(Err(ConnectionError::MutexLockError))?;
+        let banned = client.is_banned(&self.peer);
         drop(client);
+        if banned {
+            println!(
+                "[CONEXION {}] El peer fue bloqueado, cierro la conexion",
+                self.id
+            );
+            return Err(ConnectionError::BannedPeerError);
+        }
         Ok(completed)
     }
 
-    /// Maneja el mensaje en caso de recibir un choke.
-    fn handle_choke(&self) -> Result<()> {
+    /// Maneja el mensaje en caso de recibir un choke. El peer descarta los pedidos que
+    /// tenia pendientes, asi que vuelven a la cola.
+    fn handle_choke(&mut self) -> Result<()> {
         println!("[CONEXION {}] Choked u.u", self.id);
+        self.choked = true;
+        let pending = self.timers.pending_requests();
+        self.requeue(pending)?;
         let mut client = self
             .client
             .lock()
@@ -1,3 +1,4 @@
+use protocol::peer_protocol::errors::PeerProtocolError;
 use std::fmt;
 
 /******************************************************************************************/
@@ -18,6 +19,12 @@ pub enum ConnectionError {
     MutexLockError,
     StorageError,
     UploadError,
+    MessageTooLongError,
+    TimeoutError,
+    IdlePeerError,
+    SelfConnectionError,
+    DuplicatePeerError,
+    BannedPeerError,
 }
 
 #[allow(dead_code)]
@@ -52,6 +59,38 @@ impl fmt::Display for ConnectionError {
             ConnectionError::UploadError => {
                 write!(f, "Fallo al querer uploadear una pieza")
             }
+            ConnectionError::MessageTooLongError => {
+                write!(f, "El peer envio un mensaje demasiado largo")
+            }
+            ConnectionError::TimeoutError => {
+                write!(f, "Se agoto el tiempo de espera del peer")
+            }
+            ConnectionError::IdlePeerError => {
+                write!(f, "El peer no envio mensajes dentro del tiempo limite")
+            }
+            ConnectionError::SelfConnectionError => {
+                write!(f, "El peer es el propio cliente")
+            }
+            ConnectionError::DuplicatePeerError => {
+                write!(f, "Ya hay una conexion abierta con el peer")
+            }
+            ConnectionError::BannedPeerError => {
+                write!(f, "El peer esta bloqueado por enviar piezas corruptas")
+            }
+        }
+    }
+}
+
+impl From<PeerProtocolError> for ConnectionError {
+    fn from(error: PeerProtocolError) -> Self {
+        match error {
+            PeerProtocolError::ConnectionClosedError | PeerProtocolError::ReadConnectionError => {
+                ConnectionError::ReadConnectionError
+            }
+            PeerProtocolError::WriteConnectionError => ConnectionError::WriteConnectionError,
+            PeerProtocolError::MessageTooLongError => ConnectionError::MessageTooLongError,
+            PeerProtocolError::TimeoutError => ConnectionError::TimeoutError,
+            _ => ConnectionError::InvalidMessageError,
         }
     }
 }
@@ -1,2 +1,3 @@
 pub mod connection;
 pub(crate) mod errors;
+pub mod timers;
@@ -0,0 +1,159 @@
+use std::time::{Duration, Instant};
+
+/******************************************************************************************/
+/*                                    PEER TIMERS                                         */
+/******************************************************************************************/
+
+/// Tiempo maximo para establecer la conexion TCP con un peer.
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Tiempo maximo para intercambiar el handshake.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Tiempo maximo para escribir un mensaje en la conexion.
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Cada cuanto se deja de esperar un mensaje para revisar los timers de la conexion.
+pub const TICK: Duration = Duration::from_secs(5);
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(120);
+const IDLE_TIMEOUT: Duration = Duration::from_secs(180);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Tiempos que rigen una conexion ya establecida con un peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerTimeouts {
+    /// Si no se envio nada durante este tiempo se envia un keep alive.
+    pub keep_alive: Duration,
+    /// Si el peer no envio nada durante este tiempo se cierra la conexion.
+    pub idle: Duration,
+    /// Si un bloque pedido no llega en este tiempo se vuelve a encolar.
+    pub request: Duration,
+}
+
+impl Default for PeerTimeouts {
+    fn default() -> Self {
+        PeerTimeouts {
+            keep_alive: KEEP_ALIVE_INTERVAL,
+            idle: IDLE_TIMEOUT,
+            request: REQUEST_TIMEOUT,
+        }
+    }
+}
+
+/// Lleva el registro de la actividad de una conexion: cuando se envio y recibio el ultimo
+/// mensaje y que bloques se pidieron al peer sin que llegaran todavia.
+#[derive(Debug)]
+pub struct PeerTimers {
+    timeouts: PeerTimeouts,
+    last_sent: Instant,
+    last_received: Instant,
+    requests: Vec<(u32, u32, Instant)>,
+}
+
+impl PeerTimers {
+    pub fn new(timeouts: PeerTimeouts, now: Instant) -> Self {
+        PeerTimers {
+            timeouts,
+            last_sent: now,
+            last_received: now,
+            requests: vec![],
+        }
+    }
+
+    pub fn message_sent(&mut self, now: Instant) {
+        self.last_sent = now;
+    }
+
+    pub fn message_received(&mut self, now: Instant) {
+        self.last_received = now;
+    }
+
+    /// Registra el pedido de un bloque.
+    pub fn block_requested(&mut self, piece_index: u32, block_index: u32, now: Instant) {
+        self.requests.push((piece_index, block_index, now));
+    }
+
+    /// Olvida el pedido de un bloque una vez que llego.
+    pub fn block_received(&mut self, piece_index: u32, block_index: u32) {
+        self.requests
+            .retain(|(piece, block, _time)| (*piece, *block) != (piece_index, block_index));
+    }
+
+    /// Indica si hace falta enviar un keep alive para que el peer no cierre la conexion.
+    pub fn keep_alive_due(&self, now: Instant) -> bool {
+        now.duration_since(self.last_sent) >= self.timeouts.keep_alive
+    }
+
+    /// Indica si el peer estuvo en silencio mas tiempo del permitido.
+    pub fn is_idle(&self, now: Instant) -> bool {
+        now.duration_since(self.last_received) >= self.timeouts.idle
+    }
+
+    /// Quita y devuelve los bloques pedidos que superaron el tiempo de espera.
+    pub fn expired_requests(&mut self, now: Instant) -> Vec<(u32, u32)> {
+        let timeout = self.timeouts.request;
+        let (expired, pending) = self
+            .requests
+            .iter()
+            .partition(|(_piece, _block, time)| now.duration_since(*time) >= timeout);
+        self.requests = pending;
+        expired
+            .into_iter()
+            .map(|(piece, block, _time)| (piece, block))
+            .collect()
+    }
+
+    /// Quita y devuelve todos los bloques pedidos que no llegaron.
+    pub fn pending_requests(&mut self) -> Vec<(u32, u32)> {
+        self.requests
+            .drain(..)
+            .map(|(piece, block, _time)| (piece, block))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod peer_timers_should {
+    use super::*;
+
+    fn timers(now: Instant) -> PeerTimers {
+        PeerTimers::new(PeerTimeouts::default(), now)
+    }
+
+    #[test]
+    fn ask_for_keep_alive_after_the_interval_without_sending() {
+        let start = Instant::now();
+        let mut timers = timers(start);
+
+        assert!(!timers.keep_alive_due(start + Duration::from_secs(119)));
+        assert!(timers.keep_alive_due(start + KEEP_ALIVE_INTERVAL));
+
+        timers.message_sent(start + Duration::from_secs(100));
+        assert!(!timers.keep_alive_due(start + KEEP_ALIVE_INTERVAL));
+    }
+
+    #[test]
+    fn detect_silent_peers() {
+        let start = Instant::now();
+        let mut timers = timers(start);
+
+        timers.message_received(start + Duration::from_secs(60));
+        assert!(!timers.is_idle(start + IDLE_TIMEOUT));
+        assert!(timers.is_idle(start + Duration::from_secs(60) + IDLE_TIMEOUT));
+    }
+
+    #[test]
+    fn expire_requests_that_never_arrived() {
+        let start = Instant::now();
+        let mut timers = timers(start);
+        timers.block_requested(0, 0, start);
+        timers.block_requested(0, 1, start);
+        timers.block_requested(1, 0, start + Duration::from_secs(30));
+        timers.block_received(0, 1);
+
+        assert_eq!(
+            timers.expired_requests(start + REQUEST_TIMEOUT),
+            vec![(0, 0)]
+        );
+        assert_eq!(timers.expired_requests(start + REQUEST_TIMEOUT), vec![]);
+        assert_eq!(timers.pending_requests(), vec![(1, 0)]);
+        assert_eq!(timers.pending_requests(), vec![]);
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/******************************************************************************************/
+/*                                      BAN LIST                                          */
+/******************************************************************************************/
+
+/// Cantidad de piezas corruptas atribuidas a un peer tras las cuales se lo bloquea.
+const MAX_STRIKES: u32 = 3;
+/// Tiempo durante el cual un peer bloqueado no puede volver a conectarse.
+pub const BAN_DURATION: Duration = Duration::from_secs(3600);
+
+/// Peer que envio un bloque, identificado por su ip y su id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Contributor {
+    pub ip: String,
+    pub id: String,
+}
+
+impl Contributor {
+    pub fn new(ip: String, id: String) -> Self {
+        Contributor { ip, id }
+    }
+}
+
+/// Bloque de una pieza junto con el peer que lo envio y el hash de su data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockSource {
+    pub block_index: u32,
+    pub contributor: Contributor,
+    pub hash: Vec<u8>,
+}
+
+/// Registra a que peers se atribuyen las piezas corruptas y bloquea a los responsables.
+///
+/// Si una pieza corrupta la envio un unico peer, se le suma una falta. Si la enviaron
+/// varios, todos suman una falta y se guarda el hash de cada bloque: cuando la pieza
+/// finalmente se verifica, los peers cuyos bloques no coinciden con la data correcta
+/// se bloquean de inmediato y a los demas se les perdona la falta (smart ban).
+#[derive(Debug, Default)]
+pub struct BanList {
+    strikes: HashMap<Contributor, u32>,
+    suspects: HashMap<u32, Vec<BlockSource>>,
+    banned_ips: HashMap<String, Instant>,
+    banned_ids: HashMap<String, Instant>,
+}
+
+impl BanList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registra que la pieza fallo la verificacion. Devuelve los peers que se bloquearon.
+    pub fn corrupt_piece(
+        &mut self,
+        piece_index: u32,
+        sources: Vec<BlockSource>,
+        now: Instant,
+    ) -> Vec<Contributor> {
+        let mut contributors: Vec<Contributor> = vec![];
+        for source in sources.iter() {
+            if !contributors.contains(&source.contributor) {
+                contributors.push(source.contributor.clone());
+            }
+        }
+        if contributors.len() > 1 {
+            self.suspects
+                .entry(piece_index)
+                .or_default()
+                .extend(sources);
+        }
+
+        let mut banned = vec![];
+        for contributor in contributors {
+            let strikes = self.strikes.entry(contributor.clone()).or_insert(0);
+            *strikes += 1;
+            if *strikes >= MAX_STRIKES {
+                self.ban(&contributor, now);
+                banned.push(contributor);
+            }
+        }
+        banned
+    }
+
+    /// Registra que la pieza se verifico y, si antes habia fallado con varios peers,
+    /// bloquea a los que enviaron bloques distintos a los correctos. Devuelve los peers
+    /// que se bloquearon.
+    pub fn valid_piece(
+        &mut self,
+        piece_index: u32,
+        sources: &[BlockSource],
+        now: Instant,
+    ) -> Vec<Contributor> {
+        let suspects = match self.suspects.remove(&piece_index) {
+            Some(suspects) => suspects,
+            None => return vec![],
+        };
+        let mut banned = vec![];
+        for suspect in suspects {
+            let good = sources
+                .iter()
+                .find(|source| source.block_index == suspect.block_index);
+            match good {
+                Some(good) if good.hash == suspect.hash => {
+                    if let Some(strikes) = self.strikes.get_mut(&suspect.contributor) {
+                        *strikes = strikes.saturating_sub(1);
+                    }
+                }
+                Some(_) if !banned.contains(&suspect.contributor) => {
+                    self.ban(&suspect.contributor, now);
+                    banned.push(suspect.contributor);
+                }
+                _ => {}
+            }
+        }
+        banned
+    }
+
+    /// Bloquea la ip y el id del peer durante `BAN_DURATION`.
+    pub fn ban(&mut self, contributor: &Contributor, now: Instant) {
+        let until = now + BAN_DURATION;
+        self.banned_ips.insert(contributor.ip.clone(), until);
+        if !contributor.id.is_empty() {
+            self.banned_ids.insert(contributor.id.clone(), until);
+        }
+        self.strikes.remove(contributor);
+    }
+
+    /// Indica si la ip o el id estan bloqueados. El id puede ser vacio si todavia no se conoce.
+    pub fn is_banned(&self, ip: &str, id: &str, now: Instant) -> bool {
+        let active = |until: Option<&Instant>| until.is_some_and(|until| *until > now);
+        active(self.banned_ips.get(ip)) || (!id.is_empty() && active(self.banned_ids.get(id)))
+    }
+}
+
+#[cfg(test)]
+mod ban_list_should {
+    use super::*;
+
+    fn contributor(ip: &str, id: &str) -> Contributor {
+        Contributor::new(ip.to_string(), id.to_string())
+    }
+
+    fn source(block_index: u32, contributor: &Contributor, hash: u8) -> BlockSource {
+        BlockSource {
+            block_index,
+            contributor: contributor.clone(),
+            hash: vec![hash],
+        }
+    }
+
+    #[test]
+    fn ban_a_single_contributor_after_repeated_failures() {
+        let now = Instant::now();
+        let mut bans = BanList::new();
+        let bad = contributor("10.0.0.1", "BAD");
+
+        for piece in 0..MAX_STRIKES - 1 {
+            assert!(bans
+                .corrupt_piece(piece, vec![source(0, &bad, 1)], now)
+                .is_empty());
+        }
+        assert_eq!(
+            bans.corrupt_piece(9, vec![source(0, &bad, 1)], now),
+            vec![bad.clone()]
+        );
+
+        assert!(bans.is_banned("10.0.0.1", "", now));
+        assert!(bans.is_banned("10.0.0.2", "BAD", now));
+        assert!(!bans.is_banned("10.0.0.1", "BAD", now + BAN_DURATION));
+    }
+
+    #[test]
+    fn ban_only_the_peer_that_sent_the_wrong_block() {
+        let now = Instant::now();
+        let mut bans = BanList::new();
+        let good = contributor("10.0.0.1", "GOOD");
+        let bad = contributor("10.0.0.2", "BAD");
+        let other = contributor("10.0.0.3", "OTHER");
+
+        let failed = vec![source(0, &good, 1), source(1, &bad, 2)];
+        assert!(bans.corrupt_piece(4, failed, now).is_empty());
+
+        let verified = vec![source(0, &other, 1), source(1, &other, 3)];
+        assert_eq!(bans.valid_piece(4, &verified, now), vec![bad]);
+
+        assert!(bans.is_banned("10.0.0.2", "BAD", now));
+        assert!(!bans.is_banned("10.0.0.1", "GOOD", now));
+        assert!(bans.valid_piece(4, &verified, now).is_empty());
+    }
+
+    #[test]
+    fn forgive_peers_whose_blocks_were_correct() {
+        let now = Instant::now();
+        let mut bans = BanList::new();
+        let good = contributor("10.0.0.1", "GOOD");
+        let bad = contributor("10.0.0.2", "BAD");
+
+        for piece in 0..MAX_STRIKES {
+            let failed = vec![source(0, &good, 1), source(1, &bad, 2)];
+            bans.corrupt_piece(piece, failed, now);
+            if piece < MAX_STRIKES - 1 {
+                let verified = vec![source(0, &good, 1), source(1, &good, 3)];
+                bans.valid_piece(piece, &verified, now);
+            }
+        }
+
+        assert!(!bans.is_banned("10.0.0.1", "GOOD", now));
+        assert!(bans.is_banned("10.0.0.2", "BAD", now));
+    }
+}
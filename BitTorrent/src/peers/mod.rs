@@ -1,2 +1,4 @@
+pub mod ban;
 mod errors;
 pub mod peer;
+pub mod pool;
@@ -0,0 +1,217 @@
+use crate::peers::peer::Peer;
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+/******************************************************************************************/
+/*                                     PEER POOL                                          */
+/******************************************************************************************/
+
+/// Espera antes del primer reintento a un peer que fallo. Se duplica con cada fallo.
+const BASE_BACKOFF: Duration = Duration::from_secs(15);
+const MAX_BACKOFF: Duration = Duration::from_secs(600);
+/// Cantidad de fallos seguidos tras los cuales se descarta el peer.
+const MAX_FAILURES: u32 = 6;
+
+/// Peer candidato a conectarse, con su historial de fallos.
+#[derive(Debug, Clone)]
+struct Candidate {
+    peer: Peer,
+    failures: u32,
+    /// Momento a partir del cual puede reintentarse. Si no hay, puede conectarse ya.
+    retry_at: Option<Instant>,
+    connected: bool,
+}
+
+/// Conjunto de peers candidatos de un torrent, sin importar de donde se obtuvieron.
+/// Los peers se identifican por su direccion y, si se conoce, por su id. Nunca se agrega
+/// el propio cliente y los peers que fallan se reintentan con backoff exponencial.
+#[derive(Debug)]
+pub struct PeerPool {
+    own_id: String,
+    own_port: String,
+    candidates: Vec<Candidate>,
+    banned: HashSet<String>,
+}
+
+impl PeerPool {
+    pub fn new(own_id: String, own_port: String) -> Self {
+        PeerPool {
+            own_id,
+            own_port,
+            candidates: vec![],
+            banned: HashSet::new(),
+        }
+    }
+
+    /// Direccion normalizada del peer, que lo identifica dentro del pool.
+    pub fn address(peer: &Peer) -> String {
+        match (peer.ip.parse::<IpAddr>(), peer.port.parse::<u16>()) {
+            (Ok(ip), Ok(port)) => SocketAddr::new(ip, port).to_string(),
+            _ => peer.ip.clone() + ":" + &peer.port,
+        }
+    }
+
+    /// Indica si el peer es el propio cliente: tiene nuestro id o escucha en nuestro puerto
+    /// de una direccion local.
+    fn is_own(&self, peer: &Peer) -> bool {
+        if !peer.id.is_empty() && peer.id == self.own_id {
+            return true;
+        }
+        match peer.ip.parse::<IpAddr>() {
+            Ok(ip) => (ip.is_loopback() || ip.is_unspecified()) && peer.port == self.own_port,
+            Err(_) => false,
+        }
+    }
+
+    /// Agrega el peer si no es el propio cliente, no fue descartado y no hay otro con la misma
+    /// direccion o id. Devuelve true si se agrego.
+    pub fn add(&mut self, peer: Peer) -> bool {
+        let address = Self::address(&peer);
+        if self.is_own(&peer) || self.banned.contains(&address) {
+            return false;
+        }
+        let duplicated = self.candidates.iter().any(|candidate| {
+            Self::address(&candidate.peer) == address
+                || (!peer.id.is_empty() && candidate.peer.id == peer.id)
+        });
+        if duplicated {
+            return false;
+        }
+        self.candidates.push(Candidate {
+            peer,
+            failures: 0,
+            retry_at: None,
+            connected: false,
+        });
+        true
+    }
+
+    /// Devuelve el proximo peer al que se puede conectar y lo marca como conectado.
+    pub fn next_ready(&mut self, now: Instant) -> Option<Peer> {
+        let candidate = self.candidates.iter_mut().find(|candidate| {
+            !candidate.connected && candidate.retry_at.is_none_or(|retry_at| retry_at <= now)
+        })?;
+        candidate.connected = true;
+        Some(candidate.peer.clone())
+    }
+
+    /// Registra que la conexion con el peer fallo. Se reintentara con una espera que se
+    /// duplica en cada fallo, hasta descartarlo tras `MAX_FAILURES` fallos seguidos.
+    pub fn failed(&mut self, address: &str, now: Instant) {
+        if let Some(position) = self.position(address) {
+            let candidate = &mut self.candidates[position];
+            candidate.failures += 1;
+            if candidate.failures >= MAX_FAILURES {
+                self.candidates.remove(position);
+                return;
+            }
+            let backoff = BASE_BACKOFF * 2u32.pow(candidate.failures - 1);
+            candidate.retry_at = Some(now + backoff.min(MAX_BACKOFF));
+            candidate.connected = false;
+        }
+    }
+
+    /// Registra que la conexion con el peer termino sin errores.
+    pub fn finished(&mut self, address: &str) {
+        if let Some(position) = self.position(address) {
+            let candidate = &mut self.candidates[position];
+            candidate.failures = 0;
+            candidate.retry_at = None;
+            candidate.connected = false;
+        }
+    }
+
+    /// Descarta el peer para siempre, por ejemplo si resulto ser el propio cliente.
+    pub fn ban(&mut self, address: &str) {
+        if let Some(position) = self.position(address) {
+            self.candidates.remove(position);
+        }
+        self.banned.insert(address.to_string());
+    }
+
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Indica si no queda ningun peer al que conectarse, ni ahora ni mas adelante.
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    fn position(&self, address: &str) -> Option<usize> {
+        self.candidates
+            .iter()
+            .position(|candidate| Self::address(&candidate.peer) == address)
+    }
+}
+
+#[cfg(test)]
+mod peer_pool_should {
+    use super::*;
+
+    fn peer(id: &str, ip: &str, port: &str) -> Peer {
+        Peer::new(id.to_string(), ip.to_string(), port.to_string())
+    }
+
+    fn pool() -> PeerPool {
+        PeerPool::new("ourselves-0000000000".to_string(), "6881".to_string())
+    }
+
+    #[test]
+    fn deduplicate_by_address_and_id() {
+        let mut pool = pool();
+
+        assert!(pool.add(peer("", "10.0.0.1", "6881")));
+        assert!(!pool.add(peer("A", "10.0.0.1", "6881")));
+        assert!(pool.add(peer("B", "10.0.0.2", "6881")));
+        assert!(!pool.add(peer("B", "10.0.0.3", "6881")));
+        assert!(pool.add(peer("", "0:0:0:0:0:0:0:1", "7000")));
+        assert!(!pool.add(peer("", "::1", "7000")));
+        assert_eq!(pool.len(), 3);
+    }
+
+    #[test]
+    fn never_add_ourselves() {
+        let mut pool = pool();
+
+        assert!(!pool.add(peer("ourselves-0000000000", "10.0.0.1", "6881")));
+        assert!(!pool.add(peer("", "127.0.0.1", "6881")));
+        assert!(pool.add(peer("", "127.0.0.1", "6882")));
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn retry_failed_peers_with_exponential_backoff() {
+        let mut pool = pool();
+        let start = Instant::now();
+        pool.add(peer("", "10.0.0.1", "6881"));
+        let address = "10.0.0.1:6881";
+
+        assert!(pool.next_ready(start).is_some());
+        assert!(pool.next_ready(start).is_none());
+
+        pool.failed(address, start);
+        assert!(pool.next_ready(start + BASE_BACKOFF / 2).is_none());
+        assert!(pool.next_ready(start + BASE_BACKOFF).is_some());
+
+        pool.failed(address, start);
+        assert!(pool.next_ready(start + BASE_BACKOFF).is_none());
+        assert!(pool.next_ready(start + BASE_BACKOFF * 2).is_some());
+
+        for _ in 2..MAX_FAILURES {
+            pool.failed(address, start);
+        }
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn forget_banned_peers() {
+        let mut pool = pool();
+        pool.add(peer("", "10.0.0.1", "6881"));
+        pool.ban("10.0.0.1:6881");
+
+        assert!(pool.is_empty());
+        assert!(!pool.add(peer("", "10.0.0.1", "6881")));
+    }
+}
@@ -1,9 +1,12 @@
+use crate::peers::ban::Contributor;
+
 /******************************************************************************************/
 /*                                       BLOCK                                           */
 /******************************************************************************************/
 
 /// Estructura que modela a un bloque.
-/// Tiene un indice, un tamaño, la data corespondiente y un bool que indica si ya fue pedido o no.
+/// Tiene un indice, un tamaño, la data corespondiente, un bool que indica si ya fue pedido o no
+/// y el peer que envio la data.
 #[derive(Debug, PartialEq)]
 #[allow(dead_code)]
 pub struct Block {
@@ -11,6 +14,7 @@ pub struct Block {
     pub index: u32,
     pub length: u32,
     pub data: Vec<u8>,
+    pub source: Option<Contributor>,
 }
 
 #[allow(dead_code)]
@@ -21,6 +25,7 @@ impl Block {
             length,
             data: vec![],
             requested: false,
+            source: None,
         }
     }
 }
@@ -39,6 +44,7 @@ mod block_should {
                 length: 16,
                 data: vec![],
                 requested: false,
+                source: None,
             }
         );
     }
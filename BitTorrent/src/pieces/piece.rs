@@ -1,9 +1,10 @@
 use super::errors::PiecesError;
 use crate::bitclient::client::Event;
 use crate::downloads::downloader::Downloader;
+use crate::peers::ban::{BlockSource, Contributor};
 use crate::pieces::block::Block;
-use crate::torrent_file::metainfo::MetaInfo;
 use gtk4::glib::Sender as gtkSender;
+use protocol::torrent_file::metainfo::MetaInfo;
 use std::sync::mpsc::Sender;
 use std::time::Instant;
 
@@ -11,6 +12,15 @@ use std::time::Instant;
 /*                                       PIECE                                          */
 /******************************************************************************************/
 
+/// Resultado de almacenar un bloque. Si se completo la pieza, indica si su hash fue
+/// correcto junto con el peer que envio cada bloque y el hash de su data.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verification {
+    Incomplete,
+    Valid(Vec<BlockSource>),
+    Corrupt(Vec<BlockSource>),
+}
+
 /// Estructura que modela la pieza.
 /// Tiene un largo, indice, vector de bloques, hash de la pieza y un bool que indica sis se completo.
 #[derive(Debug, PartialEq)]
@@ -65,16 +75,20 @@ impl Piece {
     /// En caso de que se complete la pieza, se verificara si el hash es correcto.
     /// En caso de que sea correcto, se unifica la data de todos los bloques y se llamara a downloader para que la almacene en el archivo.
     /// Si no coinciden los hashes, se marca la pieza como corrupta, se borra toda la data almacenada en los bloques y no se descarga la pieza.
+    /// Se registra que peer envio cada bloque para poder atribuirle las piezas corruptas.
     pub fn store(
         &mut self,
         downloader: &mut Downloader,
         block_index: u32,
         data: Vec<u8>,
+        source: Contributor,
         log: Sender<String>,
         event_bus: gtkSender<Event>,
-    ) -> Result<(), PiecesError> {
+    ) -> Result<Verification, PiecesError> {
         let start = Instant::now();
-        self.blocks[block_index as usize].data = data;
+        let block = &mut self.blocks[block_index as usize];
+        block.data = data;
+        block.source = Some(source);
 
         if self.have_all_blocks() {
             // concatenate data from blocks together
@@ -104,6 +118,7 @@ impl Piece {
                 downloader
                     .download(data, offset)
                     .or(Err(PiecesError::DownloadingError))?;
+                let sources = self.block_sources();
                 self.clear_block_data();
 
                 let duration = start.elapsed().as_secs_f64();
@@ -113,6 +128,7 @@ impl Piece {
                     .or(Err(PiecesError::DownloadingError))?;
 
                 self.is_complete = true;
+                return Ok(Verification::Valid(sources));
             } else {
                 let string = "[INFO] La pieza ".to_owned()
                     + &self.index.to_string()
@@ -122,10 +138,26 @@ impl Piece {
                 println!("La Pieza esta corrupta, borro la data descargada!");
                 println!("Esperaba {:?}", self.hash);
                 println!("Obtuve {:?}", hashed_data);
+                let sources = self.block_sources();
                 self.clear_block_data();
+                return Ok(Verification::Corrupt(sources));
             }
         }
-        Ok(())
+        Ok(Verification::Incomplete)
+    }
+
+    /// Devuelve el peer que envio cada bloque junto con el hash de su data.
+    fn block_sources(&self) -> Vec<BlockSource> {
+        self.blocks
+            .iter()
+            .filter_map(|block| {
+                block.source.clone().map(|contributor| BlockSource {
+                    block_index: block.index,
+                    contributor,
+                    hash: MetaInfo::hashing(&block.data),
+                })
+            })
+            .collect()
     }
 
     /// Busca en su vector de bloques cual es el proximo bloque necesario a pedir.
@@ -159,6 +191,7 @@ impl Piece {
         for block in self.blocks.iter_mut() {
             block.data = vec![];
             block.requested = false;
+            block.source = None;
         }
     }
 
@@ -166,6 +199,14 @@ impl Piece {
     pub fn mark_as_requested(&mut self, block_index: u32) {
         self.blocks[block_index as usize].requested = true;
     }
+
+    /// Marca el bloque como no solicitado, si todavia no llego su data.
+    pub fn mark_as_not_requested(&mut self, block_index: u32) {
+        let block = &mut self.blocks[block_index as usize];
+        if block.data.is_empty() {
+            block.requested = false;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -205,7 +246,8 @@ mod piece_should {
                 index: 0,
                 length: 256,
                 data: vec![],
-                requested: false
+                requested: false,
+                source: None,
             })
         );
 
@@ -224,6 +266,7 @@ mod piece_should {
                 length: 12,
                 data: vec![1, 3, 4],
                 requested: false,
+                source: None,
             }],
             hash: vec![1, 2, 3],
             is_complete: true,
@@ -242,6 +285,7 @@ mod piece_should {
                 length: 12,
                 data: vec![],
                 requested: false,
+                source: None,
             }],
             hash: vec![1, 2, 3],
             is_complete: false,
@@ -260,6 +304,7 @@ mod piece_should {
                 length: 12,
                 data: vec![1, 2],
                 requested: false,
+                source: None,
             }],
             hash: vec![1, 2, 3],
             is_complete: false,
@@ -272,10 +317,21 @@ mod piece_should {
                 index: 12,
                 length: 12,
                 data: vec![],
-                requested: false
+                requested: false,
+                source: None,
             }]
         );
     }
+
+    #[test]
+    fn requeue_requested_block() {
+        let mut p = Piece::new(256, 4, 4, vec![1, 2, 3], BLOCK_SIZE);
+        p.mark_as_requested(0);
+        assert_eq!(p.next_block_to_request(), None);
+
+        p.mark_as_not_requested(0);
+        assert_eq!(p.next_block_to_request().map(|block| block.index), Some(0));
+    }
     /*
        #[test]
        fn download_piece() {
@@ -0,0 +1,40 @@
+use std::fmt;
+
+/******************************************************************************************/
+/*                                 STREAMING ERROR                                        */
+/******************************************************************************************/
+
+#[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
+#[allow(dead_code)]
+pub enum StreamingError {
+    TcpBindError,
+    ReadConnectionError,
+    WriteConnectionError,
+    InvalidRequestError,
+    InvalidRangeError,
+    MutexLockError,
+    UploadError,
+    PieceTimeoutError,
+}
+
+impl fmt::Display for StreamingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StreamingError::TcpBindError => {
+                write!(f, "No se pudo iniciar el servidor de streaming")
+            }
+            StreamingError::ReadConnectionError => write!(f, "No se pudo leer en la conexion"),
+            StreamingError::WriteConnectionError => {
+                write!(f, "No se pudo escribir en la conexion")
+            }
+            StreamingError::InvalidRequestError => write!(f, "La request HTTP es invalida"),
+            StreamingError::InvalidRangeError => write!(f, "El rango pedido es invalido"),
+            StreamingError::MutexLockError => write!(f, "Fallo al tratar de obtener el lock"),
+            StreamingError::UploadError => write!(f, "Fallo al leer los datos del archivo"),
+            StreamingError::PieceTimeoutError => {
+                write!(f, "Se agoto el tiempo de espera de una pieza")
+            }
+        }
+    }
+}
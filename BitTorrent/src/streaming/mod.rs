@@ -0,0 +1,3 @@
+pub(crate) mod errors;
+pub mod sequential;
+pub mod stream_server;
@@ -0,0 +1,205 @@
+use crate::pieces::piece::Piece;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/******************************************************************************************/
+/*                                 SEQUENTIAL WINDOW                                      */
+/******************************************************************************************/
+
+pub const DEFAULT_WINDOW_SIZE: u32 = 8;
+pub const DEFAULT_DEADLINE: u64 = 10;
+
+/// Estructura que modela la ventana deslizante de la descarga secuencial.
+/// La ventana son las proximas `size` piezas incompletas a partir de `position`, y junto con las
+/// piezas priorizadas forman las piezas urgentes. Si un bloque urgente no llega antes de `deadline`
+/// se vuelve a pedir a otro peer.
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct SequentialWindow {
+    pub enabled: bool,
+    pub size: u32,
+    pub deadline: Duration,
+    pub position: u32,
+    pub priority: Vec<u32>,
+    requested_at: HashMap<(u32, u32), Instant>,
+}
+
+#[allow(dead_code)]
+impl SequentialWindow {
+    pub fn new(enabled: bool, size: u32, deadline: Duration) -> Self {
+        SequentialWindow {
+            enabled,
+            size,
+            deadline,
+            position: 0,
+            priority: vec![],
+            requested_at: HashMap::new(),
+        }
+    }
+
+    /// Prioriza las piezas recibidas y mueve la ventana a la primera pieza priorizada.
+    /// Como la posicion depende de todas las piezas priorizadas y no de las ultimas
+    /// recibidas, varios pedidos simultaneos no la mueven de un lado a otro.
+    pub fn boost(&mut self, pieces: &[u32]) {
+        for index in pieces {
+            if !self.priority.contains(index) {
+                self.priority.push(*index);
+            }
+        }
+        if let Some(first) = self.priority.iter().min() {
+            self.position = *first;
+        }
+    }
+
+    /// Devuelve las piezas urgentes: primero las priorizadas y luego la ventana, si esta habilitada.
+    pub fn urgent_pieces(&self, pieces: &[Piece]) -> Vec<u32> {
+        let mut urgent: Vec<u32> = self
+            .priority
+            .iter()
+            .filter(|index| Self::is_incomplete(pieces, **index))
+            .copied()
+            .collect();
+        if !self.enabled {
+            return urgent;
+        }
+        let mut in_window = 0;
+        for index in self.sequential_order(pieces.len() as u32) {
+            if in_window >= self.size {
+                break;
+            }
+            if Self::is_incomplete(pieces, index) {
+                in_window += 1;
+                if !urgent.contains(&index) {
+                    urgent.push(index);
+                }
+            }
+        }
+        urgent
+    }
+
+    /// Devuelve el orden en el que deben recorrerse las piezas que no son urgentes.
+    /// En modo secuencial se recorren desde la posicion actual, volviendo al principio al final.
+    pub fn pieces_order(&self, num_pieces: u32) -> Vec<u32> {
+        if self.enabled {
+            self.sequential_order(num_pieces)
+        } else {
+            (0..num_pieces).collect()
+        }
+    }
+
+    fn sequential_order(&self, num_pieces: u32) -> Vec<u32> {
+        let position = self.position.min(num_pieces);
+        (position..num_pieces).chain(0..position).collect()
+    }
+
+    fn is_incomplete(pieces: &[Piece], index: u32) -> bool {
+        match pieces.get(index as usize) {
+            Some(piece) => !piece.is_complete,
+            None => false,
+        }
+    }
+
+    /// Registra el momento en el que se pidio el bloque.
+    pub fn mark_as_requested(&mut self, piece_index: u32, block_index: u32) {
+        self.requested_at
+            .insert((piece_index, block_index), Instant::now());
+    }
+
+    /// Olvida el pedido del bloque una vez que llego su data.
+    pub fn mark_as_received(&mut self, piece_index: u32, block_index: u32) {
+        self.requested_at.remove(&(piece_index, block_index));
+    }
+
+    /// Olvida el pedido de un bloque que no llego, para que vuelva a pedirse.
+    pub fn cancel_request(&mut self, piece_index: u32, block_index: u32) {
+        self.requested_at.remove(&(piece_index, block_index));
+    }
+
+    /// Olvida la pieza una vez que fue verificada.
+    pub fn mark_as_complete(&mut self, piece_index: u32) {
+        self.priority.retain(|index| *index != piece_index);
+        self.requested_at
+            .retain(|(index, _block), _time| *index != piece_index);
+    }
+
+    /// Verifica si un bloque pedido supero el deadline sin llegar.
+    pub fn is_overdue(&self, piece_index: u32, block_index: u32) -> bool {
+        match self.requested_at.get(&(piece_index, block_index)) {
+            Some(time) => time.elapsed() >= self.deadline,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod sequential_window_should {
+    use super::*;
+
+    static BLOCK_SIZE: u32 = 16384; // 2^14
+
+    fn generate_pieces(num_pieces: u32) -> Vec<Piece> {
+        (0..num_pieces)
+            .map(|index| Piece::new(BLOCK_SIZE, index, BLOCK_SIZE, vec![], BLOCK_SIZE))
+            .collect()
+    }
+
+    #[test]
+    fn keep_index_order_when_disabled() {
+        let window = SequentialWindow::new(false, 2, Duration::from_secs(DEFAULT_DEADLINE));
+        assert_eq!(window.pieces_order(4), vec![0, 1, 2, 3]);
+        assert_eq!(window.urgent_pieces(&generate_pieces(4)), vec![]);
+    }
+
+    #[test]
+    fn slide_over_complete_pieces() {
+        let mut pieces = generate_pieces(6);
+        let mut window = SequentialWindow::new(true, 2, Duration::from_secs(DEFAULT_DEADLINE));
+        window.position = 1;
+        pieces[1].is_complete = true;
+
+        assert_eq!(window.urgent_pieces(&pieces), vec![2, 3]);
+        assert_eq!(window.pieces_order(6), vec![1, 2, 3, 4, 5, 0]);
+    }
+
+    #[test]
+    fn put_boosted_pieces_first() {
+        let pieces = generate_pieces(6);
+        let mut window = SequentialWindow::new(true, 2, Duration::from_secs(DEFAULT_DEADLINE));
+        window.boost(&[4, 5]);
+
+        assert_eq!(window.position, 4);
+        assert_eq!(window.urgent_pieces(&pieces), vec![4, 5]);
+
+        window.mark_as_complete(4);
+        assert_eq!(window.priority, vec![5]);
+    }
+
+    #[test]
+    fn keep_the_position_with_concurrent_boosts() {
+        let mut window = SequentialWindow::new(true, 2, Duration::from_secs(DEFAULT_DEADLINE));
+        window.boost(&[1, 2]);
+        window.boost(&[7, 8]);
+        assert_eq!(window.position, 1);
+
+        window.boost(&[1, 2]);
+        window.boost(&[7, 8]);
+        assert_eq!(window.position, 1);
+
+        window.mark_as_complete(1);
+        window.mark_as_complete(2);
+        window.boost(&[7, 8]);
+        assert_eq!(window.position, 7);
+    }
+
+    #[test]
+    fn detect_overdue_blocks() {
+        let mut window = SequentialWindow::new(true, 2, Duration::from_secs(0));
+        assert!(!window.is_overdue(0, 0));
+
+        window.mark_as_requested(0, 0);
+        assert!(window.is_overdue(0, 0));
+
+        window.mark_as_received(0, 0);
+        assert!(!window.is_overdue(0, 0));
+    }
+}
@@ -0,0 +1,431 @@
+use crate::bitclient::client::BitClient;
+use crate::bitclient::errors::ClientError;
+use crate::streaming::errors::StreamingError;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/******************************************************************************************/
+/*                                 STREAM SERVER                                          */
+/******************************************************************************************/
+
+const HOST: &str = "127.0.0.1";
+const MAX_HEADER_LEN: usize = 8192;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const PIECE_TIMEOUT: Duration = Duration::from_secs(300);
+
+type Result<T> = std::result::Result<T, StreamingError>;
+
+/// Rango de bytes pedido por el header Range, ambos extremos inclusive.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// Parsea el valor del header Range (bytes=inicio-fin, bytes=inicio- o bytes=-sufijo)
+    /// para un archivo de tamaño size. Solo se soporta un unico rango.
+    pub fn parse(value: &str, size: u64) -> Result<ByteRange> {
+        let range = value
+            .trim()
+            .strip_prefix("bytes=")
+            .ok_or(StreamingError::InvalidRangeError)?;
+        if range.contains(',') || size == 0 {
+            return Err(StreamingError::InvalidRangeError);
+        }
+        let (start, end) = range
+            .split_once('-')
+            .ok_or(StreamingError::InvalidRangeError)?;
+        let (start, end) = match (start.trim(), end.trim()) {
+            ("", "") => return Err(StreamingError::InvalidRangeError),
+            ("", suffix) => {
+                let suffix = Self::parse_number(suffix)?;
+                if suffix == 0 {
+                    return Err(StreamingError::InvalidRangeError);
+                }
+                (size.saturating_sub(suffix), size - 1)
+            }
+            (start, "") => (Self::parse_number(start)?, size - 1),
+            (start, end) => (
+                Self::parse_number(start)?,
+                Self::parse_number(end)?.min(size - 1),
+            ),
+        };
+        if start > end || start >= size {
+            return Err(StreamingError::InvalidRangeError);
+        }
+        Ok(ByteRange { start, end })
+    }
+
+    fn parse_number(number: &str) -> Result<u64> {
+        number
+            .parse::<u64>()
+            .or(Err(StreamingError::InvalidRangeError))
+    }
+
+    /// Cantidad de bytes del rango. Es cero si el rango se armo con `start` mayor a `end`.
+    pub fn len(&self) -> u64 {
+        self.end
+            .checked_sub(self.start)
+            .map_or(0, |difference| difference + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Request HTTP recibida por el servidor de streaming.
+#[derive(Debug, PartialEq, Eq)]
+pub struct StreamRequest {
+    pub method: String,
+    pub path: String,
+    pub range: Option<String>,
+}
+
+impl StreamRequest {
+    /// Lee la request line y los headers hasta la linea vacia.
+    pub fn read(reader: &mut impl BufRead) -> Result<StreamRequest> {
+        let mut total = 0;
+        let mut lines = vec![];
+        loop {
+            let mut line = String::new();
+            let read = reader
+                .read_line(&mut line)
+                .or(Err(StreamingError::ReadConnectionError))?;
+            total += read;
+            if read == 0 || total > MAX_HEADER_LEN {
+                return Err(StreamingError::InvalidRequestError);
+            }
+            let line = line.trim_end().to_string();
+            if line.is_empty() {
+                break;
+            }
+            lines.push(line);
+        }
+        let request_line: Vec<&str> = lines
+            .first()
+            .ok_or(StreamingError::InvalidRequestError)?
+            .split(' ')
+            .collect();
+        if request_line.len() != 3 {
+            return Err(StreamingError::InvalidRequestError);
+        }
+        let mut range = None;
+        for header in lines.iter().skip(1) {
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("range") {
+                    range = Some(value.trim().to_string());
+                }
+            }
+        }
+        Ok(StreamRequest {
+            method: request_line[0].to_string(),
+            path: request_line[1].to_string(),
+            range,
+        })
+    }
+}
+
+/// Estructura encargada de servir por HTTP el archivo del torrent mientras se descarga.
+/// Cada lectura bloquea hasta que las piezas necesarias esten verificadas, priorizandolas en la descarga.
+#[allow(dead_code)]
+pub struct StreamServer {
+    client: Arc<Mutex<BitClient>>,
+    listener: TcpListener,
+    log: Sender<String>,
+}
+
+#[allow(dead_code)]
+impl StreamServer {
+    fn new(mutex: Arc<Mutex<BitClient>>, port: &str) -> Result<Self> {
+        let client = mutex.lock().or(Err(StreamingError::MutexLockError))?;
+        let log = client.log.clone();
+        drop(client);
+
+        let addr = HOST.to_owned() + ":" + port;
+        let listener = TcpListener::bind(addr).or(Err(StreamingError::TcpBindError))?;
+        println!("[STREAMING] Sirviendo el archivo en el puerto {}", port);
+        log.send("- [INFO] Servidor de streaming inicializado".to_string())
+            .or(Err(StreamingError::WriteConnectionError))?;
+        Ok(StreamServer {
+            client: mutex,
+            listener,
+            log,
+        })
+    }
+
+    /// Atiende una request: responde con el archivo completo o con el rango pedido.
+    pub fn attend_connection(client: Arc<Mutex<BitClient>>, stream: TcpStream) -> Result<()> {
+        let mut reader = BufReader::new(
+            stream
+                .try_clone()
+                .or(Err(StreamingError::ReadConnectionError))?,
+        );
+        let mut stream = stream;
+        let request = match StreamRequest::read(&mut reader) {
+            Ok(request) => request,
+            Err(_) => return Self::write_status(&mut stream, "400 Bad Request", &[]),
+        };
+        if request.method != "GET" && request.method != "HEAD" {
+            return Self::write_status(
+                &mut stream,
+                "405 Method Not Allowed",
+                &["Allow: GET, HEAD"],
+            );
+        }
+
+        let lock = client.lock().or(Err(StreamingError::MutexLockError))?;
+        let size = lock.metainfo.info.length;
+        let name = lock.metainfo.info.name.clone();
+        drop(lock);
+        if request.path != "/" && request.path.trim_start_matches('/') != name {
+            return Self::write_status(&mut stream, "404 Not Found", &[]);
+        }
+
+        let (status, range) = match &request.range {
+            None if size == 0 => ("200 OK", None),
+            None => (
+                "200 OK",
+                Some(ByteRange {
+                    start: 0,
+                    end: size - 1,
+                }),
+            ),
+            Some(value) => match ByteRange::parse(value, size) {
+                Ok(range) => ("206 Partial Content", Some(range)),
+                Err(_) => {
+                    let content_range = format!("Content-Range: bytes */{}", size);
+                    return Self::write_status(
+                        &mut stream,
+                        "416 Range Not Satisfiable",
+                        &[&content_range],
+                    );
+                }
+            },
+        };
+
+        let length = range.map(|range| range.len()).unwrap_or(0);
+        let mut headers = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\nConnection: close\r\n",
+            status,
+            Self::content_type(&name),
+            length
+        );
+        if let (Some(range), true) = (range, request.range.is_some()) {
+            headers.push_str(&format!(
+                "Content-Range: bytes {}-{}/{}\r\n",
+                range.start, range.end, size
+            ));
+        }
+        headers.push_str("\r\n");
+        stream
+            .write_all(headers.as_bytes())
+            .or(Err(StreamingError::WriteConnectionError))?;
+
+        if let (Some(range), "GET") = (range, request.method.as_str()) {
+            Self::write_range(&client, &mut stream, range)?;
+        }
+        Ok(())
+    }
+
+    /// Escribe el rango pieza por pieza, esperando a que cada una este verificada.
+    fn write_range(
+        client: &Arc<Mutex<BitClient>>,
+        stream: &mut TcpStream,
+        range: ByteRange,
+    ) -> Result<()> {
+        let lock = client.lock().or(Err(StreamingError::MutexLockError))?;
+        let piece_length = lock.metainfo.info.piece_length as u64;
+        let window = lock.sequential.size.max(1) as u64;
+        drop(lock);
+
+        let first = range.start / piece_length;
+        let last = range.end / piece_length;
+        for index in first..=last {
+            let next = last.min(index + window - 1);
+            let pending: Vec<u32> = (index..=next).map(|piece| piece as u32).collect();
+            Self::wait_for_piece(client, index as u32, &pending)?;
+
+            let start = range.start.max(index * piece_length);
+            let end = range.end.min((index + 1) * piece_length - 1);
+            let mut lock = client.lock().or(Err(StreamingError::MutexLockError))?;
+            let data = lock
+                .downloader
+                .upload(start, end - start + 1)
+                .or(Err(StreamingError::UploadError))?;
+            drop(lock);
+            stream
+                .write_all(&data)
+                .or(Err(StreamingError::WriteConnectionError))?;
+        }
+        Ok(())
+    }
+
+    /// Bloquea hasta que la pieza este verificada, priorizando las siguientes piezas del rango.
+    fn wait_for_piece(client: &Arc<Mutex<BitClient>>, index: u32, pending: &[u32]) -> Result<()> {
+        let start = Instant::now();
+        loop {
+            let mut lock = client.lock().or(Err(StreamingError::MutexLockError))?;
+            if lock.pieces[index as usize].is_complete {
+                return Ok(());
+            }
+            lock.sequential.boost(pending);
+            drop(lock);
+            if start.elapsed() > PIECE_TIMEOUT {
+                return Err(StreamingError::PieceTimeoutError);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Escribe una respuesta sin cuerpo con el status y los headers recibidos.
+    fn write_status(stream: &mut TcpStream, status: &str, headers: &[&str]) -> Result<()> {
+        let mut message = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n", status);
+        for header in headers {
+            message.push_str(header);
+            message.push_str("\r\n");
+        }
+        message.push_str("\r\n");
+        stream
+            .write_all(message.as_bytes())
+            .or(Err(StreamingError::WriteConnectionError))?;
+        Ok(())
+    }
+
+    /// Deduce el Content-Type a partir de la extension del archivo.
+    fn content_type(name: &str) -> &'static str {
+        let extension = name.rsplit('.').next().unwrap_or("").to_lowercase();
+        match extension.as_str() {
+            "mp4" | "m4v" => "video/mp4",
+            "mkv" => "video/x-matroska",
+            "webm" => "video/webm",
+            "avi" => "video/x-msvideo",
+            "mp3" => "audio/mpeg",
+            "ogg" => "audio/ogg",
+            "flac" => "audio/flac",
+            "pdf" => "application/pdf",
+            "txt" => "text/plain",
+            _ => "application/octet-stream",
+        }
+    }
+}
+
+/// Si el cliente tiene configurado STREAMING_PORT, dispara un thread que escucha las requests
+/// y atiende cada una en su propio thread.
+pub fn start(
+    mutex: Arc<Mutex<BitClient>>,
+) -> std::result::Result<Option<JoinHandle<()>>, ClientError> {
+    let client = mutex.lock().or(Err(ClientError::MutexLockError))?;
+    let port = client.streaming_port.clone();
+    drop(client);
+    let port = match port {
+        Some(port) => port,
+        None => return Ok(None),
+    };
+
+    let server = StreamServer::new(mutex, &port).map_err(ClientError::StreamingError)?;
+    let server_thread = thread::spawn(move || {
+        for stream in server.listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let client = server.client.clone();
+                    let log = server.log.clone();
+                    thread::spawn(move || {
+                        if let Err(err) = StreamServer::attend_connection(client, stream) {
+                            println!("[STREAMING] Error: {}", err);
+                            let _ = log.send("- [ERROR] Streaming: ".to_owned() + &err.to_string());
+                        }
+                    });
+                }
+                Err(e) => println!("[STREAMING] Error {:?}", e),
+            }
+        }
+    });
+    Ok(Some(server_thread))
+}
+
+#[cfg(test)]
+mod stream_server_should {
+    use super::*;
+
+    #[test]
+    fn parse_closed_range() {
+        assert_eq!(
+            ByteRange::parse("bytes=10-19", 100).unwrap(),
+            ByteRange { start: 10, end: 19 }
+        );
+    }
+
+    #[test]
+    fn parse_open_and_suffix_ranges() {
+        assert_eq!(
+            ByteRange::parse("bytes=90-", 100).unwrap(),
+            ByteRange { start: 90, end: 99 }
+        );
+        assert_eq!(
+            ByteRange::parse("bytes=-5", 100).unwrap(),
+            ByteRange { start: 95, end: 99 }
+        );
+        assert_eq!(
+            ByteRange::parse("bytes=50-500", 100).unwrap(),
+            ByteRange { start: 50, end: 99 }
+        );
+    }
+
+    #[test]
+    fn measure_the_range_length() {
+        let range = ByteRange { start: 10, end: 19 };
+        assert_eq!(range.len(), 10);
+        assert!(!range.is_empty());
+
+        let inverted = ByteRange { start: 20, end: 10 };
+        assert_eq!(inverted.len(), 0);
+        assert!(inverted.is_empty());
+    }
+
+    #[test]
+    fn fail_if_range_is_not_satisfiable() {
+        assert_eq!(
+            ByteRange::parse("bytes=100-", 100).unwrap_err().to_string(),
+            "El rango pedido es invalido"
+        );
+        assert!(ByteRange::parse("bytes=20-10", 100).is_err());
+        assert!(ByteRange::parse("bytes=0-1,5-6", 100).is_err());
+        assert!(ByteRange::parse("items=0-1", 100).is_err());
+    }
+
+    #[test]
+    fn read_request_with_range() {
+        let raw = "GET /video.mp4 HTTP/1.1\r\nHost: 127.0.0.1\r\nrange: bytes=0-1023\r\n\r\n";
+        let request = StreamRequest::read(&mut raw.as_bytes()).unwrap();
+        assert_eq!(
+            request,
+            StreamRequest {
+                method: "GET".to_string(),
+                path: "/video.mp4".to_string(),
+                range: Some("bytes=0-1023".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn fail_if_request_is_truncated() {
+        let raw = "GET / HTTP/1.1\r\nHost: 127.0.0.1\r\n";
+        assert!(StreamRequest::read(&mut raw.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn guess_content_type() {
+        assert_eq!(StreamServer::content_type("movie.MKV"), "video/x-matroska");
+        assert_eq!(
+            StreamServer::content_type("sample"),
+            "application/octet-stream"
+        );
+    }
+}
@@ -0,0 +1,478 @@
+use crate::torrent_file::errors::CreatorError;
+use protocol::encoder::bencode_encoder::EncodingParser;
+use protocol::encoder::bencode_parser::{Bencode, DecodingParser};
+use protocol::torrent_file::metainfo::MetaInfo;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/******************************************************************************************/
+/*                                  TORRENT CREATOR                                       */
+/******************************************************************************************/
+
+pub const MIN_PIECE_LENGTH: u32 = 16384; // 2^14
+pub const MAX_PIECE_LENGTH: u32 = 16777216; // 2^24
+const TARGET_NUM_PIECES: u64 = 1500;
+const CREATED_BY: &str = concat!("bittorrent/", env!("CARGO_PKG_VERSION"));
+
+type Result<T> = std::result::Result<T, CreatorError>;
+
+/// Archivo que forma parte del contenido del torrent.
+#[derive(Debug, Clone, PartialEq)]
+struct FileEntry {
+    disk_path: PathBuf,
+    path: Vec<String>,
+    length: u64,
+}
+
+/// Estructura que arma un archivo .torrent a partir de un archivo o de un directorio.
+/// Los campos opcionales se completan antes de llamar a `create`.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct TorrentCreator {
+    pub announce: String,
+    pub announce_list: Vec<Vec<String>>,
+    pub comment: Option<String>,
+    pub created_by: Option<String>,
+    pub creation_date: Option<i64>,
+    pub private: bool,
+    pub url_list: Vec<String>,
+    pub piece_length: Option<u32>,
+    pub threads: usize,
+}
+
+#[allow(dead_code)]
+impl TorrentCreator {
+    /// Inicializa el creador con el tracker principal, la fecha actual y un thread de hasheo por cpu.
+    pub fn new(announce: &str) -> Self {
+        let threads = thread::available_parallelism()
+            .map(|threads| threads.get())
+            .unwrap_or(1);
+        TorrentCreator {
+            announce: announce.to_string(),
+            announce_list: vec![],
+            comment: None,
+            created_by: Some(CREATED_BY.to_string()),
+            creation_date: Some(chrono::Utc::now().timestamp()),
+            private: false,
+            url_list: vec![],
+            piece_length: None,
+            threads,
+        }
+    }
+
+    /// Crea el torrent del contenido en `path`, lo escribe en `output` y devuelve el info hash.
+    pub fn create_file(&self, path: &str, output: &str) -> Result<Vec<u8>> {
+        let encoded = EncodingParser.encode(self.create(path)?);
+        let span = DecodingParser
+            .dict_value_span(&encoded, "info")
+            .or(Err(CreatorError::InfoHashError))?
+            .ok_or(CreatorError::InfoHashError)?;
+        let info_hash = MetaInfo::hashing(&encoded[span]);
+        let mut file = File::create(output).or(Err(CreatorError::WriteFileError))?;
+        file.write_all(&encoded)
+            .or(Err(CreatorError::WriteFileError))?;
+        Ok(info_hash)
+    }
+
+    /// Recorre el contenido en `path`, hashea sus piezas y devuelve el diccionario del metainfo,
+    /// con las claves ordenadas como exige la especificacion.
+    pub fn create(&self, path: &str) -> Result<Bencode> {
+        let root = Path::new(path);
+        let name = root
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or(CreatorError::InvalidPathError)?
+            .to_string();
+        let files = Self::collect_files(root)?;
+        let total: u64 = files.iter().map(|file| file.length).sum();
+        if total == 0 {
+            return Err(CreatorError::EmptyContentError);
+        }
+        let piece_length = match self.piece_length {
+            Some(length) => Self::validate_piece_length(length)?,
+            None => Self::choose_piece_length(total),
+        };
+        let pieces = Self::hash_pieces(&files, piece_length, self.threads)?;
+
+        let mut info = vec![];
+        if root.is_dir() {
+            let list = files
+                .iter()
+                .map(|file| {
+                    Bencode::dict(vec![
+                        ("length", Bencode::Int(file.length as i64)),
+                        (
+                            "path",
+                            Bencode::List(file.path.iter().cloned().map(Bencode::from).collect()),
+                        ),
+                    ])
+                })
+                .collect();
+            info.push(("files", Bencode::List(list)));
+        } else {
+            info.push(("length", Bencode::Int(total as i64)));
+        }
+        info.push(("name", Bencode::from(name)));
+        info.push(("piece length", Bencode::Int(piece_length as i64)));
+        info.push(("pieces", Bencode::ByteString(pieces)));
+        if self.private {
+            info.push(("private", Bencode::Int(1)));
+        }
+
+        let mut dict = vec![("announce", Bencode::from(self.announce.clone()))];
+        let announce_list = self.tiers();
+        if !announce_list.is_empty() {
+            let tiers = announce_list
+                .into_iter()
+                .map(|tier| Bencode::List(tier.into_iter().map(Bencode::from).collect()))
+                .collect();
+            dict.push(("announce-list", Bencode::List(tiers)));
+        }
+        if let Some(comment) = &self.comment {
+            dict.push(("comment", Bencode::from(comment.clone())));
+        }
+        if let Some(created_by) = &self.created_by {
+            dict.push(("created by", Bencode::from(created_by.clone())));
+        }
+        if let Some(date) = self.creation_date {
+            dict.push(("creation date", Bencode::Int(date)));
+        }
+        dict.push(("info", Bencode::dict(info)));
+        if !self.url_list.is_empty() {
+            let urls = self.url_list.iter().cloned().map(Bencode::from).collect();
+            dict.push(("url-list", Bencode::List(urls)));
+        }
+        Ok(Bencode::dict(dict))
+    }
+
+    /// Devuelve los tiers del announce-list. Si el tracker principal no esta en ninguno,
+    /// se agrega como primer tier, ya que los clientes ignoran `announce` cuando hay announce-list.
+    fn tiers(&self) -> Vec<Vec<String>> {
+        let mut tiers: Vec<Vec<String>> = self
+            .announce_list
+            .iter()
+            .filter(|tier| !tier.is_empty())
+            .cloned()
+            .collect();
+        if tiers.is_empty() {
+            return tiers;
+        }
+        if !tiers.iter().any(|tier| tier.contains(&self.announce)) {
+            tiers.insert(0, vec![self.announce.clone()]);
+        }
+        tiers
+    }
+
+    /// Elige la menor potencia de 2 que deje alrededor de 1500 piezas, entre 16 KiB y 16 MiB.
+    pub fn choose_piece_length(total: u64) -> u32 {
+        let target = (total / TARGET_NUM_PIECES).next_power_of_two();
+        target.clamp(MIN_PIECE_LENGTH as u64, MAX_PIECE_LENGTH as u64) as u32
+    }
+
+    fn validate_piece_length(length: u32) -> Result<u32> {
+        if !length.is_power_of_two() || !(MIN_PIECE_LENGTH..=MAX_PIECE_LENGTH).contains(&length) {
+            return Err(CreatorError::InvalidPieceLengthError);
+        }
+        Ok(length)
+    }
+
+    /// Devuelve los archivos del contenido. Los directorios se recorren recursivamente
+    /// y en orden alfabetico para que el torrent sea reproducible. Los enlaces simbolicos
+    /// dentro del directorio se ignoran, para no recorrer ciclos ni hashear dos veces el
+    /// mismo archivo.
+    fn collect_files(root: &Path) -> Result<Vec<FileEntry>> {
+        if root.is_file() {
+            let length = fs::metadata(root)
+                .or(Err(CreatorError::ReadFileError))?
+                .len();
+            let name = root
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or(CreatorError::InvalidPathError)?;
+            return Ok(vec![FileEntry {
+                disk_path: root.to_path_buf(),
+                path: vec![name.to_string()],
+                length,
+            }]);
+        }
+        if !root.is_dir() {
+            return Err(CreatorError::InvalidPathError);
+        }
+        let mut files = vec![];
+        Self::walk_directory(root, &mut vec![], &mut files)?;
+        Ok(files)
+    }
+
+    fn walk_directory(
+        dir: &Path,
+        prefix: &mut Vec<String>,
+        files: &mut Vec<FileEntry>,
+    ) -> Result<()> {
+        let mut entries = fs::read_dir(dir)
+            .or(Err(CreatorError::ReadDirectoryError))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::result::Result<Vec<PathBuf>, _>>()
+            .or(Err(CreatorError::ReadDirectoryError))?;
+        entries.sort();
+        for entry in entries {
+            let metadata = fs::symlink_metadata(&entry).or(Err(CreatorError::ReadFileError))?;
+            if metadata.file_type().is_symlink() {
+                continue;
+            }
+            let name = entry
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or(CreatorError::InvalidPathError)?
+                .to_string();
+            prefix.push(name);
+            if metadata.is_dir() {
+                Self::walk_directory(&entry, prefix, files)?;
+            } else {
+                files.push(FileEntry {
+                    disk_path: entry,
+                    path: prefix.clone(),
+                    length: metadata.len(),
+                });
+            }
+            prefix.pop();
+        }
+        Ok(())
+    }
+
+    /// Hashea las piezas repartiendolas en bloques contiguos entre los threads,
+    /// y devuelve los hashes concatenados en orden.
+    fn hash_pieces(files: &[FileEntry], piece_length: u32, threads: usize) -> Result<Vec<u8>> {
+        let total: u64 = files.iter().map(|file| file.length).sum();
+        let piece_length = piece_length as u64;
+        let num_pieces = total.div_ceil(piece_length);
+        let threads = (threads.max(1) as u64).min(num_pieces);
+        let per_thread = num_pieces.div_ceil(threads);
+
+        let results: Vec<Result<Vec<u8>>> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|thread_index| {
+                    let first = thread_index * per_thread;
+                    let last = ((thread_index + 1) * per_thread).min(num_pieces);
+                    scope.spawn(move || {
+                        let mut hashes = vec![];
+                        for index in first..last {
+                            let offset = index * piece_length;
+                            let length = piece_length.min(total - offset);
+                            let data = Self::read_range(files, offset, length)?;
+                            hashes.extend(MetaInfo::hashing(&data));
+                        }
+                        Ok(hashes)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or(Err(CreatorError::HashingThreadError))
+                })
+                .collect()
+        });
+
+        let mut pieces = vec![];
+        for result in results {
+            pieces.extend(result?);
+        }
+        Ok(pieces)
+    }
+
+    /// Lee `length` bytes a partir de `offset`, tomando los archivos como si estuvieran concatenados.
+    fn read_range(files: &[FileEntry], offset: u64, length: u64) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(length as usize);
+        let end = offset + length;
+        let mut file_start = 0;
+        for entry in files {
+            let file_end = file_start + entry.length;
+            if file_end > offset && file_start < end {
+                let from = offset.max(file_start) - file_start;
+                let to = end.min(file_end) - file_start;
+                let mut file = File::open(&entry.disk_path).or(Err(CreatorError::OpenFileError))?;
+                file.seek(SeekFrom::Start(from))
+                    .or(Err(CreatorError::ReadFileError))?;
+                let mut buffer = vec![0; (to - from) as usize];
+                file.read_exact(&mut buffer)
+                    .or(Err(CreatorError::ReadFileError))?;
+                data.extend(buffer);
+            }
+            if file_end >= end {
+                break;
+            }
+            file_start = file_end;
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod torrent_creator_should {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("creator_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn keys(bencode: &Bencode) -> Vec<String> {
+        match bencode {
+            Bencode::Dictionary(dict) => dict
+                .iter()
+                .map(|(key, _)| String::from_utf8_lossy(key).to_string())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn choose_piece_length_between_limits() {
+        assert_eq!(TorrentCreator::choose_piece_length(20), MIN_PIECE_LENGTH);
+        assert_eq!(TorrentCreator::choose_piece_length(1500 * 262144), 262144);
+        assert_eq!(
+            TorrentCreator::choose_piece_length(u64::MAX / 2),
+            MAX_PIECE_LENGTH
+        );
+    }
+
+    #[test]
+    fn create_single_file_torrent_readable_by_metainfo() {
+        let content = temp_path("single.txt");
+        let data: Vec<u8> = (0..40000).map(|i| (i % 251) as u8).collect();
+        fs::write(&content, &data).unwrap();
+        let output = temp_path("single.torrent");
+
+        let mut creator = TorrentCreator::new("http://127.0.0.1:8080/announce");
+        creator.threads = 3;
+        let info_hash = creator
+            .create_file(content.to_str().unwrap(), output.to_str().unwrap())
+            .unwrap();
+
+        let meta = MetaInfo::new(output.to_str().unwrap()).unwrap();
+        assert_eq!(meta.info_hash, info_hash);
+        assert_eq!(meta.announce, "http://127.0.0.1:8080/announce");
+        assert_eq!(meta.info.length, 40000);
+        assert_eq!(meta.info.piece_length, MIN_PIECE_LENGTH);
+        assert_eq!(meta.info.num_pieces, 3);
+        assert_eq!(meta.info.pieces[0], MetaInfo::hashing(&data[..16384]));
+        assert_eq!(meta.info.pieces[2], MetaInfo::hashing(&data[32768..]));
+
+        fs::remove_file(content).unwrap();
+        fs::remove_file(output).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn skip_symlinks_inside_directories() {
+        let dir = temp_path("symlinks");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.txt"), vec![1; 100]).unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("loop")).unwrap();
+        std::os::unix::fs::symlink(dir.join("a.txt"), dir.join("b.txt")).unwrap();
+
+        let files = TorrentCreator::collect_files(&dir).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, vec!["a.txt"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn create_directory_torrent_with_pieces_across_files() {
+        let dir = temp_path("dir");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("b.txt"), vec![2; 10000]).unwrap();
+        fs::write(dir.join("a.txt"), vec![1; 10000]).unwrap();
+        fs::write(dir.join("sub").join("c.txt"), vec![3; 5000]).unwrap();
+
+        let mut creator = TorrentCreator::new("http://tracker/announce");
+        creator.announce_list = vec![vec![String::from("http://backup/announce")]];
+        creator.comment = Some(String::from("prueba"));
+        creator.private = true;
+        creator.url_list = vec![String::from("http://mirror/")];
+        creator.creation_date = Some(1000);
+        let metainfo = creator.create(dir.to_str().unwrap()).unwrap();
+
+        assert_eq!(
+            keys(&metainfo),
+            vec![
+                "announce",
+                "announce-list",
+                "comment",
+                "created by",
+                "creation date",
+                "info",
+                "url-list"
+            ]
+        );
+        let encoded = EncodingParser.encode(metainfo.clone());
+        assert_eq!(DecodingParser.decode_from_u8(encoded).unwrap(), metainfo);
+
+        let info = match &metainfo {
+            Bencode::Dictionary(dict) => dict[5].1.clone(),
+            _ => panic!("el metainfo no es un diccionario"),
+        };
+        assert_eq!(
+            keys(&info),
+            vec!["files", "name", "piece length", "pieces", "private"]
+        );
+        let mut content = vec![1; 10000];
+        content.extend(vec![2; 10000]);
+        content.extend(vec![3; 5000]);
+        let mut expected = MetaInfo::hashing(&content[..16384]);
+        expected.extend(MetaInfo::hashing(&content[16384..]));
+        if let Bencode::Dictionary(dict) = info {
+            assert_eq!(dict[3].1, Bencode::ByteString(expected));
+            if let Bencode::List(files) = &dict[0].1 {
+                assert_eq!(
+                    files[2],
+                    Bencode::dict(vec![
+                        ("length", Bencode::Int(5000)),
+                        (
+                            "path",
+                            Bencode::List(vec![Bencode::from("sub"), Bencode::from("c.txt")])
+                        ),
+                    ])
+                );
+            }
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn put_announce_in_first_tier() {
+        let mut creator = TorrentCreator::new("http://tracker/announce");
+        creator.announce_list = vec![vec![String::from("http://backup/announce")], vec![]];
+        assert_eq!(
+            creator.tiers(),
+            vec![
+                vec![String::from("http://tracker/announce")],
+                vec![String::from("http://backup/announce")]
+            ]
+        );
+    }
+
+    #[test]
+    fn fail_with_invalid_piece_length() {
+        let content = temp_path("invalid.txt");
+        fs::write(&content, b"hola").unwrap();
+        let mut creator = TorrentCreator::new("http://tracker/announce");
+        creator.piece_length = Some(20000);
+
+        assert_eq!(
+            creator
+                .create(content.to_str().unwrap())
+                .unwrap_err()
+                .to_string(),
+            "El tamaño de pieza debe ser una potencia de 2 entre 16 KiB y 16 MiB"
+        );
+        fs::remove_file(content).unwrap();
+    }
+}
@@ -1,29 +1,44 @@
 use std::fmt;
 
 /******************************************************************************************/
-/*                                  Metainfo ERROR                                        */
+/*                                  Creator ERROR                                         */
 /******************************************************************************************/
 
 #[derive(Debug)]
 #[allow(clippy::enum_variant_names)]
 #[allow(dead_code)]
-pub enum MetaInfoError {
-    ReadFileError,
+pub enum CreatorError {
     OpenFileError,
-    DecodingError,
-    IntegerConvertionError,
+    ReadFileError,
+    WriteFileError,
+    ReadDirectoryError,
+    EmptyContentError,
+    InvalidPathError,
+    InvalidPieceLengthError,
+    HashingThreadError,
+    InfoHashError,
 }
 
 #[allow(dead_code)]
-impl fmt::Display for MetaInfoError {
+impl fmt::Display for CreatorError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            MetaInfoError::ReadFileError => write!(f, "No se pudo leer el archivo"),
-            MetaInfoError::OpenFileError => write!(f, "No se pudo abrir el archivo"),
-            MetaInfoError::DecodingError => write!(f, "No se pudo parsear el archivo"),
-            MetaInfoError::IntegerConvertionError => {
-                write!(f, "Fallo al intentar castear un entero")
+            CreatorError::OpenFileError => write!(f, "No se pudo abrir el archivo"),
+            CreatorError::ReadFileError => write!(f, "No se pudo leer el archivo"),
+            CreatorError::WriteFileError => write!(f, "No se pudo escribir el archivo torrent"),
+            CreatorError::ReadDirectoryError => write!(f, "No se pudo recorrer el directorio"),
+            CreatorError::EmptyContentError => write!(f, "No hay contenido para crear el torrent"),
+            CreatorError::InvalidPathError => write!(f, "La ruta del contenido es invalida"),
+            CreatorError::InvalidPieceLengthError => {
+                write!(
+                    f,
+                    "El tamaño de pieza debe ser una potencia de 2 entre 16 KiB y 16 MiB"
+                )
+            }
+            CreatorError::HashingThreadError => {
+                write!(f, "Fallo un thread durante el hasheo de las piezas")
             }
+            CreatorError::InfoHashError => write!(f, "No se pudo calcular el info hash"),
         }
     }
 }
@@ -3,7 +3,7 @@ use std::fmt;
 /*                                  Tracker ERROR                                        */
 /******************************************************************************************/
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 #[allow(clippy::enum_variant_names)]
 pub enum TrackerError {
     WriteConnectionError,
@@ -12,6 +12,10 @@ pub enum TrackerError {
     URLEncodingError,
     InvalidSyntaxError,
     RequestError,
+    /// El tracker rechazo el announce con el motivo de `failure reason`.
+    FailureReasonError(String),
+    /// El tracker respondio el announce con una advertencia en `warning message`.
+    WarningMessageError(String),
 }
 
 impl fmt::Display for TrackerError {
@@ -27,6 +31,12 @@ impl fmt::Display for TrackerError {
                 write!(f, "No se pudo parsear el diccionario")
             }
             TrackerError::RequestError => write!(f, "No se pudo realizar la request"),
+            TrackerError::FailureReasonError(ref reason) => {
+                write!(f, "El tracker rechazo el announce: {}", reason)
+            }
+            TrackerError::WarningMessageError(ref message) => {
+                write!(f, "Advertencia del tracker: {}", message)
+            }
         }
     }
 }
@@ -1,7 +1,7 @@
-use crate::encoder::url_encoder::URLEncoder;
 use crate::tracker::errors::TrackerError;
 use crate::tracker::tracker_response::TrackerResponse;
 use native_tls::{TlsConnector, TlsStream};
+use protocol::encoder::url_encoder::URLEncoder;
 use std::io::Read;
 use std::io::Write;
 use std::net::TcpStream;
@@ -97,14 +97,11 @@ impl Connector {
                 stream
                     .write_all(req.as_bytes())
                     .or(Err(TrackerError::WriteConnectionError))?;
-                println!("Pasé el write");
-                let mut buffer = [0_u8; 1024];
-
-                let _ = stream
-                    .read(&mut buffer)
+                let mut res = vec![];
+                stream
+                    .read_to_end(&mut res)
                     .or(Err(TrackerError::ReadConnectionError))?;
-                println!("Pasé el read");
-                Ok(buffer.to_vec())
+                Ok(res)
             }
         }
     }
@@ -179,48 +176,23 @@ impl TrackerRequest {
     }
 
     ///Esta funcion recibe un Vector con la respuesta del Tracker
-    /// y calcula a partir de donde inicia la parte a ser interpretada como un Diccionario Bencode.
-    /// Devuelve el indice a esa posicion.
-    fn index(vec: Vec<u8>) -> usize {
-        let string = String::from_utf8_lossy(vec.as_slice());
-        let mut str_vec: Vec<&str> = string.split("\r\n").collect();
-        str_vec.pop().unwrap();
-
-        let mut joined_str_vec = str_vec.join("\r\n");
-        joined_str_vec.push_str("\r\n");
-
-        let bytes_vec = joined_str_vec.into_bytes();
-        bytes_vec.len() - 1
+    /// y calcula a partir de donde inicia el body, que es lo que se interpreta como un Diccionario Bencode.
+    /// Devuelve el indice a esa posicion. El body puede ser binario, por lo que se busca el fin de los headers.
+    fn index(response: &[u8]) -> Option<usize> {
+        response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .map(|position| position + 4)
     }
 
     ///Esta funcion recibe la respuesta del Tracker como vector
     /// y parsea el diccionario a partir del indice devuelto por Index.
-    /// Devuelve un TrackerResponse.
+    /// Devuelve un TrackerResponse, o el motivo por el que el tracker rechazo el announce.
     fn parse_response(response: &mut [u8]) -> Result<TrackerResponse, TrackerError> {
-        //print!("Desde Parse Response: {}", String::from_utf8_lossy(response));
-        let index = TrackerRequest::index(response.to_owned());
-        let info = &response[index + 1..];
-
-        let response = TrackerResponse::new()
-            .from(info.to_owned())
-            .or(Err(TrackerError::ReadConnectionError))?;
+        let index = TrackerRequest::index(response).ok_or(TrackerError::InvalidSyntaxError)?;
+        let info = &response[index..];
 
-        //print!("Desde Parse Response: {:?}", response);
-        Ok(response)
-    }
-
-    fn parse_our_response(response: &mut [u8]) -> Result<TrackerResponse, TrackerError> {
-        let string = String::from_utf8_lossy(response).to_string();
-        //println!("Desde Our Response:{}", string);
-        let str_vec: Vec<&str> = string.split("\r\n").collect();
-
-        let info = str_vec[5];
-        //println!("Desde Our Response:{:?}", info);
-
-        let response = TrackerResponse::new()
-            .from(info.as_bytes().to_owned())
-            .or(Err(TrackerError::ReadConnectionError))?;
-        Ok(response)
+        TrackerResponse::new().from(info.to_owned())
     }
 
     /// Esta funcion realiza las conexiones correspondientes con el Tracker, se anuncia.
@@ -234,16 +206,11 @@ impl TrackerRequest {
             .stream(&req)
             .or(Err(TrackerError::RequestError))?;
 
-        if self.host == OUR_HOST {
-            let tracker_response = TrackerRequest::parse_our_response(&mut response)
-                .or(Err(TrackerError::InvalidSyntaxError))?;
-            println!("{:?}", tracker_response);
-            return Ok(tracker_response);
-        }
-
         //println!("{}", String::from_utf8_lossy(&*response));
-        let tracker_response = TrackerRequest::parse_response(&mut response)
-            .or(Err(TrackerError::InvalidSyntaxError))?;
+        let tracker_response = TrackerRequest::parse_response(&mut response)?;
+        if let Some(warning) = tracker_response.warning() {
+            println!("[CLIENTE] {}", warning);
+        }
         Ok(tracker_response)
     }
 }
@@ -1,6 +1,14 @@
 use super::errors::TrackerError;
-use crate::encoder::bencode_parser::{Bencode, DecodingParser};
 use crate::peers::peer::Peer;
+use protocol::encoder::bencode_parser::Bencode;
+use protocol::encoder::bencode_serde;
+use protocol::encoder::bencode_tokenizer::DecodingOptions;
+use serde::Deserialize;
+use std::net::IpAddr;
+use std::time::Duration;
+
+const IPV4_LEN: usize = 4;
+const IPV6_LEN: usize = 16;
 
 /******************************************************************************************/
 /*                               TRACKER RESPONSE                                         */
@@ -9,6 +17,9 @@ use crate::peers::peer::Peer;
 #[derive(PartialEq, Debug, Clone)]
 pub struct TrackerResponse {
     interval: String,
+    min_interval: String,
+    tracker_id: String,
+    warning_message: Option<String>,
     complete: String,
     incomplete: String,
     pub peers: Vec<Peer>,
@@ -24,6 +35,9 @@ impl TrackerResponse {
     pub fn new() -> TrackerResponse {
         TrackerResponse {
             interval: "".to_string(),
+            min_interval: "".to_string(),
+            tracker_id: "".to_string(),
+            warning_message: None,
             complete: "".to_string(),
             incomplete: "".to_string(),
             peers: vec![],
@@ -31,75 +45,138 @@ impl TrackerResponse {
     }
 
     /// Esta funcion recibe la respuesta del tracker como un Vector de u8
-    /// y  la devuelve como una TrackerResponse
+    /// y  la devuelve como una TrackerResponse. Si el tracker rechazo el announce
+    /// devuelve el motivo como `TrackerError::FailureReasonError`.
     pub fn from(&mut self, vec: Vec<u8>) -> Result<TrackerResponse, TrackerError> {
-        let dict = DecodingParser
-            .decode_from_u8(vec)
+        let options = DecodingOptions {
+            lenient: true,
+            ..DecodingOptions::default()
+        };
+        let announce: AnnounceResponse = bencode_serde::from_bytes_with(&vec, options)
             .or(Err(TrackerError::InvalidSyntaxError))?;
-        let mut response = TrackerResponse::new();
+        if let Some(reason) = announce.failure_reason {
+            return Err(TrackerError::FailureReasonError(
+                String::from_utf8_lossy(&reason).to_string(),
+            ));
+        }
+        let to_string = |value: Option<i64>| value.map(|v| v.to_string()).unwrap_or_default();
 
-        if let Bencode::Dictionary(dict) = dict {
-            for (key, value) in dict {
-                match value {
-                    Bencode::Int(value) => {
-                        if key == "interval" {
-                            response.interval = value.clone().to_string()
-                        }
-                        if key == "complete" {
-                            response.complete = value.clone().to_string()
-                        }
-                        if key == "incomplete" {
-                            response.incomplete = value.clone().to_string()
-                        }
-                    }
-                    Bencode::List(value) => {
-                        response.peers = self.get_peers(value);
-                    }
-                    _ => {}
-                }
+        let mut response = TrackerResponse::new();
+        response.interval = to_string(announce.interval);
+        response.min_interval = to_string(announce.min_interval);
+        response.tracker_id = announce
+            .tracker_id
+            .map(|id| String::from_utf8_lossy(&id).to_string())
+            .unwrap_or_default();
+        response.warning_message = announce
+            .warning_message
+            .map(|message| String::from_utf8_lossy(&message).to_string());
+        response.complete = to_string(announce.complete);
+        response.incomplete = to_string(announce.incomplete);
+        match announce.peers {
+            Some(Bencode::List(list)) => response.peers = self.get_peers(list),
+            Some(Bencode::ByteString(bytes)) => {
+                response.peers = Self::get_compact_peers(&bytes, IPV4_LEN)
             }
+            _ => {}
+        }
+        if let Some(bytes) = announce.peers6 {
+            response
+                .peers
+                .extend(Self::get_compact_peers(&bytes, IPV6_LEN));
         }
         Ok(response)
     }
 
+    /// Devuelve el intervalo entre announces que pidio el tracker, si lo informo.
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval.parse().ok().map(Duration::from_secs)
+    }
+
+    /// Devuelve el intervalo minimo entre announces que pidio el tracker, si lo informo.
+    pub fn min_interval(&self) -> Option<Duration> {
+        self.min_interval.parse().ok().map(Duration::from_secs)
+    }
+
+    /// Devuelve la advertencia que envio el tracker junto con la respuesta, si la hubo.
+    /// La respuesta es valida igualmente, por lo que solo se informa.
+    pub fn warning(&self) -> Option<TrackerError> {
+        self.warning_message
+            .clone()
+            .map(TrackerError::WarningMessageError)
+    }
+
+    /// Interpreta los peers en formato compacto (BEP 23 y BEP 7): cada peer ocupa los bytes
+    /// de la ip seguidos de 2 bytes del puerto, en network byte order. No incluyen el id.
+    fn get_compact_peers(bytes: &[u8], ip_len: usize) -> Vec<Peer> {
+        bytes
+            .chunks_exact(ip_len + 2)
+            .filter_map(|chunk| {
+                let ip = match ip_len {
+                    IPV4_LEN => IpAddr::from(<[u8; IPV4_LEN]>::try_from(&chunk[..ip_len]).ok()?),
+                    _ => IpAddr::from(<[u8; IPV6_LEN]>::try_from(&chunk[..ip_len]).ok()?),
+                };
+                let port = u16::from_be_bytes([chunk[ip_len], chunk[ip_len + 1]]);
+                Some(Peer::new(String::new(), ip.to_string(), port.to_string()))
+            })
+            .collect()
+    }
+
     /// Esta funcion recibe una lista de Bencodes e interpreta la misma,
-    /// devolviendo los Peers que contiene
+    /// devolviendo los Peers que contiene. Los elementos que no son peers validos se ignoran.
     fn get_peers(&mut self, list: Vec<Bencode>) -> Vec<Peer> {
-        let mut id = "".to_string();
-        let mut ip = "".to_string();
-        let mut port = "".to_string();
-        let mut peers = vec![];
-        for i in list {
-            if let Bencode::Dictionary(i) = i {
-                for (key, value) in i {
-                    match value {
-                        Bencode::String(value) => {
-                            if key == "peer id" {
-                                id = value.clone()
-                            }
-                            if key == "ip" {
-                                ip = value.clone()
-                            }
-                            if key == "port" {
-                                port = value.clone()
-                            }
-                        }
-                        Bencode::Int(value) => {
-                            if key == "port" {
-                                port = value.to_string()
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-                let peer = Peer::new(id.clone(), ip.clone(), port.clone());
-                peers.push(peer)
-            }
-        }
-        peers
+        list.into_iter()
+            .filter_map(|item| bencode_serde::from_bencode::<PeerEntry>(item).ok())
+            .map(|entry| {
+                let port = match entry.port {
+                    PortEntry::Int(port) => port.to_string(),
+                    PortEntry::Str(port) => port,
+                };
+                Peer::new(
+                    String::from_utf8_lossy(&entry.id).to_string(),
+                    entry.ip,
+                    port,
+                )
+            })
+            .collect()
     }
 }
 
+/// Formato en bencode de la respuesta al announce.
+#[derive(Deserialize)]
+struct AnnounceResponse {
+    #[serde(rename = "failure reason")]
+    failure_reason: Option<serde_bytes::ByteBuf>,
+    #[serde(rename = "warning message")]
+    warning_message: Option<serde_bytes::ByteBuf>,
+    interval: Option<i64>,
+    #[serde(rename = "min interval")]
+    min_interval: Option<i64>,
+    #[serde(rename = "tracker id")]
+    tracker_id: Option<serde_bytes::ByteBuf>,
+    complete: Option<i64>,
+    incomplete: Option<i64>,
+    peers: Option<Bencode>,
+    peers6: Option<serde_bytes::ByteBuf>,
+}
+
+/// Formato en bencode de cada peer de la lista. El tracker del proyecto envia el id bajo la
+/// clave `id` y el puerto como string, por lo que se aceptan ambas variantes.
+#[derive(Deserialize)]
+struct PeerEntry {
+    #[serde(rename = "peer id", alias = "id", default, with = "serde_bytes")]
+    id: Vec<u8>,
+    ip: String,
+    port: PortEntry,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PortEntry {
+    Int(i64),
+    Str(String),
+}
+
 #[cfg(test)]
 mod tracker_response_should {
     use super::*;
@@ -107,16 +184,11 @@ mod tracker_response_should {
     #[test]
     fn get_peers_info() {
         let mut list: Vec<Bencode> = vec![];
-        let mut dict: Vec<(String, Bencode)> = vec![];
-        dict.push((
-            String::from("ip"),
-            Bencode::String(String::from("91.189.95.21")),
-        ));
-        dict.push((
-            String::from("peer id"),
-            Bencode::String(String::from("T03I--00TiFSaYzPDIpT")),
-        ));
-        dict.push((String::from("port"), Bencode::Int(6891)));
+        let dict = vec![
+            (b"ip".to_vec(), Bencode::from("91.189.95.21")),
+            (b"peer id".to_vec(), Bencode::from("T03I--00TiFSaYzPDIpT")),
+            (b"port".to_vec(), Bencode::Int(6891)),
+        ];
         list.push(Bencode::Dictionary(dict));
 
         let mut tracker_response = TrackerResponse::new();
@@ -126,4 +198,82 @@ mod tracker_response_should {
         assert_eq!(response[0].ip, "91.189.95.21");
         assert_eq!(response[0].port, "6891");
     }
+
+    #[test]
+    fn parse_response_from_our_tracker() {
+        let data = b"d8:completei1e10:incompletei2e8:intervali10e5:peersld2:id4:abcd2:ip9:127.0.0.14:port4:6881eee";
+        let response = TrackerResponse::new().from(data.to_vec()).unwrap();
+
+        assert_eq!(response.interval, "10");
+        assert_eq!(response.complete, "1");
+        assert_eq!(response.incomplete, "2");
+        assert_eq!(
+            response.peers,
+            vec![Peer::new(
+                "abcd".to_string(),
+                "127.0.0.1".to_string(),
+                "6881".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn fail_with_invalid_response() {
+        assert!(TrackerResponse::new()
+            .from(b"d8:interval".to_vec())
+            .is_err());
+    }
+
+    #[test]
+    fn fail_with_failure_reason() {
+        let data = b"d14:failure reason31:El tracker no hostea el torrente";
+        let error = TrackerResponse::new().from(data.to_vec()).unwrap_err();
+
+        assert_eq!(
+            error,
+            TrackerError::FailureReasonError("El tracker no hostea el torrent".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_warning_min_interval_and_tracker_id() {
+        let data = b"d8:intervali10e12:min intervali5e5:peersle10:tracker id2:ID15:warning message5:avisoe";
+        let response = TrackerResponse::new().from(data.to_vec()).unwrap();
+
+        assert_eq!(response.min_interval, "5");
+        assert_eq!(response.interval(), Some(Duration::from_secs(10)));
+        assert_eq!(response.min_interval(), Some(Duration::from_secs(5)));
+        assert_eq!(TrackerResponse::new().interval(), None);
+        assert_eq!(response.tracker_id, "ID");
+        assert_eq!(
+            response.warning(),
+            Some(TrackerError::WarningMessageError("aviso".to_string()))
+        );
+        assert_eq!(TrackerResponse::new().warning(), None);
+    }
+
+    #[test]
+    fn parse_compact_peers() {
+        let mut data = b"d8:intervali10e5:peers12:".to_vec();
+        data.extend([127, 0, 0, 1, 0x1a, 0xe1, 10, 0, 0, 2, 0x1a, 0xe2]);
+        data.extend(b"6:peers618:");
+        data.extend([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0x1a, 0xe3]);
+        data.push(b'e');
+
+        let response = TrackerResponse::new().from(data).unwrap();
+        let addresses: Vec<(String, String)> = response
+            .peers
+            .into_iter()
+            .map(|peer| (peer.ip, peer.port))
+            .collect();
+
+        assert_eq!(
+            addresses,
+            vec![
+                ("127.0.0.1".to_string(), "6881".to_string()),
+                ("10.0.0.2".to_string(), "6882".to_string()),
+                ("::1".to_string(), "6883".to_string()),
+            ]
+        );
+    }
 }
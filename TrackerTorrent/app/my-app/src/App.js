@@ -41,121 +41,33 @@ function a11yProps(index: number) {
 }
 
 
-function filterByHours(peers, from) {
-    const now = new Date()
-    const hours = now.getHours()
-    const day = now.getDay()
-
-    const last_peers = peers.filter(peer => new Date(peer.time_last_request).getTime() > new Date(now.setHours(hours-from)).getTime())
-    const this_day_peers = last_peers.filter(peer => new Date(peer.time_last_request).getDay() === day).map(peer => new Date(peer.time_last_request).getHours())
-    const another_day_peers = last_peers.filter(peer => new Date(peer.time_last_request).getDay() > day).map(peer => new Date(peer.time_last_request).getHours())
-
-
+// Suma las metricas de todos los torrents en cada uno de los ultimos `size` intervalos.
+function totals(response, size, metric) {
+    const series = (response.data && response.data.series) || []
     const data = []
-
-    if (from > hours) {
-            const inicio = from - hours
-            for (let i = 24 - inicio + 1; i < 24; i++) {
-                const bucket = another_day_peers.filter(hour => hour === i)
-                console.log("bucket ayer: ", i, bucket)
-                data.push(bucket.length)
+    for (let i = size; i > 0; i--) {
+        let total = 0
+        series.forEach(torrent => {
+            const bucket = torrent.buckets[torrent.buckets.length - i]
+            if (bucket) {
+                total += metric(bucket)
             }
-
-            for (let i = 0; i <= hours; i++) {
-                const bucket = this_day_peers.filter(hour => hour === i)
-                console.log("bucket hoy: ", i, bucket)
-                data.push(bucket.length)
-            }
-    }
-    else {
-        for (let i = from; i > 0; i--) {
-            const bucket = last_peers.filter(peer => (new Date(peer.time_last_request).getTime() > new Date(now.setHours(hours-i)).getTime()) && (new Date(peer.time_last_request).getTime() < new Date(now.setHours(hours-i+1)).getTime()))
-            data.push(bucket.length)
-        }
+        })
+        data.push(total)
     }
-    console.log(data)
     return data
 }
 
-function filterByHourTrackerConnection(response, from) {
-    const now = new Date()
-    const hours = now.getHours()
-    const day = now.getDay()
-
-    const last_peers = response.data.info.filter(peer => new Date(peer.time_last_request).getTime() > new Date(now.setHours(hours - from)).getTime())
-    const today_peers = last_peers.filter(peer => new Date(peer.time_last_request).getDay() === day)
-    const another_day_peers = last_peers.filter(peer => new Date(peer.time_last_request).getDay() > day)
-
-    const data = []
-
-    if (from > hours) {
-        const inicio = from - hours
-        for (let i = 24 - inicio + 1; i < 24; i++) {
-            const bucket = another_day_peers.filter(peer => new Date(peer.time_last_request).getHours() === i).map(peer => peer.torrent)
-            const uniq = [...new Set(bucket)];
-            data.push(uniq.length)
-        }
-
-        for (let i = 0; i <= hours; i++) {
-            const bucket = today_peers.filter(peer => new Date(peer.time_last_request).getHours() === i).map(peer => peer.torrent)
-            const uniq = [...new Set(bucket)];
-            data.push(uniq.length)
-        }
-
-    } else {
-        for (let i = from; i > 0; i--) {
-            const peer_by_bucket = last_peers.filter(peer => (new Date(peer.time_last_request).getTime() > new Date(now.setHours(hours - i)).getTime()) && (new Date(peer.time_last_request).getTime() < new Date(now.setHours(hours - i + 1)).getTime()))
-            const bucket = peer_by_bucket.map(peer => peer.torrent)
-            const uniq = [...new Set(bucket)];
-            data.push(uniq.length)
-        }
-    }
-    return data
+function activePeers(response, size) {
+    return totals(response, size, bucket => bucket.peers)
 }
 
-function filterByDay(peers, from) {
-    const now = new Date()
-    const date = now.getDate()
-    const last_peers = peers.filter(peer => new Date(peer.time_last_request).getDate() >  date - from).map(peer => new Date(peer.time_last_request).getDate())
-
-    const data = []
-    for (let i = date-from+1; i <= date; i++) {
-        const bucket = last_peers.filter(day => day === i)
-        data.push(bucket.length)
-    }
-    return data
+function completedPeers(response, size) {
+    return totals(response, size, bucket => bucket.completed)
 }
 
-function filterByDayTrackerConnection(response, from) {
-    const now = new Date()
-    const date = now.getDate()
-    const last_peers = response.data.info.filter(peer => new Date(peer.time_last_request).getDate() >  date - from)
-
-    const data = []
-    for (let i = date-from+1; i <= date; i++) {
-        const peer_by_bucket = last_peers.filter(peer => new Date(peer.time_last_request).getDate() === i)
-        const bucket = peer_by_bucket.map(peer => peer.torrent)
-        const uniq = [...new Set(bucket)];
-        data.push(uniq.length)
-    }
-    return data
-}
-
-
-function filterByHourPeersState(response, from, state) {
-    let peers = response.data.info;
-    if (state) {
-        peers = response.data.info.filter(peer => peer.completed)
-    }
-    return filterByHours(peers, from)
-}
-
-function filterByDayPeersState(response, from, state){
-    let peers = response.data.info;
-    if (state) {
-        peers = response.data.info.filter(peer => peer.completed)
-    }
-    return filterByDay(peers, from)
+function downloadedTorrents(response, size) {
+    return totals(response, size, bucket => bucket.completed > 0 ? 1 : 0)
 }
 
 
@@ -175,14 +87,24 @@ function getDayAxis(size) {
 
 export default function App() {
     const [value, setValue] = React.useState(0);
-    const [response, setResponse] = React.useState(MOCK_TORRENT);
+    const [hourly, setHourly] = React.useState(MOCK_TORRENT);
+    const [daily, setDaily] = React.useState(MOCK_TORRENT);
 
     React.useEffect(() => {
         axios.get("http://127.0.0.1:8080/stats", {
             method: "get",
+            params: { resolution: "hour" },
+        }).then((response) => {
+           console.log(response);
+           setHourly(response);
+        });
+        const now = Math.floor(Date.now() / 1000)
+        axios.get("http://127.0.0.1:8080/stats", {
+            method: "get",
+            params: { resolution: "day", from: now - 3 * 24 * 3600, to: now },
         }).then((response) => {
            console.log(response);
-           setResponse(response);
+           setDaily(response);
         });
     }, []);
 
@@ -210,46 +132,46 @@ export default function App() {
 
             <TabPanel value={value} index={0}>
                 <h2>Peers activos</h2>
-                <BarGraph props={{xaxis: getAxis(1), data: filterByHourPeersState(response,1, false)}}/>
+                <BarGraph props={{xaxis: getAxis(1), data: activePeers(hourly, 1)}}/>
 
                 <h2>Peers que completaron la descarga</h2>
-                <BarGraph props={{xaxis: getAxis(1), data: filterByHourPeersState(response,1, true)}}/>
+                <BarGraph props={{xaxis: getAxis(1), data: completedPeers(hourly, 1)}}/>
 
                 <h2>Torrents descargados</h2>
-                <BarGraph props={{xaxis: getAxis(1), data: filterByHourTrackerConnection(response, 1)}}/>
+                <BarGraph props={{xaxis: getAxis(1), data: downloadedTorrents(hourly, 1)}}/>
             </TabPanel>
 
             <TabPanel value={value} index={1}>
                 <h2>Peers activos</h2>
-                <BarGraph props={{xaxis: getAxis(5), data: filterByHourPeersState(response, 5, false)}}/>
+                <BarGraph props={{xaxis: getAxis(5), data: activePeers(hourly, 5)}}/>
 
                 <h2>Peers que completaron la descarga</h2>
-                <BarGraph props={{xaxis: getAxis(5), data: filterByHourPeersState(response, 5, true)}}/>
+                <BarGraph props={{xaxis: getAxis(5), data: completedPeers(hourly, 5)}}/>
 
                 <h2>Torrents descargados</h2>
-                <BarGraph props={{xaxis: getAxis(5), data: filterByHourTrackerConnection(response, 5)}}/>
+                <BarGraph props={{xaxis: getAxis(5), data: downloadedTorrents(hourly, 5)}}/>
             </TabPanel>
 
             <TabPanel value={value} index={2}>
                 <h2>Peers activos</h2>
-                <BarGraph props={{xaxis: getAxis(24), data: filterByHourPeersState(response, 24, false)}}/>
+                <BarGraph props={{xaxis: getAxis(24), data: activePeers(hourly, 24)}}/>
 
                 <h2>Peers que completaron la descarga</h2>
-                <BarGraph props={{xaxis: getAxis(24), data: filterByHourPeersState(response, 24, true)}}/>
+                <BarGraph props={{xaxis: getAxis(24), data: completedPeers(hourly, 24)}}/>
 
                 <h2>Torrents descargados</h2>
-                <BarGraph props={{xaxis: getAxis(24), data: filterByHourTrackerConnection(response,24)}}/>
+                <BarGraph props={{xaxis: getAxis(24), data: downloadedTorrents(hourly, 24)}}/>
             </TabPanel>
 
             <TabPanel value={value} index={3}>
                 <h2>Peers activos</h2>
-                <BarGraph props={{xaxis: getDayAxis(3), data: filterByDayPeersState(response,3, false)}}/>
+                <BarGraph props={{xaxis: getDayAxis(3), data: activePeers(daily, 3)}}/>
 
                 <h2>Peers que completaron la descarga</h2>
-                <BarGraph props={{xaxis: getDayAxis(3), data: filterByDayPeersState(response,3, true)}}/>
+                <BarGraph props={{xaxis: getDayAxis(3), data: completedPeers(daily, 3)}}/>
 
                 <h2>Torrents descargados</h2>
-                <BarGraph props={{xaxis: getDayAxis(3), data: filterByDayTrackerConnection(response,3)}}/>
+                <BarGraph props={{xaxis: getDayAxis(3), data: downloadedTorrents(daily, 3)}}/>
             </TabPanel>
 
         </Box>
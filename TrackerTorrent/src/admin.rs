@@ -0,0 +1,430 @@
+use crate::errors::AdminError;
+use crate::http::http_request::HttpRequest;
+use crate::http::http_response::HttpResponse;
+use crate::torrent::{Torrent, INFO_HASH_LEN};
+use crate::tracker::BitTracker;
+use protocol::encoder::url_encoder::URLEncoder;
+use protocol::torrent_file::metainfo::MetaInfo;
+use serde::Serialize;
+use std::net::IpAddr;
+
+/******************************************************************************************/
+/*                                       ADMIN                                            */
+/******************************************************************************************/
+
+/// Endpoints de la API de administracion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminEndpoint {
+    /// GET /admin/torrents
+    ListTorrents,
+    /// POST /admin/torrents, con el archivo .torrent como body.
+    UploadTorrent,
+    /// PUT /admin/torrents/:info_hash
+    AddTorrent,
+    /// DELETE /admin/torrents/:info_hash
+    RemoveTorrent,
+    /// PUT /admin/whitelist/:info_hash
+    Whitelist,
+    /// DELETE /admin/whitelist/:info_hash
+    Unwhitelist,
+    /// PUT /admin/blacklist/:info_hash
+    Blacklist,
+    /// DELETE /admin/blacklist/:info_hash
+    Unblacklist,
+    /// GET /admin/users
+    ListUsers,
+    /// PUT /admin/users/:name, responde con la passkey generada.
+    AddUser,
+    /// DELETE /admin/users/:name
+    RemoveUser,
+}
+
+/// Informacion de un torrent registrado, tal como la devuelve la API.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TorrentInfo {
+    pub info_hash: String,
+    pub name: String,
+    pub peers: usize,
+    pub completed: usize,
+}
+
+/// Atiende una request a la API de administracion. Solo se aceptan requests desde la propia
+/// maquina y, si el tracker tiene un token configurado, con el header
+/// `Authorization: Bearer <token>`. Los cambios se guardan en un snapshot inmediatamente.
+pub fn handle(
+    tracker: &mut BitTracker,
+    endpoint: AdminEndpoint,
+    params: &[(String, String)],
+    request: &HttpRequest,
+    remote: IpAddr,
+) -> HttpResponse {
+    let result = authorize(tracker, request, remote).and_then(|_| {
+        let response = dispatch(tracker, endpoint, params, request)?;
+        if endpoint != AdminEndpoint::ListTorrents && endpoint != AdminEndpoint::ListUsers {
+            tracker.save_now().or(Err(AdminError::StorageError))?;
+        }
+        Ok(response)
+    });
+    match result {
+        Ok(response) => response,
+        Err(error) => {
+            println!("[TRACKER ADMIN] {}", error);
+            HttpResponse::new(error.status())
+                .with_body("text/plain", format!("{}\n", error).into_bytes())
+        }
+    }
+}
+
+fn authorize(
+    tracker: &BitTracker,
+    request: &HttpRequest,
+    remote: IpAddr,
+) -> Result<(), AdminError> {
+    if !remote.is_loopback() {
+        return Err(AdminError::UnauthorizedError);
+    }
+    if let Some(token) = &tracker.admin_token {
+        let expected = format!("Bearer {}", token);
+        if request.header("authorization") != Some(expected.as_str()) {
+            return Err(AdminError::UnauthorizedError);
+        }
+    }
+    Ok(())
+}
+
+fn dispatch(
+    tracker: &mut BitTracker,
+    endpoint: AdminEndpoint,
+    params: &[(String, String)],
+    request: &HttpRequest,
+) -> Result<HttpResponse, AdminError> {
+    match endpoint {
+        AdminEndpoint::ListTorrents => {
+            let torrents: Vec<TorrentInfo> =
+                tracker.torrents.iter().map(TorrentInfo::from).collect();
+            json(200, &torrents)
+        }
+        AdminEndpoint::UploadTorrent => {
+            let metainfo =
+                MetaInfo::from_bytes(&request.body).or(Err(AdminError::InvalidTorrentError))?;
+            let torrent =
+                Torrent::from_metainfo(metainfo).or(Err(AdminError::InvalidTorrentError))?;
+            let info = TorrentInfo::from(&torrent);
+            tracker.add_torrent(torrent)?;
+            json(201, &info)
+        }
+        AdminEndpoint::AddTorrent => {
+            let info_hash = info_hash(params)?;
+            let torrent =
+                Torrent::from_info_hash(&info_hash).or(Err(AdminError::InvalidInfoHashError))?;
+            let info = TorrentInfo::from(&torrent);
+            tracker.add_torrent(torrent)?;
+            json(201, &info)
+        }
+        AdminEndpoint::RemoveTorrent => {
+            let torrent = tracker.remove_torrent(&info_hash_url(params)?)?;
+            json(200, &TorrentInfo::from(&torrent))
+        }
+        AdminEndpoint::Whitelist => {
+            tracker.whitelist.insert(info_hash_url(params)?);
+            Ok(HttpResponse::new(204))
+        }
+        AdminEndpoint::Unwhitelist => {
+            tracker.whitelist.remove(&info_hash_url(params)?);
+            Ok(HttpResponse::new(204))
+        }
+        AdminEndpoint::Blacklist => {
+            tracker.blacklist.insert(info_hash_url(params)?);
+            Ok(HttpResponse::new(204))
+        }
+        AdminEndpoint::Unblacklist => {
+            tracker.blacklist.remove(&info_hash_url(params)?);
+            Ok(HttpResponse::new(204))
+        }
+        AdminEndpoint::ListUsers => json(200, &tracker.users),
+        AdminEndpoint::AddUser => {
+            let user = tracker.add_user(param(params, "name")?)?;
+            json(201, user)
+        }
+        AdminEndpoint::RemoveUser => {
+            let user = tracker.remove_user(param(params, "name")?)?;
+            json(200, &user)
+        }
+    }
+}
+
+fn param<'a>(params: &'a [(String, String)], key: &str) -> Result<&'a str, AdminError> {
+    params
+        .iter()
+        .find(|(name, _value)| name == key)
+        .map(|(_name, value)| value.as_str())
+        .ok_or(AdminError::InvalidUserError)
+}
+
+/// Lee el info hash del path, en hexadecimal.
+fn info_hash(params: &[(String, String)]) -> Result<Vec<u8>, AdminError> {
+    let value = param(params, "info_hash").or(Err(AdminError::InvalidInfoHashError))?;
+    let info_hash = hex::decode(value).or(Err(AdminError::InvalidInfoHashError))?;
+    if info_hash.len() != INFO_HASH_LEN {
+        return Err(AdminError::InvalidInfoHashError);
+    }
+    Ok(info_hash)
+}
+
+fn info_hash_url(params: &[(String, String)]) -> Result<String, AdminError> {
+    URLEncoder
+        .urlencode(info_hash(params)?)
+        .or(Err(AdminError::InvalidInfoHashError))
+}
+
+fn json<T: Serialize>(status: u16, value: &T) -> Result<HttpResponse, AdminError> {
+    let body = serde_json::to_vec(value).or(Err(AdminError::StorageError))?;
+    Ok(HttpResponse::new(status).with_body("application/json", body))
+}
+
+impl From<&Torrent> for TorrentInfo {
+    fn from(torrent: &Torrent) -> Self {
+        TorrentInfo {
+            info_hash: hex::encode(&torrent.info_hash),
+            name: torrent.name.clone(),
+            peers: torrent.peers.len(),
+            completed: torrent.completed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod admin_should {
+    use super::*;
+    use crate::request::Request;
+    use std::fs;
+    use std::net::Ipv4Addr;
+    use std::sync::mpsc;
+
+    const LOCALHOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    fn tracker() -> BitTracker {
+        let (tx, _rx) = mpsc::channel();
+        let mut tracker = BitTracker::new("./config_file", tx).unwrap();
+        tracker.torrents.clear();
+        tracker
+    }
+
+    fn request(method: &str, path: &str, body: &[u8]) -> HttpRequest {
+        HttpRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: vec![],
+            version: "HTTP/1.1".to_string(),
+            headers: vec![],
+            body: body.to_vec(),
+        }
+    }
+
+    fn params() -> Vec<(String, String)> {
+        vec![("info_hash".to_string(), HASH.to_string())]
+    }
+
+    fn announce(tracker: &mut BitTracker, info_hash: &[u8]) -> bool {
+        let mut request = Request::new();
+        request.info_hash_url = URLEncoder.urlencode(info_hash.to_vec()).unwrap();
+        request.peer_id = "PEER".to_string();
+        request.port = "6881".to_string();
+        tracker.receive_request(&request).is_ok()
+    }
+
+    #[test]
+    fn register_and_remove_torrents() {
+        let mut tracker = tracker();
+        let put = request("PUT", "/admin/torrents/", b"");
+        let info_hash = hex::decode(HASH).unwrap();
+
+        assert!(!announce(&mut tracker, &info_hash));
+        let response = handle(
+            &mut tracker,
+            AdminEndpoint::AddTorrent,
+            &params(),
+            &put,
+            LOCALHOST,
+        );
+        assert_eq!(response.status, 201);
+        assert!(announce(&mut tracker, &info_hash));
+        let response = handle(
+            &mut tracker,
+            AdminEndpoint::AddTorrent,
+            &params(),
+            &put,
+            LOCALHOST,
+        );
+        assert_eq!(response.status, 409);
+
+        let list = request("GET", "/admin/torrents", b"");
+        let response = handle(
+            &mut tracker,
+            AdminEndpoint::ListTorrents,
+            &[],
+            &list,
+            LOCALHOST,
+        );
+        let expected = format!(
+            "[{{\"info_hash\":\"{}\",\"name\":\"{}\",\"peers\":1,\"completed\":0}}]",
+            HASH, HASH
+        );
+        assert_eq!(response.body, expected.into_bytes());
+
+        let delete = request("DELETE", "/admin/torrents/", b"");
+        let response = handle(
+            &mut tracker,
+            AdminEndpoint::RemoveTorrent,
+            &params(),
+            &delete,
+            LOCALHOST,
+        );
+        assert_eq!(response.status, 200);
+        assert!(!announce(&mut tracker, &info_hash));
+        let response = handle(
+            &mut tracker,
+            AdminEndpoint::RemoveTorrent,
+            &params(),
+            &delete,
+            LOCALHOST,
+        );
+        assert_eq!(response.status, 404);
+    }
+
+    #[test]
+    fn upload_torrent_file() {
+        let mut tracker = tracker();
+        let bytes = fs::read("./torrents/INFORME - BITTORRENT.pdf.torrent").unwrap();
+        let post = request("POST", "/admin/torrents", &bytes);
+
+        let response = handle(
+            &mut tracker,
+            AdminEndpoint::UploadTorrent,
+            &[],
+            &post,
+            LOCALHOST,
+        );
+        assert_eq!(response.status, 201);
+        assert_eq!(tracker.torrents[0].name, "INFORME - BITTORRENT.pdf");
+
+        let post = request("POST", "/admin/torrents", b"no es un torrent");
+        let response = handle(
+            &mut tracker,
+            AdminEndpoint::UploadTorrent,
+            &[],
+            &post,
+            LOCALHOST,
+        );
+        assert_eq!(response.status, 400);
+    }
+
+    #[test]
+    fn auto_register_in_open_mode_honouring_lists() {
+        let mut tracker = tracker();
+        tracker.open = true;
+        let put = request("PUT", "/admin/blacklist/", b"");
+
+        assert!(announce(&mut tracker, &[1; 20]));
+        assert_eq!(tracker.torrents.len(), 1);
+
+        handle(
+            &mut tracker,
+            AdminEndpoint::Blacklist,
+            &params(),
+            &put,
+            LOCALHOST,
+        );
+        assert!(!announce(&mut tracker, &hex::decode(HASH).unwrap()));
+
+        handle(
+            &mut tracker,
+            AdminEndpoint::Whitelist,
+            &params(),
+            &put,
+            LOCALHOST,
+        );
+        handle(
+            &mut tracker,
+            AdminEndpoint::Unblacklist,
+            &params(),
+            &put,
+            LOCALHOST,
+        );
+        assert!(announce(&mut tracker, &hex::decode(HASH).unwrap()));
+        assert!(!announce(&mut tracker, &[1; 20]));
+        assert!(!announce(&mut tracker, &[2; 20]));
+        assert_eq!(tracker.torrents.len(), 2);
+    }
+
+    #[test]
+    fn create_and_remove_users() {
+        let mut tracker = tracker();
+        let name = vec![("name".to_string(), "sofia".to_string())];
+        let put = request("PUT", "/admin/users/sofia", b"");
+
+        let response = handle(&mut tracker, AdminEndpoint::AddUser, &name, &put, LOCALHOST);
+        assert_eq!(response.status, 201);
+        let user: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(user["passkey"], tracker.users[0].passkey.as_str());
+        let response = handle(&mut tracker, AdminEndpoint::AddUser, &name, &put, LOCALHOST);
+        assert_eq!(response.status, 409);
+
+        let delete = request("DELETE", "/admin/users/sofia", b"");
+        let response = handle(
+            &mut tracker,
+            AdminEndpoint::RemoveUser,
+            &name,
+            &delete,
+            LOCALHOST,
+        );
+        assert_eq!(response.status, 200);
+        assert!(tracker.users.is_empty());
+        let response = handle(
+            &mut tracker,
+            AdminEndpoint::RemoveUser,
+            &name,
+            &delete,
+            LOCALHOST,
+        );
+        assert_eq!(response.status, 404);
+    }
+
+    #[test]
+    fn reject_unauthorized_requests() {
+        let mut tracker = tracker();
+        tracker.admin_token = Some("secreto".to_string());
+        let mut put = request("PUT", "/admin/torrents/", b"");
+        let remote = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+        let response = handle(
+            &mut tracker,
+            AdminEndpoint::AddTorrent,
+            &params(),
+            &put,
+            remote,
+        );
+        assert_eq!(response.status, 403);
+        let response = handle(
+            &mut tracker,
+            AdminEndpoint::AddTorrent,
+            &params(),
+            &put,
+            LOCALHOST,
+        );
+        assert_eq!(response.status, 403);
+
+        put.headers
+            .push(("authorization".to_string(), "Bearer secreto".to_string()));
+        let response = handle(
+            &mut tracker,
+            AdminEndpoint::AddTorrent,
+            &params(),
+            &put,
+            LOCALHOST,
+        );
+        assert_eq!(response.status, 201);
+        assert_eq!(tracker.torrents.len(), 1);
+    }
+}
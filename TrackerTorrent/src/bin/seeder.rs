@@ -0,0 +1,21 @@
+use std::env::args;
+use trackertorrent::seed::config::SeederConfig;
+use trackertorrent::seed::seeder::Seeder;
+
+fn main() {
+    let args = args().collect::<Vec<String>>();
+    if args.len() != 2 {
+        println!("[ERROR] Cantidad de argumentos inválido");
+        return;
+    }
+    let config = match SeederConfig::from_file(&args[1]) {
+        Ok(config) => config,
+        Err(error) => {
+            println!("[ERROR] {}", error);
+            return;
+        }
+    };
+    if let Err(error) = Seeder::start(&config) {
+        println!("[ERROR] {}", error);
+    }
+}
@@ -0,0 +1,376 @@
+use crate::errors::{BitTrackerError, ConfigError};
+use crate::response::INTERVAL;
+use crate::torrent::INFO_HASH_LEN;
+use native_tls::{Identity, TlsAcceptor};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::net::SocketAddr;
+use std::str::FromStr;
+
+/******************************************************************************************/
+/*                                   TRACKER CONFIG                                       */
+/******************************************************************************************/
+
+const HTTP_ADDRESS: &str = "127.0.0.1:8080";
+const UDP_ADDRESS: &str = "127.0.0.1:8080";
+const MIN_INTERVAL: u64 = 5;
+const PEER_TIMEOUT_FACTOR: u64 = 3;
+const LOG_DIR: &str = "./logs";
+const STATE_DIR: &str = "./state";
+const NUMWANT_DEFAULT: usize = 50;
+const NUMWANT_MAX: usize = 200;
+const WORKERS: usize = 8;
+const MAX_CONNECTIONS: usize = 256;
+const CONNECTION_TIMEOUT: u64 = 30;
+const KEEP_ALIVE_TIMEOUT: u64 = 5;
+const RATE_LIMIT: u32 = 120;
+const RATE_WINDOW: u64 = 60;
+
+/// Certificado y clave, en formato PEM, con los que se atienden announces por HTTPS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub address: String,
+    pub certificate: String,
+    pub key: String,
+}
+
+impl TlsConfig {
+    /// Carga el certificado y la clave (PKCS #8) y genera el acceptor de conexiones TLS.
+    pub fn acceptor(&self) -> Result<TlsAcceptor, ConfigError> {
+        let certificate = fs::read(&self.certificate).or(Err(ConfigError::InvalidTlsError))?;
+        let key = fs::read(&self.key).or(Err(ConfigError::InvalidTlsError))?;
+        let identity =
+            Identity::from_pkcs8(&certificate, &key).or(Err(ConfigError::InvalidTlsError))?;
+        TlsAcceptor::new(identity).or(Err(ConfigError::InvalidTlsError))
+    }
+}
+
+/// Configuracion del tracker. Se lee de un archivo donde cada linea tiene el formato
+/// `CLAVE:valor`, igual que la configuracion del cliente. Las lineas vacias y las que
+/// empiezan con `#` se ignoran, y las lineas sin clave se toman como rutas a archivos
+/// .torrent, como en el formato anterior.
+///
+/// Claves: `HTTP_ADDRESS`, `UDP_ADDRESS`, `HTTPS_ADDRESS`, `TLS_CERTIFICATE`, `TLS_KEY`,
+/// `INTERVAL`, `MIN_INTERVAL` (segundos), `PEER_TIMEOUT_FACTOR`, `LOG_DIR`, `TORRENT_DIR`,
+/// `TORRENT` (puede repetirse), `STATE_DIR`, `OPEN` (true o false), `WHITELIST` y
+/// `BLACKLIST` (info hashes en hexadecimal separados por comas), `ADMIN_TOKEN`,
+/// `NUMWANT_DEFAULT`, `NUMWANT_MAX`, `WORKERS`, `MAX_CONNECTIONS`, `CONNECTION_TIMEOUT`,
+/// `KEEP_ALIVE_TIMEOUT`, `RATE_LIMIT` y `RATE_WINDOW` (segundos).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackerConfig {
+    pub http_address: String,
+    pub udp_address: String,
+    pub tls: Option<TlsConfig>,
+    pub interval: u64,
+    pub min_interval: u64,
+    /// Cantidad de intervalos de announce sin noticias de un peer tras los cuales se lo
+    /// considera desconectado.
+    pub peer_timeout_factor: u64,
+    pub log_dir: String,
+    /// Directorio del que se cargan todos los archivos .torrent.
+    pub torrent_dir: Option<String>,
+    pub torrents: Vec<String>,
+    pub state_dir: String,
+    pub open: bool,
+    pub whitelist: Vec<String>,
+    pub blacklist: Vec<String>,
+    pub admin_token: Option<String>,
+    pub numwant_default: usize,
+    pub numwant_max: usize,
+    pub workers: usize,
+    pub max_connections: usize,
+    pub connection_timeout: u64,
+    /// Segundos que se espera la siguiente request de una conexion keep-alive.
+    pub keep_alive_timeout: u64,
+    pub rate_limit: u32,
+    pub rate_window: u64,
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        TrackerConfig {
+            http_address: HTTP_ADDRESS.to_string(),
+            udp_address: UDP_ADDRESS.to_string(),
+            tls: None,
+            interval: INTERVAL as u64,
+            min_interval: MIN_INTERVAL,
+            peer_timeout_factor: PEER_TIMEOUT_FACTOR,
+            log_dir: LOG_DIR.to_string(),
+            torrent_dir: None,
+            torrents: vec![],
+            state_dir: STATE_DIR.to_string(),
+            open: false,
+            whitelist: vec![],
+            blacklist: vec![],
+            admin_token: None,
+            numwant_default: NUMWANT_DEFAULT,
+            numwant_max: NUMWANT_MAX,
+            workers: WORKERS,
+            max_connections: MAX_CONNECTIONS,
+            connection_timeout: CONNECTION_TIMEOUT,
+            keep_alive_timeout: KEEP_ALIVE_TIMEOUT,
+            rate_limit: RATE_LIMIT,
+            rate_window: RATE_WINDOW,
+        }
+    }
+}
+
+impl TrackerConfig {
+    /// Lee y valida el archivo de configuracion.
+    pub fn from_file(path: &str) -> Result<Self, BitTrackerError> {
+        Self::parse(&read_lines(path)?).map_err(BitTrackerError::ConfigError)
+    }
+
+    /// Genera la configuracion a partir de las lineas del archivo y la valida.
+    pub fn parse(lines: &[String]) -> Result<Self, ConfigError> {
+        let mut config = TrackerConfig::default();
+        let mut https_address = None;
+        let mut certificate = None;
+        let mut key = None;
+        for line in lines {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = match line.split_once(':') {
+                Some((name, value)) => (name.trim(), value.trim().to_string()),
+                None => {
+                    config.torrents.push(line.to_string());
+                    continue;
+                }
+            };
+            match name {
+                "HTTP_ADDRESS" => config.http_address = value,
+                "UDP_ADDRESS" => config.udp_address = value,
+                "HTTPS_ADDRESS" => https_address = Some(value),
+                "TLS_CERTIFICATE" => certificate = Some(value),
+                "TLS_KEY" => key = Some(value),
+                "INTERVAL" => config.interval = parse_value(name, &value)?,
+                "MIN_INTERVAL" => config.min_interval = parse_value(name, &value)?,
+                "PEER_TIMEOUT_FACTOR" => config.peer_timeout_factor = parse_value(name, &value)?,
+                "LOG_DIR" => config.log_dir = value,
+                "TORRENT_DIR" => config.torrent_dir = Some(value),
+                "TORRENT" => config.torrents.push(value),
+                "STATE_DIR" => config.state_dir = value,
+                "OPEN" => config.open = parse_value(name, &value)?,
+                "WHITELIST" => config.whitelist.extend(list(&value)),
+                "BLACKLIST" => config.blacklist.extend(list(&value)),
+                "ADMIN_TOKEN" => config.admin_token = Some(value),
+                "NUMWANT_DEFAULT" => config.numwant_default = parse_value(name, &value)?,
+                "NUMWANT_MAX" => config.numwant_max = parse_value(name, &value)?,
+                "WORKERS" => config.workers = parse_value(name, &value)?,
+                "MAX_CONNECTIONS" => config.max_connections = parse_value(name, &value)?,
+                "CONNECTION_TIMEOUT" => config.connection_timeout = parse_value(name, &value)?,
+                "KEEP_ALIVE_TIMEOUT" => config.keep_alive_timeout = parse_value(name, &value)?,
+                "RATE_LIMIT" => config.rate_limit = parse_value(name, &value)?,
+                "RATE_WINDOW" => config.rate_window = parse_value(name, &value)?,
+                _ => return Err(ConfigError::UnknownKeyError(name.to_string())),
+            }
+        }
+        config.tls = match (https_address, certificate, key) {
+            (Some(address), Some(certificate), Some(key)) => Some(TlsConfig {
+                address,
+                certificate,
+                key,
+            }),
+            (None, None, None) => None,
+            _ => return Err(ConfigError::MissingTlsError),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Verifica que los valores sean consistentes entre si.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        address("HTTP_ADDRESS", &self.http_address)?;
+        address("UDP_ADDRESS", &self.udp_address)?;
+        if let Some(tls) = &self.tls {
+            address("HTTPS_ADDRESS", &tls.address)?;
+        }
+        if self.min_interval == 0 || self.min_interval > self.interval {
+            return Err(ConfigError::InvalidIntervalError);
+        }
+        for info_hash in self.whitelist.iter().chain(self.blacklist.iter()) {
+            match hex::decode(info_hash) {
+                Ok(bytes) if bytes.len() == INFO_HASH_LEN => {}
+                _ => return Err(ConfigError::InvalidValueError(info_hash.clone())),
+            }
+        }
+        let positive = [
+            ("PEER_TIMEOUT_FACTOR", self.peer_timeout_factor),
+            ("NUMWANT_MAX", self.numwant_max as u64),
+            ("WORKERS", self.workers as u64),
+            ("MAX_CONNECTIONS", self.max_connections as u64),
+            ("CONNECTION_TIMEOUT", self.connection_timeout),
+            ("KEEP_ALIVE_TIMEOUT", self.keep_alive_timeout),
+            ("RATE_LIMIT", self.rate_limit as u64),
+            ("RATE_WINDOW", self.rate_window),
+        ];
+        if let Some((name, _value)) = positive.iter().find(|(_name, value)| *value == 0) {
+            return Err(ConfigError::InvalidValueError(name.to_string()));
+        }
+        if self.numwant_default > self.numwant_max {
+            return Err(ConfigError::InvalidValueError(
+                "NUMWANT_DEFAULT".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Rutas de los torrents a hostear: las indicadas una por una seguidas de los archivos
+    /// .torrent del directorio de torrents, ordenados por nombre.
+    pub fn torrent_paths(&self) -> Result<Vec<String>, BitTrackerError> {
+        let mut paths = self.torrents.clone();
+        if let Some(dir) = &self.torrent_dir {
+            let entries = fs::read_dir(dir).or(Err(BitTrackerError::OpenFileError))?;
+            let mut found = vec![];
+            for entry in entries {
+                let path = entry.or(Err(BitTrackerError::ReadFileError))?.path();
+                if path
+                    .extension()
+                    .is_some_and(|extension| extension == "torrent")
+                {
+                    found.push(path.to_string_lossy().to_string());
+                }
+            }
+            found.sort();
+            for path in found {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+        Ok(paths)
+    }
+}
+
+/// Lee las lineas de un archivo de configuracion.
+pub(crate) fn read_lines(path: &str) -> Result<Vec<String>, BitTrackerError> {
+    let file = File::open(path).or(Err(BitTrackerError::OpenFileError))?;
+    let reader = BufReader::new(file);
+    let mut lines = vec![];
+    for line in reader.lines() {
+        lines.push(line.or(Err(BitTrackerError::ReadFileError))?);
+    }
+    Ok(lines)
+}
+
+pub(crate) fn parse_value<T: FromStr>(name: &str, value: &str) -> Result<T, ConfigError> {
+    value
+        .parse()
+        .or(Err(ConfigError::InvalidValueError(name.to_string())))
+}
+
+fn list(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_ascii_lowercase)
+}
+
+pub(crate) fn address(name: &str, value: &str) -> Result<SocketAddr, ConfigError> {
+    value
+        .parse()
+        .or(Err(ConfigError::InvalidValueError(name.to_string())))
+}
+
+#[cfg(test)]
+mod tracker_config_should {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parse_keys_and_legacy_torrent_lines() {
+        let config = TrackerConfig::parse(&lines(
+            "# tracker\n\
+             HTTP_ADDRESS:0.0.0.0:6969\n\
+             INTERVAL:1800\n\
+             MIN_INTERVAL:900\n\
+             PEER_TIMEOUT_FACTOR:2\n\
+             OPEN:true\n\
+             WHITELIST:AB01ab01ab01ab01ab01ab01ab01ab01ab01ab01, \n\
+             TORRENT:./torrents/sample.torrent\n\
+             ./torrents/example.torrent\n",
+        ))
+        .unwrap();
+
+        assert_eq!(config.http_address, "0.0.0.0:6969");
+        assert_eq!(config.udp_address, UDP_ADDRESS);
+        assert_eq!((config.interval, config.min_interval), (1800, 900));
+        assert_eq!(config.peer_timeout_factor, 2);
+        assert!(config.open);
+        assert_eq!(config.whitelist, vec!["ab01".repeat(10)]);
+        assert_eq!(
+            config.torrents,
+            vec!["./torrents/sample.torrent", "./torrents/example.torrent"]
+        );
+        assert!(config.tls.is_none());
+
+        let config = TrackerConfig::parse(&lines("INTERVAL:1800\nMIN_INTERVAL:900")).unwrap();
+        assert_eq!(config.peer_timeout_factor, PEER_TIMEOUT_FACTOR);
+    }
+
+    #[test]
+    fn reject_invalid_configurations() {
+        let parse = |text: &str| TrackerConfig::parse(&lines(text));
+
+        assert_eq!(
+            parse("PORT:8080"),
+            Err(ConfigError::UnknownKeyError("PORT".to_string()))
+        );
+        assert_eq!(
+            parse("HTTP_ADDRESS:localhost"),
+            Err(ConfigError::InvalidValueError("HTTP_ADDRESS".to_string()))
+        );
+        assert_eq!(
+            parse("INTERVAL:diez"),
+            Err(ConfigError::InvalidValueError("INTERVAL".to_string()))
+        );
+        assert_eq!(
+            parse("INTERVAL:60\nMIN_INTERVAL:90"),
+            Err(ConfigError::InvalidIntervalError)
+        );
+        assert_eq!(
+            parse("PEER_TIMEOUT_FACTOR:0"),
+            Err(ConfigError::InvalidValueError(
+                "PEER_TIMEOUT_FACTOR".to_string()
+            ))
+        );
+        assert_eq!(
+            parse("TLS_CERTIFICATE:./cert.pem"),
+            Err(ConfigError::MissingTlsError)
+        );
+        assert_eq!(
+            parse("BLACKLIST:abcd"),
+            Err(ConfigError::InvalidValueError("abcd".to_string()))
+        );
+        assert_eq!(
+            parse("WORKERS:0"),
+            Err(ConfigError::InvalidValueError("WORKERS".to_string()))
+        );
+    }
+
+    #[test]
+    fn load_torrents_from_directory() {
+        let config = TrackerConfig {
+            torrent_dir: Some("./torrents".to_string()),
+            torrents: vec!["./torrents/sample.torrent".to_string()],
+            ..Default::default()
+        };
+        let paths = config.torrent_paths().unwrap();
+
+        assert_eq!(paths[0], "./torrents/sample.torrent");
+        assert_eq!(
+            paths
+                .iter()
+                .filter(|path| path.ends_with("sample.torrent"))
+                .count(),
+            1
+        );
+        assert!(paths.len() > 2);
+    }
+}
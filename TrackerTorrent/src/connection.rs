@@ -1,112 +1,313 @@
-use crate::errors::BitTrackerError;
+use crate::admin::{self, AdminEndpoint};
+use crate::errors::{AnnounceError, BitTrackerError, HttpError, StatsError};
+use crate::http::http_request::HttpRequest;
+use crate::http::http_response::HttpResponse;
+use crate::http::router::{Route, Router};
 use crate::peer::Peer;
 use crate::request::Request;
+use crate::response::Response;
+use crate::stats::StatsQuery;
 use crate::tracker::BitTracker;
-use std::io::BufReader;
-use std::io::Read;
-use std::io::Write;
-use std::net::TcpStream;
+use chrono::Local;
+use native_tls::TlsStream;
+use std::io::{BufRead, BufReader};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
 use std::sync::mpsc::Sender;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 /******************************************************************************************/
 /*                                 CONNECTION                                             */
 /******************************************************************************************/
 
+/// Endpoints que atiende el tracker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Endpoint {
     Announce,
     Stats,
+    Metrics,
     End,
-    BadRequest,
+    Admin(AdminEndpoint),
+}
+
+impl Endpoint {
+    /// Nombre con el que se identifica al endpoint en las metricas.
+    fn label(&self) -> &'static str {
+        match self {
+            Endpoint::Announce => "announce",
+            Endpoint::Stats => "stats",
+            Endpoint::Metrics => "metrics",
+            Endpoint::End => "end",
+            Endpoint::Admin(_) => "admin",
+        }
+    }
+}
+
+/// Stream de una conexion, en texto plano o cifrado con TLS.
+#[derive(Debug)]
+pub enum Stream {
+    Plain(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl Stream {
+    /// Direccion del otro extremo de la conexion.
+    pub fn peer_addr(&self) -> std::io::Result<SocketAddr> {
+        match self {
+            Stream::Plain(stream) => stream.peer_addr(),
+            Stream::Tls(stream) => stream.get_ref().peer_addr(),
+        }
+    }
+
+    /// Cambia cuanto se espera en cada lectura antes de que falle.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match self {
+            Stream::Plain(stream) => stream.set_read_timeout(timeout),
+            Stream::Tls(stream) => stream.get_ref().set_read_timeout(timeout),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.read(buffer),
+            Stream::Tls(stream) => stream.read(buffer),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.write(buffer),
+            Stream::Tls(stream) => stream.write(buffer),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Plain(stream) => stream.flush(),
+            Stream::Tls(stream) => stream.flush(),
+        }
+    }
 }
 
 /// Estructura encargada de manejar la comunicacion entre el tracker y otro peer.
+/// Las responses se escriben en el mismo stream del que lee el reader.
 #[allow(dead_code)]
 #[derive(Debug)]
 pub struct Connection {
     pub id: usize,
     pub peer: Option<Peer>,
-    pub stream: TcpStream,
-    pub reader: BufReader<TcpStream>,
+    pub address: SocketAddr,
+    pub reader: BufReader<Stream>,
     pub tracker: Arc<Mutex<BitTracker>>,
     pub log: Sender<String>,
+    router: Router<Endpoint>,
+    read_timeout: Duration,
+    keep_alive_timeout: Duration,
 }
 
 #[allow(dead_code)]
-#[allow(clippy::unused_io_amount)]
 impl Connection {
     /// Inicializa la conexion
     fn new(
         id: usize,
-        stream: TcpStream,
+        stream: Stream,
         mutex: Arc<Mutex<BitTracker>>,
     ) -> Result<Self, BitTrackerError> {
-        let stream_clone = stream
-            .try_clone()
+        let address = stream
+            .peer_addr()
             .or(Err(BitTrackerError::FailToConnectError))?;
-        let reader = BufReader::new(stream_clone);
+        let reader = BufReader::new(stream);
         let tracker = mutex.lock().or(Err(BitTrackerError::MutexLockError))?;
         let log = tracker.log.clone();
+        let read_timeout = Duration::from_secs(tracker.connection_timeout);
+        let keep_alive_timeout = Duration::from_secs(tracker.keep_alive_timeout);
         drop(tracker);
         Ok(Connection {
             id,
             peer: None,
-            stream,
+            address,
             reader,
             tracker: mutex,
             log,
+            router: Self::router(),
+            read_timeout,
+            keep_alive_timeout,
         })
     }
 
-    /// Lee de la conexion, utiliza un readline, leera hasta obtener un \n
-    fn read_stream(&mut self) -> Result<String, BitTrackerError> {
-        let mut buffer = [0_u8; 1024];
-        self.stream
-            .read(&mut buffer)
-            .or(Err(BitTrackerError::ReadConnectionError))?;
-        let message = String::from_utf8_lossy(&buffer).to_string();
-        Ok(message)
-    }
-
-    /// Obtiene un mensaje de la conexion y lo convierte en un enum del tipo Edpoint
-    fn match_endpoint(message: String) -> Result<Endpoint, BitTrackerError> {
-        let split: Vec<&str> = message.split('?').collect();
-        if split[0].contains("announce") {
-            Ok(Endpoint::Announce)
-        } else if split[0].contains("stats") {
-            Ok(Endpoint::Stats)
-        } else if split[0].contains("end") {
-            Ok(Endpoint::End)
-        } else {
-            Ok(Endpoint::BadRequest)
+    /// Rutas que atiende el tracker.
+    fn router() -> Router<Endpoint> {
+        Router::new()
+            .route("GET", "/announce", Endpoint::Announce)
+            .route("GET", "/:passkey/announce", Endpoint::Announce)
+            .route("GET", "/stats", Endpoint::Stats)
+            .route("GET", "/metrics", Endpoint::Metrics)
+            .route("GET", "/end", Endpoint::End)
+            .route(
+                "GET",
+                "/admin/torrents",
+                Endpoint::Admin(AdminEndpoint::ListTorrents),
+            )
+            .route(
+                "POST",
+                "/admin/torrents",
+                Endpoint::Admin(AdminEndpoint::UploadTorrent),
+            )
+            .route(
+                "PUT",
+                "/admin/torrents/:info_hash",
+                Endpoint::Admin(AdminEndpoint::AddTorrent),
+            )
+            .route(
+                "DELETE",
+                "/admin/torrents/:info_hash",
+                Endpoint::Admin(AdminEndpoint::RemoveTorrent),
+            )
+            .route(
+                "PUT",
+                "/admin/whitelist/:info_hash",
+                Endpoint::Admin(AdminEndpoint::Whitelist),
+            )
+            .route(
+                "DELETE",
+                "/admin/whitelist/:info_hash",
+                Endpoint::Admin(AdminEndpoint::Unwhitelist),
+            )
+            .route(
+                "PUT",
+                "/admin/blacklist/:info_hash",
+                Endpoint::Admin(AdminEndpoint::Blacklist),
+            )
+            .route(
+                "DELETE",
+                "/admin/blacklist/:info_hash",
+                Endpoint::Admin(AdminEndpoint::Unblacklist),
+            )
+            .route(
+                "GET",
+                "/admin/users",
+                Endpoint::Admin(AdminEndpoint::ListUsers),
+            )
+            .route(
+                "PUT",
+                "/admin/users/:name",
+                Endpoint::Admin(AdminEndpoint::AddUser),
+            )
+            .route(
+                "DELETE",
+                "/admin/users/:name",
+                Endpoint::Admin(AdminEndpoint::RemoveUser),
+            )
+    }
+
+    /// Espera a lo sumo `keep_alive_timeout` a que llegue la siguiente request, para que los
+    /// clientes inactivos no ocupen un thread del pool. Devuelve false si no llego nada y
+    /// hay que cerrar la conexion.
+    fn wait_next_request(&mut self) -> bool {
+        let stream = self.reader.get_ref();
+        if stream
+            .set_read_timeout(Some(self.keep_alive_timeout))
+            .is_err()
+        {
+            return false;
+        }
+        let ready = matches!(self.reader.fill_buf(), Ok(buffer) if !buffer.is_empty());
+        let _ = self
+            .reader
+            .get_ref()
+            .set_read_timeout(Some(self.read_timeout));
+        ready
+    }
+
+    /// Lee la proxima request HTTP de la conexion. Devuelve None si el peer cerro la conexion.
+    fn read_stream(&mut self) -> Result<Option<HttpRequest>, HttpError> {
+        HttpRequest::read(&mut self.reader)
+    }
+
+    /// Maneja las requests recibidas desde la conexion y registra cuanto tardo en atenderlas.
+    /// Devuelve true si hay que cerrar la conexion.
+    fn handle_message(&mut self, request: &HttpRequest) -> Result<bool, BitTrackerError> {
+        let start = Instant::now();
+        if let Some(retry_after) = self.rate_limit(start)? {
+            println!(
+                "[TRACKER] La conexion {} supero el limite de requests",
+                self.id
+            );
+            let response = HttpResponse::error(503)
+                .with_header("Retry-After", &retry_after.to_string())
+                .with_header("Connection", "close");
+            self.send(response)?;
+            return Ok(true);
         }
+        let route = self.router.find(&request.method, &request.path);
+        let label = match &route {
+            Route::Found(endpoint, _params) => endpoint.label(),
+            _ => "unknown",
+        };
+        let result = self.dispatch(request, route);
+        let mut tracker = self
+            .tracker
+            .lock()
+            .or(Err(BitTrackerError::MutexLockError))?;
+        tracker
+            .metrics
+            .observe("http", label, start.elapsed().as_secs_f64());
+        drop(tracker);
+        result
     }
 
-    /// Maneja los mensajes recibidos desde la conexion, estos puden ser Announce, Stats o End.
-    /// En caso de recibir otro mensaje, devuelve un bad request.
-    fn handle_message(&mut self, message: String) -> Result<bool, BitTrackerError> {
-        let endpoint = Self::match_endpoint(message.to_lowercase())?;
-        match endpoint {
-            Endpoint::Announce => {
+    /// Registra la request en el limite de la ip de la conexion. Si lo supera devuelve los
+    /// segundos que el cliente deberia esperar.
+    fn rate_limit(&mut self, now: Instant) -> Result<Option<u64>, BitTrackerError> {
+        let ip = self.address.ip();
+        let mut tracker = self
+            .tracker
+            .lock()
+            .or(Err(BitTrackerError::MutexLockError))?;
+        if tracker.rate_limiter.allow(ip, now) {
+            return Ok(None);
+        }
+        tracker.metrics.count_rejected("rate_limit");
+        Ok(Some(tracker.rate_limiter.retry_after(ip, now)))
+    }
+
+    /// Atiende la request segun el endpoint: Announce, Stats, Metrics, End o la API de
+    /// administracion. Si el path no existe responde 404, y si existe para otro metodo 405.
+    fn dispatch(
+        &mut self,
+        request: &HttpRequest,
+        route: Route<Endpoint>,
+    ) -> Result<bool, BitTrackerError> {
+        match route {
+            Route::Found(Endpoint::Announce, params) => {
                 println!("[TRACKER] Recibi un Announce de la conexion {}", self.id);
                 let log_message = "- [INFO] Recibi un announce de la conexion : ".to_string()
                     + &self.id.to_string();
                 self.log
                     .send(log_message)
                     .or(Err(BitTrackerError::WriteLogError))?;
-                self.handle_announce(message)?;
+                self.handle_announce(request, &params)?;
             }
-            Endpoint::Stats => {
+            Route::Found(Endpoint::Stats, _params) => {
                 println!("[TRACKER] Recibi un Stats de la conexion {}", self.id);
                 let log_message =
                     "- [INFO] Recibi un Stats de la conexion : ".to_string() + &self.id.to_string();
                 self.log
                     .send(log_message)
                     .or(Err(BitTrackerError::WriteLogError))?;
-                self.handle_stats()?;
+                self.handle_stats(request)?;
+            }
+            Route::Found(Endpoint::Metrics, _params) => {
+                println!("[TRACKER] Recibi un Metrics de la conexion {}", self.id);
+                self.handle_metrics()?;
             }
-            Endpoint::End => {
+            Route::Found(Endpoint::End, _params) => {
                 println!("[TRACKER] Recibi un END de la conexion {}", self.id);
                 let log_message =
                     "- [INFO] Recibi un END de la conexion : ".to_string() + &self.id.to_string();
@@ -115,97 +316,150 @@ impl Connection {
                     .or(Err(BitTrackerError::WriteLogError))?;
                 return Ok(true);
             }
-            Endpoint::BadRequest => {
+            Route::Found(Endpoint::Admin(endpoint), params) => {
                 println!(
-                    "[TRACKER] Recibi un mensaje incomprensible de la conexion {}",
-                    self.id
+                    "[TRACKER] Recibi un {} {} de la conexion {}",
+                    request.method, request.path, self.id
+                );
+                let log_message = format!(
+                    "- [INFO] Recibi un {} {} de la conexion : {}",
+                    request.method, request.path, self.id
                 );
-                let log_message = "- [INFO] Recibi un mensaje incomprensible de la conexion : "
-                    .to_string()
-                    + &self.id.to_string();
                 self.log
                     .send(log_message)
                     .or(Err(BitTrackerError::WriteLogError))?;
-                self.send_bad_request()?;
+                self.handle_admin(endpoint, &params, request)?;
+            }
+            Route::NotFound => {
+                println!(
+                    "[TRACKER] Recibi una request a un path desconocido de la conexion {}: {}",
+                    self.id, request.path
+                );
+                let log_message =
+                    "- [INFO] Recibi una request a un path desconocido de la conexion : "
+                        .to_string()
+                        + &self.id.to_string();
+                self.log
+                    .send(log_message)
+                    .or(Err(BitTrackerError::WriteLogError))?;
+                self.send(HttpResponse::error(404))?;
+            }
+            Route::MethodNotAllowed(allowed) => {
+                println!(
+                    "[TRACKER] Recibi un metodo no permitido de la conexion {}: {} {}",
+                    self.id, request.method, request.path
+                );
+                let response = HttpResponse::error(405).with_header("Allow", &allowed.join(", "));
+                self.send(response)?;
             }
         }
         Ok(false)
     }
 
-    /// Si recibe una mensaje de Statas, calcula las estadisticas y devuelve un json por la conexion
-    fn handle_stats(&mut self) -> Result<(), BitTrackerError> {
+    /// Atiende las requests a la API de administracion.
+    fn handle_admin(
+        &mut self,
+        endpoint: AdminEndpoint,
+        params: &[(String, String)],
+        request: &HttpRequest,
+    ) -> Result<(), BitTrackerError> {
+        let remote = self.address.ip();
         let mut tracker = self
             .tracker
             .lock()
             .or(Err(BitTrackerError::MutexLockError))?;
-        let stats = tracker.get_stats()?;
+        let response = admin::handle(&mut tracker, endpoint, params, request, remote);
         drop(tracker);
-        let serialized = serde_json::to_string(&stats).unwrap() + "\n";
-        let message = format!(
-            "HTTP/1.1 200 OK \r\nContent-Length:{}\r\nContent-Type: application/json\r\nAccess-Control-Allow-Origin: *\r\n\r\n{}",
-            serialized.chars().count(),
-            serialized
-          );
-
-        self.stream
-            .write_all((message + "\n").as_bytes())
-            .or(Err(BitTrackerError::WriteConnectionError))?;
-        Ok(())
+        self.send(response)
+    }
+
+    /// Si recibe una mensaje de Statas, calcula las estadisticas y devuelve un json por la conexion.
+    /// Incluye las metricas historicas por hora o por dia segun los parametros de la request.
+    fn handle_stats(&mut self, request: &HttpRequest) -> Result<(), BitTrackerError> {
+        let query = match StatsQuery::from_http(request, Local::now().timestamp()) {
+            Ok(query) => query,
+            Err(error) => return self.send_stats_error(error),
+        };
+        let mut tracker = self
+            .tracker
+            .lock()
+            .or(Err(BitTrackerError::MutexLockError))?;
+        let mut stats = tracker.get_stats()?;
+        let series = tracker.get_history(&query);
+        drop(tracker);
+        stats.series = match series {
+            Ok(series) => series,
+            Err(error) => return self.send_stats_error(error),
+        };
+        let serialized =
+            serde_json::to_string(&stats).or(Err(BitTrackerError::WriteConnectionError))?;
+        let response = HttpResponse::new(200)
+            .with_header("Access-Control-Allow-Origin", "*")
+            .with_body("application/json", serialized.into_bytes());
+        self.send(response)
+    }
+
+    fn send_stats_error(&mut self, error: StatsError) -> Result<(), BitTrackerError> {
+        let response = HttpResponse::new(error.status())
+            .with_header("Access-Control-Allow-Origin", "*")
+            .with_body("text/plain", format!("{}\n", error).into_bytes());
+        self.send(response)
+    }
+
+    /// Devuelve las metricas del tracker en el formato de texto de Prometheus.
+    fn handle_metrics(&mut self) -> Result<(), BitTrackerError> {
+        let tracker = self
+            .tracker
+            .lock()
+            .or(Err(BitTrackerError::MutexLockError))?;
+        let metrics = tracker.metrics.render(&tracker);
+        drop(tracker);
+        let response =
+            HttpResponse::new(200).with_body("text/plain; version=0.0.4", metrics.into_bytes());
+        self.send(response)
     }
 
     /// En caso de recibir un announce, almacena la informacion en el tracker y genera la response.
-    fn handle_announce(&mut self, message: String) -> Result<(), BitTrackerError> {
+    /// Si el peer no informa su ip se usa la de la conexion. Si el announce llega por
+    /// `/<passkey>/announce` se asocia al usuario de la passkey. Los announces rechazados se
+    /// responden con el motivo en `failure reason`.
+    fn handle_announce(
+        &mut self,
+        http_request: &HttpRequest,
+        params: &[(String, String)],
+    ) -> Result<(), BitTrackerError> {
         let mut request = Request::new();
-        match request.parse_request(message) {
+        let result = match request.from_http(http_request) {
             Ok(request) => {
+                if let Some((_name, passkey)) = params.iter().find(|(name, _)| name == "passkey") {
+                    request.passkey = passkey.clone();
+                }
+                if request.ip.is_empty() {
+                    request.ip = self.address.ip().to_string();
+                }
                 println!("[TRACKER CONEXION {}]: {:?}", self.id, request);
                 let mut tracker = self
                     .tracker
                     .lock()
                     .or(Err(BitTrackerError::MutexLockError))?;
-
                 let result = tracker.receive_request(request);
                 drop(tracker);
-                match result {
-                    Ok(has_torrent) => {
-                        // Debo leer dos veces mas de la Conexión ya que un mensaje announce tiene 3 \n
-                        /*
-                        let mut buffer = String::new();
-                        self.reader
-                            .read_line(&mut buffer)
-                            .or(Err(BitTrackerError::ReadConnectionError))?;
-                        self.reader
-                            .read_line(&mut buffer)
-                            .or(Err(BitTrackerError::ReadConnectionError))?;
-                        */
-                        if has_torrent {
-                            self.make_response(request)?;
-                        } else {
-                            self.send_bad_request()?;
-                        }
-                    }
-                    Err(error) => {
-                        let message = "- [ERROR] Conexion ".to_string()
-                            + &self.id.to_string()
-                            + ": "
-                            + &error.to_string();
-                        println!("{}", message);
-                        self.log
-                            .send(message)
-                            .or(Err(BitTrackerError::WriteLogError))?;
-                        self.send_bad_request()?;
-                    }
-                }
-
-                Ok(())
+                result
             }
-            Err(_) => {
-                println!(
-                    "[ERROR] Parametro invalido en la request de la conexion {}",
-                    self.id
-                );
-                self.send_bad_request()?;
-                Ok(())
+            Err(error) => Err(AnnounceError::from(error)),
+        };
+        match result {
+            Ok(()) => self.make_response(&request),
+            Err(error) => {
+                let message = "- [ERROR] Conexion ".to_string()
+                    + &self.id.to_string()
+                    + ": announce rechazado: "
+                    + &error.to_string();
+                println!("{}", message);
+                self.log
+                    .send(message)
+                    .or(Err(BitTrackerError::WriteLogError))?;
+                self.send_failure(&error)
             }
         }
     }
@@ -219,35 +473,85 @@ impl Connection {
         let mut response = tracker.make_response(request)?;
         drop(tracker);
         let message = response.make_message();
-        self.stream
-            .write_all(message.as_bytes())
+        self.reader
+            .get_mut()
+            .write_all(&message)
             .or(Err(BitTrackerError::WriteConnectionError))?;
         Ok(())
     }
 
-    /// Escribe un mensaje de Bad Request por la conexion.
-    fn send_bad_request(&mut self) -> Result<(), BitTrackerError> {
-        let bad_request = "HTTP/1.1 400 Bad Request \r\n";
-        self.stream
-            .write_all(bad_request.as_bytes())
+    /// Escribe la response de un announce rechazado. Se cuenta como request invalida en las
+    /// metricas aunque se envie con status 200.
+    fn send_failure(&mut self, error: &AnnounceError) -> Result<(), BitTrackerError> {
+        let mut tracker = self
+            .tracker
+            .lock()
+            .or(Err(BitTrackerError::MutexLockError))?;
+        tracker.metrics.count_bad_request("http");
+        drop(tracker);
+        self.reader
+            .get_mut()
+            .write_all(&Response::failure(&error.to_string()))
+            .or(Err(BitTrackerError::WriteConnectionError))
+    }
+
+    /// Escribe la response por la conexion. Las responses 4xx se cuentan como requests
+    /// invalidas en las metricas.
+    fn send(&mut self, response: HttpResponse) -> Result<(), BitTrackerError> {
+        if (400..500).contains(&response.status) {
+            let mut tracker = self
+                .tracker
+                .lock()
+                .or(Err(BitTrackerError::MutexLockError))?;
+            tracker.metrics.count_bad_request("http");
+            drop(tracker);
+        }
+        self.reader
+            .get_mut()
+            .write_all(&response.to_bytes())
             .or(Err(BitTrackerError::WriteConnectionError))?;
         Ok(())
     }
 
-    /// Inicializa la conexion y se queda leyendo.
+    /// Inicializa la conexion y atiende requests hasta que el peer la cierre, pida cerrarla,
+    /// no envie la siguiente request a tiempo o envie una request invalida, a la que se
+    /// responde con el error correspondiente.
     pub fn connect(
         id: usize,
-        stream: TcpStream,
+        stream: Stream,
         tracker: Arc<Mutex<BitTracker>>,
     ) -> Result<(), BitTrackerError> {
         let mut connection = Connection::new(id, stream, tracker)?;
 
         let mut done = false;
         while !done {
-            let messages = connection.read_stream()?;
-            if !messages.is_empty() {
-                println!("[TRACKER] Recibi el mensaje : {}", messages);
-                done = connection.handle_message(messages)?;
+            match connection.read_stream() {
+                Ok(Some(request)) => {
+                    println!(
+                        "[TRACKER] Recibi la request : {} {}",
+                        request.method, request.path
+                    );
+                    done = connection.handle_message(&request)?
+                        || !request.keep_alive()
+                        || !connection.wait_next_request();
+                }
+                Ok(None) => done = true,
+                Err(HttpError::ReadError) => return Err(BitTrackerError::ReadConnectionError),
+                Err(error) => {
+                    let message = "- [ERROR] Conexion ".to_string()
+                        + &connection.id.to_string()
+                        + ": "
+                        + &error.to_string();
+                    println!("{}", message);
+                    connection
+                        .log
+                        .send(message)
+                        .or(Err(BitTrackerError::WriteLogError))?;
+                    let response =
+                        HttpResponse::error(error.status()).with_header("Connection", "close");
+                    connection.send(response)?;
+                    done = true;
+                }
             }
         }
 
@@ -264,11 +568,97 @@ impl Connection {
 #[cfg(test)]
 mod connection_should {
     use super::*;
-    use std::io::BufRead;
+    use std::io::{BufRead, Read};
     use std::net::TcpListener;
     use std::sync::mpsc;
     use std::thread;
 
+    fn read_response(reader: &mut BufReader<TcpStream>) -> (String, String) {
+        let mut status = String::new();
+        reader.read_line(&mut status).unwrap();
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        (status, String::from_utf8(body).unwrap())
+    }
+
+    #[test]
+    fn route_requests_by_path_and_method() {
+        let (tx, _rx) = mpsc::channel();
+        let tracker = BitTracker::new("./config_file", tx).unwrap();
+        let tracker = Arc::new(Mutex::new(tracker));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            stream
+                .write_all(b"GET /foo?x=announce HTTP/1.1\r\n\r\n")
+                .unwrap();
+            assert_eq!(read_response(&mut reader).0, "HTTP/1.1 404 Not Found\r\n");
+
+            stream.write_all(b"POST /stats HTTP/1.1\r\n\r\n").unwrap();
+            assert_eq!(
+                read_response(&mut reader).0,
+                "HTTP/1.1 405 Method Not Allowed\r\n"
+            );
+
+            stream.write_all(b"GET /stats HTTP/1.1\r\n\r\n").unwrap();
+            let (status, body) = read_response(&mut reader);
+            assert_eq!(status, "HTTP/1.1 200 OK\r\n");
+            assert!(body.starts_with('{'));
+
+            stream.write_all(b"GET /stats HTTP/9.9\r\n\r\n").unwrap();
+            assert_eq!(
+                read_response(&mut reader).0,
+                "HTTP/1.1 505 HTTP Version Not Supported\r\n"
+            );
+        });
+
+        let stream = listener.accept().unwrap();
+        Connection::connect(1, Stream::Plain(stream.0), tracker).unwrap();
+
+        client.join().unwrap();
+    }
+
+    #[test]
+    fn close_idle_keep_alive_connections() {
+        let (tx, _rx) = mpsc::channel();
+        let mut tracker = BitTracker::new("./config_file", tx).unwrap();
+        tracker.keep_alive_timeout = 1;
+        let tracker = Arc::new(Mutex::new(tracker));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            stream.write_all(b"GET /stats HTTP/1.1\r\n\r\n").unwrap();
+            assert_eq!(read_response(&mut reader).0, "HTTP/1.1 200 OK\r\n");
+            assert_eq!(reader.read(&mut [0; 1]).unwrap(), 0);
+        });
+
+        let start = Instant::now();
+        let stream = listener.accept().unwrap();
+        Connection::connect(1, Stream::Plain(stream.0), tracker).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        client.join().unwrap();
+    }
+
     #[test]
     #[ignore]
     fn initialize_connection() {
@@ -281,7 +671,7 @@ mod connection_should {
         let connection = thread::spawn(move || {
             println!("Por enviar basado");
             let mut stream = TcpStream::connect("127.0.0.1:8080").unwrap();
-            let message = "GET /BASADO?\n";
+            let message = "GET /BASADO? HTTP/1.1\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
             println!("Esperando respuesta");
             let stream_clone = stream.try_clone().unwrap();
@@ -290,12 +680,12 @@ mod connection_should {
             reader.read_line(&mut buffer).unwrap();
             println!("[Peer] recibi Response : {}", buffer);
 
-            let message = "GET /END\n";
+            let message = "GET /end HTTP/1.1\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
         });
 
         let stream = listener.accept().unwrap();
-        Connection::connect(1, stream.0, tracker).unwrap();
+        Connection::connect(1, Stream::Plain(stream.0), tracker).unwrap();
 
         connection.join().unwrap();
     }
@@ -311,7 +701,7 @@ mod connection_should {
 
         let connection = thread::spawn(move || {
             let mut stream = TcpStream::connect("127.0.0.1:8080").unwrap();
-            let message = "GET /STATS\n";
+            let message = "GET /stats HTTP/1.1\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
 
             let stream_clone = stream.try_clone().unwrap();
@@ -325,12 +715,12 @@ mod connection_should {
             reader.read_line(&mut buffer).unwrap();
             println!("[Peer] recibi Response : {}", buffer);
 
-            let message = "GET /END\n";
+            let message = "GET /end HTTP/1.1\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
         });
 
         let stream = listener.accept().unwrap();
-        Connection::connect(1, stream.0, tracker).unwrap();
+        Connection::connect(1, Stream::Plain(stream.0), tracker).unwrap();
 
         connection.join().unwrap();
     }
@@ -346,7 +736,7 @@ mod connection_should {
 
         let connection = thread::spawn(move || {
             let mut stream = TcpStream::connect("127.0.0.1:8080").unwrap();
-            let message = "GET /announce?infohash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-4R01010-D23T24S25F26&port=6881&uploaded=0&downloaded=0&left=0&compact=1&event=started&ip=186.189.238.5 HTTP/1.0\r\nHost: torrent.ubuntu.com\r\n\r\n";
+            let message = "GET /announce?info_hash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-4R01010-D23T24S25F26&port=6881&uploaded=0&downloaded=0&left=0&compact=1&event=started&ip=186.189.238.5 HTTP/1.1\r\nHost: torrent.ubuntu.com\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
 
             let stream_clone = stream.try_clone().unwrap();
@@ -355,13 +745,13 @@ mod connection_should {
             reader.read_line(&mut buffer).unwrap();
             println!("[Peer] recibi Response : {}", buffer);
 
-            let message = "GET /announce?infohash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-SeHaceElOtro-D23T24S25F26&port=6831&uploaded=1&downloaded=2&left=0&compact=0&event=completed&ip=186.189.238.5 HTTP/1.0\r\nHost: torrent.ubuntu.com\r\n\r\n";
+            let message = "GET /announce?info_hash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-SeHaceElOtro-D23T24S25F26&port=6831&uploaded=1&downloaded=2&left=0&compact=0&event=completed&ip=186.189.238.5 HTTP/1.1\r\nHost: torrent.ubuntu.com\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
             let mut buffer = String::new();
             reader.read_line(&mut buffer).unwrap();
             println!("[Peer] recibi Response : {}", buffer);
 
-            let message = "GET /STATS\n";
+            let message = "GET /stats HTTP/1.1\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
             let mut buffer = String::new();
             reader.read_line(&mut buffer).unwrap();
@@ -373,12 +763,12 @@ mod connection_should {
             reader.read_line(&mut buffer).unwrap();
             println!("[Peer] recibi Stats : {}", buffer);
 
-            let message = "GET /END\n";
+            let message = "GET /end HTTP/1.1\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
         });
 
         let stream = listener.accept().unwrap();
-        Connection::connect(1, stream.0, tracker).unwrap();
+        Connection::connect(1, Stream::Plain(stream.0), tracker).unwrap();
 
         connection.join().unwrap();
     }
@@ -394,7 +784,7 @@ mod connection_should {
 
         let connection = thread::spawn(move || {
             let mut stream = TcpStream::connect("127.0.0.1:8080").unwrap();
-            let message = "GET /announce?infohash=7f%ff%c4g&peer_id=-4R01010-D23T24S25F26&port=6881&event=started&ip=186.189.238.5 HTTP/1.0\r\nHost: torrent.ubuntu.com\r\n\r\n";
+            let message = "GET /announce?info_hash=7f%ff%c4g&peer_id=-4R01010-D23T24S25F26&port=6881&event=started&ip=186.189.238.5 HTTP/1.1\r\nHost: torrent.ubuntu.com\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
 
             let stream_clone = stream.try_clone().unwrap();
@@ -403,13 +793,13 @@ mod connection_should {
             reader.read_line(&mut buffer).unwrap();
             println!("[Peer] recibi Response : {}", buffer);
 
-            let message = "GET /announce?infohash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-SeHaceElOtro-D23T24S25F26&port=6831&uploaded=1&downloaded=2&left=0&compact=0&event=completed&ip=186.189.238.5 HTTP/1.0\r\nHost: torrent.ubuntu.com\r\n\r\n";
+            let message = "GET /announce?info_hash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-SeHaceElOtro-D23T24S25F26&port=6831&uploaded=1&downloaded=2&left=0&compact=0&event=completed&ip=186.189.238.5 HTTP/1.1\r\nHost: torrent.ubuntu.com\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
             let mut buffer = String::new();
             reader.read_line(&mut buffer).unwrap();
             println!("[Peer] recibi Response : {}", buffer);
 
-            let message = "GET /STATS\n";
+            let message = "GET /stats HTTP/1.1\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
             let mut buffer = String::new();
             reader.read_line(&mut buffer).unwrap();
@@ -421,12 +811,12 @@ mod connection_should {
             reader.read_line(&mut buffer).unwrap();
             println!("[Peer] recibi Stats : {}", buffer);
 
-            let message = "GET /END\n";
+            let message = "GET /end HTTP/1.1\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
         });
 
         let stream = listener.accept().unwrap();
-        Connection::connect(1, stream.0, tracker).unwrap();
+        Connection::connect(1, Stream::Plain(stream.0), tracker).unwrap();
 
         connection.join().unwrap();
     }
@@ -442,7 +832,7 @@ mod connection_should {
 
         let connection = thread::spawn(move || {
             let mut stream = TcpStream::connect("127.0.0.1:8080").unwrap();
-            let message = "GET /announce?infohash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-4R01010-D23T24S25F26&port=6881&uploaded=0&downloaded=0&left=0&compact=1&event=started&ip=186.189.238.5 HTTP/1.0\r\nHost: torrent.ubuntu.com\r\n\r\n";
+            let message = "GET /announce?info_hash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-4R01010-D23T24S25F26&port=6881&uploaded=0&downloaded=0&left=0&compact=1&event=started&ip=186.189.238.5 HTTP/1.1\r\nHost: torrent.ubuntu.com\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
 
             let stream_clone = stream.try_clone().unwrap();
@@ -451,13 +841,13 @@ mod connection_should {
             reader.read_line(&mut buffer).unwrap();
             println!("[Peer] recibi Response : {}", buffer);
 
-            let message = "GET /announce?infohash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-4R01010-D23T24S25F26&port=6831&uploaded=1&downloaded=2&left=0&compact=1&event=completed&ip=186.189.238.5 HTTP/1.0\r\nHost: torrent.ubuntu.com\r\n\r\n";
+            let message = "GET /announce?info_hash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-4R01010-D23T24S25F26&port=6831&uploaded=1&downloaded=2&left=0&compact=1&event=completed&ip=186.189.238.5 HTTP/1.1\r\nHost: torrent.ubuntu.com\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
             let mut buffer = String::new();
             reader.read_line(&mut buffer).unwrap();
             println!("[Peer] recibi Response : {}", buffer);
 
-            let message = "GET /STATS\n";
+            let message = "GET /stats HTTP/1.1\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
             let mut buffer = String::new();
             reader.read_line(&mut buffer).unwrap();
@@ -469,12 +859,12 @@ mod connection_should {
             reader.read_line(&mut buffer).unwrap();
             println!("[Peer] recibi Stats : {}", buffer);
 
-            let message = "GET /END\n";
+            let message = "GET /end HTTP/1.1\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
         });
 
         let stream = listener.accept().unwrap();
-        Connection::connect(1, stream.0, tracker).unwrap();
+        Connection::connect(1, Stream::Plain(stream.0), tracker).unwrap();
 
         connection.join().unwrap();
     }
@@ -484,19 +874,19 @@ mod connection_should {
     fn send_multiple_announce() {
         let connection1 = thread::spawn(move || {
             let mut stream = TcpStream::connect("127.0.0.1:8080").unwrap();
-            let message = "GET /announce?infohash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-4R01010-D23T24S25F26&port=6881&uploaded=0&downloaded=0&left=0&compact=1&event=started&ip=186.189.238.5 HTTP/1.0\r\nHost: torrent.ubuntu.com\r\n\r\n";
+            let message = "GET /announce?info_hash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-4R01010-D23T24S25F26&port=6881&uploaded=0&downloaded=0&left=0&compact=1&event=started&ip=186.189.238.5 HTTP/1.1\r\nHost: torrent.ubuntu.com\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
 
             //let mut stream = TcpStream::connect("127.0.0.1:8080").unwrap();
-            let message = "GET /announce?infohash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-420-D23T24S25F26&port=6881&uploaded=0&downloaded=0&left=0&compact=1&event=started&ip=186.189.238.5 HTTP/1.0\r\nHost: torrent.ubuntu.com\r\n\r\n";
+            let message = "GET /announce?info_hash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-420-D23T24S25F26&port=6881&uploaded=0&downloaded=0&left=0&compact=1&event=started&ip=186.189.238.5 HTTP/1.1\r\nHost: torrent.ubuntu.com\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
 
             let mut stream = TcpStream::connect("127.0.0.1:8080").unwrap();
-            let message = "GET /announce?infohash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-415-D23T24S25F26&port=6881&uploaded=0&downloaded=0&left=0&compact=1&event=completed&ip=186.189.238.5 HTTP/1.0\r\nHost: torrent.ubuntu.com\r\n\r\n";
+            let message = "GET /announce?info_hash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-415-D23T24S25F26&port=6881&uploaded=0&downloaded=0&left=0&compact=1&event=completed&ip=186.189.238.5 HTTP/1.1\r\nHost: torrent.ubuntu.com\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
 
             //let mut stream = TcpStream::connect("127.0.0.1:8080").unwrap();
-            let message = "GET /announce?infohash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-470-D23T24S25F26&port=6881&uploaded=0&downloaded=0&left=0&compact=1&event=completed&ip=186.189.238.5 HTTP/1.0\r\nHost: torrent.ubuntu.com\r\n\r\n";
+            let message = "GET /announce?info_hash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id=-470-D23T24S25F26&port=6881&uploaded=0&downloaded=0&left=0&compact=1&event=completed&ip=186.189.238.5 HTTP/1.1\r\nHost: torrent.ubuntu.com\r\n\r\n";
             stream.write_all(message.as_bytes()).unwrap();
         });
 
@@ -507,7 +897,7 @@ mod connection_should {
     #[ignore]
     fn request_stats() {
         let mut stream = TcpStream::connect("127.0.0.1:8080").unwrap();
-        let message = "GET /STATS\n";
+        let message = "GET /stats HTTP/1.1\r\n\r\n";
         stream.write_all(message.as_bytes()).unwrap();
 
         let stream_clone = stream.try_clone().unwrap();
@@ -521,7 +911,7 @@ mod connection_should {
         reader.read_line(&mut buffer).unwrap();
         println!("[Peer] recibi Response : {}", buffer);
 
-        let message = "GET /END\n";
+        let message = "GET /end HTTP/1.1\r\n\r\n";
         stream.write_all(message.as_bytes()).unwrap();
     }
 }
@@ -1,3 +1,5 @@
+use protocol::peer_protocol::errors::PeerProtocolError;
+use protocol::torrent_file::errors::MetaInfoError;
 use std::fmt;
 /******************************************************************************************/
 /*                                  BitTracker ERROR                                        */
@@ -30,6 +32,8 @@ pub enum BitTrackerError {
     InvalidUTF8HandshakeError,
     BadPeerResponseError,
     UploadError,
+    ConfigError(ConfigError),
+    AnnounceFailureError(String),
 }
 #[allow(dead_code)]
 impl fmt::Display for BitTrackerError {
@@ -69,6 +73,36 @@ impl fmt::Display for BitTrackerError {
             BitTrackerError::BadPeerResponseError => {
                 write!(f, "Error al recibir respuesta del peer")
             }
+            BitTrackerError::ConfigError(ref error) => {
+                write!(f, "Configuracion invalida: {}", error)
+            }
+            BitTrackerError::AnnounceFailureError(ref reason) => {
+                write!(f, "El tracker rechazo el announce: {}", reason)
+            }
+        }
+    }
+}
+
+impl From<MetaInfoError> for BitTrackerError {
+    fn from(error: MetaInfoError) -> Self {
+        match error {
+            MetaInfoError::ReadFileError => BitTrackerError::ReadFileError,
+            MetaInfoError::OpenFileError => BitTrackerError::OpenFileError,
+            MetaInfoError::DecodingError => BitTrackerError::DecodingError,
+            MetaInfoError::IntegerConvertionError => BitTrackerError::IntegerConvertionError,
+        }
+    }
+}
+
+impl From<PeerProtocolError> for BitTrackerError {
+    fn from(error: PeerProtocolError) -> Self {
+        match error {
+            PeerProtocolError::ConnectionClosedError
+            | PeerProtocolError::ReadConnectionError
+            | PeerProtocolError::TimeoutError => BitTrackerError::ReadConnectionError,
+            PeerProtocolError::WriteConnectionError => BitTrackerError::WriteConnectionError,
+            PeerProtocolError::MessageTooLongError => BitTrackerError::DataSizeError,
+            _ => BitTrackerError::InvalidMessageError,
         }
     }
 }
@@ -76,12 +110,14 @@ impl fmt::Display for BitTrackerError {
 /******************************************************************************************/
 /*                                  Request ERROR                                        */
 /******************************************************************************************/
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 #[allow(clippy::enum_variant_names)]
 #[allow(dead_code)]
 pub enum RequestError {
     InvalidSyntaxError,
     InvalidParameterError,
+    MissingParameterError(&'static str),
+    InvalidPortError,
 }
 
 impl fmt::Display for RequestError {
@@ -93,6 +129,248 @@ impl fmt::Display for RequestError {
             RequestError::InvalidParameterError => {
                 write!(f, "No se pudo parsear el diccionario")
             }
+            RequestError::MissingParameterError(name) => write!(f, "Falta el parametro {}", name),
+            RequestError::InvalidPortError => write!(f, "Puerto invalido"),
+        }
+    }
+}
+
+/******************************************************************************************/
+/*                                    HTTP ERROR                                          */
+/******************************************************************************************/
+#[derive(Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum HttpError {
+    ReadError,
+    MalformedRequestError,
+    HeadTooLargeError,
+    BodyTooLargeError,
+    UnsupportedVersionError,
+}
+
+impl HttpError {
+    /// Devuelve el codigo de estado con el que se responde a la request invalida.
+    pub fn status(&self) -> u16 {
+        match *self {
+            HttpError::ReadError | HttpError::MalformedRequestError => 400,
+            HttpError::HeadTooLargeError => 431,
+            HttpError::BodyTooLargeError => 413,
+            HttpError::UnsupportedVersionError => 505,
+        }
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HttpError::ReadError => write!(f, "No se pudo leer la request HTTP"),
+            HttpError::MalformedRequestError => write!(f, "La request HTTP esta mal formada"),
+            HttpError::HeadTooLargeError => {
+                write!(f, "Los headers de la request HTTP son demasiado grandes")
+            }
+            HttpError::BodyTooLargeError => {
+                write!(f, "El body de la request HTTP es demasiado grande")
+            }
+            HttpError::UnsupportedVersionError => write!(f, "Version de HTTP no soportada"),
+        }
+    }
+}
+
+/******************************************************************************************/
+/*                                     UDP ERROR                                          */
+/******************************************************************************************/
+#[derive(Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum UdpError {
+    MalformedPacketError,
+    InvalidProtocolError,
+    UnknownActionError,
+    TooManyHashesError,
+    InvalidConnectionIdError,
+    InvalidAnnounceError,
+    AnnounceError(AnnounceError),
+    InternalError,
+}
+
+impl fmt::Display for UdpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UdpError::MalformedPacketError => write!(f, "Paquete mal formado"),
+            UdpError::InvalidProtocolError => write!(f, "Protocol id invalido"),
+            UdpError::UnknownActionError => write!(f, "Accion desconocida"),
+            UdpError::TooManyHashesError => write!(f, "Demasiados info hashes en el scrape"),
+            UdpError::InvalidConnectionIdError => write!(f, "Connection id invalido o expirado"),
+            UdpError::InvalidAnnounceError => write!(f, "Announce invalido"),
+            UdpError::AnnounceError(ref error) => write!(f, "{}", error),
+            UdpError::InternalError => write!(f, "Error interno del tracker"),
+        }
+    }
+}
+
+/******************************************************************************************/
+/*                                    ADMIN ERROR                                         */
+/******************************************************************************************/
+#[derive(Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum AdminError {
+    UnauthorizedError,
+    InvalidInfoHashError,
+    InvalidTorrentError,
+    NotAllowedError,
+    AlreadyRegisteredError,
+    UnknownTorrentError,
+    InvalidUserError,
+    UserExistsError,
+    UnknownUserError,
+    StorageError,
+}
+
+impl AdminError {
+    /// Devuelve el codigo de estado con el que se responde a la request.
+    pub fn status(&self) -> u16 {
+        match *self {
+            AdminError::UnauthorizedError | AdminError::NotAllowedError => 403,
+            AdminError::InvalidInfoHashError
+            | AdminError::InvalidTorrentError
+            | AdminError::InvalidUserError => 400,
+            AdminError::AlreadyRegisteredError | AdminError::UserExistsError => 409,
+            AdminError::UnknownTorrentError | AdminError::UnknownUserError => 404,
+            AdminError::StorageError => 500,
+        }
+    }
+}
+
+impl fmt::Display for AdminError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AdminError::UnauthorizedError => write!(f, "No autorizado"),
+            AdminError::InvalidInfoHashError => write!(f, "Info hash invalido"),
+            AdminError::InvalidTorrentError => write!(f, "Archivo .torrent invalido"),
+            AdminError::NotAllowedError => write!(f, "El torrent no esta permitido"),
+            AdminError::AlreadyRegisteredError => write!(f, "El torrent ya esta registrado"),
+            AdminError::UnknownTorrentError => write!(f, "El tracker no hostea el torrent"),
+            AdminError::InvalidUserError => write!(f, "Nombre de usuario invalido"),
+            AdminError::UserExistsError => write!(f, "El usuario ya existe"),
+            AdminError::UnknownUserError => write!(f, "El usuario no existe"),
+            AdminError::StorageError => write!(f, "No se pudo guardar el estado del tracker"),
+        }
+    }
+}
+
+/******************************************************************************************/
+/*                                    STATS ERROR                                         */
+/******************************************************************************************/
+#[derive(Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum StatsError {
+    InvalidResolutionError,
+    InvalidRangeError,
+    UnknownTorrentError,
+}
+
+impl StatsError {
+    /// Devuelve el codigo de estado con el que se responde a la request.
+    pub fn status(&self) -> u16 {
+        match *self {
+            StatsError::InvalidResolutionError | StatsError::InvalidRangeError => 400,
+            StatsError::UnknownTorrentError => 404,
+        }
+    }
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StatsError::InvalidResolutionError => {
+                write!(f, "Resolucion invalida, debe ser hour o day")
+            }
+            StatsError::InvalidRangeError => write!(f, "Rango de tiempo invalido"),
+            StatsError::UnknownTorrentError => write!(f, "El tracker no hostea el torrent"),
+        }
+    }
+}
+
+/******************************************************************************************/
+/*                                   ANNOUNCE ERROR                                       */
+/******************************************************************************************/
+/// Motivos por los que el tracker rechaza un announce. El mensaje se envia al peer como
+/// `failure reason`.
+#[derive(Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum AnnounceError {
+    MissingParameterError(&'static str),
+    InvalidPortError,
+    InvalidEventError,
+    InvalidRequestError,
+    UnknownTorrentError,
+    BannedTorrentError,
+    UnknownPasskeyError,
+    PasskeyRequiredError,
+    StorageError,
+}
+
+impl From<RequestError> for AnnounceError {
+    fn from(error: RequestError) -> Self {
+        match error {
+            RequestError::MissingParameterError(name) => AnnounceError::MissingParameterError(name),
+            RequestError::InvalidPortError => AnnounceError::InvalidPortError,
+            _ => AnnounceError::InvalidRequestError,
+        }
+    }
+}
+
+impl fmt::Display for AnnounceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AnnounceError::MissingParameterError(name) => write!(f, "Falta el parametro {}", name),
+            AnnounceError::InvalidPortError => write!(f, "Puerto invalido"),
+            AnnounceError::InvalidEventError => write!(f, "Evento invalido"),
+            AnnounceError::InvalidRequestError => write!(f, "Announce invalido"),
+            AnnounceError::UnknownTorrentError => write!(f, "El tracker no hostea el torrent"),
+            AnnounceError::BannedTorrentError => {
+                write!(f, "El torrent esta bloqueado en el tracker")
+            }
+            AnnounceError::UnknownPasskeyError => write!(f, "Passkey desconocida"),
+            AnnounceError::PasskeyRequiredError => {
+                write!(f, "El torrent es privado, el announce requiere una passkey")
+            }
+            AnnounceError::StorageError => write!(f, "Error interno del tracker"),
+        }
+    }
+}
+
+/******************************************************************************************/
+/*                                    CONFIG ERROR                                        */
+/******************************************************************************************/
+#[derive(Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum ConfigError {
+    /// Clave desconocida en el archivo de configuracion.
+    UnknownKeyError(String),
+    /// Valor invalido para la clave.
+    InvalidValueError(String),
+    MissingTlsError,
+    InvalidTlsError,
+    InvalidIntervalError,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::UnknownKeyError(ref key) => write!(f, "Clave desconocida: {}", key),
+            ConfigError::InvalidValueError(ref key) => {
+                write!(f, "Valor invalido para la clave {}", key)
+            }
+            ConfigError::MissingTlsError => write!(
+                f,
+                "HTTPS_ADDRESS, TLS_CERTIFICATE y TLS_KEY deben configurarse juntos"
+            ),
+            ConfigError::InvalidTlsError => {
+                write!(f, "No se pudo cargar el certificado o la clave TLS")
+            }
+            ConfigError::InvalidIntervalError => {
+                write!(f, "Se debe cumplir 0 < MIN_INTERVAL <= INTERVAL")
+            }
         }
     }
 }
@@ -0,0 +1,310 @@
+use crate::errors::HttpError;
+use protocol::encoder::url_encoder::URLEncoder;
+use std::io::{BufRead, Read};
+
+/******************************************************************************************/
+/*                                   HTTP REQUEST                                         */
+/******************************************************************************************/
+
+pub const MAX_HEAD_SIZE: usize = 8192;
+pub const MAX_HEADERS: usize = 64;
+/// Alcanza para subir archivos .torrent por la API de administracion.
+pub const MAX_BODY_SIZE: usize = 1 << 20;
+
+type Result<T> = std::result::Result<T, HttpError>;
+
+/// Parametros del query string, en orden, con el valor decodificado.
+pub type QueryParams = Vec<(String, Vec<u8>)>;
+
+/// Estructura que modela una request HTTP/1.x ya parseada.
+/// El path y los parametros del query string estan decodificados; los valores de los parametros
+/// se guardan como bytes porque el info_hash y el peer_id pueden no ser UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub query: QueryParams,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Lee una request completa de la conexion: request line, headers y, si hay Content-Length,
+    /// el body. Devuelve None si la conexion se cerro antes de recibir una nueva request.
+    pub fn read(reader: &mut impl BufRead) -> Result<Option<HttpRequest>> {
+        let mut head_size = 0;
+        let mut request_line = String::new();
+        // Se ignoran las lineas vacias previas a la request line (RFC 7230, 3.5).
+        while request_line.is_empty() {
+            request_line = match Self::read_line(reader, &mut head_size)? {
+                Some(line) => line,
+                None => return Ok(None),
+            };
+        }
+        let (method, target, version) = Self::parse_request_line(&request_line)?;
+
+        let mut headers = vec![];
+        loop {
+            let line =
+                Self::read_line(reader, &mut head_size)?.ok_or(HttpError::MalformedRequestError)?;
+            if line.is_empty() {
+                break;
+            }
+            if headers.len() == MAX_HEADERS {
+                return Err(HttpError::HeadTooLargeError);
+            }
+            headers.push(Self::parse_header(&line)?);
+        }
+
+        let (path, query) = Self::parse_target(target)?;
+        let mut request = HttpRequest {
+            method: method.to_string(),
+            path,
+            query,
+            version: version.to_string(),
+            headers,
+            body: vec![],
+        };
+        request.body = request.read_body(reader)?;
+        Ok(Some(request))
+    }
+
+    /// Lee una linea terminada en \n (con o sin \r), controlando el tamaño maximo del head.
+    fn read_line(reader: &mut impl BufRead, head_size: &mut usize) -> Result<Option<String>> {
+        let mut line = vec![];
+        let remaining = (MAX_HEAD_SIZE - *head_size) as u64;
+        let read = reader
+            .by_ref()
+            .take(remaining + 1)
+            .read_until(b'\n', &mut line)
+            .or(Err(HttpError::ReadError))?;
+        if read == 0 {
+            return Ok(None);
+        }
+        *head_size += read;
+        if *head_size > MAX_HEAD_SIZE {
+            return Err(HttpError::HeadTooLargeError);
+        }
+        if line.pop() != Some(b'\n') {
+            return Err(HttpError::MalformedRequestError);
+        }
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        let line = String::from_utf8(line).or(Err(HttpError::MalformedRequestError))?;
+        Ok(Some(line))
+    }
+
+    fn parse_request_line(line: &str) -> Result<(&str, &str, &str)> {
+        let parts: Vec<&str> = line.split(' ').collect();
+        if parts.len() != 3 {
+            return Err(HttpError::MalformedRequestError);
+        }
+        let (method, target, version) = (parts[0], parts[1], parts[2]);
+        if method.is_empty() || !method.bytes().all(|byte| byte.is_ascii_uppercase()) {
+            return Err(HttpError::MalformedRequestError);
+        }
+        if !target.starts_with('/') {
+            return Err(HttpError::MalformedRequestError);
+        }
+        if !version.starts_with("HTTP/") {
+            return Err(HttpError::MalformedRequestError);
+        }
+        if version != "HTTP/1.0" && version != "HTTP/1.1" {
+            return Err(HttpError::UnsupportedVersionError);
+        }
+        Ok((method, target, version))
+    }
+
+    /// Separa el header en nombre y valor. Los nombres se guardan en minuscula.
+    fn parse_header(line: &str) -> Result<(String, String)> {
+        let (name, value) = line
+            .split_once(':')
+            .ok_or(HttpError::MalformedRequestError)?;
+        if name.is_empty() || name.bytes().any(|byte| byte.is_ascii_whitespace()) {
+            return Err(HttpError::MalformedRequestError);
+        }
+        Ok((name.to_ascii_lowercase(), value.trim().to_string()))
+    }
+
+    /// Separa el path del query string y decodifica ambos.
+    fn parse_target(target: &str) -> Result<(String, QueryParams)> {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let path = URLEncoder
+            .urldecode(&path.replace('+', "%2B"))
+            .or(Err(HttpError::MalformedRequestError))?;
+        let path = String::from_utf8(path).or(Err(HttpError::MalformedRequestError))?;
+
+        let mut params = vec![];
+        for param in query.split('&').filter(|param| !param.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            let key = URLEncoder
+                .urldecode(key)
+                .or(Err(HttpError::MalformedRequestError))?;
+            let value = URLEncoder
+                .urldecode(value)
+                .or(Err(HttpError::MalformedRequestError))?;
+            params.push((String::from_utf8_lossy(&key).to_string(), value));
+        }
+        Ok((path, params))
+    }
+
+    /// Lee el body indicado por el header Content-Length. No se soporta Transfer-Encoding.
+    fn read_body(&self, reader: &mut impl BufRead) -> Result<Vec<u8>> {
+        if self.header("transfer-encoding").is_some() {
+            return Err(HttpError::MalformedRequestError);
+        }
+        let length = match self.header("content-length") {
+            Some(length) => length
+                .parse::<usize>()
+                .or(Err(HttpError::MalformedRequestError))?,
+            None => return Ok(vec![]),
+        };
+        if length > MAX_BODY_SIZE {
+            return Err(HttpError::BodyTooLargeError);
+        }
+        let mut body = vec![0; length];
+        reader
+            .read_exact(&mut body)
+            .or(Err(HttpError::MalformedRequestError))?;
+        Ok(body)
+    }
+
+    /// Devuelve el valor del header, sin importar mayusculas y minusculas en el nombre.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _value)| key.eq_ignore_ascii_case(name))
+            .map(|(_key, value)| value.as_str())
+    }
+
+    /// Devuelve el valor decodificado del parametro del query string.
+    pub fn param(&self, key: &str) -> Option<&[u8]> {
+        self.query
+            .iter()
+            .find(|(param, _value)| param == key)
+            .map(|(_param, value)| value.as_slice())
+    }
+
+    /// Devuelve el valor del parametro como texto, si es UTF-8 valido.
+    pub fn param_str(&self, key: &str) -> Option<&str> {
+        self.param(key)
+            .and_then(|value| std::str::from_utf8(value).ok())
+    }
+
+    /// Indica si la conexion debe mantenerse abierta despues de responder.
+    /// En HTTP/1.1 es el comportamiento por defecto, en HTTP/1.0 debe pedirse explicitamente.
+    pub fn keep_alive(&self) -> bool {
+        let connection = self.header("connection").map(str::to_ascii_lowercase);
+        match connection.as_deref() {
+            Some("close") => false,
+            Some("keep-alive") => true,
+            _ => self.version == "HTTP/1.1",
+        }
+    }
+}
+
+#[cfg(test)]
+mod http_request_should {
+    use super::*;
+
+    fn parse(raw: &[u8]) -> Result<Option<HttpRequest>> {
+        HttpRequest::read(&mut &raw[..])
+    }
+
+    #[test]
+    fn parse_announce_with_binary_params() {
+        let raw = b"GET /announce?info_hash=%a9%b3%27cNr%21~%e0&peer_id=-4R01010-D23T24S25F26&port=6881 HTTP/1.0\r\nHost: 127.0.0.1:8080\r\n\r\n";
+        let request = parse(raw).unwrap().unwrap();
+
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/announce");
+        assert_eq!(
+            request.param("info_hash"),
+            Some(&[0xa9, 0xb3, 0x27, b'c', b'N', b'r', 0x21, b'~', 0xe0][..])
+        );
+        assert_eq!(request.param_str("port"), Some("6881"));
+        assert_eq!(request.header("HOST"), Some("127.0.0.1:8080"));
+        assert!(!request.keep_alive());
+    }
+
+    #[test]
+    fn read_pipelined_requests_with_body() {
+        let raw = b"POST /a HTTP/1.1\r\nContent-Length: 5\r\n\r\nhelloGET /b HTTP/1.1\nConnection: close\n\n";
+        let mut reader = &raw[..];
+
+        let first = HttpRequest::read(&mut reader).unwrap().unwrap();
+        assert_eq!(first.body, b"hello");
+        assert!(first.keep_alive());
+
+        let second = HttpRequest::read(&mut reader).unwrap().unwrap();
+        assert_eq!(second.path, "/b");
+        assert!(!second.keep_alive());
+
+        assert_eq!(HttpRequest::read(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn not_confuse_query_with_path() {
+        let request = parse(b"GET /foo?x=announce HTTP/1.1\r\n\r\n")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(request.path, "/foo");
+        assert_eq!(request.param_str("x"), Some("announce"));
+    }
+
+    #[test]
+    fn reject_malformed_requests() {
+        for raw in [
+            &b"GET /announce\r\n\r\n"[..],
+            b"get /announce HTTP/1.1\r\n\r\n",
+            b"GET announce HTTP/1.1\r\n\r\n",
+            b"GET /announce HTTP/1.1\r\nHost\r\n\r\n",
+            b"GET /announce HTTP/1.1\r\nHost: a\r\n",
+            b"GET /announce?info_hash=%zz HTTP/1.1\r\n\r\n",
+            b"POST /a HTTP/1.1\r\nContent-Length: 10\r\n\r\nabc",
+            b"POST /a HTTP/1.1\r\nContent-Length: -1\r\n\r\n",
+        ] {
+            assert_eq!(parse(raw), Err(HttpError::MalformedRequestError));
+        }
+        assert_eq!(
+            parse(b"GET / HTTP/2.0\r\n\r\n"),
+            Err(HttpError::UnsupportedVersionError)
+        );
+    }
+
+    #[test]
+    fn enforce_size_limits() {
+        let long_query = format!(
+            "GET /announce?{} HTTP/1.1\r\n\r\n",
+            "a".repeat(MAX_HEAD_SIZE)
+        );
+        assert_eq!(
+            parse(long_query.as_bytes()),
+            Err(HttpError::HeadTooLargeError)
+        );
+
+        let body = format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_SIZE + 1
+        );
+        assert_eq!(parse(body.as_bytes()), Err(HttpError::BodyTooLargeError));
+    }
+
+    #[test]
+    fn accept_long_query_strings() {
+        let peer_id = "%ff".repeat(20);
+        let raw = format!(
+            "GET /announce?info_hash={}&peer_id={}&{} HTTP/1.1\r\n\r\n",
+            "%aa".repeat(20),
+            peer_id,
+            "key=value&".repeat(100)
+        );
+        let request = parse(raw.as_bytes()).unwrap().unwrap();
+
+        assert_eq!(request.param("peer_id"), Some(&[0xff; 20][..]));
+        assert_eq!(request.query.len(), 102);
+    }
+}
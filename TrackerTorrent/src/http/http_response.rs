@@ -0,0 +1,86 @@
+/******************************************************************************************/
+/*                                   HTTP RESPONSE                                        */
+/******************************************************************************************/
+
+/// Estructura que modela una response HTTP/1.1. El header Content-Length se agrega al
+/// serializarla, a partir del body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn new(status: u16) -> HttpResponse {
+        HttpResponse {
+            status,
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    /// Genera una response de error con el motivo como body de texto plano.
+    pub fn error(status: u16) -> HttpResponse {
+        let body = format!("{} {}\n", status, Self::reason(status));
+        HttpResponse::new(status).with_body("text/plain", body.into_bytes())
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> HttpResponse {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_body(mut self, content_type: &str, body: Vec<u8>) -> HttpResponse {
+        self.body = body;
+        self.with_header("Content-Type", content_type)
+    }
+
+    /// Serializa la response para escribirla en la conexion.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, Self::reason(self.status));
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str(&format!("Content-Length: {}\r\n\r\n", self.body.len()));
+        let mut bytes = head.into_bytes();
+        bytes.extend(&self.body);
+        bytes
+    }
+
+    fn reason(status: u16) -> &'static str {
+        match status {
+            200 => "OK",
+            201 => "Created",
+            204 => "No Content",
+            400 => "Bad Request",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            409 => "Conflict",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            500 => "Internal Server Error",
+            503 => "Service Unavailable",
+            505 => "HTTP Version Not Supported",
+            _ => "Unknown",
+        }
+    }
+}
+
+#[cfg(test)]
+mod http_response_should {
+    use super::*;
+
+    #[test]
+    fn serialize_with_content_length() {
+        let response = HttpResponse::new(200)
+            .with_header("Connection", "close")
+            .with_body("text/plain", b"hola".to_vec());
+
+        assert_eq!(
+            response.to_bytes(),
+            b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Type: text/plain\r\nContent-Length: 4\r\n\r\nhola"
+        );
+    }
+}
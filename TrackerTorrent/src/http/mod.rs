@@ -0,0 +1,3 @@
+pub mod http_request;
+pub mod http_response;
+pub mod router;
@@ -0,0 +1,123 @@
+/******************************************************************************************/
+/*                                      ROUTER                                            */
+/******************************************************************************************/
+
+/// Resultado de buscar la ruta de una request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route<T> {
+    /// El endpoint y los segmentos variables del path (`:nombre`) con su valor.
+    Found(T, Vec<(String, String)>),
+    NotFound,
+    /// El path existe pero no para ese metodo. Contiene los metodos permitidos.
+    MethodNotAllowed(Vec<&'static str>),
+}
+
+/// Estructura que asocia metodo y path con un endpoint.
+/// Los paths se comparan segmento a segmento; un segmento `:nombre` acepta cualquier valor.
+#[derive(Debug, Clone)]
+pub struct Router<T> {
+    routes: Vec<(&'static str, &'static str, T)>,
+}
+
+impl<T: Clone> Default for Router<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> Router<T> {
+    pub fn new() -> Router<T> {
+        Router { routes: vec![] }
+    }
+
+    /// Agrega una ruta.
+    pub fn route(mut self, method: &'static str, path: &'static str, endpoint: T) -> Router<T> {
+        self.routes.push((method, path, endpoint));
+        self
+    }
+
+    /// Busca el endpoint que corresponde al metodo y path recibidos.
+    pub fn find(&self, method: &str, path: &str) -> Route<T> {
+        let mut allowed = vec![];
+        for (route_method, route_path, endpoint) in &self.routes {
+            if let Some(params) = Self::match_path(route_path, path) {
+                if *route_method == method {
+                    return Route::Found(endpoint.clone(), params);
+                }
+                allowed.push(*route_method);
+            }
+        }
+        if allowed.is_empty() {
+            Route::NotFound
+        } else {
+            Route::MethodNotAllowed(allowed)
+        }
+    }
+
+    fn match_path(pattern: &str, path: &str) -> Option<Vec<(String, String)>> {
+        let pattern: Vec<&str> = pattern.split('/').collect();
+        let path: Vec<&str> = path.split('/').collect();
+        if pattern.len() != path.len() {
+            return None;
+        }
+        let mut params = vec![];
+        for (expected, segment) in pattern.iter().zip(path.iter()) {
+            match expected.strip_prefix(':') {
+                Some(name) if !segment.is_empty() => {
+                    params.push((name.to_string(), segment.to_string()))
+                }
+                Some(_) => return None,
+                None if expected != segment => return None,
+                None => {}
+            }
+        }
+        Some(params)
+    }
+}
+
+#[cfg(test)]
+mod router_should {
+    use super::*;
+
+    fn router() -> Router<&'static str> {
+        Router::new()
+            .route("GET", "/announce", "announce")
+            .route("GET", "/stats", "stats")
+            .route("POST", "/stats", "reset")
+            .route("GET", "/:passkey/announce", "private")
+    }
+
+    #[test]
+    fn match_by_method_and_path() {
+        assert_eq!(
+            router().find("GET", "/announce"),
+            Route::Found("announce", vec![])
+        );
+        assert_eq!(
+            router().find("POST", "/stats"),
+            Route::Found("reset", vec![])
+        );
+    }
+
+    #[test]
+    fn capture_path_params() {
+        assert_eq!(
+            router().find("GET", "/abc123/announce"),
+            Route::Found(
+                "private",
+                vec![("passkey".to_string(), "abc123".to_string())]
+            )
+        );
+        assert_eq!(router().find("GET", "//announce"), Route::NotFound);
+    }
+
+    #[test]
+    fn distinguish_not_found_from_method_not_allowed() {
+        assert_eq!(router().find("GET", "/foo"), Route::NotFound);
+        assert_eq!(router().find("GET", "/announce/"), Route::NotFound);
+        assert_eq!(
+            router().find("DELETE", "/stats"),
+            Route::MethodNotAllowed(vec!["GET", "POST"])
+        );
+    }
+}
@@ -1,13 +1,19 @@
+pub mod admin;
+pub mod config;
 pub mod connection;
-pub mod encoder;
 mod errors;
+pub mod http;
 pub mod logger;
-pub mod metainfo;
+pub mod metrics;
 pub mod peer;
-pub mod peer_protocol;
+pub mod pool;
+pub mod rate_limiter;
 pub mod request;
 pub mod response;
 pub mod seed;
 pub mod stats;
+pub mod storage;
 pub mod torrent;
 pub mod tracker;
+pub mod udp;
+pub mod user;
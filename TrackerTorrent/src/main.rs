@@ -1,10 +1,10 @@
 use std::env::args;
 use std::sync::mpsc;
 use std::thread;
+use trackertorrent::config::TrackerConfig;
 use trackertorrent::logger::Logger;
 use trackertorrent::tracker::BitTracker;
 
-const LOG_PATH: &str = "./logs";
 const LOG_NAME: &str = "BitTracker";
 
 fn main() {
@@ -13,15 +13,21 @@ fn main() {
         println!("[ERROR] Cantidad de argumentos inválido");
         return;
     }
-    let config_file = args[1].clone();
+    let config = match TrackerConfig::from_file(&args[1]) {
+        Ok(config) => config,
+        Err(error) => {
+            println!("[ERROR] {}", error);
+            return;
+        }
+    };
 
     let (tx, rx) = mpsc::channel();
 
     //Inicializo el Logger
-    match Logger::new(LOG_PATH, LOG_NAME, rx) {
+    match Logger::new(&config.log_dir, LOG_NAME, rx) {
         Ok(mut logger) => {
             let log = thread::spawn(move || logger.listening());
-            if let Err(error) = BitTracker::start(&config_file, tx) {
+            if let Err(error) = BitTracker::start(&config, tx) {
                 println!("[ERROR] {}", error);
             }
             if let Err(error) = log.join() {
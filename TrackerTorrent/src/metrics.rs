@@ -0,0 +1,317 @@
+use crate::tracker::BitTracker;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/******************************************************************************************/
+/*                                      METRICS                                           */
+/******************************************************************************************/
+
+/// Limites superiores, en segundos, de los buckets del histograma de latencia.
+const LATENCY_BUCKETS: [f64; 9] = [0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+/// Eventos de announce que se exportan aunque todavia no se hayan recibido.
+const EVENTS: [&str; 4] = ["started", "completed", "stopped", "none"];
+
+/// Histograma de duraciones. Cada posicion de `counts` cuenta las observaciones que caen en
+/// el bucket correspondiente de `LATENCY_BUCKETS`; la ultima las que superan a todos.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    counts: [u64; LATENCY_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Histogram {
+            counts: [0; LATENCY_BUCKETS.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    pub fn observe(&mut self, seconds: f64) {
+        let position = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.counts[position] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Contadores del tracker que se exportan en `GET /metrics` con el formato de texto de
+/// Prometheus. Los gauges de torrents y peers no se guardan, se calculan al exportar.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    announces: BTreeMap<String, u64>,
+    scrapes: u64,
+    bad_requests: BTreeMap<&'static str, u64>,
+    rejected: BTreeMap<&'static str, u64>,
+    latency: BTreeMap<(&'static str, &'static str), Histogram>,
+    connections: u64,
+    active_connections: u64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let mut metrics = Metrics::default();
+        for event in EVENTS {
+            metrics.announces.insert(event.to_string(), 0);
+        }
+        for protocol in ["http", "udp"] {
+            metrics.bad_requests.insert(protocol, 0);
+        }
+        for reason in ["overload", "rate_limit"] {
+            metrics.rejected.insert(reason, 0);
+        }
+        metrics
+    }
+
+    /// Cuenta un announce. Los announces sin evento se cuentan como `none`.
+    pub fn count_announce(&mut self, event: &str) {
+        let event = match event {
+            "started" | "completed" | "stopped" => event,
+            _ => "none",
+        };
+        *self.announces.entry(event.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn count_scrape(&mut self) {
+        self.scrapes += 1;
+    }
+
+    /// Cuenta una request invalida o rechazada del protocolo indicado (`http` o `udp`).
+    pub fn count_bad_request(&mut self, protocol: &'static str) {
+        *self.bad_requests.entry(protocol).or_insert(0) += 1;
+    }
+
+    /// Cuenta una request o conexion rechazada con 503, por sobrecarga (`overload`) o por
+    /// superar el limite de requests de la ip (`rate_limit`).
+    pub fn count_rejected(&mut self, reason: &'static str) {
+        *self.rejected.entry(reason).or_insert(0) += 1;
+    }
+
+    /// Registra cuanto tardo en atenderse una request al endpoint.
+    pub fn observe(&mut self, protocol: &'static str, endpoint: &'static str, seconds: f64) {
+        self.latency
+            .entry((protocol, endpoint))
+            .or_default()
+            .observe(seconds);
+    }
+
+    pub fn open_connection(&mut self) {
+        self.connections += 1;
+        self.active_connections += 1;
+    }
+
+    pub fn close_connection(&mut self) {
+        self.active_connections = self.active_connections.saturating_sub(1);
+    }
+
+    /// Exporta los contadores junto con los gauges calculados a partir del estado del tracker.
+    pub fn render(&self, tracker: &BitTracker) -> String {
+        let mut text = String::new();
+        header(
+            &mut text,
+            "tracker_announces_total",
+            "counter",
+            "Announces recibidos por evento.",
+        );
+        for (event, count) in &self.announces {
+            let _ = writeln!(
+                text,
+                "tracker_announces_total{{event=\"{}\"}} {}",
+                event, count
+            );
+        }
+        header(
+            &mut text,
+            "tracker_scrapes_total",
+            "counter",
+            "Scrapes recibidos.",
+        );
+        let _ = writeln!(text, "tracker_scrapes_total {}", self.scrapes);
+        header(
+            &mut text,
+            "tracker_bad_requests_total",
+            "counter",
+            "Requests invalidas o rechazadas por protocolo.",
+        );
+        for (protocol, count) in &self.bad_requests {
+            let _ = writeln!(
+                text,
+                "tracker_bad_requests_total{{protocol=\"{}\"}} {}",
+                protocol, count
+            );
+        }
+        header(
+            &mut text,
+            "tracker_rejected_total",
+            "counter",
+            "Requests y conexiones rechazadas con 503 por motivo.",
+        );
+        for (reason, count) in &self.rejected {
+            let _ = writeln!(
+                text,
+                "tracker_rejected_total{{reason=\"{}\"}} {}",
+                reason, count
+            );
+        }
+
+        let mut peers = 0;
+        let mut seeders = 0;
+        for torrent in &tracker.torrents {
+            peers += torrent.peers.len();
+            seeders += torrent.peers.iter().filter(|peer| peer.is_seeder()).count();
+        }
+        let active = tracker
+            .torrents
+            .iter()
+            .filter(|torrent| !torrent.peers.is_empty())
+            .count();
+        let gauges = [
+            (
+                "tracker_torrents",
+                "Torrents registrados.",
+                tracker.torrents.len(),
+            ),
+            (
+                "tracker_active_torrents",
+                "Torrents con al menos un peer.",
+                active,
+            ),
+            ("tracker_peers", "Peers conectados.", peers),
+            (
+                "tracker_seeders",
+                "Peers con la descarga completa.",
+                seeders,
+            ),
+            ("tracker_leechers", "Peers descargando.", peers - seeders),
+        ];
+        for (name, help, value) in gauges {
+            header(&mut text, name, "gauge", help);
+            let _ = writeln!(text, "{} {}", name, value);
+        }
+
+        header(
+            &mut text,
+            "tracker_request_duration_seconds",
+            "histogram",
+            "Tiempo en atender una request por protocolo y endpoint.",
+        );
+        for ((protocol, endpoint), histogram) in &self.latency {
+            let labels = format!("protocol=\"{}\",endpoint=\"{}\"", protocol, endpoint);
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.counts.iter()) {
+                cumulative += count;
+                let _ = writeln!(
+                    text,
+                    "tracker_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                text,
+                "tracker_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, histogram.count
+            );
+            let _ = writeln!(
+                text,
+                "tracker_request_duration_seconds_sum{{{}}} {}",
+                labels, histogram.sum
+            );
+            let _ = writeln!(
+                text,
+                "tracker_request_duration_seconds_count{{{}}} {}",
+                labels, histogram.count
+            );
+        }
+
+        header(
+            &mut text,
+            "tracker_connections_total",
+            "counter",
+            "Conexiones TCP aceptadas.",
+        );
+        let _ = writeln!(text, "tracker_connections_total {}", self.connections);
+        header(
+            &mut text,
+            "tracker_connections_active",
+            "gauge",
+            "Conexiones TCP abiertas.",
+        );
+        let _ = writeln!(
+            text,
+            "tracker_connections_active {}",
+            self.active_connections
+        );
+        text
+    }
+}
+
+fn header(text: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(text, "# HELP {} {}", name, help);
+    let _ = writeln!(text, "# TYPE {} {}", name, kind);
+}
+
+#[cfg(test)]
+mod metrics_should {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn accumulate_histogram_buckets() {
+        let mut histogram = Histogram::new();
+        histogram.observe(0.0001);
+        histogram.observe(0.002);
+        histogram.observe(10.0);
+
+        assert_eq!(histogram.count, 3);
+        assert_eq!(histogram.counts[0], 1);
+        assert_eq!(histogram.counts[2], 1);
+        assert_eq!(histogram.counts[LATENCY_BUCKETS.len()], 1);
+    }
+
+    #[test]
+    fn render_text_exposition_format() {
+        let (tx, _rx) = mpsc::channel();
+        let tracker = BitTracker::new("./config_file", tx).unwrap();
+        let mut metrics = Metrics::new();
+        metrics.count_announce("started");
+        metrics.count_announce("");
+        metrics.count_bad_request("udp");
+        metrics.count_rejected("overload");
+        metrics.observe("http", "announce", 0.002);
+        metrics.open_connection();
+        metrics.open_connection();
+        metrics.close_connection();
+
+        let text = metrics.render(&tracker);
+        assert!(text.contains("# TYPE tracker_announces_total counter\n"));
+        assert!(text.contains("tracker_announces_total{event=\"started\"} 1\n"));
+        assert!(text.contains("tracker_announces_total{event=\"none\"} 1\n"));
+        assert!(text.contains("tracker_announces_total{event=\"stopped\"} 0\n"));
+        assert!(text.contains("tracker_bad_requests_total{protocol=\"udp\"} 1\n"));
+        assert!(text.contains("tracker_rejected_total{reason=\"overload\"} 1\n"));
+        assert!(text.contains("tracker_rejected_total{reason=\"rate_limit\"} 0\n"));
+        assert!(text.contains(&format!("tracker_torrents {}\n", tracker.torrents.len())));
+        assert!(text.contains(
+            "tracker_request_duration_seconds_bucket{protocol=\"http\",endpoint=\"announce\",le=\"0.001\"} 0\n"
+        ));
+        assert!(text.contains(
+            "tracker_request_duration_seconds_bucket{protocol=\"http\",endpoint=\"announce\",le=\"0.005\"} 1\n"
+        ));
+        assert!(text.contains(
+            "tracker_request_duration_seconds_count{protocol=\"http\",endpoint=\"announce\"} 1\n"
+        ));
+        assert!(text.contains("tracker_connections_total 2\n"));
+        assert!(text.contains("tracker_connections_active 1\n"));
+    }
+}
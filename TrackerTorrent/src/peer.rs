@@ -1,9 +1,10 @@
 use crate::{errors::BitTrackerError, request::Request};
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
 /******************************************************************************************/
 /*                                       PEER                                             */
 /******************************************************************************************/
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
 pub enum Event {
     Started,
     Stopped,
@@ -29,7 +30,10 @@ impl Peer {
         let id = request.peer_id.clone();
         let ip = request.ip.clone();
         let port = request.port.clone();
-        let event = Self::match_event(&request.event)?;
+        let event = match Self::has_event(request) {
+            true => Self::match_event(&request.event)?,
+            false => Event::Started,
+        };
         let uploaded = request.uploaded.clone();
         let downloaded = request.downloaded.clone();
         let left = request.left.clone();
@@ -52,7 +56,9 @@ impl Peer {
         if request.peer_id != self.id {
             return Err(BitTrackerError::InvalidSyntaxError);
         }
-        self.event = Self::match_event(&request.event)?;
+        if Self::has_event(request) {
+            self.event = Self::match_event(&request.event)?;
+        }
         self.uploaded = request.uploaded.clone();
         self.downloaded = request.downloaded.clone();
         self.left = request.left.clone();
@@ -60,6 +66,22 @@ impl Peer {
         Ok(())
     }
 
+    /// Indica si la request trae un evento. Los announces periodicos no lo envian, o envian
+    /// `event=empty`, y en ese caso el peer conserva el estado que tenia.
+    pub fn has_event(request: &Request) -> bool {
+        !request.event.is_empty() && request.event != "empty"
+    }
+
+    /// Indica si el peer tiene el archivo completo.
+    pub fn is_seeder(&self) -> bool {
+        self.event == Event::Completed || self.left == "0"
+    }
+
+    /// Indica si el peer dejo de enviar announces hace mas de `timeout`.
+    pub fn is_expired(&self, now: DateTime<Local>, timeout: Duration) -> bool {
+        now.signed_duration_since(self.time_last_request) > timeout
+    }
+
     fn match_event(event: &str) -> Result<Event, BitTrackerError> {
         match event {
             "started" => Ok(Event::Started),
@@ -83,11 +105,14 @@ mod peer_should {
             ip: "127.0.0.1".to_string(),
             port: "12345".to_string(),
             compact: 0.to_string(),
+            no_peer_id: 0.to_string(),
+            numwant: 50.to_string(),
+            passkey: "".to_string(),
             event: "started".to_string(),
             uploaded: 0.to_string(),
             downloaded: 0.to_string(),
             left: 0.to_string(),
-            time: time,
+            time,
         };
 
         //println!("Request : {:?}",request);
@@ -113,11 +138,14 @@ mod peer_should {
             ip: "127.0.0.1".to_string(),
             port: "12345".to_string(),
             compact: 0.to_string(),
+            no_peer_id: 0.to_string(),
+            numwant: 50.to_string(),
+            passkey: "".to_string(),
             event: "started".to_string(),
             uploaded: 0.to_string(),
             downloaded: 0.to_string(),
             left: 100.to_string(),
-            time: time,
+            time,
         };
         let mut peer = Peer::new(&request).unwrap();
 
@@ -128,11 +156,14 @@ mod peer_should {
             ip: "127.0.0.1".to_string(),
             port: "12345".to_string(),
             compact: 0.to_string(),
+            no_peer_id: 0.to_string(),
+            numwant: 50.to_string(),
+            passkey: "".to_string(),
             event: "completed".to_string(),
             uploaded: 10.to_string(),
             downloaded: 100.to_string(),
             left: 0.to_string(),
-            time: time,
+            time,
         };
 
         peer.actualize_request(&new_request).unwrap();
@@ -155,11 +186,14 @@ mod peer_should {
             ip: "127.0.0.1".to_string(),
             port: "12345".to_string(),
             compact: 0.to_string(),
+            no_peer_id: 0.to_string(),
+            numwant: 50.to_string(),
+            passkey: "".to_string(),
             event: "started".to_string(),
             uploaded: 0.to_string(),
             downloaded: 0.to_string(),
             left: 100.to_string(),
-            time: time,
+            time,
         };
         let mut peer = Peer::new(&request).unwrap();
 
@@ -170,16 +204,44 @@ mod peer_should {
             ip: "127.0.0.1".to_string(),
             port: "12345".to_string(),
             compact: 0.to_string(),
+            no_peer_id: 0.to_string(),
+            numwant: 50.to_string(),
+            passkey: "".to_string(),
             event: "completed".to_string(),
             uploaded: 10.to_string(),
             downloaded: 100.to_string(),
             left: 0.to_string(),
-            time: time,
+            time,
         };
 
-        if let Err(_) = peer.actualize_request(&new_request) {
-            assert!(true);
-        }
+        assert!(peer.actualize_request(&new_request).is_err());
+    }
+
+    #[test]
+    fn keep_event_on_regular_announce() {
+        let mut request = Request::new();
+        request.peer_id = "PEER-KBRON".to_string();
+        let mut peer = Peer::new(&request).unwrap();
+        assert_eq!(peer.event, Event::Started);
+
+        request.event = "completed".to_string();
+        peer.actualize_request(&request).unwrap();
+        request.event = "empty".to_string();
+        request.left = 0.to_string();
+        peer.actualize_request(&request).unwrap();
+
+        assert_eq!(peer.event, Event::Completed);
+        assert_eq!(peer.left, "0".to_string());
+    }
+
+    #[test]
+    fn expire_after_timeout() {
+        let request = Request::new();
+        let peer = Peer::new(&request).unwrap();
+        let timeout = Duration::seconds(30);
+
+        assert!(!peer.is_expired(request.time + Duration::seconds(30), timeout));
+        assert!(peer.is_expired(request.time + Duration::seconds(31), timeout));
     }
 
     #[test]
@@ -187,8 +249,6 @@ mod peer_should {
         assert_eq!(Event::Started, Peer::match_event("started").unwrap());
         assert_eq!(Event::Stopped, Peer::match_event("stopped").unwrap());
         assert_eq!(Event::Completed, Peer::match_event("completed").unwrap());
-        if let Err(_) = Peer::match_event("basado") {
-            assert!(true);
-        }
+        assert!(Peer::match_event("basado").is_err());
     }
 }
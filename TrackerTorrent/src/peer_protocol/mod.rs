@@ -1,3 +0,0 @@
-mod errors;
-pub mod handshake;
-pub mod messages;
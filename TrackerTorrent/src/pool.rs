@@ -0,0 +1,146 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/******************************************************************************************/
+/*                                    THREAD POOL                                         */
+/******************************************************************************************/
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Pool con una cantidad fija de threads que atienden los trabajos en orden de llegada.
+/// Lleva la cuenta de los trabajos pendientes, encolados o en ejecucion, para que quien los
+/// envia pueda rechazar trabajo nuevo cuando se alcanza el maximo.
+pub struct ThreadPool {
+    workers: Vec<JoinHandle<()>>,
+    sender: Option<Sender<Job>>,
+    pending: Arc<AtomicUsize>,
+    max_pending: usize,
+}
+
+impl ThreadPool {
+    /// Inicializa el pool con `size` threads, al menos uno, que aceptan hasta `max_pending`
+    /// trabajos a la vez.
+    pub fn new(size: usize, max_pending: usize) -> ThreadPool {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let pending = Arc::new(AtomicUsize::new(0));
+        let workers = (0..size.max(1))
+            .map(|_| {
+                let receiver = receiver.clone();
+                let pending = pending.clone();
+                thread::spawn(move || work(receiver, pending))
+            })
+            .collect();
+        ThreadPool {
+            workers,
+            sender: Some(sender),
+            pending,
+            max_pending,
+        }
+    }
+
+    /// Indica si se alcanzo la cantidad maxima de trabajos pendientes.
+    pub fn is_full(&self) -> bool {
+        self.pending() >= self.max_pending
+    }
+
+    /// Cantidad de trabajos encolados o en ejecucion.
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    /// Encola el trabajo. Quien lo envia debe verificar antes que el pool no este lleno.
+    pub fn execute<F>(&self, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        if let Some(sender) = &self.sender {
+            self.pending.fetch_add(1, Ordering::SeqCst);
+            if sender.send(Box::new(job)).is_err() {
+                self.pending.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+impl Drop for ThreadPool {
+    /// Deja de aceptar trabajos y espera a que terminen los encolados.
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Ejecuta trabajos hasta que se cierre el pool. Un trabajo que entra en panico no termina
+/// el thread.
+fn work(receiver: Arc<Mutex<Receiver<Job>>>, pending: Arc<AtomicUsize>) {
+    loop {
+        let job = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => return,
+        };
+        let job = match job {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        if panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+            println!("[ERROR] Un trabajo del pool entro en panico");
+        }
+        pending.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod thread_pool_should {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn run_jobs_in_workers() {
+        let pool = ThreadPool::new(2, 10);
+        let (tx, rx) = mpsc::channel();
+        for job in 0..4 {
+            let tx = tx.clone();
+            pool.execute(move || tx.send(job).unwrap());
+        }
+        let mut results: Vec<i32> = (0..4).map(|_| rx.recv().unwrap()).collect();
+        results.sort();
+        assert_eq!(results, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn report_full_until_jobs_finish() {
+        let pool = ThreadPool::new(1, 2);
+        let (release, wait) = mpsc::channel::<()>();
+        let wait = Arc::new(Mutex::new(wait));
+        for _ in 0..2 {
+            let wait = wait.clone();
+            pool.execute(move || wait.lock().unwrap().recv().unwrap());
+        }
+        assert!(pool.is_full());
+
+        release.send(()).unwrap();
+        release.send(()).unwrap();
+        for _ in 0..100 {
+            if pool.pending() == 0 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!pool.is_full());
+    }
+
+    #[test]
+    fn survive_panicking_jobs() {
+        let pool = ThreadPool::new(1, 10);
+        pool.execute(|| panic!("trabajo invalido"));
+        let (tx, rx) = mpsc::channel();
+        pool.execute(move || tx.send(()).unwrap());
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+}
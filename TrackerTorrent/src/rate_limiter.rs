@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/******************************************************************************************/
+/*                                   RATE LIMITER                                         */
+/******************************************************************************************/
+
+/// Cantidad de ips registradas a partir de la cual se descartan las ventanas vencidas.
+const MAX_TRACKED_IPS: usize = 4096;
+
+/// Limita la cantidad de requests que acepta el tracker de cada ip. Cuenta las requests en
+/// ventanas fijas de `window`: cuando una ip supera `limit` en su ventana se la rechaza hasta
+/// que la ventana termine.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    pub limit: u32,
+    pub window: Duration,
+    hits: HashMap<IpAddr, (Instant, u32)>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: u32, window: Duration) -> RateLimiter {
+        RateLimiter {
+            limit,
+            window,
+            hits: HashMap::new(),
+        }
+    }
+
+    /// Registra una request de la ip. Devuelve si se la acepta.
+    pub fn allow(&mut self, ip: IpAddr, now: Instant) -> bool {
+        if self.hits.len() >= MAX_TRACKED_IPS {
+            self.forget_expired(now);
+        }
+        let window = self.window;
+        let (start, count) = self.hits.entry(ip).or_insert((now, 0));
+        if now.duration_since(*start) >= window {
+            *start = now;
+            *count = 0;
+        }
+        *count = count.saturating_add(1);
+        *count <= self.limit
+    }
+
+    /// Indica si la ip ya supero el limite en su ventana actual, sin registrar una request.
+    pub fn is_limited(&self, ip: IpAddr, now: Instant) -> bool {
+        match self.hits.get(&ip) {
+            Some((start, count)) => {
+                now.duration_since(*start) < self.window && *count >= self.limit
+            }
+            None => false,
+        }
+    }
+
+    /// Segundos que faltan para que termine la ventana de la ip.
+    pub fn retry_after(&self, ip: IpAddr, now: Instant) -> u64 {
+        match self.hits.get(&ip) {
+            Some((start, _count)) => self
+                .window
+                .saturating_sub(now.duration_since(*start))
+                .as_secs()
+                .max(1),
+            None => 1,
+        }
+    }
+
+    fn forget_expired(&mut self, now: Instant) {
+        let window = self.window;
+        self.hits
+            .retain(|_ip, (start, _count)| now.duration_since(*start) < window);
+    }
+}
+
+#[cfg(test)]
+mod rate_limiter_should {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn limit_requests_per_ip_and_window() {
+        let mut limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let other = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let now = Instant::now();
+
+        assert!(limiter.allow(ip, now));
+        assert!(!limiter.is_limited(ip, now));
+        assert!(limiter.allow(ip, now));
+        assert!(limiter.is_limited(ip, now));
+        assert!(!limiter.allow(ip, now + Duration::from_secs(1)));
+        assert_eq!(limiter.retry_after(ip, now + Duration::from_secs(1)), 59);
+        assert!(limiter.allow(other, now));
+
+        let later = now + Duration::from_secs(60);
+        assert!(!limiter.is_limited(ip, later));
+        assert!(limiter.allow(ip, later));
+    }
+}
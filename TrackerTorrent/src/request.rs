@@ -1,6 +1,8 @@
 use crate::errors::RequestError;
+use crate::http::http_request::HttpRequest;
 use chrono::DateTime;
 use chrono::Local;
+use protocol::encoder::url_encoder::URLEncoder;
 /******************************************************************************************/
 /*                                  TRACKER REQUEST                                      */
 /******************************************************************************************/
@@ -9,10 +11,16 @@ use chrono::Local;
 #[derive(PartialEq, Debug, Clone)]
 pub struct Request {
     pub info_hash_url: String,
+    /// Peer id urlencodeado, como el info_hash. Los clientes suelen usar bytes arbitrarios,
+    /// que no deben alterarse para que cada peer conserve su id.
     pub peer_id: String,
     pub ip: String,
     pub port: String,
     pub compact: String,
+    pub no_peer_id: String,
+    pub numwant: String,
+    /// Passkey del usuario, recibida en el path del announce. Vacia en los announces publicos.
+    pub passkey: String,
     pub event: String,
     pub uploaded: String,
     pub downloaded: String,
@@ -20,7 +28,6 @@ pub struct Request {
     pub time: DateTime<Local>,
 }
 
-#[allow(clippy::needless_range_loop)]
 #[allow(clippy::new_without_default)]
 impl Request {
     pub fn new() -> Request {
@@ -32,65 +39,86 @@ impl Request {
             downloaded: "".to_string(),
             left: "".to_string(),
             compact: "".to_string(),
+            no_peer_id: "".to_string(),
+            numwant: "".to_string(),
+            passkey: "".to_string(),
             event: "".to_string(),
             ip: "".to_string(),
             time: Local::now(),
         }
     }
 
-    /// Esta funcion recibe 1 parametro cualquiera de la request y lo guarda donde corresponde
-    pub fn parse_param(&mut self, param_string: &str) -> Result<&mut Request, RequestError> {
-        let param: Vec<&str> = param_string.split('=').collect();
-
-        match param[0] {
-            "peer_id" => self.peer_id = param[1].to_string(),
-            "port" => self.port = param[1].to_string(),
-            "uploaded" => self.uploaded = param[1].to_string(),
-            "downloaded" => self.downloaded = param[1].to_string(),
-            "left" => self.left = param[1].to_string(),
-            "compact" => self.compact = param[1].to_string(),
-            "event" => {
-                //Separamos el parametro final del Host y el Protocol
-                let final_params: Vec<&str> = param[1].split(' ').collect();
-                self.event = final_params[0].to_string();
+    /// Esta funcion recibe 1 parametro cualquiera de la request, ya decodificado,
+    /// y lo guarda donde corresponde. Los parametros desconocidos se ignoran.
+    pub fn parse_param(&mut self, key: &str, value: &[u8]) -> Result<&mut Request, RequestError> {
+        match key {
+            "info_hash" => {
+                self.info_hash_url = Self::urlencode(value)?;
+                return Ok(self);
             }
-            "ip" => {
-                //Separamos el parametro final del Host y el Protocol
-                let final_params: Vec<&str> = param[1].split(' ').collect();
-                self.ip = final_params[0].to_string();
+            "peer_id" => {
+                self.peer_id = Self::urlencode(value)?;
+                return Ok(self);
             }
-
-            _ => return Err(RequestError::InvalidParameterError),
+            _ => {}
+        }
+        let value = String::from_utf8_lossy(value).to_string();
+        match key {
+            "port" => self.port = value,
+            "uploaded" => self.uploaded = value,
+            "downloaded" => self.downloaded = value,
+            "left" => self.left = value,
+            "compact" => self.compact = value,
+            "no_peer_id" => self.no_peer_id = value,
+            "numwant" => self.numwant = value,
+            "event" => self.event = value,
+            "ip" => self.ip = value,
+            _ => {}
         }
+        Ok(self)
+    }
 
-        Ok(self) //Preguntar
+    fn urlencode(value: &[u8]) -> Result<String, RequestError> {
+        URLEncoder
+            .urlencode(value.to_vec())
+            .or(Err(RequestError::InvalidParameterError))
+    }
+
+    /// Inicializa la estructura Request con los parametros del query string de la request HTTP.
+    /// El info_hash y el peer_id se vuelven a encodear con URLEncoder, para compararlos con los
+    /// de los torrents y peers sin perder bytes.
+    /// Falla indicando el primer parametro obligatorio que falte, o si el puerto es invalido.
+    pub fn from_http(&mut self, http: &HttpRequest) -> Result<&mut Request, RequestError> {
+        for (key, value) in &http.query {
+            self.parse_param(key, value)?;
+        }
+        let required = [
+            ("info_hash", &self.info_hash_url),
+            ("peer_id", &self.peer_id),
+            ("port", &self.port),
+        ];
+        if let Some((name, _value)) = required.iter().find(|(_name, value)| value.is_empty()) {
+            return Err(RequestError::MissingParameterError(name));
+        }
+        if !matches!(self.port.parse::<u16>(), Ok(port) if port > 0) {
+            return Err(RequestError::InvalidPortError);
+        }
+        Ok(self)
     }
 
     /// Esta funcion recibe el request string del tracker,
     /// parsea e inicializa la estructura Request con los parametros recibidos.
     pub fn parse_request(&mut self, request_string: String) -> Result<&mut Request, RequestError> {
-        let query_params: Vec<&str> = request_string.split('&').collect();
-
-        let request_and_info: Vec<&str> = query_params[0].split('?').collect();
-        //println!("request type: {}", request_and_info[0]);
-        //println!("info hash: {}", request_and_info[1]);
-        let split: Vec<&str> = request_and_info[1].split('=').collect();
-        self.info_hash_url = split[1].to_string();
-
-        let query_params_length = query_params.len();
-        //println!("{}",query_params_length);
-
-        for i in 1..query_params_length {
-            Request::parse_param(self, query_params[i])
-                .or(Err(RequestError::InvalidParameterError))?;
-        }
-
-        Ok(self)
+        let http = HttpRequest::read(&mut request_string.as_bytes())
+            .or(Err(RequestError::InvalidSyntaxError))?
+            .ok_or(RequestError::InvalidSyntaxError)?;
+        self.from_http(&http)
     }
 }
 
 #[cfg(test)]
 mod request_should {
+    use crate::errors::RequestError;
     use crate::request::Request;
     use sha1::Digest;
     use sha1::Sha1;
@@ -117,4 +145,53 @@ mod request_should {
         assert_eq!(request.event, "started");
         assert_eq!(request.ip, "186.189.238.5");
     }
+
+    #[test]
+    fn normalize_info_hash_encoding() {
+        let mut request = Request::new();
+        let message =
+            "GET /announce?info_hash=%2A%AEl5&peer_id=abc&port=1&numwant=50 HTTP/1.1\r\n\r\n";
+
+        request.parse_request(message.to_string()).unwrap();
+
+        assert_eq!(request.info_hash_url, "%2a%ael5");
+    }
+
+    #[test]
+    fn keep_binary_peer_ids() {
+        let message = |peer_id: &str| {
+            format!(
+                "GET /announce?info_hash=%2A&peer_id=-4R0001-{}&port=1 HTTP/1.1\r\n\r\n",
+                peer_id
+            )
+        };
+        let mut first = Request::new();
+        first.parse_request(message(&"%FF".repeat(12))).unwrap();
+        let mut second = Request::new();
+        second.parse_request(message(&"%FE".repeat(12))).unwrap();
+
+        assert_eq!(first.peer_id, "-4R0001-".to_owned() + &"%ff".repeat(12));
+        assert_ne!(first.peer_id, second.peer_id);
+    }
+
+    #[test]
+    fn fail_without_required_params() {
+        let mut request = Request::new();
+        let message = "GET /announce?peer_id=abc&port=1 HTTP/1.1\r\n\r\n";
+
+        assert_eq!(
+            request.parse_request(message.to_string()),
+            Err(RequestError::MissingParameterError("info_hash"))
+        );
+    }
+
+    #[test]
+    fn fail_with_invalid_port() {
+        let message = "GET /announce?info_hash=%2A&peer_id=abc&port=70000 HTTP/1.1\r\n\r\n";
+
+        assert_eq!(
+            Request::new().parse_request(message.to_string()),
+            Err(RequestError::InvalidPortError)
+        );
+    }
 }
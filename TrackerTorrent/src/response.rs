@@ -1,114 +1,196 @@
-use crate::encoder::bencode_encoder::EncodingParser;
-use crate::encoder::bencode_parser::Bencode;
+use crate::http::http_response::HttpResponse;
+use protocol::encoder::bencode_parser::Bencode;
+use protocol::encoder::bencode_serde;
+use serde::Serialize;
 
 /******************************************************************************************/
 /*                                  TRACKER RESPONSE                                     */
 /******************************************************************************************/
 
-const INTERVAL: usize = 10;
+/// Segundos que los peers deben esperar entre announces.
+pub const INTERVAL: usize = 10;
+
+/// Formatos posibles de la lista de peers de la response.
+#[derive(PartialEq, Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Peers {
+    /// Lista de diccionarios con ip, puerto y, opcionalmente, el id del peer.
+    Dictionary { peers: Vec<Bencode> },
+    /// Formato compacto (BEP 23): 6 bytes por peer IPv4 en `peers` y 18 bytes por peer
+    /// IPv6 en `peers6` (BEP 7).
+    Compact {
+        #[serde(with = "serde_bytes")]
+        peers: Vec<u8>,
+        #[serde(with = "serde_bytes", skip_serializing_if = "Vec::is_empty")]
+        peers6: Vec<u8>,
+    },
+}
 
 /// Estructura que modela la response del tracker.
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub struct Response {
+    #[serde(rename = "tracker id")]
     pub tracker_id: String,
     pub interval: usize,
+    /// Segundos minimos entre announces. Los peers no deben anunciarse antes.
+    #[serde(rename = "min interval")]
+    pub min_interval: usize,
+    /// Advertencia para el peer. La response se procesa normalmente.
+    #[serde(rename = "warning message", skip_serializing_if = "Option::is_none")]
+    pub warning_message: Option<String>,
     pub complete: usize,
     pub incomplete: usize,
-    pub bencode_peers: Vec<Bencode>,
+    #[serde(flatten)]
+    pub peers: Peers,
 }
 
 impl Response {
     /// Inicializa la response.
-    pub fn new(
-        tracker_id: String,
-        complete: usize,
-        incomplete: usize,
-        bencode_peers: Vec<Bencode>,
-    ) -> Self {
+    pub fn new(tracker_id: String, complete: usize, incomplete: usize, peers: Peers) -> Self {
         Response {
             tracker_id,
             interval: INTERVAL,
+            min_interval: INTERVAL / 2,
+            warning_message: None,
             complete,
             incomplete,
-            bencode_peers,
+            peers,
         }
     }
 
     /// Bencodea la response en el formato correspondiente.
-    fn bencode(&mut self) -> String {
-        let complete = ("complete".to_string(), Bencode::Int(self.complete as i64));
-        let incomplete = (
-            "incomplete".to_string(),
-            Bencode::Int(self.incomplete as i64),
-        );
-        let interval = ("interval".to_string(), Bencode::Int(self.interval as i64));
-        let mut dict = vec![complete, incomplete, interval];
-        let list = Bencode::List(self.bencode_peers.clone());
-        dict.push(("peers".to_string(), list));
+    fn bencode(&mut self) -> Vec<u8> {
+        bencode_serde::to_bytes(self).unwrap_or_default()
+    }
 
-        let encoded = EncodingParser::encode(&EncodingParser, Bencode::Dictionary(dict));
-        String::from_utf8_lossy(&encoded).to_string()
+    /// Genera el mensaje para devolver por la conexion. El body se envia tal cual, ya que
+    /// los peers compactos son binarios.
+    pub fn make_message(&mut self) -> Vec<u8> {
+        HttpResponse::new(200)
+            .with_body("text/plain", self.bencode())
+            .to_bytes()
     }
 
-    /// Genera el mensaje para devolver por la conexion
-    pub fn make_message(&mut self) -> String {
-        let bencode = self.bencode();
-        format!(
-            "HTTP/1.1 200 OK \r\nHost: 127.0.0.1:8080\r\nContent-Length:{}\r\nContent-Type: text/plain\r\n\r\n{}",
-            bencode.chars().count(),
-            bencode)
+    /// Genera el mensaje con el que se rechaza un announce: un diccionario con el motivo en
+    /// `failure reason`. Se envia con status 200 para que el peer lo interprete.
+    pub fn failure(reason: &str) -> Vec<u8> {
+        let body = bencode_serde::to_bytes(&Failure { reason }).unwrap_or_default();
+        HttpResponse::new(200)
+            .with_body("text/plain", body)
+            .to_bytes()
     }
 }
 
+/// Formato en bencode de la response a un announce rechazado.
+#[derive(Serialize)]
+struct Failure<'a> {
+    #[serde(rename = "failure reason")]
+    reason: &'a str,
+}
+
 #[cfg(test)]
 mod response_should {
     use super::*;
-    use crate::encoder::bencode_parser::DecodingParser;
+    use protocol::encoder::bencode_parser::DecodingParser;
 
     #[test]
     fn initilize() {
-        let id = ("id".to_string(), Bencode::String("PEER-BACAN".to_string()));
-        let ip = ("ip".to_string(), Bencode::String("127.0.0.1".to_string()));
-        let port = ("port".to_string(), Bencode::String("420".to_string()));
-        let dict1 = Bencode::Dictionary([id, ip, port].to_vec());
+        let id = ("id", Bencode::from("PEER-BACAN"));
+        let ip = ("ip", Bencode::from("127.0.0.1"));
+        let port = ("port", Bencode::from("420"));
+        let dict1 = Bencode::dict([id, ip, port].to_vec());
 
-        let id = ("id".to_string(), Bencode::String("PEER-PANA".to_string()));
-        let ip = ("ip".to_string(), Bencode::String("127.0.0.2".to_string()));
-        let port = ("port".to_string(), Bencode::String("440".to_string()));
-        let dict2 = Bencode::Dictionary([id, ip, port].to_vec());
+        let id = ("id", Bencode::from("PEER-PANA"));
+        let ip = ("ip", Bencode::from("127.0.0.2"));
+        let port = ("port", Bencode::from("440"));
+        let dict2 = Bencode::dict([id, ip, port].to_vec());
 
         let list = [dict1, dict2].to_vec();
         let tracker_id = "TRACKER-BACAN".to_string();
-        let response = Response::new(tracker_id.clone(), 0, 2, list.clone());
+        let peers = Peers::Dictionary { peers: list };
+        let response = Response::new(tracker_id.clone(), 0, 2, peers.clone());
 
         assert_eq!(response.tracker_id, tracker_id);
         assert_eq!(response.complete, 0);
         assert_eq!(response.incomplete, 2);
-        assert_eq!(response.bencode_peers, list);
+        assert_eq!(response.peers, peers);
     }
 
     #[test]
     fn generate_message() {
-        let id = ("id".to_string(), Bencode::String("PEER-BACAN".to_string()));
-        let ip = ("ip".to_string(), Bencode::String("127.0.0.1".to_string()));
-        let port = ("port".to_string(), Bencode::String("420".to_string()));
-        let dict1 = Bencode::Dictionary([id, ip, port].to_vec());
+        let id = ("id", Bencode::from("PEER-BACAN"));
+        let ip = ("ip", Bencode::from("127.0.0.1"));
+        let port = ("port", Bencode::from("420"));
+        let dict1 = Bencode::dict([id, ip, port].to_vec());
 
-        let id = ("id".to_string(), Bencode::String("PEER-PANA".to_string()));
-        let ip = ("ip".to_string(), Bencode::String("127.0.0.2".to_string()));
-        let port = ("port".to_string(), Bencode::String("440".to_string()));
-        let dict2 = Bencode::Dictionary([id, ip, port].to_vec());
+        let id = ("id", Bencode::from("PEER-PANA"));
+        let ip = ("ip", Bencode::from("127.0.0.2"));
+        let port = ("port", Bencode::from("440"));
+        let dict2 = Bencode::dict([id, ip, port].to_vec());
 
         let list = [dict1, dict2].to_vec();
         let tracker_id = "TRACKER-BACAN".to_string();
-        let mut response = Response::new(tracker_id.clone(), 0, 2, list.clone());
+        let peers = Peers::Dictionary { peers: list };
+        let mut response = Response::new(tracker_id, 0, 2, peers);
 
         let message = response.make_message();
-        println!("{}", message);
-        let split: Vec<&str> = message.split("\n").collect();
-
-        let bencode =
-            DecodingParser::decode_from_string(&DecodingParser, split[2].to_string()).unwrap();
+        let index = message
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .unwrap();
+        assert!(message.starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+        let bencode = DecodingParser
+            .decode_from_u8(message[index + 4..].to_vec())
+            .unwrap();
         bencode.print();
     }
+
+    #[test]
+    fn bencode_fields_sorted() {
+        let peer = Bencode::dict(vec![("ip", Bencode::from("127.0.0.1"))]);
+        let peers = Peers::Dictionary { peers: vec![peer] };
+        let mut response = Response::new("TRACKER-BACAN".to_string(), 1, 2, peers);
+
+        assert_eq!(
+            response.bencode(),
+            b"d8:completei1e10:incompletei2e8:intervali10e12:min intervali5e5:peersld2:ip9:127.0.0.1ee10:tracker id13:TRACKER-BACANe"
+        );
+    }
+
+    #[test]
+    fn include_warning_message() {
+        let peers = Peers::Dictionary { peers: vec![] };
+        let mut response = Response::new("ID".to_string(), 0, 0, peers);
+        response.warning_message = Some("aviso".to_string());
+
+        assert_eq!(
+            response.bencode(),
+            b"d8:completei0e10:incompletei0e8:intervali10e12:min intervali5e5:peersle10:tracker id2:ID15:warning message5:avisoe"
+        );
+    }
+
+    #[test]
+    fn generate_failure_message() {
+        let message = Response::failure("Puerto invalido");
+
+        assert!(message.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(message.ends_with(b"d14:failure reason15:Puerto invalidoe"));
+    }
+
+    #[test]
+    fn send_compact_peers_as_binary() {
+        let peers = Peers::Compact {
+            peers: vec![127, 0, 0, 1, 0x1a, 0xe1],
+            peers6: vec![],
+        };
+        let mut response = Response::new("TRACKER-BACAN".to_string(), 1, 0, peers);
+
+        let mut expected =
+            b"d8:completei1e10:incompletei0e8:intervali10e12:min intervali5e5:peers6:".to_vec();
+        expected.extend([127, 0, 0, 1, 0x1a, 0xe1]);
+        expected.extend(b"10:tracker id13:TRACKER-BACANe");
+        assert_eq!(response.bencode(), expected);
+        assert!(response.make_message().ends_with(&expected));
+    }
 }
@@ -0,0 +1,187 @@
+use crate::errors::BitTrackerError;
+use crate::seed::seeder::Seeder;
+use protocol::encoder::bencode_serde;
+use protocol::encoder::bencode_tokenizer::DecodingOptions;
+use protocol::encoder::url_encoder::URLEncoder;
+use serde::Deserialize;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/******************************************************************************************/
+/*                                     ANNOUNCER                                          */
+/******************************************************************************************/
+
+/// Tiempo maximo de espera al conectarse y al leer la respuesta del tracker.
+const TRACKER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Datos de un announce del seeder. El seeder tiene los archivos completos, por lo que
+/// siempre informa `left=0` y `downloaded=0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Announce {
+    pub url: String,
+    pub info_hash: Vec<u8>,
+    pub peer_id: String,
+    pub port: u16,
+    pub uploaded: u64,
+    pub event: &'static str,
+}
+
+impl Announce {
+    /// Genera la request HTTP del announce. Falla si la url no es `http://`.
+    pub fn http_request(&self) -> Result<(String, String), BitTrackerError> {
+        let url = self
+            .url
+            .strip_prefix("http://")
+            .ok_or(BitTrackerError::URLEncodingError)?;
+        let (host, path) = match url.find('/') {
+            Some(position) => url.split_at(position),
+            None => (url, "/announce"),
+        };
+        let info_hash = URLEncoder
+            .urlencode(self.info_hash.clone())
+            .or(Err(BitTrackerError::URLEncodingError))?;
+        let peer_id = URLEncoder
+            .urlencode(self.peer_id.clone().into_bytes())
+            .or(Err(BitTrackerError::URLEncodingError))?;
+        let separator = if path.contains('?') { '&' } else { '?' };
+        let mut query = format!(
+            "info_hash={}&peer_id={}&port={}&uploaded={}&downloaded=0&left=0&compact=1",
+            info_hash, peer_id, self.port, self.uploaded
+        );
+        if !self.event.is_empty() {
+            query = query + "&event=" + self.event;
+        }
+        let request = format!(
+            "GET {}{}{} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            path, separator, query, host
+        );
+        Ok((host.to_string(), request))
+    }
+
+    /// Envia el announce al tracker. Devuelve el intervalo que informo el tracker, si lo hizo.
+    pub fn send(&self) -> Result<Option<u64>, BitTrackerError> {
+        let (host, request) = self.http_request()?;
+        let mut stream = TcpStream::connect(&host).or(Err(BitTrackerError::FailToConnectError))?;
+        stream
+            .set_read_timeout(Some(TRACKER_TIMEOUT))
+            .or(Err(BitTrackerError::FailToConnectError))?;
+        stream
+            .write_all(request.as_bytes())
+            .or(Err(BitTrackerError::WriteConnectionError))?;
+        let mut response = vec![];
+        stream
+            .read_to_end(&mut response)
+            .or(Err(BitTrackerError::ReadConnectionError))?;
+        Self::parse_response(&response)
+    }
+
+    /// Interpreta la respuesta HTTP del tracker. Si rechazo el announce devuelve el motivo.
+    pub fn parse_response(response: &[u8]) -> Result<Option<u64>, BitTrackerError> {
+        let body = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .map(|position| &response[position + 4..])
+            .ok_or(BitTrackerError::InvalidSyntaxError)?;
+        let options = DecodingOptions {
+            lenient: true,
+            ..DecodingOptions::default()
+        };
+        let reply: AnnounceReply = bencode_serde::from_bytes_with(body, options)
+            .or(Err(BitTrackerError::DecodingError))?;
+        if let Some(reason) = reply.failure_reason {
+            return Err(BitTrackerError::AnnounceFailureError(
+                String::from_utf8_lossy(&reason).to_string(),
+            ));
+        }
+        if let Some(message) = reply.warning_message {
+            println!(
+                "[SEEDER] Advertencia del tracker: {}",
+                String::from_utf8_lossy(&message)
+            );
+        }
+        Ok(reply
+            .interval
+            .and_then(|interval| u64::try_from(interval).ok()))
+    }
+}
+
+/// Campos de la respuesta al announce que usa el seeder.
+#[derive(Deserialize)]
+struct AnnounceReply {
+    #[serde(rename = "failure reason")]
+    failure_reason: Option<serde_bytes::ByteBuf>,
+    #[serde(rename = "warning message")]
+    warning_message: Option<serde_bytes::ByteBuf>,
+    interval: Option<i64>,
+}
+
+/// Anuncia periodicamente al tracker todos los torrents del seeder. El primer announce de
+/// cada torrent se envia con `event=started`. Entre rondas se espera el intervalo que
+/// informo el tracker o, si no informo ninguno, el de la configuracion.
+pub fn run(seeder: Arc<Mutex<Seeder>>, tracker: Option<String>, interval: u64, port: u16) {
+    let mut event = "started";
+    loop {
+        let announces = match seeder.lock() {
+            Ok(seeder) => seeder.announces(tracker.as_deref(), port, event),
+            Err(_) => return,
+        };
+        let mut wait: Option<u64> = None;
+        for announce in announces {
+            match announce.send() {
+                Ok(Some(tracker_interval)) => {
+                    wait = Some(wait.map_or(tracker_interval, |wait| wait.min(tracker_interval)))
+                }
+                Ok(None) => {}
+                Err(error) => println!("[SEEDER] Fallo el announce a {}: {}", announce.url, error),
+            }
+        }
+        event = "";
+        thread::sleep(Duration::from_secs(wait.unwrap_or(interval).max(1)));
+    }
+}
+
+#[cfg(test)]
+mod announcer_should {
+    use super::*;
+
+    fn announce(url: &str) -> Announce {
+        Announce {
+            url: url.to_string(),
+            info_hash: vec![0xab; 20],
+            peer_id: "SEEDER-0000000000000".to_string(),
+            port: 6881,
+            uploaded: 42,
+            event: "started",
+        }
+    }
+
+    #[test]
+    fn build_http_request() {
+        let (host, request) = announce("http://127.0.0.1:8080/announce")
+            .http_request()
+            .unwrap();
+
+        assert_eq!(host, "127.0.0.1:8080");
+        assert!(request.starts_with(&format!(
+            "GET /announce?info_hash={}&peer_id=SEEDER-0000000000000&port=6881&uploaded=42&downloaded=0&left=0&compact=1&event=started HTTP/1.1\r\n",
+            "%ab".repeat(20)
+        )));
+        assert!(request.contains("Host: 127.0.0.1:8080\r\n"));
+        assert!(announce("udp://127.0.0.1:8080").http_request().is_err());
+    }
+
+    #[test]
+    fn parse_interval_and_failure_reason() {
+        let response = b"HTTP/1.1 200 OK\r\n\r\nd8:intervali120e5:peers0:e";
+        assert_eq!(Announce::parse_response(response).unwrap(), Some(120));
+
+        let response = b"HTTP/1.1 200 OK\r\n\r\nd14:failure reason7:baneadoe";
+        assert!(matches!(
+            Announce::parse_response(response),
+            Err(BitTrackerError::AnnounceFailureError(reason)) if reason == "baneado"
+        ));
+    }
+}
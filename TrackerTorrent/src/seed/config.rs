@@ -0,0 +1,173 @@
+use crate::config::{address, parse_value, read_lines};
+use crate::errors::{BitTrackerError, ConfigError};
+
+/******************************************************************************************/
+/*                                   SEEDER CONFIG                                        */
+/******************************************************************************************/
+
+const ADDRESS: &str = "127.0.0.1:1234";
+const ANNOUNCE_INTERVAL: u64 = 30;
+const WORKERS: usize = 8;
+const MAX_CONNECTIONS: usize = 64;
+const CONNECTION_TIMEOUT: u64 = 180;
+
+/// Torrent que sirve el seeder, junto con el archivo que contiene sus datos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeedEntry {
+    pub torrent: String,
+    pub file: String,
+}
+
+/// Configuracion del seeder. Se lee de un archivo con el mismo formato `CLAVE:valor` que la
+/// configuracion del tracker; las lineas vacias y las que empiezan con `#` se ignoran.
+///
+/// Claves: `ADDRESS` (direccion donde se escuchan peers), `TRACKER` (url de announce; por
+/// defecto la del .torrent), `ANNOUNCE_INTERVAL` (segundos, si el tracker no informa otro),
+/// `WORKERS`, `MAX_CONNECTIONS`, `CONNECTION_TIMEOUT` (segundos) y `TORRENT`, que puede
+/// repetirse y tiene el formato `ruta del .torrent,ruta del archivo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeederConfig {
+    pub address: String,
+    pub tracker: Option<String>,
+    pub announce_interval: u64,
+    pub workers: usize,
+    pub max_connections: usize,
+    pub connection_timeout: u64,
+    pub torrents: Vec<SeedEntry>,
+}
+
+impl Default for SeederConfig {
+    fn default() -> Self {
+        SeederConfig {
+            address: ADDRESS.to_string(),
+            tracker: None,
+            announce_interval: ANNOUNCE_INTERVAL,
+            workers: WORKERS,
+            max_connections: MAX_CONNECTIONS,
+            connection_timeout: CONNECTION_TIMEOUT,
+            torrents: vec![],
+        }
+    }
+}
+
+impl SeederConfig {
+    /// Lee la configuracion del archivo.
+    pub fn from_file(path: &str) -> Result<Self, BitTrackerError> {
+        Self::parse(&read_lines(path)?).map_err(BitTrackerError::ConfigError)
+    }
+
+    /// Genera la configuracion a partir de las lineas del archivo y la valida.
+    pub fn parse(lines: &[String]) -> Result<Self, ConfigError> {
+        let mut config = SeederConfig::default();
+        for line in lines {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = line
+                .split_once(':')
+                .map(|(name, value)| (name.trim(), value.trim().to_string()))
+                .ok_or_else(|| ConfigError::UnknownKeyError(line.to_string()))?;
+            match name {
+                "ADDRESS" => config.address = value,
+                "TRACKER" => config.tracker = Some(value),
+                "ANNOUNCE_INTERVAL" => config.announce_interval = parse_value(name, &value)?,
+                "WORKERS" => config.workers = parse_value(name, &value)?,
+                "MAX_CONNECTIONS" => config.max_connections = parse_value(name, &value)?,
+                "CONNECTION_TIMEOUT" => config.connection_timeout = parse_value(name, &value)?,
+                "TORRENT" => {
+                    let (torrent, file) = value
+                        .split_once(',')
+                        .ok_or(ConfigError::InvalidValueError(name.to_string()))?;
+                    config.torrents.push(SeedEntry {
+                        torrent: torrent.trim().to_string(),
+                        file: file.trim().to_string(),
+                    });
+                }
+                _ => return Err(ConfigError::UnknownKeyError(name.to_string())),
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Verifica que la direccion sea valida, que los valores numericos sean positivos y que
+    /// haya al menos un torrent para servir.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        address("ADDRESS", &self.address)?;
+        let positive = [
+            ("ANNOUNCE_INTERVAL", self.announce_interval),
+            ("WORKERS", self.workers as u64),
+            ("MAX_CONNECTIONS", self.max_connections as u64),
+            ("CONNECTION_TIMEOUT", self.connection_timeout),
+            ("TORRENT", self.torrents.len() as u64),
+        ];
+        if let Some((name, _value)) = positive.iter().find(|(_name, value)| *value == 0) {
+            return Err(ConfigError::InvalidValueError(name.to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod seeder_config_should {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parse_torrent_and_file_pairs() {
+        let config = SeederConfig::parse(&lines(
+            "# seeder\n\
+             ADDRESS:0.0.0.0:6881\n\
+             TRACKER:http://127.0.0.1:8080/announce\n\
+             TORRENT:./torrents/a.torrent, ./downloads/a.pdf\n\
+             TORRENT:./torrents/b.torrent,./downloads/b.pdf\n",
+        ))
+        .unwrap();
+
+        assert_eq!(config.address, "0.0.0.0:6881");
+        assert_eq!(
+            config.tracker.as_deref(),
+            Some("http://127.0.0.1:8080/announce")
+        );
+        assert_eq!(config.announce_interval, ANNOUNCE_INTERVAL);
+        assert_eq!(
+            config.torrents,
+            vec![
+                SeedEntry {
+                    torrent: "./torrents/a.torrent".to_string(),
+                    file: "./downloads/a.pdf".to_string(),
+                },
+                SeedEntry {
+                    torrent: "./torrents/b.torrent".to_string(),
+                    file: "./downloads/b.pdf".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_invalid_configurations() {
+        let parse = |text: &str| SeederConfig::parse(&lines(text));
+
+        assert_eq!(
+            parse("ADDRESS:127.0.0.1:1234\n"),
+            Err(ConfigError::InvalidValueError("TORRENT".to_string()))
+        );
+        assert_eq!(
+            parse("TORRENT:./torrents/a.torrent\n"),
+            Err(ConfigError::InvalidValueError("TORRENT".to_string()))
+        );
+        assert_eq!(
+            parse("PUERTO:1234\n"),
+            Err(ConfigError::UnknownKeyError("PUERTO".to_string()))
+        );
+        assert_eq!(
+            parse("WORKERS:0\nTORRENT:a,b\n"),
+            Err(ConfigError::InvalidValueError("WORKERS".to_string()))
+        );
+    }
+}
@@ -1,2 +1,4 @@
+pub mod announcer;
+pub mod config;
 pub mod seeder;
 pub mod uploader;
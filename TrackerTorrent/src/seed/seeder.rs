@@ -1,44 +1,151 @@
 use crate::errors::BitTrackerError;
-use crate::metainfo::MetaInfo;
-use crate::peer_protocol::handshake::Handshake;
-use crate::peer_protocol::messages::Message;
-use crate::peer_protocol::messages::MessageId;
+use crate::pool::ThreadPool;
+use crate::seed::announcer::{self, Announce};
+use crate::seed::config::{SeedEntry, SeederConfig};
 use crate::seed::uploader::Uploader;
+use protocol::peer_protocol::codec::{CodecLimits, PeerCodec};
+use protocol::peer_protocol::handshake::Handshake;
+use protocol::peer_protocol::messages::Message;
+use protocol::peer_protocol::messages::MessageId;
+use protocol::torrent_file::metainfo::MetaInfo;
 
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use sha1::{Digest, Sha1};
 use std::io::Read;
 use std::io::Write;
 use std::net::TcpListener;
 use std::net::{Shutdown, TcpStream};
 use std::sync::{Arc, Mutex};
 use std::thread;
-//use std::fs::File;
-//use std::io::BufReader;
-//use std::env::args;
-//use std::io::BufRead;
+use std::time::Duration;
 
 const HANDSHAKE_LEN: usize = 68;
-const LEN: usize = 4;
+/// Largo maximo de un bloque que puede pedir un peer.
+const MAX_BLOCK_LEN: u32 = 1 << 17;
 
-#[allow(dead_code)]
+/******************************************************************************************/
+/*                                  SEED TORRENT                                          */
+/******************************************************************************************/
 
-/// Estrucutura server encargada de seedear
-pub struct Seeder {
-    pub uploader: Uploader,
+/// Torrent que sirve el seeder, con el archivo de donde se leen sus piezas.
+#[derive(Debug)]
+pub struct SeedTorrent {
     pub metainfo: MetaInfo,
+    pub uploader: Uploader,
+    /// Piezas cuyos datos coinciden con el hash del .torrent. Solo se envian estas.
+    pub pieces: Vec<bool>,
+    /// Bytes enviados a los peers, que se informan al tracker.
+    pub uploaded: u64,
+}
+
+impl SeedTorrent {
+    /// Carga el .torrent y el archivo de la entrada de la configuracion y verifica las piezas.
+    pub fn new(entry: &SeedEntry) -> Result<Self, BitTrackerError> {
+        let metainfo = MetaInfo::new(&entry.torrent)?;
+        Self::from_metainfo(metainfo, &entry.file)
+    }
+
+    /// Abre el archivo del torrent y verifica cada pieza contra su hash.
+    pub fn from_metainfo(metainfo: MetaInfo, file_path: &str) -> Result<Self, BitTrackerError> {
+        let uploader = Uploader::new(file_path)?;
+        let mut torrent = SeedTorrent {
+            pieces: vec![false; metainfo.info.num_pieces],
+            metainfo,
+            uploader,
+            uploaded: 0,
+        };
+        torrent.verify();
+        Ok(torrent)
+    }
+
+    /// Lee cada pieza del archivo y la marca como disponible si su SHA1 coincide con el del
+    /// .torrent. Devuelve la cantidad de piezas verificadas.
+    pub fn verify(&mut self) -> usize {
+        for index in 0..self.pieces.len() {
+            let valid = match self.piece_len(index as u32) {
+                Some(length) => {
+                    let offset = index as u64 * self.metainfo.info.piece_length as u64;
+                    match self.uploader.upload(offset, length) {
+                        Ok(data) => Sha1::digest(&data)[..] == self.metainfo.info.pieces[index][..],
+                        Err(_) => false,
+                    }
+                }
+                None => false,
+            };
+            self.pieces[index] = valid;
+        }
+        self.pieces.iter().filter(|valid| **valid).count()
+    }
+
+    /// Largo de la pieza. La ultima puede ser mas corta que el resto.
+    fn piece_len(&self, index: u32) -> Option<u64> {
+        let piece_length = self.metainfo.info.piece_length as u64;
+        let offset = index as u64 * piece_length;
+        if index as usize >= self.pieces.len() || offset >= self.metainfo.info.length {
+            return None;
+        }
+        Some(piece_length.min(self.metainfo.info.length - offset))
+    }
+
+    /// Valida el bloque pedido por un peer y devuelve su offset en el archivo. Devuelve None
+    /// si la pieza no esta verificada y un error si el pedido esta fuera de la pieza o supera
+    /// el largo maximo de un bloque.
+    pub fn block_offset(
+        &self,
+        index: u32,
+        begin: u32,
+        length: u32,
+    ) -> Result<Option<u64>, BitTrackerError> {
+        let piece_len = self
+            .piece_len(index)
+            .ok_or(BitTrackerError::InvalidMessageError)?;
+        if length == 0 || length > MAX_BLOCK_LEN || begin as u64 + length as u64 > piece_len {
+            return Err(BitTrackerError::InvalidMessageError);
+        }
+        if !self.pieces[index as usize] {
+            return Ok(None);
+        }
+        Ok(Some(
+            index as u64 * self.metainfo.info.piece_length as u64 + begin as u64,
+        ))
+    }
+}
+
+/******************************************************************************************/
+/*                                      SEEDER                                            */
+/******************************************************************************************/
+
+/// Estrucutura server encargada de seedear. Sirve todos sus torrents en la misma direccion
+/// y elige cual segun el info hash del handshake del peer.
+pub struct Seeder {
+    pub torrents: Vec<SeedTorrent>,
     pub id: String,
 }
 
-#[allow(dead_code)]
 impl Seeder {
-    fn new(torrent_path: String, file_path: String, id: String) -> Result<Self, BitTrackerError> {
-        let uploader = Uploader::new(&file_path)?;
-        let metainfo = MetaInfo::new(&torrent_path)?;
-        Ok(Seeder {
-            uploader,
-            metainfo,
-            id,
-        })
+    /// Carga los torrents de la configuracion. Los que no se pueden abrir se informan y se
+    /// omiten; falla si no queda ninguno.
+    pub fn new(config: &SeederConfig, id: String) -> Result<Self, BitTrackerError> {
+        let mut torrents = vec![];
+        for entry in &config.torrents {
+            match SeedTorrent::new(entry) {
+                Ok(torrent) => {
+                    let verified = torrent.pieces.iter().filter(|valid| **valid).count();
+                    println!(
+                        "[SEEDER] {}: {} de {} piezas verificadas",
+                        torrent.metainfo.info.name,
+                        verified,
+                        torrent.pieces.len()
+                    );
+                    torrents.push(torrent);
+                }
+                Err(error) => println!("[ERROR] No se pudo cargar {}: {}", entry.torrent, error),
+            }
+        }
+        if torrents.is_empty() {
+            return Err(BitTrackerError::OpenFileError);
+        }
+        Ok(Seeder { torrents, id })
     }
 
     /// Genera aleatoriamente el id de nuestro peer.
@@ -51,41 +158,89 @@ impl Seeder {
         id
     }
 
-    pub fn start(
-        torrent_path: String,
-        file_path: String,
-        id: String,
-        addr: String,
-    ) -> Result<(), BitTrackerError> {
-        let seeder = Seeder::new(torrent_path, file_path, id)?;
-        let listener = TcpListener::bind(addr).or(Err(BitTrackerError::FailToConnectError))?;
+    /// Busca el torrent por su info hash.
+    pub fn find(&self, info_hash: &[u8]) -> Option<usize> {
+        self.torrents
+            .iter()
+            .position(|torrent| torrent.metainfo.info_hash == info_hash)
+    }
+
+    /// Genera los announces de todos los torrents. Si no se configuro un tracker se usa el
+    /// announce de cada .torrent.
+    pub fn announces(
+        &self,
+        tracker: Option<&str>,
+        port: u16,
+        event: &'static str,
+    ) -> Vec<Announce> {
+        self.torrents
+            .iter()
+            .map(|torrent| Announce {
+                url: tracker.unwrap_or(&torrent.metainfo.announce).to_string(),
+                info_hash: torrent.metainfo.info_hash.clone(),
+                peer_id: self.id.clone(),
+                port,
+                uploaded: torrent.uploaded,
+                event,
+            })
+            .collect()
+    }
+
+    /// Carga los torrents, empieza a anunciarlos al tracker y atiende a los peers que se
+    /// conectan a la direccion de la configuracion.
+    pub fn start(config: &SeederConfig) -> Result<(), BitTrackerError> {
+        let seeder = Seeder::new(config, Self::generate_id())?;
+        let listener =
+            TcpListener::bind(&config.address).or(Err(BitTrackerError::FailToConnectError))?;
+        let port = listener
+            .local_addr()
+            .or(Err(BitTrackerError::FailToConnectError))?
+            .port();
         println!("[SEEDER] Estableci la conexion, listo para recibir pedidos!");
-        let mut connections = vec![];
         let mutex = Arc::new(Mutex::new(seeder));
-        for (id, stream) in listener.incoming().into_iter().enumerate() {
-            match stream {
-                Ok(stream) => {
-                    let clone = mutex.clone();
-                    connections.push(thread::spawn(move || attend_connection(stream, clone, id)))
-                }
+
+        let clone = mutex.clone();
+        let tracker = config.tracker.clone();
+        let interval = config.announce_interval;
+        thread::spawn(move || announcer::run(clone, tracker, interval, port));
+
+        let pool = ThreadPool::new(config.workers, config.max_connections);
+        Self::listen(
+            listener,
+            mutex,
+            &pool,
+            Duration::from_secs(config.connection_timeout),
+        );
+        Ok(())
+    }
+
+    /// Atiende cada conexion en un thread del pool. Si el pool esta lleno la conexion se
+    /// cierra.
+    fn listen(
+        listener: TcpListener,
+        mutex: Arc<Mutex<Seeder>>,
+        pool: &ThreadPool,
+        timeout: Duration,
+    ) {
+        for (id, stream) in listener.incoming().enumerate() {
+            let stream = match stream {
+                Ok(stream) => stream,
                 Err(error) => {
                     println!("[ERROR] {}", error);
                     continue;
                 }
+            };
+            if pool.is_full() {
+                println!("[SEEDER] Se rechazo la conexion {}: sin lugar", id);
+                continue;
             }
-        }
-        for (index, connection) in connections.into_iter().enumerate() {
-            match connection.join() {
-                Ok(result_connection) => {
-                    if let Err(error) = result_connection {
-                        println!("[ERROR] Conexion {}: {}", index, error);
-                    }
+            let clone = mutex.clone();
+            pool.execute(move || {
+                if let Err(error) = attend_connection(stream, clone, id, timeout) {
+                    println!("[ERROR] Conexion {}: {}", id, error);
                 }
-                Err(_) => println!("[ERROR] Fallo el join de la conexion numero {}", index),
-            }
+            });
         }
-
-        Ok(())
     }
 }
 
@@ -93,24 +248,52 @@ pub fn attend_connection(
     stream: TcpStream,
     seeder: Arc<Mutex<Seeder>>,
     id: usize,
+    timeout: Duration,
 ) -> Result<(), BitTrackerError> {
+    stream
+        .set_read_timeout(Some(timeout))
+        .or(Err(BitTrackerError::FailToConnectError))?;
+    stream
+        .set_write_timeout(Some(timeout))
+        .or(Err(BitTrackerError::FailToConnectError))?;
     let mut connection = SeederConnection::new(stream, seeder, id);
     connection.attend_connection()?;
     Ok(())
 }
 
-#[allow(dead_code)]
+/******************************************************************************************/
+/*                                 SEEDER CONNECTION                                      */
+/******************************************************************************************/
+
 pub struct SeederConnection {
     stream: TcpStream,
     seeder: Arc<Mutex<Seeder>>,
     id: usize,
+    /// Posicion en el seeder del torrent que pidio el peer en el handshake.
+    torrent: usize,
+    /// Indica si el peer esta chokeado. Sus requests se ignoran hasta que envie interested.
+    choked: bool,
+    codec: PeerCodec,
+    /// Handle propio del archivo del torrent, para leer los bloques sin tomar el lock del
+    /// seeder. Se abre luego del handshake.
+    uploader: Option<Uploader>,
 }
-#[allow(dead_code)]
+
 impl SeederConnection {
     pub fn new(stream: TcpStream, seeder: Arc<Mutex<Seeder>>, id: usize) -> Self {
-        SeederConnection { stream, seeder, id }
+        SeederConnection {
+            stream,
+            seeder,
+            id,
+            torrent: 0,
+            choked: true,
+            codec: PeerCodec::default(),
+            uploader: None,
+        }
     }
 
+    /// Realiza el handshake, envia el bitfield del torrent y atiende los mensajes del peer
+    /// hasta que cierre la conexion o deje de enviar mensajes dentro del timeout.
     pub fn attend_connection(&mut self) -> Result<(), BitTrackerError> {
         let valid = self.attempt_handshake()?;
         if !valid {
@@ -118,6 +301,7 @@ impl SeederConnection {
                 "[SEEDER] El handshake con la conexion {} , fue invalido",
                 self.id
             );
+            self.end(self.id);
             return Ok(());
         }
         println!(
@@ -125,41 +309,26 @@ impl SeederConnection {
             self.id
         );
 
-        let _bitfield = self.send_bitfield()?;
-        //println!("Bitfield: {:?}",bitfield);
-        //println!("Bitfield len: {}",bitfield.len());
-        //self.write_messages(bitfield)?;
-        println!("[SERVER] envie el bitfield a la conexion {}", self.id);
-
-        let message = Message::send_have(0);
-        //println!("Server : envie mensjae {:?}" , message);
-        self.write_messages(message)?;
-        println!(
-            "[SEEDER] envie have de la pieza 0 a la conexion {}",
-            self.id
-        );
+        let bitfield = self.send_bitfield()?;
+        self.write_message(&bitfield)?;
+        println!("[SEEDER] envie el bitfield a la conexion {}", self.id);
 
-        let mut done = false;
-        while !done {
-            let messages = self
-                .read_stream()
-                .or(Err(BitTrackerError::ReadConnectionError))?;
-            match self.handle_server_message(messages, self.id) {
-                Err(err) => {
-                    return Err(err);
-                }
-                Ok(result) => {
-                    done = result;
-                }
+        let result = loop {
+            let message = match self.read_stream() {
+                Ok(message) => message,
+                Err(BitTrackerError::ReadConnectionError) => break Ok(()),
+                Err(error) => break Err(error),
+            };
+            if let Err(error) = self.handle_server_message(message, self.id) {
+                break Err(error);
             }
-        }
-        if let Err(err) = self.end(self.id) {
-            return Err(err);
-        }
-        Ok(())
+        };
+        self.end(self.id);
+        result
     }
 
-    // Realiza la operacion inversa, recibe un bitmap booleano y lo transforma en un formato binario comprimido.
+    // Realiza la operacion inversa, recibe un bitmap booleano y lo transforma en un formato
+    // binario comprimido. El bit mas significativo del primer byte es la pieza 0.
     pub fn bytes_from_bitmap(bitmap: Vec<bool>) -> Vec<u8> {
         let mut init = 0;
         let mut finish = 8;
@@ -188,227 +357,169 @@ impl SeederConnection {
 
     fn binary_value(offset: u8) -> u8 {
         match offset {
-            0 => 1,
-            1 => 2,
-            2 => 4,
-            3 => 8,
-            4 => 16,
-            5 => 32,
-            6 => 64,
-            7 => 128,
+            0 => 128,
+            1 => 64,
+            2 => 32,
+            3 => 16,
+            4 => 8,
+            5 => 4,
+            6 => 2,
+            7 => 1,
             _ => 0,
         }
     }
 
-    /// Envia el bitfield por la conexion
-    fn send_bitfield(&mut self) -> Result<Vec<u8>, BitTrackerError> {
+    /// Genera el mensaje bitfield con las piezas verificadas del torrent.
+    fn send_bitfield(&mut self) -> Result<MessageId, BitTrackerError> {
         let seeder = self
             .seeder
             .lock()
             .or(Err(BitTrackerError::MutexLockError))?;
-        let num_pieces = seeder.metainfo.info.num_pieces;
+        let bitfield = seeder.torrents[self.torrent].pieces.clone();
         drop(seeder);
-        //let bitmap_len: usize = ((num_pieces as f32) / 8.0).ceil() as usize;
-        //println!("Bitmap_len {}:",bitmap_len);
-        let bitfield: Vec<bool> = vec![true; num_pieces];
-        //println!("Bitfield {:?}:",bitfield);
-        let mut bytes = Self::bytes_from_bitmap(bitfield);
-        //println!("COmpress Bitfield {:?}:",bytes);
-        let message =
-            Message::send_bitfield(&mut bytes).or(Err(BitTrackerError::InvalidMessageError))?;
-        Ok(message)
+        Ok(MessageId::Bitfield(Self::bytes_from_bitmap(bitfield)))
     }
 
-    /// Realiza el handshake con el otro peer, en caso de que no coincidan los info hash de ambos devuelve false
+    /// Recibe el handshake del peer y, si pide un torrent del seeder, responde con el
+    /// handshake de ese torrent. Devuelve false si el seeder no tiene el torrent.
     fn attempt_handshake(&mut self) -> Result<bool, BitTrackerError> {
+        let mut buffer: [u8; HANDSHAKE_LEN] = [0; HANDSHAKE_LEN];
+        self.stream
+            .read_exact(&mut buffer)
+            .or(Err(BitTrackerError::ReadConnectionError))?;
+        let handshake_request = Handshake::from_bytes(buffer.to_vec())
+            .or(Err(BitTrackerError::InvalidUTF8HandshakeError))?;
+        if handshake_request.pstr != "BitTorrent protocol" {
+            return Err(BitTrackerError::BadPeerResponseError);
+        }
+
         let seeder = self
             .seeder
             .lock()
             .or(Err(BitTrackerError::MutexLockError))?;
-        let seeder_id = seeder.id.clone();
-        let info_hash = seeder.metainfo.info_hash.clone();
+        let torrent = match seeder.find(&handshake_request.info_hash) {
+            Some(torrent) => torrent,
+            None => return Ok(false),
+        };
+        let handshake = Handshake::new(handshake_request.info_hash, seeder.id.clone());
+        let num_pieces = seeder.torrents[torrent].pieces.len();
+        let path = seeder.torrents[torrent].uploader.path.clone();
         drop(seeder);
 
-        let handshake = Handshake::new(info_hash, seeder_id);
-        let request = handshake.as_bytes();
+        self.uploader = Some(Uploader::new(&path)?);
+        self.torrent = torrent;
+        self.codec = PeerCodec::new(CodecLimits::for_pieces(num_pieces));
         self.stream
-            .write_all(&request)
+            .write_all(&handshake.as_bytes())
             .or(Err(BitTrackerError::WriteConnectionError))?;
-        let mut buffer: [u8; HANDSHAKE_LEN] = [0; HANDSHAKE_LEN];
-        self.stream
-            .read_exact(&mut buffer)
-            .or(Err(BitTrackerError::ReadConnectionError))?;
-
-        let handshake_response = Handshake::from_bytes(buffer.to_vec())
-            .or(Err(BitTrackerError::InvalidUTF8HandshakeError))?;
-        if handshake_response.info_hash == handshake.info_hash {
-            Ok(true)
-        } else {
-            Err(BitTrackerError::BadPeerResponseError)
-        }
+        Ok(true)
     }
 
-    /// Funcion para cerrar la conexion.
-    pub fn end(&mut self, id: usize) -> Result<(), BitTrackerError> {
-        println! {"[SERVER] Cerrando la conexion de {}!",id};
-        self.stream
-            .shutdown(Shutdown::Both)
-            .or(Err(BitTrackerError::FailToConnectError))?;
-        Ok(())
+    /// Funcion para cerrar la conexion. Si el peer ya la cerro no hay nada que hacer.
+    pub fn end(&mut self, id: usize) {
+        println! {"[SEEDER] Cerrando la conexion de {}!",id};
+        let _ = self.stream.shutdown(Shutdown::Both);
     }
 
     /// Funcion encargada de leer desde la conexion y transformarlo en mensaje
     pub fn read_stream(&mut self) -> Result<Message, BitTrackerError> {
-        let len: usize = self.read_size()?;
-        let mut byte_message: Vec<u8>;
-        match len {
-            0 => {
-                byte_message = vec![];
-            }
-            _ => {
-                byte_message = vec![0; len];
-                self.stream
-                    .read_exact(&mut byte_message)
-                    .or(Err(BitTrackerError::ReadConnectionError))?;
-            }
-        }
-        let len = len
-            .try_into()
-            .or(Err(BitTrackerError::InvalidMessageError))?;
-        let message =
-            Message::new(len, byte_message).or(Err(BitTrackerError::InvalidMessageError))?;
+        let message = self.codec.read_message(&mut self.stream)?;
         Ok(message)
     }
 
-    /// Lee el largo del mensaje, es decir los primeros 4 bytes
-    fn read_size(&mut self) -> Result<usize, BitTrackerError> {
-        let mut byte_len: [u8; LEN] = [0; LEN];
-        self.stream
-            .read_exact(&mut byte_len)
-            .or(Err(BitTrackerError::ReadConnectionError))?;
-        let len: usize = u32::from_be_bytes(byte_len)
-            .try_into()
-            .or(Err(BitTrackerError::InvalidMessageError))?;
-        Ok(len)
-    }
-
     /// Escribe un mensaje por la conexion
-    pub fn write_messages(&mut self, bytes: Vec<u8>) -> Result<(), BitTrackerError> {
-        self.stream
-            .write_all(&bytes)
-            .or(Err(BitTrackerError::WriteConnectionError))?;
+    pub fn write_message(&mut self, message: &MessageId) -> Result<(), BitTrackerError> {
+        self.codec.write_message(&mut self.stream, message)?;
         Ok(())
     }
 
+    /// Lee el bloque del archivo sin tomar el lock del seeder y luego suma los bytes a lo
+    /// subido del torrent.
+    fn read_block(&mut self, offset: u64, length: u32) -> Result<Vec<u8>, BitTrackerError> {
+        let block = self
+            .uploader
+            .as_mut()
+            .ok_or(BitTrackerError::UploadError)?
+            .upload(offset, length as u64)
+            .or(Err(BitTrackerError::UploadError))?;
+        let mut seeder = self
+            .seeder
+            .lock()
+            .or(Err(BitTrackerError::MutexLockError))?;
+        seeder.torrents[self.torrent].uploaded += length as u64;
+        drop(seeder);
+        Ok(block)
+    }
+
     /// Funcion encargada de manejar los distintos mensajes y peticiones que puede recibir nuestro servidor.
-    /// Matchea los mensajes por su id y en base a esto hace lo que debe.
+    /// Matchea los mensajes por su id y en base a esto hace lo que debe. El seeder unchokea a
+    /// todo peer interesado y responde sus requests de piezas verificadas.
     pub fn handle_server_message(
         &mut self,
         message: Message,
         id: usize,
-    ) -> Result<bool, BitTrackerError> {
+    ) -> Result<(), BitTrackerError> {
         match message.id {
-            MessageId::KeepAlive => {}
-            MessageId::Bitfield(_bytes) => {
-                println! {"[SERVER] Recibi un Bitfield de la conexion {}!",id};
-                let message = Message::send_have(0);
-                self.write_messages(message)?;
+            MessageId::Interested => {
+                println! {"[SEEDER] Recibi un Interested de la conexion {}!",id};
+                if self.choked {
+                    self.choked = false;
+                    self.write_message(&MessageId::Unchoke)?;
+                }
             }
-            MessageId::Unchoke => {
-                println! {"[SERVER] Recibi un Unchoke de la conexion {}!",id};
-                let choke = Message::send_choke();
-                self.write_messages(choke)?;
-                return Ok(true);
+            MessageId::NotInterested => {
+                println! {"[SEEDER] Recibi un NotInterested de la conexion {}!",id};
+                if !self.choked {
+                    self.choked = true;
+                    self.write_message(&MessageId::Choke)?;
+                }
             }
-
             MessageId::Request(piece_index, begin, length) => {
-                println! {"[SERVER] Recibi un Request de la conexion {}!",id};
-                let mut seeder = self
+                if self.choked {
+                    return Ok(());
+                }
+                let seeder = self
                     .seeder
                     .lock()
                     .or(Err(BitTrackerError::MutexLockError))?;
-                let piece_length = seeder.metainfo.info.piece_length;
-                let offset = piece_index * piece_length + begin;
-                let mut block = seeder
-                    .uploader
-                    .upload(offset as u64, length as u64)
-                    .or(Err(BitTrackerError::UploadError))?;
+                let offset =
+                    seeder.torrents[self.torrent].block_offset(piece_index, begin, length)?;
                 drop(seeder);
-                let message = Message::send_piece(piece_index, begin, &mut block)
-                    .or(Err(BitTrackerError::InvalidMessageError))?;
-                self.write_messages(message)?;
-            }
-
-            MessageId::Cancel(_piece_index, _begin, _length) => {
-                println! {"[SERVER] Recibi un Cancel de la conexion {}!",id};
-                let choke = Message::send_choke();
-                self.write_messages(choke)?;
-                return Ok(true);
-            }
-            MessageId::Choke => {
-                println! {"[SERVER] Recibi un Choke de la conexion {}!",id};
-                return Ok(true);
-            }
-            MessageId::NotInterested => {
-                println! {"[SERVER] Recibi un NotInerested de la conexion {}!",id};
-                let choke = Message::send_choke();
-                self.write_messages(choke)?;
-                return Ok(true);
-            }
-            MessageId::Interested => {
-                println! {"[SERVER] Recibi un Interested de la conexion {}!",id};
-                let unchoke = Message::send_unchoke();
-                self.write_messages(unchoke)?;
-            }
-            _ => {
-                return Err(BitTrackerError::InvalidMessageError);
+                match offset {
+                    Some(offset) => {
+                        let block = self.read_block(offset, length)?;
+                        self.write_message(&MessageId::Piece(piece_index, begin, block))?;
+                    }
+                    None => println!(
+                        "[SEEDER] La conexion {} pidio la pieza {}, que no esta verificada",
+                        id, piece_index
+                    ),
+                }
             }
+            // Las piezas se envian apenas se piden, por lo que no queda nada que cancelar.
+            MessageId::KeepAlive
+            | MessageId::Choke
+            | MessageId::Unchoke
+            | MessageId::Have(_)
+            | MessageId::Bitfield(_)
+            | MessageId::Cancel(..)
+            | MessageId::Piece(..) => {}
         }
-        Ok(false)
-    }
-}
-
-/*
-fn main() {
-    let args = args().collect::<Vec<String>>();
-    if args.len() != 2 {
-        println!("[ERROR] Cantidad de argumentos inválido");
-        return;
-    }
-    let config_file = args[1].clone();
-    if let Err(error) = start_seeder(&config_file){
-        println!("{}",error);
-    };
-
-}
-*/
-/*
-fn start_seeder(config_file: &str ) -> Result<(),BitTrackerError>{
-    let file = File::open(config_file).or(Err(BitTrackerError::OpenFileError))?;
-    let reader = BufReader::new(file);
-    let mut lines= Vec::new();
-    for line in reader.lines() {
-        let line = line.or(Err(BitTrackerError::ReadFileError))?;
-        lines.push(line);
+        Ok(())
     }
-    let addr = HOST.to_string() + &lines[0];
-    let file_path = lines[1].clone();
-    let torrent_path = lines[2].clone();
-    let id = Seeder::generate_id();
-    Seeder::start(torrent_path, file_path, id,addr)
 }
-*/
 
 #[cfg(test)]
 mod seeder_should {
     use super::*;
-    //use std::time::Duration;
-    use std::thread;
+    use protocol::torrent_file::metainfo::Info;
+    use std::fs;
+
+    const LEN: usize = 4;
+    const PIECE_LENGTH: u32 = 16384;
 
     pub fn read_stream(stream: &mut TcpStream) -> Message {
-        //println!("Por leer mensaje");
         let len: usize = read_size(stream);
-        //println!("Lei tamaño del mensaje: {}",len);
         let mut byte_message: Vec<u8>;
         match len {
             0 => {
@@ -419,206 +530,117 @@ mod seeder_should {
                 stream.read_exact(&mut byte_message).unwrap();
             }
         }
-        let message = Message::new(len as u32, byte_message).unwrap();
-        //println!("Lei mensaje: {:?}",message);
-        message
+        Message::new(len as u32, byte_message).unwrap()
     }
 
     fn read_size(stream: &mut TcpStream) -> usize {
-        //println!("[CONEXION {}] Por leer el tamaño de del mensaje",self.id);
         let mut byte_len: [u8; LEN] = [0; LEN];
         stream.read_exact(&mut byte_len).unwrap();
-        //println!("LEi byte len: {:?}",byte_len);
-        let len: usize = u32::from_be_bytes(byte_len).try_into().unwrap();
-        len
+        u32::from_be_bytes(byte_len).try_into().unwrap()
     }
 
-    #[test]
-    #[ignore]
-    fn start_seeder() {
-        let addr = "127.0.0.1:1234".to_string();
-        let download_path = "./downloads/INFORME - BITTORRENT.pdf".to_string();
-        let torrent_path = "./torrents/INFORME - BITTORRENT.pdf.torrent".to_string();
-        let id = "-4R1010-D23T24S25F26".to_string();
-        Seeder::start(torrent_path, download_path, id, addr).unwrap();
-    }
-
-    #[test]
-    #[ignore]
-    fn start_seeder_and_announce() {
-        let download_path = "./downloads/INFORME - BITTORRENT.pdf".to_string();
-        let torrent_path = "./torrents/INFORME - BITTORRENT.pdf.torrent".to_string();
-        let id = Seeder::generate_id();
-        let cloned = id.clone();
-        let addr = "127.0.0.1:1234".to_string();
-        let seed = thread::spawn(move || Seeder::start(torrent_path, download_path, cloned, addr));
-
-        let mut stream = TcpStream::connect("127.0.0.1:8080").unwrap();
-        let message = format!(
-            "GET /announce?infohash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id={}&port=1234&uploaded=0&downloaded=0&left=0&compact=1&event=completed&ip=127.0.0.1 HTTP/1.0\r\nHost: 127.0.0.2:8080\r\n\r\n",
-            id,
-        );
-
-        stream.write_all(message.as_bytes()).unwrap();
-        seed.join().unwrap().unwrap();
-    }
-
-    #[test]
-    #[ignore]
-    fn make_handshake() {
-        let addr = "127.0.0.1:1234".to_string();
-        let info_hash = vec![
-            169, 179, 39, 99, 78, 114, 33, 126, 224, 26, 222, 142, 109, 119, 22, 16, 127, 255, 196,
-            103,
-        ];
-        //Me conecto
-        let mut stream = TcpStream::connect(addr).unwrap();
-        let id = Seeder::generate_id();
-        let handshake = Handshake::new(info_hash, id);
-        let request = handshake.as_bytes();
-        stream.write_all(&request).unwrap();
-        let mut buffer: [u8; HANDSHAKE_LEN] = [0; HANDSHAKE_LEN];
-        stream.read_exact(&mut buffer).unwrap();
-        let handshake_response = Handshake::from_bytes(buffer.to_vec()).unwrap();
-        assert_eq!(handshake_response.info_hash, handshake.info_hash);
+    /// Escribe un archivo de 2,5 piezas y genera su metainfo. Si `corrupt` se le asigna a la
+    /// segunda pieza un hash que no coincide.
+    fn seed_torrent(name: &str, info_hash: u8, corrupt: bool) -> (SeedTorrent, Vec<u8>) {
+        let data: Vec<u8> = (0..PIECE_LENGTH * 5 / 2).map(|byte| byte as u8).collect();
+        let dir = std::env::temp_dir().join("trackertorrent_seeder");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, &data).unwrap();
+
+        let mut pieces: Vec<Vec<u8>> = data
+            .chunks(PIECE_LENGTH as usize)
+            .map(|piece| Sha1::digest(piece).to_vec())
+            .collect();
+        if corrupt {
+            pieces[1] = vec![0; 20];
+        }
+        let metainfo = MetaInfo {
+            announce: "http://127.0.0.1:8080/announce".to_string(),
+            info: Info {
+                piece_length: PIECE_LENGTH,
+                num_pieces: pieces.len(),
+                pieces,
+                name: name.to_string(),
+                length: data.len() as u64,
+                private: None,
+            },
+            info_hash: vec![info_hash; 20],
+        };
+        let torrent = SeedTorrent::from_metainfo(metainfo, path.to_str().unwrap()).unwrap();
+        (torrent, data)
     }
 
-    #[test]
-    #[ignore]
-    fn make_invalid_handshake() {
-        let addr = "127.0.0.1:1234".to_string();
-        let info_hash = vec![
-            169, 10, 10, 99, 78, 114, 33, 126, 224, 26, 222, 142, 109, 119, 22, 16, 127, 255, 196,
-            103,
-        ];
-        //Me conecto
-        let mut stream = TcpStream::connect(addr).unwrap();
-        let id = Seeder::generate_id();
-        let handshake = Handshake::new(info_hash, id);
-        let request = handshake.as_bytes();
-        stream.write_all(&request).unwrap();
+    fn handshake(stream: &mut TcpStream, info_hash: u8) -> Option<Handshake> {
+        let handshake = Handshake::new(vec![info_hash; 20], Seeder::generate_id());
+        stream.write_all(&handshake.as_bytes()).unwrap();
         let mut buffer: [u8; HANDSHAKE_LEN] = [0; HANDSHAKE_LEN];
-        stream.read_exact(&mut buffer).unwrap();
-        let handshake_response = Handshake::from_bytes(buffer.to_vec()).unwrap();
-        assert_ne!(handshake_response.info_hash, handshake.info_hash);
+        stream.read_exact(&mut buffer).ok()?;
+        Handshake::from_bytes(buffer.to_vec()).ok()
     }
 
     #[test]
-    #[ignore]
-    fn make_full_protocol() {
-        let addr = "127.0.0.1:1234".to_string();
-        let info_hash = vec![
-            169, 179, 39, 99, 78, 114, 33, 126, 224, 26, 222, 142, 109, 119, 22, 16, 127, 255, 196,
-            103,
-        ];
-        //Me conecto
-        let mut stream = TcpStream::connect(addr).unwrap();
-        let id = Seeder::generate_id();
-        let handshake = Handshake::new(info_hash, id);
-        let request = handshake.as_bytes();
-        stream.write_all(&request).unwrap();
-        let mut buffer: [u8; HANDSHAKE_LEN] = [0; HANDSHAKE_LEN];
-        stream.read_exact(&mut buffer).unwrap();
-        let handshake_response = Handshake::from_bytes(buffer.to_vec()).unwrap();
-        println!("Recibi response : {:?}", handshake_response);
-        assert_eq!(handshake_response.info_hash, handshake.info_hash);
-
-        let id = 0;
-        println!("[Conexion {}] Recibi un handshake valido", id);
-
-        //let message = read_stream(&mut stream);
-        //let bitmap_len: usize = ((133 as f32) / 8.0).ceil() as usize;
-        //let mut vec = vec![1; bitmap_len];
-        //assert_eq!(message.id, MessageId::Bitfield(vec));
-        println!("[Conexion {}] Recibi un bitfield", id);
-
-        let message = read_stream(&mut stream);
-        assert_eq!(message.id, MessageId::Have(0));
-        println!("[Conexion {}] Recibi un have", id);
-
-        println!("[Conexion {}] Envio interested", id);
-        let message = Message::send_interested();
-        stream.write_all(&message).unwrap();
-
-        let message = read_stream(&mut stream);
-        assert_eq!(message.id, MessageId::Unchoke);
-        println!("[Conexion {}] Recibi un Unchoke", id);
-
-        println!("[Conexion {}] Por pedir una pieza", id);
-        let message = Message::send_request(0, 0, 10);
-        stream.write_all(&message).unwrap();
-
-        let message = read_stream(&mut stream);
+    fn verify_pieces_against_hashes() {
+        let (torrent, _data) = seed_torrent("verify.bin", 1, true);
+
+        assert_eq!(torrent.pieces, vec![true, false, true]);
+        assert_eq!(torrent.piece_len(2), Some(PIECE_LENGTH as u64 / 2));
+        assert!(torrent.block_offset(1, 0, 10).unwrap().is_none());
+        assert!(torrent.block_offset(2, PIECE_LENGTH / 2 - 5, 10).is_err());
+        assert!(torrent.block_offset(3, 0, 10).is_err());
         assert_eq!(
-            message.id,
-            MessageId::Piece(0, 0, vec![37, 80, 68, 70, 45, 49, 46, 52, 10, 37])
+            torrent.block_offset(2, 4, 4).unwrap(),
+            Some(2 * PIECE_LENGTH as u64 + 4)
         );
-        println!("[Conexion {}] Recibi una pieza", id);
-
-        println!("[Conexion {}] Por enviar choke", id);
-        let message = Message::send_choke();
-        stream.write_all(&message).unwrap();
     }
 
     #[test]
-    #[ignore]
-    fn start_seeder_and_announce1() {
-        let download_path = "./downloads/INFORME - BITTORRENT.pdf".to_string();
-        let torrent_path = "./torrents/INFORME - BITTORRENT.pdf.torrent".to_string();
-        let id = Seeder::generate_id();
-        let cloned = id.clone();
-        let addr = "127.0.0.1:1234".to_string();
-        let seed = thread::spawn(move || Seeder::start(torrent_path, download_path, cloned, addr));
-
-        let mut stream = TcpStream::connect("127.0.0.1:8080").unwrap();
-        let message = format!(
-            "GET /announce?infohash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id={}&port=1234&uploaded=0&downloaded=0&left=0&compact=1&event=completed&ip=127.0.0.1 HTTP/1.0\r\nHost: 127.0.0.2:8080\r\n\r\n",
-            id,
+    fn pack_bitfield_most_significant_bit_first() {
+        let bitmap = vec![true, false, false, false, false, false, false, true, true];
+        assert_eq!(
+            SeederConnection::bytes_from_bitmap(bitmap),
+            vec![0b1000_0001, 0b1000_0000]
         );
-
-        stream.write_all(message.as_bytes()).unwrap();
-        seed.join().unwrap().unwrap();
     }
 
     #[test]
-    #[ignore]
-    fn start_seeder_and_announce2() {
-        let download_path = "./downloads/INFORME - BITTORRENT.pdf".to_string();
-        let torrent_path = "./torrents/INFORME - BITTORRENT.pdf.torrent".to_string();
-        let id = Seeder::generate_id();
-        let cloned = id.clone();
-        let addr = "127.0.0.1:1235".to_string();
-        let seed = thread::spawn(move || Seeder::start(torrent_path, download_path, cloned, addr));
-
-        let mut stream = TcpStream::connect("127.0.0.1:8080").unwrap();
-        let message = format!(
-            "GET /announce?infohash=%a9%b3%27cNr%21~%e0%1a%de%8emw%16%10%7f%ff%c4g&peer_id={}&port=1235&uploaded=0&downloaded=0&left=0&compact=1&event=completed&ip=127.0.0.1 HTTP/1.0\r\nHost: 127.0.0.2:8080\r\n\r\n",
-            id,
+    fn serve_pieces_of_every_torrent() {
+        let (first, _data) = seed_torrent("first.bin", 2, false);
+        let (second, data) = seed_torrent("second.bin", 3, true);
+        let seeder = Seeder {
+            torrents: vec![first, second],
+            id: Seeder::generate_id(),
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let mutex = Arc::new(Mutex::new(seeder));
+        let clone = mutex.clone();
+        thread::spawn(move || {
+            let pool = ThreadPool::new(2, 4);
+            Seeder::listen(listener, clone, &pool, Duration::from_secs(5));
+        });
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        let response = handshake(&mut stream, 3).unwrap();
+        assert_eq!(response.info_hash, vec![3; 20]);
+        assert_eq!(
+            read_stream(&mut stream).id,
+            MessageId::Bitfield(vec![0b1010_0000])
         );
 
-        stream.write_all(message.as_bytes()).unwrap();
-        seed.join().unwrap().unwrap();
-    }
-
-    #[test]
-    #[ignore]
-    fn start_seeder_and_announce3() {
-        let download_path =
-            "./downloads/DIAPOS - Proyecto BitTorrent - 4Rustasticos.pdf".to_string();
-        let torrent_path =
-            "./torrents/DIAPOS - Proyecto BitTorrent - 4Rustasticos.pdf.torrent".to_string();
-        let id = Seeder::generate_id();
-        let cloned = id.clone();
-        let addr = "127.0.0.1:1236".to_string();
-        let seed = thread::spawn(move || Seeder::start(torrent_path, download_path, cloned, addr));
-
-        let mut stream = TcpStream::connect("127.0.0.1:8080").unwrap();
-        let message = format!(
-            "GET /announce?infohash=%c3%14X%f3%04p%deY%d5%b1e%f8%87%0b%b9%14D%27%a0%9c&peer_id={}&port=1236&uploaded=0&downloaded=0&left=0&compact=1&event=completed&ip=127.0.0.1 HTTP/1.0\r\nHost: 127.0.0.2:8080\r\n\r\n",
-            id,
+        stream.write_all(&Message::send_request(0, 0, 10)).unwrap();
+        stream.write_all(&Message::send_interested()).unwrap();
+        assert_eq!(read_stream(&mut stream).id, MessageId::Unchoke);
+        stream.write_all(&Message::send_request(2, 16, 8)).unwrap();
+        let offset = 2 * PIECE_LENGTH as usize + 16;
+        assert_eq!(
+            read_stream(&mut stream).id,
+            MessageId::Piece(2, 16, data[offset..offset + 8].to_vec())
         );
+        drop(stream);
 
-        stream.write_all(message.as_bytes()).unwrap();
-        seed.join().unwrap().unwrap();
+        let mut stream = TcpStream::connect(address).unwrap();
+        assert!(handshake(&mut stream, 9).is_none());
+        assert_eq!(mutex.lock().unwrap().torrents[1].uploaded, 8);
     }
 }
@@ -1,3 +1,7 @@
+use crate::errors::StatsError;
+use crate::http::http_request::HttpRequest;
+use crate::torrent::HISTORY_HOURS;
+use crate::user::User;
 use serde::{Deserialize, Serialize};
 
 /******************************************************************************************/
@@ -10,6 +14,11 @@ pub struct Stats {
     pub cant_seeders: usize,
     pub cant_torrents: usize,
     pub info: Vec<InfoPeer>,
+    #[serde(default)]
+    pub users: Vec<UserStats>,
+    /// Metricas historicas de cada torrent, segun la query de la request.
+    #[serde(default)]
+    pub series: Vec<TorrentSeries>,
 }
 
 impl Stats {
@@ -24,6 +33,8 @@ impl Stats {
             cant_seeders,
             cant_torrents,
             info,
+            users: vec![],
+            series: vec![],
         }
     }
 }
@@ -47,6 +58,156 @@ impl InfoPeer {
     }
 }
 
+/// Totales de un usuario del tracker privado. No incluye la passkey.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UserStats {
+    pub name: String,
+    pub uploaded: u64,
+    pub downloaded: u64,
+    pub ratio: Option<f64>,
+}
+
+impl From<&User> for UserStats {
+    fn from(user: &User) -> Self {
+        UserStats {
+            name: user.name.clone(),
+            uploaded: user.uploaded,
+            downloaded: user.downloaded,
+            ratio: user.ratio(),
+        }
+    }
+}
+
+/******************************************************************************************/
+/*                                  TIME SERIES                                           */
+/******************************************************************************************/
+
+const HOUR: i64 = 3600;
+const DAY: i64 = 24 * HOUR;
+
+/// Metricas de un torrent en un intervalo de tiempo que empieza en `start` (segundos desde el
+/// epoch). `peers` y `seeders` son los maximos observados en el intervalo, `announces` y
+/// `completed` los totales.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Bucket {
+    pub start: i64,
+    pub announces: usize,
+    pub peers: usize,
+    pub seeders: usize,
+    pub completed: usize,
+}
+
+impl Bucket {
+    pub fn new(start: i64) -> Self {
+        Bucket {
+            start,
+            ..Default::default()
+        }
+    }
+
+    /// Acumula las metricas de otro intervalo contenido en este.
+    pub fn merge(&mut self, other: &Bucket) {
+        self.announces += other.announces;
+        self.completed += other.completed;
+        self.peers = self.peers.max(other.peers);
+        self.seeders = self.seeders.max(other.seeders);
+    }
+}
+
+/// Serie de metricas de un torrent.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TorrentSeries {
+    pub torrent: String,
+    pub info_hash: String,
+    pub buckets: Vec<Bucket>,
+}
+
+/// Tamaño de los intervalos de una serie.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    Hour,
+    Day,
+}
+
+impl Resolution {
+    /// Duracion del intervalo en segundos.
+    pub fn seconds(&self) -> i64 {
+        match self {
+            Resolution::Hour => HOUR,
+            Resolution::Day => DAY,
+        }
+    }
+
+    /// Inicio del intervalo que contiene al momento indicado. Los dias son dias UTC.
+    pub fn start(&self, time: i64) -> i64 {
+        time - time.rem_euclid(self.seconds())
+    }
+}
+
+/// Parametros de `GET /stats`:
+/// - `resolution`: `hour` (por defecto) o `day`.
+/// - `from` y `to`: segundos desde el epoch. Por defecto las ultimas 24 horas, o los ultimos
+///   7 dias si la resolucion es diaria.
+/// - `torrent`: info hash en hexadecimal o nombre del torrent. Por defecto todos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsQuery {
+    pub resolution: Resolution,
+    pub from: i64,
+    pub to: i64,
+    pub torrent: Option<String>,
+}
+
+impl StatsQuery {
+    /// Query por defecto: las ultimas 24 horas, por hora.
+    pub fn new(now: i64) -> Self {
+        StatsQuery {
+            resolution: Resolution::Hour,
+            from: now - DAY,
+            to: now,
+            torrent: None,
+        }
+    }
+
+    /// Lee los parametros del query string. El rango no puede ser mayor a la historia que
+    /// guarda el tracker.
+    pub fn from_http(request: &HttpRequest, now: i64) -> Result<Self, StatsError> {
+        let resolution = match request.param_str("resolution") {
+            None | Some("hour") => Resolution::Hour,
+            Some("day") => Resolution::Day,
+            Some(_) => return Err(StatsError::InvalidResolutionError),
+        };
+        let to = match request.param_str("to") {
+            Some(to) => to.parse().or(Err(StatsError::InvalidRangeError))?,
+            None => now,
+        };
+        let from = match (request.param_str("from"), resolution) {
+            (Some(from), _) => from.parse().or(Err(StatsError::InvalidRangeError))?,
+            (None, Resolution::Hour) => to - DAY,
+            (None, Resolution::Day) => to - 7 * DAY,
+        };
+        if from >= to || to - from > HISTORY_HOURS * HOUR {
+            return Err(StatsError::InvalidRangeError);
+        }
+        let torrent = request
+            .param_str("torrent")
+            .filter(|torrent| !torrent.is_empty())
+            .map(str::to_string);
+        Ok(StatsQuery {
+            resolution,
+            from,
+            to,
+            torrent,
+        })
+    }
+
+    /// Inicio de cada intervalo del rango, incluyendo el que contiene a `from`.
+    pub fn starts(&self) -> impl Iterator<Item = i64> {
+        let step = self.resolution.seconds() as usize;
+        (self.resolution.start(self.from)..self.to).step_by(step)
+    }
+}
+
 #[cfg(test)]
 mod stats_should {
     use super::*;
@@ -96,4 +257,35 @@ mod stats_should {
         assert_eq!(stats.cant_torrents, 0);
         assert_eq!(stats.info, vec);
     }
+
+    fn query(path: &str) -> Result<StatsQuery, StatsError> {
+        let raw = format!("GET {} HTTP/1.1\r\n\r\n", path);
+        let request = HttpRequest::read(&mut raw.as_bytes()).unwrap().unwrap();
+        StatsQuery::from_http(&request, 1_700_000_000)
+    }
+
+    #[test]
+    fn parse_stats_query() {
+        assert_eq!(query("/stats").unwrap(), StatsQuery::new(1_700_000_000));
+
+        let query_day =
+            query("/stats?resolution=day&from=1699000000&to=1699500000&torrent=ab").unwrap();
+        assert_eq!(query_day.resolution, Resolution::Day);
+        assert_eq!(
+            (query_day.from, query_day.to),
+            (1_699_000_000, 1_699_500_000)
+        );
+        assert_eq!(query_day.torrent, Some("ab".to_string()));
+        assert_eq!(query_day.starts().next(), Some(1_698_969_600));
+
+        assert_eq!(
+            query("/stats?resolution=week"),
+            Err(StatsError::InvalidResolutionError)
+        );
+        assert_eq!(
+            query("/stats?from=1700000000&to=1600000000"),
+            Err(StatsError::InvalidRangeError)
+        );
+        assert_eq!(query("/stats?from=0"), Err(StatsError::InvalidRangeError));
+    }
 }
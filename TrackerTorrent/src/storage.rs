@@ -0,0 +1,397 @@
+use crate::errors::BitTrackerError;
+use crate::peer::{Event, Peer};
+use crate::request::Request;
+use crate::stats::Bucket;
+use crate::torrent::Torrent;
+use crate::tracker::BitTracker;
+use crate::user::User;
+use chrono::{DateTime, Local, TimeZone};
+use protocol::encoder::url_encoder::URLEncoder;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/******************************************************************************************/
+/*                                      STORAGE                                           */
+/******************************************************************************************/
+
+const SNAPSHOT_FILE: &str = "snapshot.json";
+const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// Estado de un peer tal como se guarda en disco. Los tiempos se guardan en milisegundos
+/// desde el epoch.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PeerRecord {
+    pub id: String,
+    pub ip: String,
+    pub port: String,
+    pub event: Event,
+    pub uploaded: String,
+    pub downloaded: String,
+    pub left: String,
+    pub time_last_request: i64,
+}
+
+/// Estado de un torrent tal como se guarda en disco.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TorrentRecord {
+    pub info_hash_url: String,
+    #[serde(default)]
+    pub name: String,
+    pub completed: usize,
+    /// Se guarda porque los torrents registrados por la API de administracion se restauran
+    /// sin su metainfo.
+    #[serde(default)]
+    pub private: bool,
+    /// Metricas de cada hora, indexadas por el inicio de la hora.
+    #[serde(default)]
+    pub history: BTreeMap<i64, Bucket>,
+    pub peers: Vec<PeerRecord>,
+}
+
+/// Estado completo del tracker en un momento dado.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Snapshot {
+    pub torrents: Vec<TorrentRecord>,
+    #[serde(default)]
+    pub whitelist: BTreeSet<String>,
+    #[serde(default)]
+    pub blacklist: BTreeSet<String>,
+    #[serde(default)]
+    pub users: Vec<User>,
+}
+
+/// Datos de un announce necesarios para volver a aplicarlo sobre el tracker.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AnnounceRecord {
+    pub info_hash_url: String,
+    #[serde(default)]
+    pub passkey: String,
+    pub peer_id: String,
+    pub ip: String,
+    pub port: String,
+    pub event: String,
+    pub uploaded: String,
+    pub downloaded: String,
+    pub left: String,
+    pub time: i64,
+}
+
+/// Operaciones que modifican el estado del tracker, en el orden en que ocurrieron.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum JournalEntry {
+    Announce(Box<AnnounceRecord>),
+    /// Eliminacion de los peers inactivos en el momento indicado.
+    Expire {
+        time: i64,
+    },
+}
+
+/// Estructura que persiste el estado del tracker en un directorio.
+/// Cada operacion se agrega a un journal; periodicamente se escribe un snapshot con el
+/// estado completo y se vacia el journal. Al iniciar se carga el snapshot y se vuelven a
+/// aplicar las operaciones del journal.
+#[derive(Debug)]
+pub struct Storage {
+    path: PathBuf,
+    journal: File,
+    pending: usize,
+}
+
+impl Storage {
+    /// Abre el directorio de estado, creandolo si no existe.
+    pub fn open(path: &str) -> Result<Storage, BitTrackerError> {
+        let path = PathBuf::from(path);
+        fs::create_dir_all(&path).or(Err(BitTrackerError::FileCreationError))?;
+        let journal = Self::open_journal(&path)?;
+        Ok(Storage {
+            path,
+            journal,
+            pending: 0,
+        })
+    }
+
+    fn open_journal(path: &Path) -> Result<File, BitTrackerError> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path.join(JOURNAL_FILE))
+            .or(Err(BitTrackerError::FileCreationError))
+    }
+
+    /// Lee el ultimo snapshot, o uno vacio si todavia no se escribio ninguno.
+    pub fn load_snapshot(&self) -> Result<Snapshot, BitTrackerError> {
+        let path = self.path.join(SNAPSHOT_FILE);
+        if !path.exists() {
+            return Ok(Snapshot::default());
+        }
+        let file = File::open(path).or(Err(BitTrackerError::OpenFileError))?;
+        serde_json::from_reader(BufReader::new(file)).or(Err(BitTrackerError::DecodingError))
+    }
+
+    /// Lee las operaciones del journal. Las lineas que no se pueden parsear, como la ultima
+    /// si el tracker se corto mientras la escribia, se descartan.
+    pub fn load_journal(&self) -> Result<Vec<JournalEntry>, BitTrackerError> {
+        let file =
+            File::open(self.path.join(JOURNAL_FILE)).or(Err(BitTrackerError::OpenFileError))?;
+        let mut entries = vec![];
+        for line in BufReader::new(file).lines() {
+            let line = line.or(Err(BitTrackerError::ReadFileError))?;
+            match serde_json::from_str(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(_) => println!("[TRACKER] Se descarta una entrada invalida del journal"),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Agrega una operacion al journal.
+    pub fn append(&mut self, entry: &JournalEntry) -> Result<(), BitTrackerError> {
+        let mut line = serde_json::to_string(entry).or(Err(BitTrackerError::WriteFileError))?;
+        line.push('\n');
+        self.journal
+            .write_all(line.as_bytes())
+            .or(Err(BitTrackerError::WriteFileError))?;
+        self.pending += 1;
+        Ok(())
+    }
+
+    /// Indica si hay operaciones en el journal que no estan en el ultimo snapshot.
+    pub fn has_pending(&self) -> bool {
+        self.pending > 0
+    }
+
+    /// Escribe el snapshot y vacia el journal. El snapshot se escribe en un archivo temporal
+    /// y se renombra, para no perder el anterior si el tracker se corta a mitad de camino.
+    pub fn write_snapshot(&mut self, snapshot: &Snapshot) -> Result<(), BitTrackerError> {
+        let temporal = self.path.join(format!("{}.tmp", SNAPSHOT_FILE));
+        let file = File::create(&temporal).or(Err(BitTrackerError::FileCreationError))?;
+        serde_json::to_writer(&file, snapshot).or(Err(BitTrackerError::WriteFileError))?;
+        file.sync_all().or(Err(BitTrackerError::WriteFileError))?;
+        fs::rename(&temporal, self.path.join(SNAPSHOT_FILE))
+            .or(Err(BitTrackerError::WriteFileError))?;
+        self.journal
+            .set_len(0)
+            .or(Err(BitTrackerError::WriteFileError))?;
+        self.pending = 0;
+        Ok(())
+    }
+}
+
+/******************************************************************************************/
+/*                                    CONVERSIONES                                        */
+/******************************************************************************************/
+
+/// Convierte milisegundos desde el epoch a la hora local.
+pub fn to_local(millis: i64) -> DateTime<Local> {
+    Local
+        .timestamp_millis_opt(millis)
+        .single()
+        .unwrap_or_else(Local::now)
+}
+
+impl From<&Peer> for PeerRecord {
+    fn from(peer: &Peer) -> Self {
+        PeerRecord {
+            id: peer.id.clone(),
+            ip: peer.ip.clone(),
+            port: peer.port.clone(),
+            event: peer.event.clone(),
+            uploaded: peer.uploaded.clone(),
+            downloaded: peer.downloaded.clone(),
+            left: peer.left.clone(),
+            time_last_request: peer.time_last_request.timestamp_millis(),
+        }
+    }
+}
+
+impl From<&PeerRecord> for Peer {
+    fn from(record: &PeerRecord) -> Self {
+        Peer {
+            id: record.id.clone(),
+            ip: record.ip.clone(),
+            port: record.port.clone(),
+            event: record.event.clone(),
+            uploaded: record.uploaded.clone(),
+            downloaded: record.downloaded.clone(),
+            left: record.left.clone(),
+            time_last_request: to_local(record.time_last_request),
+        }
+    }
+}
+
+impl Snapshot {
+    pub fn new(tracker: &BitTracker) -> Snapshot {
+        let torrents = tracker
+            .torrents
+            .iter()
+            .map(|torrent| TorrentRecord {
+                info_hash_url: torrent.info_hash_url.clone(),
+                name: torrent.name.clone(),
+                completed: torrent.completed,
+                private: torrent.is_private(),
+                history: torrent.history.clone(),
+                peers: torrent.peers.iter().map(PeerRecord::from).collect(),
+            })
+            .collect();
+        Snapshot {
+            torrents,
+            whitelist: tracker.whitelist.clone(),
+            blacklist: tracker.blacklist.clone(),
+            users: tracker.users.clone(),
+        }
+    }
+
+    /// Restaura el estado guardado. Los torrents que no estan en la configuracion, porque se
+    /// registraron mientras el tracker corria, se vuelven a registrar a partir del info hash.
+    pub fn restore(&self, tracker: &mut BitTracker) {
+        tracker.whitelist.extend(self.whitelist.iter().cloned());
+        tracker.blacklist.extend(self.blacklist.iter().cloned());
+        tracker.users = self.users.clone();
+        for record in &self.torrents {
+            let position = tracker
+                .torrents
+                .iter()
+                .position(|torrent| torrent.info_hash_url == record.info_hash_url);
+            let position = match position {
+                Some(position) => position,
+                None => match Self::unknown_torrent(record) {
+                    Some(torrent) => {
+                        tracker.torrents.push(torrent);
+                        tracker.torrents.len() - 1
+                    }
+                    None => continue,
+                },
+            };
+            let torrent = &mut tracker.torrents[position];
+            torrent.completed = record.completed;
+            torrent.private |= record.private;
+            torrent.history = record.history.clone();
+            torrent.peers = record.peers.iter().map(Peer::from).collect();
+        }
+    }
+
+    fn unknown_torrent(record: &TorrentRecord) -> Option<Torrent> {
+        let info_hash = URLEncoder.urldecode(&record.info_hash_url).ok()?;
+        let mut torrent = Torrent::from_info_hash(&info_hash).ok()?;
+        if !record.name.is_empty() {
+            torrent.name = record.name.clone();
+        }
+        Some(torrent)
+    }
+}
+
+impl From<&Request> for AnnounceRecord {
+    fn from(request: &Request) -> Self {
+        AnnounceRecord {
+            info_hash_url: request.info_hash_url.clone(),
+            passkey: request.passkey.clone(),
+            peer_id: request.peer_id.clone(),
+            ip: request.ip.clone(),
+            port: request.port.clone(),
+            event: request.event.clone(),
+            uploaded: request.uploaded.clone(),
+            downloaded: request.downloaded.clone(),
+            left: request.left.clone(),
+            time: request.time.timestamp_millis(),
+        }
+    }
+}
+
+impl From<&AnnounceRecord> for Request {
+    fn from(record: &AnnounceRecord) -> Self {
+        let mut request = Request::new();
+        request.info_hash_url = record.info_hash_url.clone();
+        request.passkey = record.passkey.clone();
+        request.peer_id = record.peer_id.clone();
+        request.ip = record.ip.clone();
+        request.port = record.port.clone();
+        request.event = record.event.clone();
+        request.uploaded = record.uploaded.clone();
+        request.downloaded = record.downloaded.clone();
+        request.left = record.left.clone();
+        request.time = to_local(record.time);
+        request
+    }
+}
+
+#[cfg(test)]
+mod storage_should {
+    use super::*;
+
+    fn storage(name: &str) -> (Storage, PathBuf) {
+        let path = std::env::temp_dir().join(format!("bittracker-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        (Storage::open(path.to_str().unwrap()).unwrap(), path)
+    }
+
+    fn announce(peer_id: &str) -> JournalEntry {
+        JournalEntry::Announce(Box::new(AnnounceRecord {
+            info_hash_url: "%aa".to_string(),
+            passkey: "".to_string(),
+            peer_id: peer_id.to_string(),
+            ip: "127.0.0.1".to_string(),
+            port: "6881".to_string(),
+            event: "started".to_string(),
+            uploaded: "0".to_string(),
+            downloaded: "0".to_string(),
+            left: "10".to_string(),
+            time: 1_700_000_000_000,
+        }))
+    }
+
+    #[test]
+    fn replay_journal_after_reopening() {
+        let (mut storage, path) = storage("journal");
+        storage.append(&announce("A")).unwrap();
+        storage.append(&JournalEntry::Expire { time: 5 }).unwrap();
+        drop(storage);
+
+        let storage = Storage::open(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            storage.load_journal().unwrap(),
+            vec![announce("A"), JournalEntry::Expire { time: 5 }]
+        );
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn discard_torn_journal_lines() {
+        let (mut storage, path) = storage("torn");
+        storage.append(&announce("A")).unwrap();
+        storage.journal.write_all(b"{\"Announce\":{\"info").unwrap();
+
+        assert_eq!(storage.load_journal().unwrap(), vec![announce("A")]);
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn write_snapshot_and_truncate_journal() {
+        let (mut storage, path) = storage("snapshot");
+        assert_eq!(storage.load_snapshot().unwrap(), Snapshot::default());
+        storage.append(&announce("A")).unwrap();
+        assert!(storage.has_pending());
+
+        let snapshot = Snapshot {
+            whitelist: BTreeSet::from(["%aa".to_string()]),
+            blacklist: BTreeSet::new(),
+            users: vec![],
+            torrents: vec![TorrentRecord {
+                info_hash_url: "%aa".to_string(),
+                name: "aa".to_string(),
+                completed: 3,
+                private: true,
+                history: BTreeMap::from([(1_699_999_200, Bucket::new(1_699_999_200))]),
+                peers: vec![],
+            }],
+        };
+        storage.write_snapshot(&snapshot).unwrap();
+
+        assert!(!storage.has_pending());
+        assert_eq!(storage.load_snapshot().unwrap(), snapshot);
+        assert!(storage.load_journal().unwrap().is_empty());
+        fs::remove_dir_all(path).unwrap();
+    }
+}
@@ -1,35 +1,117 @@
-use crate::encoder::url_encoder::URLEncoder;
 use crate::errors::BitTrackerError;
-use crate::metainfo::MetaInfo;
 use crate::peer::Peer;
+use crate::stats::{Bucket, Resolution, StatsQuery};
+use protocol::encoder::url_encoder::URLEncoder;
+use protocol::torrent_file::metainfo::MetaInfo;
+use std::collections::BTreeMap;
+
+pub const INFO_HASH_LEN: usize = 20;
+/// Cantidad de horas de metricas que se guardan por torrent.
+pub const HISTORY_HOURS: i64 = 24 * 31;
 /******************************************************************************************/
 /*                                        TORRENT                                         */
 /******************************************************************************************/
 
 /// Estructura que modela a un torrent del tracker.
-/// Tiene su metainfo y la lista de peers conectados.
+/// Tiene su metainfo, si el torrent se registro a partir del archivo .torrent, si es privado,
+/// la lista de peers conectados, la cantidad de descargas completas y las metricas de cada hora, indexadas
+/// por el inicio de la hora.
 #[derive(Debug)]
 #[allow(dead_code)]
 pub struct Torrent {
     pub peers: Vec<Peer>,
-    pub metainfo: MetaInfo,
+    pub metainfo: Option<MetaInfo>,
+    pub name: String,
+    pub info_hash: Vec<u8>,
     pub info_hash_url: String,
+    pub completed: usize,
+    pub history: BTreeMap<i64, Bucket>,
+    pub private: bool,
 }
 
 #[allow(dead_code)]
 impl Torrent {
+    /// Inicializa el torrent a partir del archivo .torrent.
     pub fn new(path: &str) -> Result<Self, BitTrackerError> {
         let metainfo = MetaInfo::new(path).or(Err(BitTrackerError::OpenFileError))?;
-        let peers = vec![];
+        Self::from_metainfo(metainfo)
+    }
+
+    /// Inicializa el torrent a partir de su metainfo ya parseada.
+    pub fn from_metainfo(metainfo: MetaInfo) -> Result<Self, BitTrackerError> {
+        let mut torrent = Self::from_info_hash(&metainfo.info_hash)?;
+        torrent.name = metainfo.info.name.clone();
+        torrent.private = metainfo.info.is_private();
+        torrent.metainfo = Some(metainfo);
+        Ok(torrent)
+    }
+
+    /// Inicializa un torrent del que solo se conoce el info hash. Se usa el hash en
+    /// hexadecimal como nombre.
+    pub fn from_info_hash(info_hash: &[u8]) -> Result<Self, BitTrackerError> {
+        if info_hash.len() != INFO_HASH_LEN {
+            return Err(BitTrackerError::DecodingError);
+        }
         let info_hash_url = URLEncoder
-            .urlencode(metainfo.info_hash.clone())
+            .urlencode(info_hash.to_vec())
             .or(Err(BitTrackerError::DecodingError))?;
         Ok(Torrent {
-            metainfo,
-            peers,
+            peers: vec![],
+            metainfo: None,
+            name: hex::encode(info_hash),
+            info_hash: info_hash.to_vec(),
             info_hash_url,
+            completed: 0,
+            history: BTreeMap::new(),
+            private: false,
         })
     }
+
+    /// Indica si el torrent es privado: solo se aceptan announces de usuarios con passkey.
+    pub fn is_private(&self) -> bool {
+        self.private
+    }
+
+    /// Registra en la hora del momento indicado los announces y descargas completas recibidos,
+    /// junto con la cantidad actual de peers y seeders. Descarta las horas que superan
+    /// `HISTORY_HOURS`.
+    pub fn record(&mut self, time: i64, announces: usize, completed: usize) {
+        let start = Resolution::Hour.start(time);
+        let seeders = self.peers.iter().filter(|peer| peer.is_seeder()).count();
+        let bucket = self
+            .history
+            .entry(start)
+            .or_insert_with(|| Bucket::new(start));
+        bucket.merge(&Bucket {
+            start,
+            announces,
+            peers: self.peers.len(),
+            seeders,
+            completed,
+        });
+        self.history = self.history.split_off(&(start - HISTORY_HOURS * 3600));
+    }
+
+    /// Devuelve las metricas del torrent en cada intervalo del rango de la query. Los
+    /// intervalos sin actividad se devuelven en cero.
+    pub fn series(&self, query: &StatsQuery) -> Vec<Bucket> {
+        let step = query.resolution.seconds();
+        query
+            .starts()
+            .map(|start| {
+                let mut bucket = Bucket::new(start);
+                for (_, hour) in self.history.range(start..start + step) {
+                    bucket.merge(hour);
+                }
+                bucket
+            })
+            .collect()
+    }
+
+    /// Indica si el torrent corresponde al info hash en hexadecimal o al nombre indicado.
+    pub fn matches(&self, torrent: &str) -> bool {
+        self.name == torrent || hex::encode(&self.info_hash).eq_ignore_ascii_case(torrent)
+    }
 }
 
 #[cfg(test)]
@@ -39,13 +121,53 @@ mod torrent_should {
     #[test]
     fn initialize_torrent() {
         let path = "./torrents/INFORME - BITTORRENT.pdf.torrent";
-        let tracker = Torrent::new(path).unwrap();
-        println!("Announce: {:?}", tracker.metainfo.announce);
-        println!("Hash: {:?}", tracker.metainfo.info_hash);
-        println!("Hash Url: {}", tracker.info_hash_url);
-        println!("Name: {:?}", tracker.metainfo.info.name);
-        println!("Num pieces: {:?}", tracker.metainfo.info.num_pieces);
-        println!("Length: {:?}", tracker.metainfo.info.length);
-        println!("Piece Length: {:?}", tracker.metainfo.info.piece_length);
+        let torrent = Torrent::new(path).unwrap();
+        let metainfo = torrent.metainfo.as_ref().unwrap();
+        println!("Announce: {:?}", metainfo.announce);
+        println!("Hash: {:?}", metainfo.info_hash);
+        println!("Hash Url: {}", torrent.info_hash_url);
+        println!("Name: {:?}", metainfo.info.name);
+        println!("Num pieces: {:?}", metainfo.info.num_pieces);
+        println!("Length: {:?}", metainfo.info.length);
+        println!("Piece Length: {:?}", metainfo.info.piece_length);
+        assert_eq!(torrent.name, metainfo.info.name);
+        assert_eq!(torrent.info_hash, metainfo.info_hash);
+    }
+
+    #[test]
+    fn initialize_from_info_hash() {
+        let torrent = Torrent::from_info_hash(&[0xab; 20]).unwrap();
+
+        assert_eq!(torrent.name, "ab".repeat(20));
+        assert_eq!(torrent.info_hash_url, "%ab".repeat(20));
+        assert!(torrent.metainfo.is_none());
+        assert!(Torrent::from_info_hash(&[0xab; 19]).is_err());
+    }
+
+    #[test]
+    fn aggregate_history_by_hour_and_day() {
+        let mut torrent = Torrent::from_info_hash(&[0xab; 20]).unwrap();
+        let day = 1_699_920_000;
+        torrent.record(day + 60, 3, 0);
+        torrent.record(day + 3600 + 60, 1, 1);
+        torrent.record(day + 86400, 2, 0);
+
+        let mut query = StatsQuery::new(day + 2 * 3600);
+        query.from = day;
+        let hours = torrent.series(&query);
+        assert_eq!(hours.len(), 2);
+        assert_eq!(hours[0].start, day);
+        assert_eq!(hours[0].announces, 3);
+        assert_eq!(hours[1].completed, 1);
+
+        query.resolution = Resolution::Day;
+        query.to = day + 2 * 86400;
+        let days = torrent.series(&query);
+        assert_eq!(days.len(), 2);
+        assert_eq!((days[0].announces, days[0].completed), (4, 1));
+        assert_eq!(days[1].announces, 2);
+
+        torrent.record(day + HISTORY_HOURS * 3600 + 3600, 1, 0);
+        assert_eq!(torrent.history.keys().next(), Some(&(day + 3600)));
     }
 }
@@ -1,26 +1,38 @@
-use crate::connection::Connection;
-use crate::encoder::bencode_parser::Bencode;
-use crate::errors::BitTrackerError;
+use crate::config::TrackerConfig;
+use crate::connection::{Connection, Stream};
+use crate::errors::{AdminError, AnnounceError, BitTrackerError, StatsError};
+use crate::http::http_response::HttpResponse;
+use crate::metrics::Metrics;
 use crate::peer::Event;
 use crate::peer::Peer;
+use crate::pool::ThreadPool;
+use crate::rate_limiter::RateLimiter;
 use crate::request::Request;
-use crate::response::Response;
+use crate::response::{Peers, Response};
 use crate::stats::InfoPeer;
-use crate::stats::Stats;
+use crate::stats::{Stats, StatsQuery, TorrentSeries, UserStats};
+use crate::storage::{to_local, AnnounceRecord, JournalEntry, Snapshot, Storage};
 use crate::torrent::Torrent;
+use crate::udp::udp_server::UdpServer;
+use crate::user::User;
+use chrono::{DateTime, Duration, Local};
+use native_tls::TlsAcceptor;
+use protocol::encoder::bencode_parser::Bencode;
+use protocol::encoder::url_encoder::URLEncoder;
+use rand::seq::SliceRandom;
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::net::{TcpListener, TcpStream};
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::net::{IpAddr, TcpListener, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Instant;
 /******************************************************************************************/
 /*                                  BIT TRACKER                                           */
 /******************************************************************************************/
 
-const ADDRESS: &str = "127.0.0.1:8080";
-
 /// Estructura que modela nuestro Tracker
 /// Tiene un id, una lista de torrents que puede hostear y un sender para loguear.
 #[derive(Debug)]
@@ -29,26 +41,87 @@ pub struct BitTracker {
     pub id: String,
     pub torrents: Vec<Torrent>,
     pub log: Sender<String>,
+    /// Segundos que los peers deben esperar entre announces.
+    pub interval: u64,
+    /// Segundos que los peers deben esperar como minimo antes de volver a enviar un announce.
+    pub min_interval: u64,
+    /// Tiempo sin announces luego del cual un peer se considera desconectado. Es
+    /// `PEER_TIMEOUT_FACTOR` veces el intervalo de announce.
+    pub peer_timeout: Duration,
+    /// Cantidad de peers enviados si el announce no incluye `numwant`.
+    pub numwant_default: usize,
+    /// Cantidad maxima de peers enviados en una response.
+    pub numwant_max: usize,
+    /// Donde se persiste el estado. Si es None el estado solo vive en memoria.
+    pub storage: Option<Storage>,
+    /// En modo abierto se registran automaticamente los torrents desconocidos.
+    pub open: bool,
+    /// Si no esta vacia, solo se aceptan los torrents de la lista. Contiene info_hash_url.
+    pub whitelist: BTreeSet<String>,
+    /// Torrents que nunca se aceptan. Contiene info_hash_url.
+    pub blacklist: BTreeSet<String>,
+    /// Token que deben enviar las requests a la API de administracion, si esta configurado.
+    pub admin_token: Option<String>,
+    /// Usuarios del tracker privado.
+    pub users: Vec<User>,
+    /// Contadores que se exportan en `GET /metrics`.
+    pub metrics: Metrics,
+    /// Cantidad de threads que atienden conexiones HTTP.
+    pub workers: usize,
+    /// Cantidad maxima de conexiones HTTP abiertas o esperando un thread. Las siguientes se
+    /// rechazan con 503.
+    pub max_connections: usize,
+    /// Segundos que se espera a leer o escribir en una conexion antes de cerrarla.
+    pub connection_timeout: u64,
+    /// Segundos que se espera la siguiente request de una conexion keep-alive antes de
+    /// cerrarla y liberar el thread.
+    pub keep_alive_timeout: u64,
+    /// Limite de requests por ip.
+    pub rate_limiter: RateLimiter,
 }
 
 #[allow(unused_assignments)]
 #[allow(dead_code)]
 impl BitTracker {
-    /// Inicializa el tracker, recibe un archivo de configuracion con los nombres de los torrents a hostear.
+    /// Inicializa el tracker a partir del archivo de configuracion.
     pub fn new(config: &str, tx: Sender<String>) -> Result<Self, BitTrackerError> {
-        let file = File::open(config).or(Err(BitTrackerError::OpenFileError))?;
-        let reader = BufReader::new(file);
+        Self::from_config(&TrackerConfig::from_file(config)?, tx)
+    }
+
+    /// Inicializa el tracker con los torrents y parametros de la configuracion.
+    pub fn from_config(
+        config: &TrackerConfig,
+        tx: Sender<String>,
+    ) -> Result<Self, BitTrackerError> {
         let mut torrents: Vec<Torrent> = Vec::new();
-        for line in reader.lines() {
-            let line = line.or(Err(BitTrackerError::ReadFileError))?;
-            let torrent = Torrent::new(&line)?;
-            torrents.push(torrent);
+        for path in config.torrent_paths()? {
+            torrents.push(Torrent::new(&path)?);
         }
         let id = Self::generate_id();
         Ok(BitTracker {
             id,
             torrents,
             log: tx,
+            interval: config.interval,
+            min_interval: config.min_interval,
+            peer_timeout: Duration::seconds((config.interval * config.peer_timeout_factor) as i64),
+            numwant_default: config.numwant_default,
+            numwant_max: config.numwant_max,
+            storage: None,
+            open: config.open,
+            whitelist: info_hash_urls(&config.whitelist)?,
+            blacklist: info_hash_urls(&config.blacklist)?,
+            admin_token: config.admin_token.clone(),
+            users: vec![],
+            metrics: Metrics::new(),
+            workers: config.workers,
+            max_connections: config.max_connections,
+            connection_timeout: config.connection_timeout,
+            keep_alive_timeout: config.keep_alive_timeout,
+            rate_limiter: RateLimiter::new(
+                config.rate_limit,
+                std::time::Duration::from_secs(config.rate_window),
+            ),
         })
     }
 
@@ -73,128 +146,601 @@ impl BitTracker {
                 cant_torrents += 1;
             }
             for peer in &torrent.peers {
-                let mut completed = false;
+                let completed = peer.is_seeder();
                 cant_peers += 1;
-                if let Event::Completed = peer.event {
-                    completed = true;
+                if completed {
                     cant_seeders += 1;
                 }
                 let info_peer = InfoPeer::new(
                     peer.id.clone(),
                     peer.time_last_request.clone().to_string(),
                     completed,
-                    torrent.metainfo.info.name.clone(),
+                    torrent.name.clone(),
                 );
                 list.push(info_peer);
             }
         }
 
-        Ok(Stats::new(cant_peers, cant_seeders, cant_torrents, list))
+        let mut stats = Stats::new(cant_peers, cant_seeders, cant_torrents, list);
+        stats.users = self.users.iter().map(UserStats::from).collect();
+        Ok(stats)
     }
 
-    pub fn receive_request(&mut self, request: &Request) -> Result<bool, BitTrackerError> {
-        let mut has_torrent = false;
-        for torrent in &mut self.torrents {
-            if torrent.info_hash_url == request.info_hash_url {
-                has_torrent = true;
-                let mut has_peer = false;
-                for peer in &mut torrent.peers {
-                    if peer.id == request.peer_id {
-                        has_peer = true;
-                        peer.actualize_request(request)?;
-                        return Ok(has_torrent);
+    /// Devuelve las metricas historicas de los torrents pedidos en la query.
+    pub fn get_history(&self, query: &StatsQuery) -> Result<Vec<TorrentSeries>, StatsError> {
+        let series: Vec<TorrentSeries> = self
+            .torrents
+            .iter()
+            .filter(|torrent| match &query.torrent {
+                Some(name) => torrent.matches(name),
+                None => true,
+            })
+            .map(|torrent| TorrentSeries {
+                torrent: torrent.name.clone(),
+                info_hash: hex::encode(&torrent.info_hash),
+                buckets: torrent.series(query),
+            })
+            .collect();
+        if query.torrent.is_some() && series.is_empty() {
+            return Err(StatsError::UnknownTorrentError);
+        }
+        Ok(series)
+    }
+
+    /// Registra el announce del peer en el torrent correspondiente. Si el peer envia
+    /// `event=stopped` se lo quita de la lista. Falla con el motivo por el que se rechaza el
+    /// announce, que se le envia al peer.
+    pub fn receive_request(&mut self, request: &Request) -> Result<(), AnnounceError> {
+        self.metrics.count_announce(&request.event);
+        self.apply_request(request)?;
+        self.persist(JournalEntry::Announce(Box::new(AnnounceRecord::from(
+            request,
+        ))))
+        .or(Err(AnnounceError::StorageError))
+    }
+
+    fn apply_request(&mut self, request: &Request) -> Result<(), AnnounceError> {
+        if self.blacklist.contains(&request.info_hash_url) {
+            return Err(AnnounceError::BannedTorrentError);
+        }
+        if !self.is_allowed(&request.info_hash_url) {
+            return Err(AnnounceError::UnknownTorrentError);
+        }
+        if !request.passkey.is_empty() && self.find_user(&request.passkey).is_none() {
+            return Err(AnnounceError::UnknownPasskeyError);
+        }
+        if self.open && self.find_torrent(&request.info_hash_url).is_none() {
+            let info_hash = URLEncoder
+                .urldecode(&request.info_hash_url)
+                .or(Err(AnnounceError::InvalidRequestError))?;
+            let torrent =
+                Torrent::from_info_hash(&info_hash).or(Err(AnnounceError::InvalidRequestError))?;
+            println!("[TRACKER] Se registro el torrent {}", torrent.name);
+            self.torrents.push(torrent);
+        }
+        let torrent = self
+            .torrents
+            .iter_mut()
+            .find(|torrent| torrent.info_hash_url == request.info_hash_url)
+            .ok_or(AnnounceError::UnknownTorrentError)?;
+        if torrent.is_private() && request.passkey.is_empty() {
+            return Err(AnnounceError::PasskeyRequiredError);
+        }
+        let position = torrent
+            .peers
+            .iter()
+            .position(|peer| peer.id == request.peer_id);
+        if let Some(user) = self
+            .users
+            .iter_mut()
+            .find(|user| !request.passkey.is_empty() && user.passkey == request.passkey)
+        {
+            let (uploaded, downloaded) = match position {
+                Some(position) => (
+                    torrent.peers[position].uploaded.as_str(),
+                    torrent.peers[position].downloaded.as_str(),
+                ),
+                None => ("0", "0"),
+            };
+            user.account(
+                delta(uploaded, &request.uploaded),
+                delta(downloaded, &request.downloaded),
+            );
+        }
+        if request.event == "stopped" {
+            if let Some(position) = position {
+                torrent.peers.remove(position);
+            }
+            torrent.record(request.time.timestamp(), 1, 0);
+            return Ok(());
+        }
+        let completed = match position {
+            Some(position) => torrent.peers[position].event != Event::Completed,
+            None => true,
+        };
+        let completed = usize::from(request.event == "completed" && completed);
+        torrent.completed += completed;
+        match position {
+            Some(position) => torrent.peers[position]
+                .actualize_request(request)
+                .or(Err(AnnounceError::InvalidEventError))?,
+            None => torrent
+                .peers
+                .push(Peer::new(request).or(Err(AnnounceError::InvalidEventError))?),
+        }
+        torrent.record(request.time.timestamp(), 1, completed);
+        Ok(())
+    }
+
+    /// Busca al usuario por su passkey.
+    pub fn find_user(&self, passkey: &str) -> Option<&User> {
+        self.users.iter().find(|user| user.passkey == passkey)
+    }
+
+    /// Crea un usuario con una passkey nueva.
+    pub fn add_user(&mut self, name: &str) -> Result<&User, AdminError> {
+        if self.users.iter().any(|user| user.name == name) {
+            return Err(AdminError::UserExistsError);
+        }
+        self.users.push(User::new(name));
+        Ok(&self.users[self.users.len() - 1])
+    }
+
+    /// Elimina al usuario. Sus announces dejan de aceptarse.
+    pub fn remove_user(&mut self, name: &str) -> Result<User, AdminError> {
+        let position = self
+            .users
+            .iter()
+            .position(|user| user.name == name)
+            .ok_or(AdminError::UnknownUserError)?;
+        Ok(self.users.remove(position))
+    }
+
+    /// Busca el torrent por su info_hash_url.
+    pub fn find_torrent(&self, info_hash_url: &str) -> Option<&Torrent> {
+        self.torrents
+            .iter()
+            .find(|torrent| torrent.info_hash_url == info_hash_url)
+    }
+
+    /// Indica si el tracker acepta el torrent segun la whitelist y la blacklist.
+    pub fn is_allowed(&self, info_hash_url: &str) -> bool {
+        !self.blacklist.contains(info_hash_url)
+            && (self.whitelist.is_empty() || self.whitelist.contains(info_hash_url))
+    }
+
+    /// Registra un torrent nuevo.
+    pub fn add_torrent(&mut self, torrent: Torrent) -> Result<(), AdminError> {
+        if !self.is_allowed(&torrent.info_hash_url) {
+            return Err(AdminError::NotAllowedError);
+        }
+        if self.find_torrent(&torrent.info_hash_url).is_some() {
+            return Err(AdminError::AlreadyRegisteredError);
+        }
+        self.torrents.push(torrent);
+        Ok(())
+    }
+
+    /// Deja de trackear el torrent, junto con sus peers.
+    pub fn remove_torrent(&mut self, info_hash_url: &str) -> Result<Torrent, AdminError> {
+        let position = self
+            .torrents
+            .iter()
+            .position(|torrent| torrent.info_hash_url == info_hash_url)
+            .ok_or(AdminError::UnknownTorrentError)?;
+        Ok(self.torrents.remove(position))
+    }
+
+    /// Devuelve la cantidad de seeders, de descargas completas y de leechers del torrent,
+    /// o None si el tracker no lo hostea.
+    pub fn scrape(&self, info_hash_url: &str) -> Option<(usize, usize, usize)> {
+        let torrent = self
+            .torrents
+            .iter()
+            .find(|torrent| torrent.info_hash_url == info_hash_url)?;
+        let seeders = torrent.peers.iter().filter(|peer| peer.is_seeder()).count();
+        Some((seeders, torrent.completed, torrent.peers.len() - seeders))
+    }
+
+    /// Carga el estado guardado en el directorio: el ultimo snapshot y las operaciones del
+    /// journal posteriores. Luego escribe un snapshot nuevo y guarda alli los cambios siguientes.
+    pub fn restore(&mut self, path: &str) -> Result<(), BitTrackerError> {
+        let storage = Storage::open(path)?;
+        storage.load_snapshot()?.restore(self);
+        let entries = storage.load_journal()?;
+        for entry in &entries {
+            match entry {
+                JournalEntry::Announce(record) => {
+                    if let Err(error) = self.apply_request(&Request::from(record.as_ref())) {
+                        println!("[TRACKER] No se pudo restaurar un announce: {}", error);
                     }
                 }
-                if !has_peer {
-                    let peer = Peer::new(request)?;
-                    torrent.peers.push(peer);
+                JournalEntry::Expire { time } => {
+                    self.apply_expiry(to_local(*time));
                 }
             }
         }
-        Ok(has_torrent)
+        self.storage = Some(storage);
+        self.write_snapshot(true)
+    }
+
+    /// Escribe un snapshot si hubo cambios desde el anterior.
+    pub fn save(&mut self) -> Result<(), BitTrackerError> {
+        self.write_snapshot(false)
     }
 
+    /// Escribe un snapshot aunque no haya operaciones en el journal. Se usa para los cambios
+    /// que no se registran en el journal, como los de la API de administracion.
+    pub fn save_now(&mut self) -> Result<(), BitTrackerError> {
+        self.write_snapshot(true)
+    }
+
+    fn write_snapshot(&mut self, force: bool) -> Result<(), BitTrackerError> {
+        let pending = match &self.storage {
+            Some(storage) => storage.has_pending(),
+            None => return Ok(()),
+        };
+        if !pending && !force {
+            return Ok(());
+        }
+        let snapshot = Snapshot::new(self);
+        match &mut self.storage {
+            Some(storage) => storage.write_snapshot(&snapshot),
+            None => Ok(()),
+        }
+    }
+
+    /// Agrega la operacion al journal, si el tracker persiste su estado.
+    fn persist(&mut self, entry: JournalEntry) -> Result<(), BitTrackerError> {
+        match &mut self.storage {
+            Some(storage) => storage.append(&entry),
+            None => Ok(()),
+        }
+    }
+
+    /// Quita de todos los torrents a los peers que no enviaron announces dentro del timeout.
+    /// Devuelve la cantidad de peers eliminados.
+    pub fn remove_expired_peers(&mut self, now: DateTime<Local>) -> Result<usize, BitTrackerError> {
+        let removed = self.apply_expiry(now);
+        if removed > 0 {
+            self.persist(JournalEntry::Expire {
+                time: now.timestamp_millis(),
+            })?;
+        }
+        Ok(removed)
+    }
+
+    fn apply_expiry(&mut self, now: DateTime<Local>) -> usize {
+        let timeout = self.peer_timeout;
+        let mut removed = 0;
+        for torrent in &mut self.torrents {
+            let before = torrent.peers.len();
+            torrent.peers.retain(|peer| !peer.is_expired(now, timeout));
+            removed += before - torrent.peers.len();
+        }
+        removed
+    }
+
+    /// Genera la response al announce con hasta `numwant` peers del torrent, elegidos al azar.
+    /// A los leechers se les envian primero seeders y a los seeders primero leechers. Nunca se
+    /// envia al propio peer, ni por id ni por ip y puerto.
+    /// Si el peer pide `compact=1` la lista se envia en formato binario (BEP 23), separando
+    /// los peers IPv6 en `peers6` (BEP 7).
     pub fn make_response(&mut self, request: &Request) -> Result<Response, BitTrackerError> {
         let mut complete = 0;
         let mut incomplete = 0;
-        let mut list = vec![];
+        let mut seeders = vec![];
+        let mut leechers = vec![];
         let id = self.id.clone();
+        let numwant = self.numwant(request);
         for torrent in &mut self.torrents {
             if torrent.info_hash_url == request.info_hash_url {
                 for peer in &torrent.peers {
-                    if request.peer_id != peer.id {
-                        let dict = Self::make_dict(peer, request);
-                        list.push(dict);
-                        match peer.event {
-                            Event::Completed => {
-                                complete += 1;
-                            }
-                            _ => {
-                                incomplete += 1;
-                            }
-                        }
+                    if request.peer_id == peer.id {
+                        continue;
+                    }
+                    if peer.is_seeder() {
+                        complete += 1;
+                    } else {
+                        incomplete += 1;
+                    }
+                    if peer.ip == request.ip && peer.port == request.port {
+                        continue;
+                    }
+                    match peer.is_seeder() {
+                        true => seeders.push(peer),
+                        false => leechers.push(peer),
                     }
                 }
             }
         }
 
-        Ok(Response::new(id, complete, incomplete, list))
+        let mut rng = thread_rng();
+        seeders.shuffle(&mut rng);
+        leechers.shuffle(&mut rng);
+        let (mut others, rest) = match request.left == "0" {
+            true => (leechers, seeders),
+            false => (seeders, leechers),
+        };
+        others.extend(rest);
+        others.truncate(numwant);
+
+        let peers = if request.compact == "1" {
+            Self::make_compact(&others)
+        } else {
+            let list = others
+                .iter()
+                .filter_map(|peer| Self::make_dict(peer, request))
+                .collect();
+            Peers::Dictionary { peers: list }
+        };
+        let mut response = Response::new(id, complete, incomplete, peers);
+        response.interval = self.interval as usize;
+        response.min_interval = self.min_interval as usize;
+        if matches!(request.numwant.parse::<usize>(), Ok(numwant) if numwant > self.numwant_max) {
+            response.warning_message = Some(format!(
+                "numwant excede el maximo del tracker, se envian hasta {} peers",
+                self.numwant_max
+            ));
+        }
+        Ok(response)
+    }
+
+    /// Cantidad de peers a enviar: el `numwant` de la request, o el valor por defecto si no
+    /// lo envio o es invalido, acotado al maximo del tracker.
+    fn numwant(&self, request: &Request) -> usize {
+        request
+            .numwant
+            .parse::<usize>()
+            .unwrap_or(self.numwant_default)
+            .min(self.numwant_max)
     }
 
-    fn make_dict(peer: &Peer, request: &Request) -> Bencode {
-        let ip = ("ip".to_string(), Bencode::String(peer.ip.clone()));
-        let port = ("port".to_string(), Bencode::String(peer.port.clone()));
+    /// Genera el diccionario de un peer. Se omite el peer id si el peer envio `no_peer_id=1`.
+    /// El peer id se envia con los mismos bytes que envio el peer.
+    /// Los peers con un puerto invalido no se envian.
+    fn make_dict(peer: &Peer, request: &Request) -> Option<Bencode> {
+        let port = peer.port.parse::<u16>().ok()?;
+        let ip = ("ip", Bencode::from(peer.ip.clone()));
+        let port = ("port", Bencode::Int(port as i64));
         let mut dict = vec![ip, port];
-        if request.compact == 0.to_string() {
-            dict.push(("id".to_string(), Bencode::String(peer.id.clone())));
+        if request.no_peer_id != "1" {
+            let id = URLEncoder.urldecode(&peer.id).ok()?;
+            dict.push(("peer id", Bencode::ByteString(id)));
         }
-        Bencode::Dictionary(dict)
+        Some(Bencode::dict(dict))
     }
 
-    pub fn start(config: &str, tx: Sender<String>) -> Result<(), BitTrackerError> {
-        let tracker = BitTracker::new(config, tx)?;
+    /// Genera la lista compacta: 4 bytes de ip por peer IPv4 y 16 por peer IPv6, seguidos
+    /// del puerto en big endian. Los peers con ip o puerto invalidos no se envian.
+    fn make_compact(peers: &[&Peer]) -> Peers {
+        let mut peers4 = vec![];
+        let mut peers6 = vec![];
+        for peer in peers {
+            let (ip, port) = match (peer.ip.parse::<IpAddr>(), peer.port.parse::<u16>()) {
+                (Ok(ip), Ok(port)) => (ip, port),
+                _ => continue,
+            };
+            let ip = match ip {
+                IpAddr::V6(ip) => ip
+                    .to_ipv4_mapped()
+                    .map(IpAddr::V4)
+                    .unwrap_or(IpAddr::V6(ip)),
+                ip => ip,
+            };
+            match ip {
+                IpAddr::V4(ip) => {
+                    peers4.extend(ip.octets());
+                    peers4.extend(port.to_be_bytes());
+                }
+                IpAddr::V6(ip) => {
+                    peers6.extend(ip.octets());
+                    peers6.extend(port.to_be_bytes());
+                }
+            }
+        }
+        Peers::Compact {
+            peers: peers4,
+            peers6,
+        }
+    }
+
+    /// Inicia el tracker con la configuracion: restaura el estado guardado y atiende
+    /// announces por HTTP, por UDP y, si hay un certificado configurado, por HTTPS.
+    pub fn start(config: &TrackerConfig, tx: Sender<String>) -> Result<(), BitTrackerError> {
+        let mut tracker = BitTracker::from_config(config, tx)?;
+        tracker.restore(&config.state_dir)?;
         println!("[TRACKER] Inicializado correctamente");
-        let mut connections = vec![];
-        let listener = TcpListener::bind(ADDRESS).or(Err(BitTrackerError::FailToConnectError))?;
+        let listener =
+            TcpListener::bind(&config.http_address).or(Err(BitTrackerError::FailToConnectError))?;
+        let socket =
+            UdpSocket::bind(&config.udp_address).or(Err(BitTrackerError::FailToConnectError))?;
+        let tls = match &config.tls {
+            Some(tls) => {
+                let acceptor = tls.acceptor().map_err(BitTrackerError::ConfigError)?;
+                let listener =
+                    TcpListener::bind(&tls.address).or(Err(BitTrackerError::FailToConnectError))?;
+                Some((listener, Arc::new(acceptor)))
+            }
+            None => None,
+        };
         tracker
             .log
             .send("- [INFO] Tracker inicializado correctamente!".to_string())
             .or(Err(BitTrackerError::WriteLogError))?;
+        let pool = Arc::new(ThreadPool::new(tracker.workers, tracker.max_connections));
+        let ids = Arc::new(AtomicUsize::new(0));
         let mutex = Arc::new(Mutex::new(tracker));
-        for (id, stream) in listener.incoming().into_iter().enumerate() {
-            match stream {
-                Ok(stream) => {
-                    let clone = mutex.clone();
-                    connections.push(thread::spawn(move || handle_connection(stream, id, clone)))
+        let reaper = mutex.clone();
+        thread::spawn(move || remove_expired_peers(reaper));
+        let udp = UdpServer::new(socket, mutex.clone());
+        thread::spawn(move || {
+            if let Err(error) = udp.listen() {
+                println!("[ERROR] Tracker UDP: {}", error);
+            }
+        });
+        if let Some((listener, acceptor)) = tls {
+            let (mutex, pool, ids) = (mutex.clone(), pool.clone(), ids.clone());
+            thread::spawn(move || {
+                if let Err(error) = Self::listen(listener, mutex, pool, ids, Some(acceptor)) {
+                    println!("[ERROR] Tracker HTTPS: {}", error);
                 }
+            });
+        }
+        Self::listen(listener, mutex, pool, ids, None)
+    }
+
+    /// Acepta conexiones y las atiende en el pool. Si se recibe un acceptor, el handshake
+    /// TLS se hace en el thread del pool.
+    fn listen(
+        listener: TcpListener,
+        mutex: Arc<Mutex<BitTracker>>,
+        pool: Arc<ThreadPool>,
+        ids: Arc<AtomicUsize>,
+        acceptor: Option<Arc<TlsAcceptor>>,
+    ) -> Result<(), BitTrackerError> {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
                 Err(error) => {
                     println!("[ERROR] {}", error);
                     continue;
                 }
-            }
-        }
-        for (index, connection) in connections.into_iter().enumerate() {
-            match connection.join() {
-                Ok(result_connection) => {
-                    if let Err(error) = result_connection {
-                        println!("[ERROR] Conexion {}: {}", index, error);
-                    }
+            };
+            if let Some(retry_after) = Self::reject(&mutex, &pool, &stream)? {
+                // Por HTTPS no se puede responder antes del handshake TLS, por lo que la
+                // conexion se cierra sin escribir nada
+                if acceptor.is_none() {
+                    reject_connection(stream, retry_after);
                 }
-                Err(_) => println!("[ERROR] Fallo el join de la conexion numero {}", index),
+                continue;
             }
+            let id = ids.fetch_add(1, Ordering::SeqCst);
+            let clone = mutex.clone();
+            let acceptor = acceptor.clone();
+            pool.execute(move || {
+                if let Err(error) = handle_connection(stream, id, clone, acceptor) {
+                    println!("[ERROR] Conexion {}: {}", id, error);
+                }
+            });
         }
         Ok(())
     }
+
+    /// Decide si rechazar la conexion porque el pool esta lleno o porque la ip supero su
+    /// limite de requests. Devuelve los segundos que el cliente deberia esperar.
+    fn reject(
+        mutex: &Arc<Mutex<BitTracker>>,
+        pool: &ThreadPool,
+        stream: &TcpStream,
+    ) -> Result<Option<u64>, BitTrackerError> {
+        let mut tracker = mutex.lock().or(Err(BitTrackerError::MutexLockError))?;
+        let now = Instant::now();
+        let retry_after = match stream.peer_addr() {
+            Ok(address) if tracker.rate_limiter.is_limited(address.ip(), now) => {
+                tracker.metrics.count_rejected("rate_limit");
+                Some(tracker.rate_limiter.retry_after(address.ip(), now))
+            }
+            _ if pool.is_full() => {
+                tracker.metrics.count_rejected("overload");
+                Some(tracker.interval)
+            }
+            _ => None,
+        };
+        drop(tracker);
+        Ok(retry_after)
+    }
+}
+
+/// Convierte los info hashes en hexadecimal de la configuracion a info_hash_url.
+fn info_hash_urls(info_hashes: &[String]) -> Result<BTreeSet<String>, BitTrackerError> {
+    info_hashes
+        .iter()
+        .map(|info_hash| {
+            let bytes = hex::decode(info_hash).or(Err(BitTrackerError::DecodingError))?;
+            URLEncoder
+                .urlencode(bytes)
+                .or(Err(BitTrackerError::URLEncodingError))
+        })
+        .collect()
+}
+
+/// Calcula cuanto aumento un contador respecto del announce anterior. Si el contador es menor
+/// que el anterior se asume que el cliente lo reinicio y se toma el valor completo.
+fn delta(previous: &str, current: &str) -> u64 {
+    let current = current.parse::<u64>().unwrap_or(0);
+    match previous.parse::<u64>() {
+        Ok(previous) if current >= previous => current - previous,
+        _ => current,
+    }
 }
 
+/// Cada intervalo de announce elimina a los peers que dejaron de enviar announces y
+/// guarda un snapshot del estado. Termina si no puede tomar el lock del tracker.
+pub fn remove_expired_peers(mutex: Arc<Mutex<BitTracker>>) -> Result<(), BitTrackerError> {
+    loop {
+        let tracker = mutex.lock().or(Err(BitTrackerError::MutexLockError))?;
+        let interval = tracker.interval;
+        drop(tracker);
+        thread::sleep(std::time::Duration::from_secs(interval));
+        let mut tracker = mutex.lock().or(Err(BitTrackerError::MutexLockError))?;
+        let removed = tracker.remove_expired_peers(Local::now())?;
+        if removed > 0 {
+            println!("[TRACKER] Se eliminaron {} peers inactivos", removed);
+            tracker
+                .log
+                .send(format!(
+                    "- [INFO] Se eliminaron {} peers inactivos",
+                    removed
+                ))
+                .or(Err(BitTrackerError::WriteLogError))?;
+        }
+        if let Err(error) = tracker.save() {
+            println!(
+                "[ERROR] No se pudo guardar el estado del tracker: {}",
+                error
+            );
+        }
+        drop(tracker);
+    }
+}
+
+/// Responde 503 a una conexion rechazada y la cierra sin leer la request.
+fn reject_connection(mut stream: TcpStream, retry_after: u64) {
+    let _ = stream.set_write_timeout(Some(std::time::Duration::from_secs(1)));
+    let response = HttpResponse::error(503)
+        .with_header("Retry-After", &retry_after.to_string())
+        .with_header("Connection", "close");
+    let _ = stream.write_all(&response.to_bytes());
+}
+
+/// Atiende la conexion en un thread del pool, luego del handshake TLS si la conexion llego
+/// por HTTPS. Si no hay actividad durante `connection_timeout` la lectura o escritura falla
+/// y la conexion se cierra.
 pub fn handle_connection(
     stream: TcpStream,
     id: usize,
     mutex: Arc<Mutex<BitTracker>>,
+    acceptor: Option<Arc<TlsAcceptor>>,
 ) -> Result<(), BitTrackerError> {
     println!("[TRACKER] Recibi una conexion le asigno id: {}", id);
-    Connection::connect(id, stream, mutex)?;
-    Ok(())
+    let mut tracker = mutex.lock().or(Err(BitTrackerError::MutexLockError))?;
+    tracker.metrics.open_connection();
+    let timeout = std::time::Duration::from_secs(tracker.connection_timeout);
+    drop(tracker);
+    let _ = stream.set_read_timeout(Some(timeout));
+    let _ = stream.set_write_timeout(Some(timeout));
+    let result = match acceptor {
+        Some(acceptor) => match acceptor.accept(stream) {
+            Ok(stream) => Connection::connect(id, Stream::Tls(Box::new(stream)), mutex.clone()),
+            Err(_) => Err(BitTrackerError::FailToConnectError),
+        },
+        None => Connection::connect(id, Stream::Plain(stream), mutex.clone()),
+    };
+    let mut tracker = mutex.lock().or(Err(BitTrackerError::MutexLockError))?;
+    tracker.metrics.close_connection();
+    drop(tracker);
+    result
 }
 
 #[cfg(test)]
@@ -209,15 +755,378 @@ mod bittracker_should {
         let tracker = BitTracker::new(path, tx).unwrap();
 
         for torrent in tracker.torrents {
+            let metainfo = torrent.metainfo.unwrap();
             println!();
-            println!("Announce: {:?}", torrent.metainfo.announce);
+            println!("Announce: {:?}", metainfo.announce);
             println!("Url Hash: {:?}", torrent.info_hash_url);
-            println!("Hash: {:?}", torrent.metainfo.info_hash);
-            println!("Name: {:?}", torrent.metainfo.info.name);
-            println!("Num pieces: {:?}", torrent.metainfo.info.num_pieces);
-            println!("Length: {:?}", torrent.metainfo.info.length);
-            println!("Piece Length: {:?}", torrent.metainfo.info.piece_length);
+            println!("Hash: {:?}", metainfo.info_hash);
+            println!("Name: {:?}", metainfo.info.name);
+            println!("Num pieces: {:?}", metainfo.info.num_pieces);
+            println!("Length: {:?}", metainfo.info.length);
+            println!("Piece Length: {:?}", metainfo.info.piece_length);
+        }
+    }
+
+    fn tracker_with_peers(peers: &[(&str, &str, &str)]) -> BitTracker {
+        let (tx, _rx) = mpsc::channel();
+        let mut tracker = BitTracker::new("./config_file", tx).unwrap();
+        let info_hash_url = tracker.torrents[0].info_hash_url.clone();
+        for (id, ip, port) in peers {
+            let mut request = Request::new();
+            request.info_hash_url = info_hash_url.clone();
+            request.peer_id = id.to_string();
+            request.ip = ip.to_string();
+            request.port = port.to_string();
+            request.event = "started".to_string();
+            tracker.receive_request(&request).unwrap();
+        }
+        tracker
+    }
+
+    fn announce(tracker: &BitTracker, compact: &str, no_peer_id: &str) -> Request {
+        let mut request = Request::new();
+        request.info_hash_url = tracker.torrents[0].info_hash_url.clone();
+        request.peer_id = "ME".to_string();
+        request.compact = compact.to_string();
+        request.no_peer_id = no_peer_id.to_string();
+        request
+    }
+
+    fn compact_ports(response: &Response) -> Vec<u16> {
+        match &response.peers {
+            Peers::Compact { peers, .. } => peers
+                .chunks(6)
+                .map(|peer| u16::from_be_bytes([peer[4], peer[5]]))
+                .collect(),
+            Peers::Dictionary { .. } => vec![],
+        }
+    }
+
+    #[test]
+    fn send_dictionary_peers_with_integer_port() {
+        let mut tracker = tracker_with_peers(&[("A", "127.0.0.1", "6881"), ("B", "::1", "x")]);
+        let request = announce(&tracker, "0", "0");
+
+        let response = tracker.make_response(&request).unwrap();
+        let peer = Bencode::dict(vec![
+            ("ip", Bencode::from("127.0.0.1".to_string())),
+            ("port", Bencode::Int(6881)),
+            ("peer id", Bencode::from("A".to_string())),
+        ]);
+        assert_eq!(response.peers, Peers::Dictionary { peers: vec![peer] });
+
+        let request = announce(&tracker, "0", "1");
+        let response = tracker.make_response(&request).unwrap();
+        let peer = Bencode::dict(vec![
+            ("ip", Bencode::from("127.0.0.1".to_string())),
+            ("port", Bencode::Int(6881)),
+        ]);
+        assert_eq!(response.peers, Peers::Dictionary { peers: vec![peer] });
+    }
+
+    #[test]
+    fn send_binary_peer_ids_unchanged() {
+        let mut tracker = tracker_with_peers(&[("%ff%00A", "127.0.0.1", "6881")]);
+        let request = announce(&tracker, "0", "0");
+
+        let response = tracker.make_response(&request).unwrap();
+        let peer = Bencode::dict(vec![
+            ("ip", Bencode::from("127.0.0.1".to_string())),
+            ("port", Bencode::Int(6881)),
+            ("peer id", Bencode::ByteString(vec![0xff, 0x00, b'A'])),
+        ]);
+        assert_eq!(response.peers, Peers::Dictionary { peers: vec![peer] });
+    }
+
+    #[test]
+    fn send_compact_ipv4_and_ipv6_peers() {
+        let mut tracker = tracker_with_peers(&[
+            ("A", "127.0.0.1", "6881"),
+            ("B", "::ffff:10.0.0.2", "80"),
+            ("C", "::1", "6882"),
+            ("D", "no-es-una-ip", "6883"),
+            ("ME", "127.0.0.1", "1"),
+        ]);
+        let request = announce(&tracker, "1", "0");
+        let response = tracker.make_response(&request).unwrap();
+        assert_eq!(compact_ports(&response).len(), 2);
+        assert_eq!(response.incomplete, 4);
+
+        let peers: Vec<&Peer> = tracker.torrents[0].peers[..4].iter().collect();
+        let mut peers6 = vec![0; 15];
+        peers6.extend([1, 0x1a, 0xe2]);
+        assert_eq!(
+            BitTracker::make_compact(&peers),
+            Peers::Compact {
+                peers: vec![127, 0, 0, 1, 0x1a, 0xe1, 10, 0, 0, 2, 0, 80],
+                peers6,
+            }
+        );
+    }
+
+    #[test]
+    fn limit_peers_to_numwant() {
+        let peers: Vec<(String, String)> = (0..10)
+            .map(|index| (format!("P{}", index), format!("{}", 6000 + index)))
+            .collect();
+        let peers: Vec<(&str, &str, &str)> = peers
+            .iter()
+            .map(|(id, port)| (id.as_str(), "127.0.0.1", port.as_str()))
+            .collect();
+        let mut tracker = tracker_with_peers(&peers);
+        tracker.numwant_default = 4;
+        tracker.numwant_max = 6;
+        let mut request = announce(&tracker, "1", "0");
+
+        let response = tracker.make_response(&request).unwrap();
+        assert_eq!(compact_ports(&response).len(), 4);
+        assert_eq!(response.incomplete, 10);
+
+        assert_eq!(response.warning_message, None);
+
+        request.numwant = "8".to_string();
+        let response = tracker.make_response(&request).unwrap();
+        assert_eq!(compact_ports(&response).len(), 6);
+        assert!(response.warning_message.is_some());
+
+        request.numwant = "0".to_string();
+        let response = tracker.make_response(&request).unwrap();
+        assert!(compact_ports(&response).is_empty());
+    }
+
+    #[test]
+    fn prefer_seeders_for_leechers_and_leechers_for_seeders() {
+        let mut tracker = tracker_with_peers(&[
+            ("L1", "127.0.0.1", "1"),
+            ("L2", "127.0.0.1", "2"),
+            ("S1", "127.0.0.1", "3"),
+            ("S2", "127.0.0.1", "4"),
+        ]);
+        for peer in &mut tracker.torrents[0].peers {
+            if peer.id.starts_with('S') {
+                peer.event = Event::Completed;
+            }
         }
+        let mut request = announce(&tracker, "1", "0");
+        request.numwant = "2".to_string();
+
+        let mut ports = compact_ports(&tracker.make_response(&request).unwrap());
+        ports.sort();
+        assert_eq!(ports, vec![3, 4]);
+
+        request.left = "0".to_string();
+        let mut ports = compact_ports(&tracker.make_response(&request).unwrap());
+        ports.sort();
+        assert_eq!(ports, vec![1, 2]);
+    }
+
+    #[test]
+    fn not_send_peer_its_own_address() {
+        let mut tracker = tracker_with_peers(&[
+            ("OTHER-ID", "127.0.0.1", "6881"),
+            ("A", "127.0.0.1", "6882"),
+        ]);
+        let mut request = announce(&tracker, "1", "0");
+        request.ip = "127.0.0.1".to_string();
+        request.port = "6881".to_string();
+
+        let response = tracker.make_response(&request).unwrap();
+        assert_eq!(compact_ports(&response), vec![6882]);
+    }
+
+    #[test]
+    fn count_peers_with_nothing_left_as_seeders() {
+        let mut tracker = tracker_with_peers(&[("LEECHER", "127.0.0.1", "6881")]);
+        let mut request = announce(&tracker, "1", "0");
+        request.ip = "127.0.0.1".to_string();
+        request.port = "6882".to_string();
+        request.left = "0".to_string();
+        tracker.receive_request(&request).unwrap();
+
+        let stats = tracker.get_stats().unwrap();
+        assert_eq!((stats.cant_peers, stats.cant_seeders), (2, 1));
+        assert!(stats.info.iter().any(|info| info.completed));
+    }
+
+    #[test]
+    fn account_user_transfers_by_passkey() {
+        let mut tracker = tracker_with_peers(&[]);
+        let passkey = tracker.add_user("sofia").unwrap().passkey.clone();
+        let mut request = announce(&tracker, "0", "0");
+        request.passkey = passkey;
+        for (uploaded, downloaded) in [("100", "50"), ("250", "50"), ("10", "0")] {
+            request.uploaded = uploaded.to_string();
+            request.downloaded = downloaded.to_string();
+            assert!(tracker.receive_request(&request).is_ok());
+        }
+        request.passkey = "desconocida".to_string();
+        assert_eq!(
+            tracker.receive_request(&request),
+            Err(AnnounceError::UnknownPasskeyError)
+        );
+
+        let stats = tracker.get_stats().unwrap();
+        assert_eq!(stats.users[0].uploaded, 260);
+        assert_eq!(stats.users[0].downloaded, 50);
+        assert_eq!(stats.users[0].ratio, Some(5.2));
+    }
+
+    #[test]
+    fn require_passkey_for_private_torrents() {
+        let mut tracker = tracker_with_peers(&[]);
+        let mut metainfo = tracker.torrents[0].metainfo.clone().unwrap();
+        metainfo.info.private = Some(1);
+        tracker.torrents[0] = Torrent::from_metainfo(metainfo).unwrap();
+        let passkey = tracker.add_user("dante").unwrap().passkey.clone();
+        let mut request = announce(&tracker, "0", "0");
+
+        assert_eq!(
+            tracker.receive_request(&request),
+            Err(AnnounceError::PasskeyRequiredError)
+        );
+        request.passkey = passkey;
+        assert!(tracker.receive_request(&request).is_ok());
+    }
+
+    #[test]
+    fn reject_banned_and_unknown_torrents() {
+        let mut tracker = tracker_with_peers(&[]);
+        let mut request = announce(&tracker, "0", "0");
+        tracker.blacklist.insert(request.info_hash_url.clone());
+
+        assert_eq!(
+            tracker.receive_request(&request),
+            Err(AnnounceError::BannedTorrentError)
+        );
+        request.info_hash_url = "%00".to_string();
+        assert_eq!(
+            tracker.receive_request(&request),
+            Err(AnnounceError::UnknownTorrentError)
+        );
+    }
+
+    #[test]
+    fn remove_peer_on_stopped() {
+        let mut tracker = tracker_with_peers(&[("A", "127.0.0.1", "6881"), ("B", "::1", "6882")]);
+        let mut request = announce(&tracker, "0", "0");
+        request.peer_id = "A".to_string();
+        request.event = "stopped".to_string();
+
+        assert!(tracker.receive_request(&request).is_ok());
+        assert!(tracker.receive_request(&request).is_ok());
+        let ids: Vec<&str> = tracker.torrents[0]
+            .peers
+            .iter()
+            .map(|peer| peer.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["B"]);
+    }
+
+    #[test]
+    fn accept_announce_without_event() {
+        let mut tracker = tracker_with_peers(&[("A", "127.0.0.1", "6881")]);
+        let mut request = announce(&tracker, "0", "0");
+        request.peer_id = "A".to_string();
+        request.port = "6881".to_string();
+
+        assert!(tracker.receive_request(&request).is_ok());
+        request.peer_id = "NEW".to_string();
+        assert!(tracker.receive_request(&request).is_ok());
+        assert_eq!(tracker.torrents[0].peers.len(), 2);
+        assert_eq!(tracker.torrents[0].peers[1].event, Event::Started);
+    }
+
+    #[test]
+    fn remove_expired_peers() {
+        let mut tracker = tracker_with_peers(&[("A", "127.0.0.1", "6881")]);
+        let mut request = announce(&tracker, "0", "0");
+        request.time = Local::now() + Duration::seconds(20);
+        tracker.receive_request(&request).unwrap();
+        let timeout = tracker.peer_timeout;
+        let now = tracker.torrents[0].peers[0].time_last_request + timeout;
+
+        assert_eq!(tracker.remove_expired_peers(now).unwrap(), 0);
+        assert_eq!(
+            tracker
+                .remove_expired_peers(now + Duration::seconds(1))
+                .unwrap(),
+            1
+        );
+        assert_eq!(tracker.torrents[0].peers[0].id, "ME".to_string());
+    }
+
+    #[test]
+    fn query_history_by_torrent() {
+        let (tx, _rx) = mpsc::channel();
+        let mut tracker = BitTracker::new("./config_file", tx).unwrap();
+        let mut request = announce(&tracker, "1", "0");
+        request.event = "completed".to_string();
+        tracker.receive_request(&request).unwrap();
+        let now = request.time.timestamp();
+
+        let mut query = StatsQuery::new(now + 1);
+        let series = tracker.get_history(&query).unwrap();
+        assert_eq!(series.len(), tracker.torrents.len());
+
+        query.torrent = Some(hex::encode(&tracker.torrents[0].info_hash));
+        let series = tracker.get_history(&query).unwrap();
+        assert_eq!(series.len(), 1);
+        let last = series[0].buckets.last().unwrap();
+        assert_eq!((last.announces, last.peers, last.seeders), (1, 1, 1));
+        assert_eq!(last.completed, 1);
+
+        query.torrent = Some("desconocido".to_string());
+        assert_eq!(
+            tracker.get_history(&query),
+            Err(StatsError::UnknownTorrentError)
+        );
+    }
+
+    #[test]
+    fn restore_state_after_restart() {
+        let path = std::env::temp_dir().join(format!("bittracker-restore-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let path = path.to_str().unwrap().to_string();
+        let (tx, _rx) = mpsc::channel();
+        let mut tracker = BitTracker::new("./config_file", tx).unwrap();
+        tracker.restore(&path).unwrap();
+        let mut request = announce(&tracker, "1", "0");
+        request.event = "completed".to_string();
+        tracker.receive_request(&request).unwrap();
+        let mut metainfo = tracker.torrents[0].metainfo.clone().unwrap();
+        metainfo.info_hash = vec![7; 20];
+        metainfo.info.private = Some(1);
+        let private = Torrent::from_metainfo(metainfo).unwrap();
+        let private_url = private.info_hash_url.clone();
+        tracker.add_torrent(private).unwrap();
+        tracker.save().unwrap();
+        request.peer_id = "OTHER".to_string();
+        request.event = "started".to_string();
+        tracker.receive_request(&request).unwrap();
+        drop(tracker);
+
+        let (tx, _rx) = mpsc::channel();
+        let mut restored = BitTracker::new("./config_file", tx).unwrap();
+        restored.restore(&path).unwrap();
+
+        let torrent = &restored.torrents[0];
+        let ids: Vec<&str> = torrent.peers.iter().map(|peer| peer.id.as_str()).collect();
+        assert_eq!(ids, vec!["ME", "OTHER"]);
+        assert_eq!(torrent.peers[0].event, Event::Completed);
+        assert_eq!(torrent.completed, 1);
+        let announces: usize = torrent.history.values().map(|hour| hour.announces).sum();
+        assert_eq!(announces, 2);
+        assert_eq!(
+            torrent.history.values().map(|hour| hour.peers).max(),
+            Some(2)
+        );
+        let mut request = announce(&restored, "1", "0");
+        request.info_hash_url = private_url;
+        assert_eq!(
+            restored.receive_request(&request),
+            Err(AnnounceError::PasskeyRequiredError)
+        );
+        std::fs::remove_dir_all(path).unwrap();
     }
 
     #[test]
@@ -225,6 +1134,7 @@ mod bittracker_should {
     fn tracker() {
         let path = "./config_file";
         let (tx, _rx) = mpsc::channel();
-        BitTracker::start(path, tx).unwrap();
+        let config = TrackerConfig::from_file(path).unwrap();
+        BitTracker::start(&config, tx).unwrap();
     }
 }
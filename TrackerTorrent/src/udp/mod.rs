@@ -0,0 +1,2 @@
+pub mod udp_message;
+pub mod udp_server;
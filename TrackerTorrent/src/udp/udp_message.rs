@@ -0,0 +1,345 @@
+use crate::errors::UdpError;
+
+/******************************************************************************************/
+/*                                   UDP MESSAGES                                         */
+/******************************************************************************************/
+
+/// Constante con la que empieza todo connect (BEP 15).
+pub const PROTOCOL_ID: u64 = 0x41727101980;
+/// Tamaño maximo de un paquete recibido o enviado.
+pub const MAX_PACKET_SIZE: usize = 1500;
+/// Cantidad maxima de info hashes por scrape, para que la response entre en un paquete.
+pub const MAX_SCRAPE_HASHES: usize = 74;
+
+const CONNECT: u32 = 0;
+const ANNOUNCE: u32 = 1;
+const SCRAPE: u32 = 2;
+const ERROR: u32 = 3;
+
+const CONNECT_SIZE: usize = 16;
+const ANNOUNCE_SIZE: usize = 98;
+const HASH_SIZE: usize = 20;
+
+/// Announce recibido por UDP. Los campos conservan los tipos del protocolo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UdpAnnounce {
+    pub connection_id: u64,
+    pub transaction_id: u32,
+    pub info_hash: Vec<u8>,
+    pub peer_id: Vec<u8>,
+    pub downloaded: i64,
+    pub left: i64,
+    pub uploaded: i64,
+    /// 0: ninguno, 1: completed, 2: started, 3: stopped.
+    pub event: u32,
+    /// 0 si el peer no informa su ip.
+    pub ip: u32,
+    pub key: u32,
+    /// -1 si el peer no indica cuantos peers quiere.
+    pub num_want: i32,
+    pub port: u16,
+}
+
+/// Mensajes que un cliente puede enviar al tracker UDP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UdpRequest {
+    Connect {
+        transaction_id: u32,
+    },
+    Announce(UdpAnnounce),
+    Scrape {
+        connection_id: u64,
+        transaction_id: u32,
+        info_hashes: Vec<Vec<u8>>,
+    },
+}
+
+/// Mensajes que el tracker UDP envia como respuesta.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UdpResponse {
+    Connect {
+        transaction_id: u32,
+        connection_id: u64,
+    },
+    /// Los peers van en formato compacto: 6 bytes por peer IPv4 o 18 por peer IPv6.
+    Announce {
+        transaction_id: u32,
+        interval: u32,
+        leechers: u32,
+        seeders: u32,
+        peers: Vec<u8>,
+    },
+    /// Por cada torrent: seeders, completados y leechers.
+    Scrape {
+        transaction_id: u32,
+        torrents: Vec<(u32, u32, u32)>,
+    },
+    Error {
+        transaction_id: u32,
+        message: String,
+    },
+}
+
+impl UdpAnnounce {
+    /// Nombre del evento tal como se envia en un announce HTTP.
+    pub fn event_name(&self) -> &'static str {
+        match self.event {
+            1 => "completed",
+            2 => "started",
+            3 => "stopped",
+            _ => "",
+        }
+    }
+}
+
+impl UdpRequest {
+    /// Parsea un paquete recibido. Los datos extra al final de un announce (BEP 41) se ignoran.
+    pub fn parse(packet: &[u8]) -> Result<UdpRequest, UdpError> {
+        if packet.len() < CONNECT_SIZE {
+            return Err(UdpError::MalformedPacketError);
+        }
+        let connection_id = read_u64(packet, 0);
+        let transaction_id = read_u32(packet, 12);
+        match read_u32(packet, 8) {
+            CONNECT => {
+                if connection_id != PROTOCOL_ID {
+                    return Err(UdpError::InvalidProtocolError);
+                }
+                Ok(UdpRequest::Connect { transaction_id })
+            }
+            ANNOUNCE => {
+                if packet.len() < ANNOUNCE_SIZE {
+                    return Err(UdpError::MalformedPacketError);
+                }
+                Ok(UdpRequest::Announce(UdpAnnounce {
+                    connection_id,
+                    transaction_id,
+                    info_hash: packet[16..36].to_vec(),
+                    peer_id: packet[36..56].to_vec(),
+                    downloaded: read_u64(packet, 56) as i64,
+                    left: read_u64(packet, 64) as i64,
+                    uploaded: read_u64(packet, 72) as i64,
+                    event: read_u32(packet, 80),
+                    ip: read_u32(packet, 84),
+                    key: read_u32(packet, 88),
+                    num_want: read_u32(packet, 92) as i32,
+                    port: u16::from_be_bytes([packet[96], packet[97]]),
+                }))
+            }
+            SCRAPE => {
+                let hashes = &packet[CONNECT_SIZE..];
+                if hashes.is_empty() || !hashes.len().is_multiple_of(HASH_SIZE) {
+                    return Err(UdpError::MalformedPacketError);
+                }
+                if hashes.len() / HASH_SIZE > MAX_SCRAPE_HASHES {
+                    return Err(UdpError::TooManyHashesError);
+                }
+                Ok(UdpRequest::Scrape {
+                    connection_id,
+                    transaction_id,
+                    info_hashes: hashes.chunks(HASH_SIZE).map(<[u8]>::to_vec).collect(),
+                })
+            }
+            _ => Err(UdpError::UnknownActionError),
+        }
+    }
+
+    /// Devuelve el transaction id de un paquete aunque no se haya podido parsear, para poder
+    /// responder con un error.
+    pub fn transaction_id(packet: &[u8]) -> Option<u32> {
+        if packet.len() < CONNECT_SIZE {
+            return None;
+        }
+        Some(read_u32(packet, 12))
+    }
+}
+
+impl UdpResponse {
+    /// Serializa la response para enviarla por el socket.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        match self {
+            UdpResponse::Connect {
+                transaction_id,
+                connection_id,
+            } => {
+                bytes.extend(CONNECT.to_be_bytes());
+                bytes.extend(transaction_id.to_be_bytes());
+                bytes.extend(connection_id.to_be_bytes());
+            }
+            UdpResponse::Announce {
+                transaction_id,
+                interval,
+                leechers,
+                seeders,
+                peers,
+            } => {
+                bytes.extend(ANNOUNCE.to_be_bytes());
+                bytes.extend(transaction_id.to_be_bytes());
+                bytes.extend(interval.to_be_bytes());
+                bytes.extend(leechers.to_be_bytes());
+                bytes.extend(seeders.to_be_bytes());
+                bytes.extend(peers);
+            }
+            UdpResponse::Scrape {
+                transaction_id,
+                torrents,
+            } => {
+                bytes.extend(SCRAPE.to_be_bytes());
+                bytes.extend(transaction_id.to_be_bytes());
+                for (seeders, completed, leechers) in torrents {
+                    bytes.extend(seeders.to_be_bytes());
+                    bytes.extend(completed.to_be_bytes());
+                    bytes.extend(leechers.to_be_bytes());
+                }
+            }
+            UdpResponse::Error {
+                transaction_id,
+                message,
+            } => {
+                bytes.extend(ERROR.to_be_bytes());
+                bytes.extend(transaction_id.to_be_bytes());
+                bytes.extend(message.as_bytes());
+            }
+        }
+        bytes
+    }
+}
+
+fn read_u32(packet: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&packet[offset..offset + 4]);
+    u32::from_be_bytes(bytes)
+}
+
+fn read_u64(packet: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&packet[offset..offset + 8]);
+    u64::from_be_bytes(bytes)
+}
+
+#[cfg(test)]
+mod udp_message_should {
+    use super::*;
+
+    fn announce_packet() -> Vec<u8> {
+        let mut packet = vec![];
+        packet.extend(7_u64.to_be_bytes());
+        packet.extend(ANNOUNCE.to_be_bytes());
+        packet.extend(42_u32.to_be_bytes());
+        packet.extend([0xaa; 20]);
+        packet.extend(b"-4R0001-123456789012");
+        packet.extend(10_u64.to_be_bytes());
+        packet.extend(90_u64.to_be_bytes());
+        packet.extend(5_u64.to_be_bytes());
+        packet.extend(2_u32.to_be_bytes());
+        packet.extend(0_u32.to_be_bytes());
+        packet.extend(1234_u32.to_be_bytes());
+        packet.extend((-1_i32).to_be_bytes());
+        packet.extend(6881_u16.to_be_bytes());
+        packet
+    }
+
+    #[test]
+    fn parse_connect() {
+        let mut packet = PROTOCOL_ID.to_be_bytes().to_vec();
+        packet.extend(CONNECT.to_be_bytes());
+        packet.extend(42_u32.to_be_bytes());
+
+        assert_eq!(
+            UdpRequest::parse(&packet),
+            Ok(UdpRequest::Connect { transaction_id: 42 })
+        );
+
+        packet[0] = 1;
+        assert_eq!(
+            UdpRequest::parse(&packet),
+            Err(UdpError::InvalidProtocolError)
+        );
+    }
+
+    #[test]
+    fn parse_announce() {
+        let request = UdpRequest::parse(&announce_packet()).unwrap();
+        let announce = match request {
+            UdpRequest::Announce(announce) => announce,
+            _ => panic!("Se esperaba un announce"),
+        };
+
+        assert_eq!(announce.connection_id, 7);
+        assert_eq!(announce.transaction_id, 42);
+        assert_eq!(announce.info_hash, vec![0xaa; 20]);
+        assert_eq!(announce.peer_id, b"-4R0001-123456789012");
+        assert_eq!(announce.left, 90);
+        assert_eq!(announce.event_name(), "started");
+        assert_eq!(announce.num_want, -1);
+        assert_eq!(announce.port, 6881);
+
+        let mut short = announce_packet();
+        short.pop();
+        assert_eq!(
+            UdpRequest::parse(&short),
+            Err(UdpError::MalformedPacketError)
+        );
+    }
+
+    #[test]
+    fn parse_scrape() {
+        let mut packet = 7_u64.to_be_bytes().to_vec();
+        packet.extend(SCRAPE.to_be_bytes());
+        packet.extend(42_u32.to_be_bytes());
+        packet.extend([1; 20]);
+        packet.extend([2; 20]);
+
+        assert_eq!(
+            UdpRequest::parse(&packet),
+            Ok(UdpRequest::Scrape {
+                connection_id: 7,
+                transaction_id: 42,
+                info_hashes: vec![vec![1; 20], vec![2; 20]],
+            })
+        );
+
+        packet.push(3);
+        assert_eq!(
+            UdpRequest::parse(&packet),
+            Err(UdpError::MalformedPacketError)
+        );
+    }
+
+    #[test]
+    fn reject_unknown_actions() {
+        let mut packet = announce_packet();
+        packet[11] = 9;
+        assert_eq!(
+            UdpRequest::parse(&packet),
+            Err(UdpError::UnknownActionError)
+        );
+        assert_eq!(UdpRequest::transaction_id(&packet), Some(42));
+        assert_eq!(UdpRequest::transaction_id(&packet[..10]), None);
+    }
+
+    #[test]
+    fn serialize_responses() {
+        let announce = UdpResponse::Announce {
+            transaction_id: 42,
+            interval: 10,
+            leechers: 1,
+            seeders: 2,
+            peers: vec![127, 0, 0, 1, 0x1a, 0xe1],
+        };
+        assert_eq!(
+            announce.to_bytes(),
+            vec![
+                0, 0, 0, 1, 0, 0, 0, 42, 0, 0, 0, 10, 0, 0, 0, 1, 0, 0, 0, 2, 127, 0, 0, 1, 0x1a,
+                0xe1
+            ]
+        );
+
+        let error = UdpResponse::Error {
+            transaction_id: 42,
+            message: "error".to_string(),
+        };
+        assert_eq!(error.to_bytes(), b"\0\0\0\x03\0\0\0\x2aerror");
+    }
+}
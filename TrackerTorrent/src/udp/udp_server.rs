@@ -0,0 +1,363 @@
+use crate::errors::{BitTrackerError, UdpError};
+use crate::request::Request;
+use crate::response::Peers;
+use crate::tracker::BitTracker;
+use crate::udp::udp_message::{UdpAnnounce, UdpRequest, UdpResponse, MAX_PACKET_SIZE};
+use protocol::encoder::url_encoder::URLEncoder;
+use rand::{thread_rng, Rng};
+use sha1::{Digest, Sha1};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/******************************************************************************************/
+/*                                    UDP SERVER                                          */
+/******************************************************************************************/
+
+/// Segundos durante los que se acepta un connection id luego de entregarlo (BEP 15).
+pub const CONNECTION_ID_TTL: u64 = 120;
+
+/// Estructura que atiende el protocolo de tracker UDP (BEP 15).
+/// Comparte el estado con el tracker HTTP. Los connection ids no se guardan: llevan el
+/// momento en que se generaron y una firma con un secreto del servidor, la ip y el puerto.
+pub struct UdpServer {
+    socket: UdpSocket,
+    tracker: Arc<Mutex<BitTracker>>,
+    secret: [u8; 16],
+}
+
+impl UdpServer {
+    pub fn new(socket: UdpSocket, tracker: Arc<Mutex<BitTracker>>) -> UdpServer {
+        UdpServer {
+            socket,
+            tracker,
+            secret: thread_rng().gen(),
+        }
+    }
+
+    /// Atiende paquetes hasta que falle el socket o no se pueda tomar el lock del tracker.
+    pub fn listen(&self) -> Result<(), BitTrackerError> {
+        let mut buffer = [0; MAX_PACKET_SIZE];
+        loop {
+            let (size, address) = self
+                .socket
+                .recv_from(&mut buffer)
+                .or(Err(BitTrackerError::ReadConnectionError))?;
+            if let Some(response) = self.handle_packet(&buffer[..size], address, now()) {
+                if let Err(error) = self.socket.send_to(&response.to_bytes(), address) {
+                    println!(
+                        "[TRACKER UDP] No se pudo responder a {}: {}",
+                        address, error
+                    );
+                }
+            }
+        }
+    }
+
+    /// Genera la response al paquete recibido. Los paquetes invalidos se responden con un
+    /// error si se puede leer su transaction id, y se ignoran si no.
+    pub fn handle_packet(
+        &self,
+        packet: &[u8],
+        address: SocketAddr,
+        now: u64,
+    ) -> Option<UdpResponse> {
+        let start = Instant::now();
+        let request = UdpRequest::parse(packet);
+        let label = match &request {
+            Ok(UdpRequest::Connect { .. }) => "connect",
+            Ok(UdpRequest::Announce(_)) => "announce",
+            Ok(UdpRequest::Scrape { .. }) => "scrape",
+            Err(_) => "unknown",
+        };
+        let result = match request {
+            Ok(UdpRequest::Connect { transaction_id }) => Ok(UdpResponse::Connect {
+                transaction_id,
+                connection_id: self.connection_id(&address, now),
+            }),
+            Ok(UdpRequest::Announce(announce)) => self
+                .check_connection_id(announce.connection_id, &address, now)
+                .and_then(|_| self.announce(&announce, &address)),
+            Ok(UdpRequest::Scrape {
+                connection_id,
+                transaction_id,
+                info_hashes,
+            }) => self
+                .check_connection_id(connection_id, &address, now)
+                .and_then(|_| self.scrape(transaction_id, &info_hashes)),
+            Err(error) => Err(error),
+        };
+        if let Ok(mut tracker) = self.tracker.lock() {
+            if result.is_err() {
+                tracker.metrics.count_bad_request("udp");
+            }
+            tracker
+                .metrics
+                .observe("udp", label, start.elapsed().as_secs_f64());
+        }
+        match result {
+            Ok(response) => Some(response),
+            Err(error) => {
+                println!("[TRACKER UDP] {}: {}", address, error);
+                UdpRequest::transaction_id(packet).map(|transaction_id| UdpResponse::Error {
+                    transaction_id,
+                    message: error.to_string(),
+                })
+            }
+        }
+    }
+
+    /// Genera el connection id para la direccion: los 32 bits altos son el momento de
+    /// creacion y los bajos una firma de ese momento y la direccion.
+    fn connection_id(&self, address: &SocketAddr, timestamp: u64) -> u64 {
+        let timestamp = timestamp as u32;
+        let mut hasher = Sha1::new();
+        hasher.update(self.secret);
+        hasher.update(timestamp.to_be_bytes());
+        match address.ip() {
+            IpAddr::V4(ip) => hasher.update(ip.octets()),
+            IpAddr::V6(ip) => hasher.update(ip.octets()),
+        }
+        hasher.update(address.port().to_be_bytes());
+        let hash = hasher.finalize();
+        let signature = u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]);
+        ((timestamp as u64) << 32) | signature as u64
+    }
+
+    /// Verifica que el connection id haya sido generado por este servidor para la direccion
+    /// y que no haya expirado.
+    fn check_connection_id(
+        &self,
+        connection_id: u64,
+        address: &SocketAddr,
+        now: u64,
+    ) -> Result<(), UdpError> {
+        let timestamp = connection_id >> 32;
+        if timestamp > now
+            || now - timestamp > CONNECTION_ID_TTL
+            || self.connection_id(address, timestamp) != connection_id
+        {
+            return Err(UdpError::InvalidConnectionIdError);
+        }
+        Ok(())
+    }
+
+    /// Registra el announce en el tracker y responde con peers compactos de la misma
+    /// familia de direcciones que el cliente.
+    fn announce(
+        &self,
+        announce: &UdpAnnounce,
+        address: &SocketAddr,
+    ) -> Result<UdpResponse, UdpError> {
+        let request = Self::make_request(announce, address)?;
+        let mut tracker = self.tracker.lock().or(Err(UdpError::InternalError))?;
+        tracker
+            .receive_request(&request)
+            .map_err(UdpError::AnnounceError)?;
+        let response = tracker
+            .make_response(&request)
+            .or(Err(UdpError::InternalError))?;
+        drop(tracker);
+
+        let peers = match (response.peers, address.is_ipv4()) {
+            (Peers::Compact { peers, .. }, true) => peers,
+            (Peers::Compact { peers6, .. }, false) => peers6,
+            (Peers::Dictionary { .. }, _) => vec![],
+        };
+        Ok(UdpResponse::Announce {
+            transaction_id: announce.transaction_id,
+            interval: response.interval as u32,
+            leechers: response.incomplete as u32,
+            seeders: response.complete as u32,
+            peers,
+        })
+    }
+
+    /// Convierte el announce UDP en la misma Request que genera un announce HTTP.
+    fn make_request(announce: &UdpAnnounce, address: &SocketAddr) -> Result<Request, UdpError> {
+        let mut request = Request::new();
+        request.info_hash_url = URLEncoder
+            .urlencode(announce.info_hash.clone())
+            .or(Err(UdpError::InvalidAnnounceError))?;
+        request.peer_id = URLEncoder
+            .urlencode(announce.peer_id.clone())
+            .or(Err(UdpError::InvalidAnnounceError))?;
+        request.ip = match announce.ip {
+            0 => address.ip().to_string(),
+            ip => Ipv4Addr::from(ip).to_string(),
+        };
+        request.port = announce.port.to_string();
+        request.compact = 1.to_string();
+        request.event = announce.event_name().to_string();
+        request.uploaded = announce.uploaded.to_string();
+        request.downloaded = announce.downloaded.to_string();
+        request.left = announce.left.to_string();
+        if announce.num_want >= 0 {
+            request.numwant = announce.num_want.to_string();
+        }
+        Ok(request)
+    }
+
+    /// Responde con seeders, completados y leechers de cada torrent pedido. Los torrents que
+    /// el tracker no hostea se informan en cero.
+    fn scrape(
+        &self,
+        transaction_id: u32,
+        info_hashes: &[Vec<u8>],
+    ) -> Result<UdpResponse, UdpError> {
+        let mut tracker = self.tracker.lock().or(Err(UdpError::InternalError))?;
+        tracker.metrics.count_scrape();
+        let mut torrents = vec![];
+        for info_hash in info_hashes {
+            let info_hash_url = URLEncoder
+                .urlencode(info_hash.clone())
+                .or(Err(UdpError::MalformedPacketError))?;
+            let (seeders, completed, leechers) = tracker.scrape(&info_hash_url).unwrap_or_default();
+            torrents.push((seeders as u32, completed as u32, leechers as u32));
+        }
+        Ok(UdpResponse::Scrape {
+            transaction_id,
+            torrents,
+        })
+    }
+}
+
+/// Segundos desde el epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod udp_server_should {
+    use super::*;
+    use crate::response::INTERVAL;
+    use crate::udp::udp_message::PROTOCOL_ID;
+    use std::sync::mpsc;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn server() -> UdpServer {
+        let (tx, _rx) = mpsc::channel();
+        let tracker = BitTracker::new("./config_file", tx).unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        UdpServer::new(socket, Arc::new(Mutex::new(tracker)))
+    }
+
+    fn address(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    fn connect(server: &UdpServer, address: SocketAddr) -> u64 {
+        let mut packet = PROTOCOL_ID.to_be_bytes().to_vec();
+        packet.extend(0_u32.to_be_bytes());
+        packet.extend(1_u32.to_be_bytes());
+        match server.handle_packet(&packet, address, NOW) {
+            Some(UdpResponse::Connect { connection_id, .. }) => connection_id,
+            response => panic!("Response inesperada: {:?}", response),
+        }
+    }
+
+    fn announce_packet(
+        server: &UdpServer,
+        connection_id: u64,
+        peer_id: &[u8; 20],
+        event: u32,
+        left: u64,
+        port: u16,
+    ) -> Vec<u8> {
+        let tracker = server.tracker.lock().unwrap();
+        let info_hash = tracker.torrents[0].info_hash.clone();
+        drop(tracker);
+        let mut packet = connection_id.to_be_bytes().to_vec();
+        packet.extend(1_u32.to_be_bytes());
+        packet.extend(2_u32.to_be_bytes());
+        packet.extend(info_hash);
+        packet.extend(peer_id);
+        packet.extend(0_u64.to_be_bytes());
+        packet.extend(left.to_be_bytes());
+        packet.extend(0_u64.to_be_bytes());
+        packet.extend(event.to_be_bytes());
+        packet.extend([0; 8]);
+        packet.extend((-1_i32).to_be_bytes());
+        packet.extend(port.to_be_bytes());
+        packet
+    }
+
+    #[test]
+    fn connect_announce_and_scrape() {
+        let server = server();
+        let first = address(1000);
+        let second = address(2000);
+        let connection_id = connect(&server, first);
+        let packet = announce_packet(
+            &server,
+            connection_id,
+            b"AAAAAAAAAAAAAAAAAAAA",
+            2,
+            100,
+            6881,
+        );
+        server.handle_packet(&packet, first, NOW);
+
+        let connection_id = connect(&server, second);
+        let packet = announce_packet(&server, connection_id, b"BBBBBBBBBBBBBBBBBBBB", 1, 0, 6882);
+        let response = server.handle_packet(&packet, second, NOW + 60);
+        assert_eq!(
+            response,
+            Some(UdpResponse::Announce {
+                transaction_id: 2,
+                interval: INTERVAL as u32,
+                leechers: 1,
+                seeders: 0,
+                peers: vec![127, 0, 0, 1, 0x1a, 0xe1],
+            })
+        );
+
+        let mut packet = connection_id.to_be_bytes().to_vec();
+        packet.extend(2_u32.to_be_bytes());
+        packet.extend(3_u32.to_be_bytes());
+        packet.extend(&announce_packet(&server, 0, &[0; 20], 0, 0, 0)[16..36]);
+        packet.extend([0; 20]);
+        assert_eq!(
+            server.handle_packet(&packet, second, NOW),
+            Some(UdpResponse::Scrape {
+                transaction_id: 3,
+                torrents: vec![(1, 1, 1), (0, 0, 0)],
+            })
+        );
+    }
+
+    #[test]
+    fn reject_expired_or_foreign_connection_ids() {
+        let server = server();
+        let connection_id = connect(&server, address(1000));
+        let packet = announce_packet(
+            &server,
+            connection_id,
+            b"AAAAAAAAAAAAAAAAAAAA",
+            2,
+            100,
+            6881,
+        );
+        let error = Some(UdpResponse::Error {
+            transaction_id: 2,
+            message: UdpError::InvalidConnectionIdError.to_string(),
+        });
+
+        let expired = NOW + CONNECTION_ID_TTL + 1;
+        assert_eq!(server.handle_packet(&packet, address(1000), expired), error);
+        assert_eq!(server.handle_packet(&packet, address(1001), NOW), error);
+        let forged = announce_packet(&server, NOW << 32, b"AAAAAAAAAAAAAAAAAAAA", 2, 100, 6881);
+        assert_eq!(server.handle_packet(&forged, address(1000), NOW), error);
+    }
+
+    #[test]
+    fn ignore_packets_without_transaction_id() {
+        let server = server();
+
+        assert_eq!(server.handle_packet(&[0; 8], address(1000), NOW), None);
+    }
+}
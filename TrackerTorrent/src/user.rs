@@ -0,0 +1,74 @@
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+
+/******************************************************************************************/
+/*                                       USER                                             */
+/******************************************************************************************/
+
+const PASSKEY_LEN: usize = 32;
+
+/// Estructura que modela a un usuario del tracker privado.
+/// Los announces del usuario llevan su passkey en el path (`/<passkey>/announce`) y se le
+/// acumulan los bytes subidos y bajados que informa en cada announce.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub name: String,
+    pub passkey: String,
+    pub uploaded: u64,
+    pub downloaded: u64,
+}
+
+impl User {
+    /// Inicializa el usuario con una passkey aleatoria.
+    pub fn new(name: &str) -> User {
+        let passkey = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(PASSKEY_LEN)
+            .map(char::from)
+            .collect();
+        User {
+            name: name.to_string(),
+            passkey,
+            uploaded: 0,
+            downloaded: 0,
+        }
+    }
+
+    /// Suma lo subido y bajado desde el announce anterior.
+    pub fn account(&mut self, uploaded: u64, downloaded: u64) {
+        self.uploaded = self.uploaded.saturating_add(uploaded);
+        self.downloaded = self.downloaded.saturating_add(downloaded);
+    }
+
+    /// Relacion entre lo subido y lo bajado. None si el usuario todavia no bajo nada.
+    pub fn ratio(&self) -> Option<f64> {
+        match self.downloaded {
+            0 => None,
+            downloaded => Some(self.uploaded as f64 / downloaded as f64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod user_should {
+    use super::*;
+
+    #[test]
+    fn generate_different_passkeys() {
+        let first = User::new("sofia");
+        let second = User::new("sofia");
+
+        assert_eq!(first.passkey.len(), PASSKEY_LEN);
+        assert_ne!(first.passkey, second.passkey);
+    }
+
+    #[test]
+    fn calculate_ratio() {
+        let mut user = User::new("dante");
+        assert_eq!(user.ratio(), None);
+
+        user.account(300, 100);
+        user.account(0, 100);
+        assert_eq!(user.ratio(), Some(1.5));
+    }
+}
@@ -3,13 +3,6 @@ use crate::encoder::bencode_parser::Bencode;
 pub struct EncodingParser;
 #[allow(dead_code)]
 impl EncodingParser {
-    /// Esta funcion recibe un string de tipo String y la codifica en formato de Bencoding,
-    /// devolviendo el string bencodeado como un Vec<u8>
-    fn encode_string(&self, string: String) -> Vec<u8> {
-        let len = string.len().to_string();
-        (len + ":" + &string).as_bytes().to_vec()
-    }
-
     /// Esta funcion recibe un string como Vec<u8> y la codifica en formato de Bencoding,
     /// devolviendo el string bencodeado como un Vec<u8>
     fn encode_byte_string(&self, bytestring: Vec<u8>) -> Vec<u8> {
@@ -42,20 +35,15 @@ impl EncodingParser {
         vec
     }
 
-    /// Esta funcion recibe un Diccionario como vector de tuplas de Strings y Bencodes
-    /// y lo codifica en formato de Bencoding.
+    /// Esta funcion recibe un Diccionario como vector de tuplas de claves y Bencodes
+    /// y lo codifica en formato de Bencoding, con las claves ordenadas como bytes crudos.
     /// devolviendo el diccionario bencodeado como un Vec<u8>
-    fn encode_dict(&self, dict: Vec<(String, Bencode)>) -> Vec<u8> {
+    fn encode_dict(&self, mut dict: Vec<(Vec<u8>, Bencode)>) -> Vec<u8> {
+        dict.sort_by(|(key, _), (other, _)| key.cmp(other));
         let mut vec: Vec<u8> = "d".as_bytes().to_vec();
-        for (key, value) in &dict {
-            let key_encode = self.encode_string(key.to_string());
-            for key_byte in key_encode {
-                vec.push(key_byte);
-            }
-            let value_encode = self.encode(value.clone());
-            for value_byte in value_encode {
-                vec.push(value_byte);
-            }
+        for (key, value) in dict {
+            vec.extend(self.encode_byte_string(key));
+            vec.extend(self.encode(value));
         }
         vec.push(b'e');
         vec
@@ -65,7 +53,6 @@ impl EncodingParser {
     /// y llama a la funcion que corresponde en funcion a que campo del enumerativo se trata
     pub fn encode(&self, bencode: Bencode) -> Vec<u8> {
         match bencode {
-            Bencode::String(string) => self.encode_string(string),
             Bencode::ByteString(bytestring) => self.encode_byte_string(bytestring),
             Bencode::Int(number) => self.encode_number(number),
             Bencode::Dictionary(dict) => self.encode_dict(dict),
@@ -88,10 +75,7 @@ mod decoding_parser_should {
     #[test]
     fn encode_string() {
         let parser = EncodingParser;
-        assert_eq!(
-            parser.encode_string(String::from("hola")),
-            "4:hola".as_bytes()
-        );
+        assert_eq!(parser.encode(Bencode::from("hola")), "4:hola".as_bytes());
     }
 
     #[test]
@@ -123,24 +107,38 @@ mod decoding_parser_should {
     #[test]
     fn encode_list() {
         let parser = EncodingParser;
-        let mut vec: Vec<Bencode> = vec![];
-        vec.push(Bencode::String(String::from("spam")));
-        vec.push(Bencode::String(String::from("eggs")));
+        let vec: Vec<Bencode> = vec![Bencode::from("spam"), Bencode::from("eggs")];
         assert_eq!(parser.encode_list(vec), "l4:spam4:eggse".as_bytes());
     }
 
     #[test]
     fn encode_dict() {
         let parser = EncodingParser;
-        let mut dict = vec![];
-        dict.push((String::from("cow"), Bencode::String(String::from("moo"))));
-        dict.push((String::from("spam"), Bencode::String(String::from("eggs"))));
+        let dict = vec![
+            (b"cow".to_vec(), Bencode::from("moo")),
+            (b"spam".to_vec(), Bencode::from("eggs")),
+        ];
         assert_eq!(
             parser.encode_dict(dict),
             "d3:cow3:moo4:spam4:eggse".as_bytes()
         );
     }
 
+    #[test]
+    fn encode_dict_with_sorted_keys() {
+        let parser = EncodingParser;
+        let dict = vec![
+            (b"spam".to_vec(), Bencode::from("eggs")),
+            (b"cow".to_vec(), Bencode::from("moo")),
+            (vec![255], Bencode::Int(1)),
+            (b"Z".to_vec(), Bencode::Int(2)),
+        ];
+        assert_eq!(
+            parser.encode(Bencode::Dictionary(dict)),
+            b"d1:Zi2e3:cow3:moo4:spam4:eggs1:\xffi1ee".to_vec()
+        );
+    }
+
     #[test]
     fn encode_file() {
         let torrent_path = "./torrents/example.torrent";
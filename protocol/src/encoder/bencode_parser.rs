@@ -0,0 +1,586 @@
+use crate::encoder::bencode_tokenizer::{DecodingOptions, Spanned, Token, Tokenizer};
+use crate::encoder::errors::DecodingError;
+use std::fmt::Debug;
+use std::ops::Range;
+
+/******************************************************************************************/
+/*                                 DECODING PARSER                                        */
+/******************************************************************************************/
+
+#[allow(dead_code)]
+pub struct DecodingParser;
+
+/// Valor bencodeado. Los strings y las claves de los diccionarios son bytes crudos,
+/// ya que la especificacion no exige que sean UTF-8.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Bencode {
+    Int(i64),
+    ByteString(Vec<u8>),
+    List(Vec<Bencode>),
+    Dictionary(Vec<(Vec<u8>, Bencode)>),
+}
+
+#[allow(dead_code)]
+impl Bencode {
+    /// Arma un diccionario a partir de claves de texto.
+    pub fn dict(entries: Vec<(&str, Bencode)>) -> Bencode {
+        Bencode::Dictionary(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.as_bytes().to_vec(), value))
+                .collect(),
+        )
+    }
+
+    /// Devuelve el string como &str si es un string UTF-8 valido.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Bencode::ByteString(bytes) => std::str::from_utf8(bytes).ok(),
+            _ => None,
+        }
+    }
+
+    /// Devuelve los bytes del string.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Bencode::ByteString(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Devuelve el entero.
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Bencode::Int(int) => Some(*int),
+            _ => None,
+        }
+    }
+
+    /// Devuelve el valor asociado a la clave si el Bencode es un diccionario.
+    pub fn get(&self, key: &str) -> Option<&Bencode> {
+        match self {
+            Bencode::Dictionary(dict) => dict
+                .iter()
+                .find(|(dict_key, _)| dict_key == key.as_bytes())
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Esta funcion imprime el Bencode.
+    pub fn print(&self) {
+        self.println("");
+    }
+
+    /// Esta funcion recibe un una indentacion como &str e imprime el Bencode en formato legible por pantalla.
+    fn println(&self, identation: &str) {
+        if let Bencode::ByteString(bytestring) = self {
+            match std::str::from_utf8(bytestring) {
+                Ok(string) => println!("{}{}", identation, string),
+                Err(_) => println!("{}{:?}", identation.to_owned() + "  ", bytestring),
+            }
+        }
+        if let Bencode::Int(int) = self {
+            println!("{}{}", identation, int);
+        }
+        if let Bencode::Dictionary(dict) = self {
+            println!("{}d", identation);
+            let new_identation = identation.to_owned() + "    ";
+            let value_identation = new_identation.to_owned() + "    ";
+            for (key, value) in dict {
+                println!("{}Key: {}", new_identation, String::from_utf8_lossy(key));
+                println!("{}Values: ", new_identation);
+                value.println(&value_identation);
+            }
+            println!("{}e", identation);
+        }
+        if let Bencode::List(list) = self {
+            println!("{}l", identation);
+            let new_identation = identation.to_owned() + "    ";
+            for element in list {
+                element.println(&new_identation);
+            }
+            println!("{}e", identation);
+        }
+    }
+}
+
+impl From<&str> for Bencode {
+    fn from(string: &str) -> Bencode {
+        Bencode::ByteString(string.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Bencode {
+    fn from(string: String) -> Bencode {
+        Bencode::ByteString(string.into_bytes())
+    }
+}
+
+type Result<T> = std::result::Result<T, DecodingError>;
+
+/// Contenedor que se esta armando mientras se recorren los tokens.
+enum Partial {
+    List(Vec<Bencode>),
+    Dictionary(Vec<(Vec<u8>, Bencode)>, Option<Vec<u8>>),
+}
+
+#[allow(dead_code)]
+impl DecodingParser {
+    /// Esta funcion recibe un string de tipo String y lo devuelve decodificado como Bencode.
+    pub fn decode_from_string(&self, string: String) -> Result<Bencode> {
+        self.decode_from_u8(string.into_bytes())
+    }
+
+    /// Esta funcion recibe un Vec<u8> y lo devuelve decodificado como Bencode.
+    /// Las claves de los diccionarios deben estar ordenadas y no repetirse.
+    pub fn decode_from_u8(&self, byte_string: Vec<u8>) -> Result<Bencode> {
+        self.decode_with(&byte_string, DecodingOptions::default())
+    }
+
+    /// Igual que decode_from_u8, pero acepta diccionarios con claves desordenadas o repetidas,
+    /// como los que generan algunos clientes y trackers.
+    pub fn decode_from_u8_lenient(&self, byte_string: Vec<u8>) -> Result<Bencode> {
+        let options = DecodingOptions {
+            lenient: true,
+            ..DecodingOptions::default()
+        };
+        self.decode_with(&byte_string, options)
+    }
+
+    /// Decodifica los bytes recibidos respetando los limites de profundidad y largo de las opciones.
+    /// Los contenedores se arman con una pila explicita, sin recursion.
+    pub fn decode_with(&self, byte_string: &[u8], options: DecodingOptions) -> Result<Bencode> {
+        let mut stack: Vec<Partial> = vec![];
+        let mut result = None;
+        for spanned in Tokenizer::new(byte_string, options) {
+            let value = match spanned?.token {
+                Token::Int(int) => Bencode::Int(int),
+                Token::ByteString(bytes) => Bencode::ByteString(bytes.to_vec()),
+                Token::List => {
+                    stack.push(Partial::List(vec![]));
+                    continue;
+                }
+                Token::Dictionary => {
+                    stack.push(Partial::Dictionary(vec![], None));
+                    continue;
+                }
+                Token::End => match stack.pop() {
+                    Some(Partial::List(list)) => Bencode::List(list),
+                    Some(Partial::Dictionary(dict, _)) => Bencode::Dictionary(dict),
+                    None => return Err(DecodingError::InvalidSyntaxError),
+                },
+            };
+            match stack.last_mut() {
+                None => result = Some(value),
+                Some(Partial::List(list)) => list.push(value),
+                Some(Partial::Dictionary(dict, key)) => match (key.take(), value) {
+                    (None, Bencode::ByteString(bytes)) => *key = Some(bytes),
+                    (Some(key), value) => dict.push((key, value)),
+                    (None, _) => return Err(DecodingError::InvalidSyntaxError),
+                },
+            }
+        }
+        result.ok_or(DecodingError::UnexpectedEndError(byte_string.len()))
+    }
+
+    /// Esta funcion recibe un diccionario bencodeado y devuelve el rango de bytes que ocupa
+    /// el valor de la clave recibida, sin volver a encodearlo. Se usa para calcular el info hash
+    /// a partir de los bytes originales del torrent.
+    pub fn dict_value_span(&self, byte_string: &[u8], key: &str) -> Result<Option<Range<usize>>> {
+        let options = DecodingOptions {
+            lenient: true,
+            ..DecodingOptions::default()
+        };
+        let mut tokenizer = Tokenizer::new(byte_string, options);
+        match tokenizer.next_token()? {
+            Some(Spanned {
+                token: Token::Dictionary,
+                ..
+            }) => {}
+            _ => return Err(DecodingError::InvalidTokenError(0)),
+        }
+        loop {
+            let spanned = tokenizer
+                .next_token()?
+                .ok_or(DecodingError::UnexpectedEndError(byte_string.len()))?;
+            match spanned.token {
+                Token::ByteString(dict_key) => {
+                    let (start, end) = tokenizer.skip_value()?;
+                    if dict_key == key.as_bytes() {
+                        return Ok(Some(start..end));
+                    }
+                }
+                _ => return Ok(None),
+            }
+        }
+    }
+}
+
+/******************************************************************************************/
+/*                                        TESTS                                           */
+/******************************************************************************************/
+
+#[cfg(test)]
+mod decoding_parser_should {
+    use super::*;
+
+    fn decode(string: &str) -> Bencode {
+        DecodingParser
+            .decode_from_string(string.to_string())
+            .unwrap()
+    }
+
+    fn decode_error(string: &str) -> DecodingError {
+        DecodingParser
+            .decode_from_string(string.to_string())
+            .unwrap_err()
+    }
+
+    #[test]
+    fn decode_number() {
+        assert_eq!(decode("i27e"), Bencode::Int(27));
+    }
+
+    #[test]
+    fn decode_negative_number() {
+        assert_eq!(decode("i-3e"), Bencode::Int(-3));
+    }
+
+    #[test]
+    fn decode_zero() {
+        assert_eq!(decode("i0e"), Bencode::Int(0));
+    }
+
+    #[test]
+    fn fail_if_number_is_not_canonical() {
+        assert_eq!(decode_error("i03e"), DecodingError::InvalidIntegerError(0));
+        assert_eq!(decode_error("i-0e"), DecodingError::InvalidIntegerError(0));
+        assert_eq!(decode_error("i-e"), DecodingError::InvalidIntegerError(0));
+        assert_eq!(decode_error("ie"), DecodingError::InvalidIntegerError(0));
+        assert_eq!(decode_error("i1-2e"), DecodingError::InvalidIntegerError(2));
+        assert_eq!(
+            decode_error("03:abc"),
+            DecodingError::InvalidIntegerError(0)
+        );
+        assert_eq!(
+            decode_error("i9223372036854775808e"),
+            DecodingError::InvalidIntegerError(0)
+        );
+    }
+
+    #[test]
+    fn decode_string() {
+        assert_eq!(
+            decode("33:Debian CD from cdimage.debian.org"),
+            Bencode::from("Debian CD from cdimage.debian.org")
+        );
+    }
+
+    #[test]
+    fn decode_simple_string() {
+        let bencode = decode("4:spam");
+        assert_eq!(bencode, Bencode::from("spam"));
+        assert_eq!(bencode.as_str(), Some("spam"));
+    }
+
+    #[test]
+    fn decode_binary_string() {
+        let parser = DecodingParser;
+        let bencode = parser.decode_from_u8(b"3:\xff\x00a".to_vec()).unwrap();
+        assert_eq!(bencode, Bencode::ByteString(vec![255, 0, b'a']));
+        assert_eq!(bencode.as_str(), None);
+    }
+
+    #[test]
+    fn decode_list() {
+        let array = vec![Bencode::from("aaa"), Bencode::Int(2)];
+        assert_eq!(decode("l3:aaai2ee"), Bencode::List(array));
+    }
+
+    #[test]
+    fn decode_list_of_lists() {
+        let inner = vec![Bencode::Int(2), Bencode::from("aa")];
+        assert_eq!(
+            decode("l1:ali2e2:aaee"),
+            Bencode::List(vec![Bencode::from("a"), Bencode::List(inner)])
+        );
+    }
+
+    #[test]
+    fn decode_list_of_dicts() {
+        let dict1 = Bencode::dict(vec![
+            ("cow", Bencode::from("moo")),
+            ("spam", Bencode::from("eggs")),
+        ]);
+        let dict2 = Bencode::dict(vec![("cayo", Bencode::from("noche"))]);
+        assert_eq!(
+            decode("ld3:cow3:moo4:spam4:eggsed4:cayo5:nocheee"),
+            Bencode::List(vec![dict1, dict2])
+        );
+    }
+
+    #[test]
+    fn decode_dict() {
+        let dict = Bencode::dict(vec![
+            ("cow", Bencode::from("moo")),
+            ("spam", Bencode::from("eggs")),
+        ]);
+        let bencode = decode("d3:cow3:moo4:spam4:eggse");
+        assert_eq!(bencode, dict);
+        assert_eq!(bencode.get("spam"), Some(&Bencode::from("eggs")));
+    }
+
+    #[test]
+    fn decode_dict_of_ints() {
+        let dict = Bencode::dict(vec![("cow", Bencode::Int(10)), ("list", Bencode::Int(0))]);
+        assert_eq!(decode("d3:cowi10e4:listi0ee"), dict);
+    }
+
+    #[test]
+    fn decode_dict_of_lists() {
+        let dict = Bencode::dict(vec![
+            ("cow", Bencode::List(vec![])),
+            (
+                "list",
+                Bencode::List(vec![Bencode::from("spam"), Bencode::from("eggs")]),
+            ),
+        ]);
+        assert_eq!(decode("d3:cowle4:listl4:spam4:eggsee"), dict);
+    }
+
+    #[test]
+    fn decode_dict_of_dicts() {
+        let dict1 = Bencode::dict(vec![("la", Bencode::from("noche"))]);
+        let dict2 = Bencode::dict(vec![("cayo", dict1)]);
+        assert_eq!(decode("d4:cayod2:la5:nocheee"), dict2);
+    }
+
+    #[test]
+    fn decode_dict_with_binary_keys() {
+        let parser = DecodingParser;
+        let bencode = parser.decode_from_u8(b"d2:\xff\x01i1ee".to_vec()).unwrap();
+        assert_eq!(
+            bencode,
+            Bencode::Dictionary(vec![(vec![255, 1], Bencode::Int(1))])
+        );
+    }
+
+    #[test]
+    fn fail_if_dict_keys_are_unsorted() {
+        assert_eq!(
+            decode_error("d4:spam4:eggs3:cow3:mooe"),
+            DecodingError::UnsortedKeysError(13)
+        );
+        assert_eq!(
+            decode_error("d3:cowi1e3:cowi2ee"),
+            DecodingError::UnsortedKeysError(9)
+        );
+    }
+
+    #[test]
+    fn accept_unsorted_dict_keys_in_lenient_mode() {
+        let parser = DecodingParser;
+        let bencode = parser
+            .decode_from_u8_lenient(b"d4:spam4:eggs3:cow3:mooe".to_vec())
+            .unwrap();
+        assert_eq!(
+            bencode,
+            Bencode::dict(vec![
+                ("spam", Bencode::from("eggs")),
+                ("cow", Bencode::from("moo")),
+            ])
+        );
+    }
+
+    #[test]
+    fn fail_if_there_is_trailing_data() {
+        assert_eq!(decode_error("i1ei2e"), DecodingError::TrailingDataError(3));
+        assert_eq!(decode_error("4:spamx"), DecodingError::TrailingDataError(6));
+    }
+
+    #[test]
+    fn fail_if_input_is_truncated() {
+        assert_eq!(decode_error(""), DecodingError::UnexpectedEndError(0));
+        assert_eq!(decode_error("i12"), DecodingError::UnexpectedEndError(3));
+        assert_eq!(decode_error("l3:aae"), DecodingError::UnexpectedEndError(6));
+        assert_eq!(decode_error("5:spam"), DecodingError::UnexpectedEndError(6));
+        assert_eq!(decode_error("l"), DecodingError::UnexpectedEndError(1));
+    }
+
+    #[test]
+    fn fail_if_syntax_is_invalid() {
+        assert_eq!(decode_error("x"), DecodingError::InvalidTokenError(0));
+        assert_eq!(
+            decode_error("di1ei2ee"),
+            DecodingError::InvalidTokenError(1)
+        );
+        assert_eq!(decode_error("4spam"), DecodingError::InvalidTokenError(1));
+        assert_eq!(
+            decode_error("d3:a2:aae"),
+            DecodingError::InvalidTokenError(6)
+        );
+        assert_eq!(
+            decode_error("l3:aa2:aae"),
+            DecodingError::InvalidTokenError(6)
+        );
+    }
+
+    #[test]
+    fn find_span_of_dict_value() {
+        let data = b"d8:announce3:url4:infod4:name1:aee";
+        let span = DecodingParser
+            .dict_value_span(data, "info")
+            .unwrap()
+            .unwrap();
+        assert_eq!(&data[span], b"d4:name1:ae");
+        assert_eq!(DecodingParser.dict_value_span(data, "other").unwrap(), None);
+    }
+
+    /**************************************************************************************/
+    /*                          PROPIEDADES (ENTRADAS ALEATORIAS)                         */
+    /**************************************************************************************/
+
+    use crate::encoder::bencode_encoder::EncodingParser;
+    use crate::encoder::bencode_tokenizer::DEFAULT_MAX_DEPTH;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const ITERATIONS: usize = 500;
+
+    fn random_bytes(rng: &mut StdRng, max: usize) -> Vec<u8> {
+        let len = rng.gen_range(0..=max);
+        (0..len).map(|_| rng.gen()).collect()
+    }
+
+    /// Genera un Bencode aleatorio con las claves de los diccionarios ordenadas y sin repetir.
+    fn random_bencode(rng: &mut StdRng, depth: usize) -> Bencode {
+        let kind = if depth == 0 {
+            rng.gen_range(0..2)
+        } else {
+            rng.gen_range(0..4)
+        };
+        match kind {
+            0 => Bencode::Int(rng.gen()),
+            1 => Bencode::ByteString(random_bytes(rng, 12)),
+            2 => {
+                let len = rng.gen_range(0..5);
+                Bencode::List((0..len).map(|_| random_bencode(rng, depth - 1)).collect())
+            }
+            _ => {
+                let len = rng.gen_range(0..5);
+                let mut dict: Vec<(Vec<u8>, Bencode)> = (0..len)
+                    .map(|_| (random_bytes(rng, 6), random_bencode(rng, depth - 1)))
+                    .collect();
+                dict.sort_by(|(key, _), (other, _)| key.cmp(other));
+                dict.dedup_by(|(key, _), (other, _)| key == other);
+                Bencode::Dictionary(dict)
+            }
+        }
+    }
+
+    fn assert_position_in_bounds(result: Result<Bencode>, len: usize) {
+        if let Err(error) = result {
+            if let Some(position) = error.position() {
+                assert!(position <= len, "{} fuera de rango ({})", error, len);
+            }
+        }
+    }
+
+    #[test]
+    fn property_roundtrip_random_values() {
+        let mut rng = StdRng::seed_from_u64(28);
+        for _ in 0..ITERATIONS {
+            let bencode = random_bencode(&mut rng, 4);
+            let encoded = EncodingParser.encode(bencode.clone());
+            let decoded = DecodingParser.decode_from_u8(encoded.clone()).unwrap();
+            assert_eq!(decoded, bencode);
+            assert_eq!(EncodingParser.encode(decoded), encoded);
+        }
+    }
+
+    #[test]
+    fn property_reject_every_truncation() {
+        let mut rng = StdRng::seed_from_u64(29);
+        for _ in 0..ITERATIONS / 5 {
+            let encoded = EncodingParser.encode(random_bencode(&mut rng, 3));
+            for len in 0..encoded.len() {
+                let result =
+                    DecodingParser.decode_with(&encoded[..len], DecodingOptions::default());
+                assert!(result.is_err());
+                assert_position_in_bounds(result, len);
+            }
+        }
+    }
+
+    #[test]
+    fn property_never_panic_with_mutated_input() {
+        let mut rng = StdRng::seed_from_u64(30);
+        for _ in 0..ITERATIONS {
+            let mut encoded = EncodingParser.encode(random_bencode(&mut rng, 4));
+            for _ in 0..rng.gen_range(1..4) {
+                let index = rng.gen_range(0..encoded.len());
+                let alphabet = b"ilde0123456789:-";
+                let choice = rng.gen_range(0..alphabet.len() + 4);
+                encoded[index] = match alphabet.get(choice) {
+                    Some(byte) => *byte,
+                    None => rng.gen(),
+                };
+            }
+            let len = encoded.len();
+            assert_position_in_bounds(DecodingParser.decode_from_u8(encoded), len);
+        }
+    }
+
+    #[test]
+    fn property_never_panic_with_random_input() {
+        let mut rng = StdRng::seed_from_u64(31);
+        for _ in 0..ITERATIONS {
+            let len = rng.gen_range(0..40);
+            let data: Vec<u8> = (0..len)
+                .map(|_| b"ilde0123456789:-x"[rng.gen_range(0..17)])
+                .collect();
+            assert_position_in_bounds(DecodingParser.decode_from_u8_lenient(data), len);
+        }
+    }
+
+    #[test]
+    fn property_accept_shuffled_keys_only_in_lenient_mode() {
+        let mut rng = StdRng::seed_from_u64(32);
+        for _ in 0..ITERATIONS {
+            let len = rng.gen_range(2..6);
+            let mut keys: Vec<Vec<u8>> = (0..len).map(|i| vec![b'a' + i as u8]).collect();
+            keys.swap(0, rng.gen_range(1..len as usize));
+            let mut encoded = b"d".to_vec();
+            for key in &keys {
+                encoded.extend(format!("1:{}i0e", key[0] as char).as_bytes());
+            }
+            encoded.push(b'e');
+            assert!(matches!(
+                DecodingParser.decode_from_u8(encoded.clone()),
+                Err(DecodingError::UnsortedKeysError(_))
+            ));
+            let decoded = DecodingParser.decode_from_u8_lenient(encoded).unwrap();
+            assert_eq!(
+                decoded,
+                Bencode::Dictionary(keys.into_iter().map(|key| (key, Bencode::Int(0))).collect())
+            );
+        }
+    }
+
+    #[test]
+    fn property_fail_on_deep_nesting_without_overflow() {
+        for nesting in [DEFAULT_MAX_DEPTH + 1, 100_000] {
+            let mut data = vec![b'l'; nesting];
+            data.extend(vec![b'e'; nesting]);
+            assert_eq!(
+                DecodingParser.decode_from_u8(data).unwrap_err(),
+                DecodingError::MaxDepthError(DEFAULT_MAX_DEPTH)
+            );
+        }
+        let mut data = vec![b'l'; DEFAULT_MAX_DEPTH];
+        data.extend(vec![b'e'; DEFAULT_MAX_DEPTH]);
+        assert!(DecodingParser.decode_from_u8(data).is_ok());
+    }
+}
@@ -0,0 +1,876 @@
+use crate::encoder::bencode_encoder::EncodingParser;
+use crate::encoder::bencode_parser::{Bencode, DecodingParser};
+use crate::encoder::bencode_tokenizer::DecodingOptions;
+use crate::encoder::errors::SerdeError;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Unexpected, Visitor};
+use serde::ser::{self, Serialize};
+use std::fmt;
+
+type Result<T> = std::result::Result<T, SerdeError>;
+
+/******************************************************************************************/
+/*                                   SERDE BENCODE                                        */
+/******************************************************************************************/
+
+/// Serializa cualquier valor que implemente `Serialize` a bencode.
+/// Los structs y mapas se convierten en diccionarios con las claves ordenadas, los `bool` en
+/// enteros, y los `None` y `()` se omiten, ya que bencode no tiene un valor nulo.
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    Ok(EncodingParser.encode(to_bencode(value)?))
+}
+
+/// Serializa el valor a un `Bencode` sin encodearlo.
+pub fn to_bencode<T: Serialize + ?Sized>(value: &T) -> Result<Bencode> {
+    value
+        .serialize(ValueSerializer)?
+        .ok_or(SerdeError::UnsupportedTypeError("unit"))
+}
+
+/// Deserializa un valor a partir de un bencode estricto.
+pub fn from_bytes<T: DeserializeOwned>(data: &[u8]) -> Result<T> {
+    from_bytes_with(data, DecodingOptions::default())
+}
+
+/// Deserializa un valor a partir de un bencode, con las opciones de decodificacion recibidas.
+pub fn from_bytes_with<T: DeserializeOwned>(data: &[u8], options: DecodingOptions) -> Result<T> {
+    from_bencode(DecodingParser.decode_with(data, options)?)
+}
+
+/// Deserializa un valor a partir de un `Bencode` ya decodificado.
+pub fn from_bencode<T: DeserializeOwned>(bencode: Bencode) -> Result<T> {
+    T::deserialize(bencode)
+}
+
+/******************************************************************************************/
+/*                                    SERIALIZER                                          */
+/******************************************************************************************/
+
+/// Serializer que construye un `Bencode`. Devuelve `None` para los valores que no tienen
+/// representacion (`None`, `()`), para que los structs y listas puedan omitirlos.
+struct ValueSerializer;
+
+/// Envuelve el valor en un diccionario con el nombre de la variante, si corresponde.
+fn wrap_variant(value: Bencode, variant: Option<&'static str>) -> Bencode {
+    match variant {
+        Some(variant) => Bencode::Dictionary(vec![(variant.as_bytes().to_vec(), value)]),
+        None => value,
+    }
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Option<Bencode>;
+    type Error = SerdeError;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
+        Ok(Some(Bencode::Int(v as i64)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
+        Ok(Some(Bencode::Int(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
+        let v = i64::try_from(v).or(Err(SerdeError::IntegerOverflowError))?;
+        self.serialize_i64(v)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok> {
+        Err(SerdeError::UnsupportedTypeError("f32"))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok> {
+        Err(SerdeError::UnsupportedTypeError("f64"))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        self.serialize_str(&v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        self.serialize_bytes(v.as_bytes())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        Ok(Some(Bencode::ByteString(v.to_vec())))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        Ok(Some(wrap_variant(to_bencode(value)?, Some(variant))))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(SeqSerializer::new(len.unwrap_or(0), None))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        Ok(SeqSerializer::new(len, None))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Ok(SeqSerializer::new(len, None))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Ok(SeqSerializer::new(len, Some(variant)))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(MapSerializer::new(None))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(MapSerializer::new(None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(MapSerializer::new(Some(variant)))
+    }
+}
+
+/// Acumula los elementos de una lista. Los elementos sin representacion se omiten.
+struct SeqSerializer {
+    items: Vec<Bencode>,
+    variant: Option<&'static str>,
+}
+
+impl SeqSerializer {
+    fn new(len: usize, variant: Option<&'static str>) -> Self {
+        SeqSerializer {
+            items: Vec::with_capacity(len),
+            variant,
+        }
+    }
+
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        if let Some(item) = value.serialize(ValueSerializer)? {
+            self.items.push(item);
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<Bencode>> {
+        Ok(Some(wrap_variant(Bencode::List(self.items), self.variant)))
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Option<Bencode>;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Option<Bencode>;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Option<Bencode>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = Option<Bencode>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.finish()
+    }
+}
+
+/// Acumula las entradas de un diccionario. Las entradas sin valor se omiten y al terminar
+/// se ordenan por clave, como exige la especificacion.
+struct MapSerializer {
+    entries: Vec<(Vec<u8>, Bencode)>,
+    key: Option<Vec<u8>>,
+    variant: Option<&'static str>,
+}
+
+impl MapSerializer {
+    fn new(variant: Option<&'static str>) -> Self {
+        MapSerializer {
+            entries: vec![],
+            key: None,
+            variant,
+        }
+    }
+
+    fn insert<T: Serialize + ?Sized>(&mut self, key: Vec<u8>, value: &T) -> Result<()> {
+        if let Some(value) = value.serialize(ValueSerializer)? {
+            self.entries.push((key, value));
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Option<Bencode>> {
+        self.entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(Some(wrap_variant(
+            Bencode::Dictionary(self.entries),
+            self.variant,
+        )))
+    }
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Option<Bencode>;
+    type Error = SerdeError;
+
+    /// Las claves pueden ser strings, bytes o enteros, que se escriben en decimal.
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        let key = match key.serialize(ValueSerializer)? {
+            Some(Bencode::ByteString(bytes)) => bytes,
+            Some(Bencode::Int(num)) => num.to_string().into_bytes(),
+            _ => return Err(SerdeError::InvalidKeyError),
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self.key.take().ok_or(SerdeError::InvalidKeyError)?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Option<Bencode>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key.as_bytes().to_vec(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer {
+    type Ok = Option<Bencode>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key.as_bytes().to_vec(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.finish()
+    }
+}
+
+/******************************************************************************************/
+/*                                   DESERIALIZER                                         */
+/******************************************************************************************/
+
+impl Bencode {
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            Bencode::Int(num) => Unexpected::Signed(*num),
+            Bencode::ByteString(bytes) => Unexpected::Bytes(bytes),
+            Bencode::List(..) => Unexpected::Seq,
+            Bencode::Dictionary(..) => Unexpected::Map,
+        }
+    }
+}
+
+/// Un `Bencode` decodificado funciona como deserializer. Los byte strings se entregan como
+/// strings si son UTF-8 validos y como bytes si no, salvo que se pidan explicitamente bytes
+/// (por ejemplo con `serde_bytes`).
+impl<'de> de::Deserializer<'de> for Bencode {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Bencode::Int(num) => visitor.visit_i64(num),
+            Bencode::ByteString(bytes) => match String::from_utf8(bytes) {
+                Ok(string) => visitor.visit_string(string),
+                Err(err) => visitor.visit_byte_buf(err.into_bytes()),
+            },
+            Bencode::List(list) => visitor.visit_seq(SeqDeserializer {
+                items: list.into_iter(),
+            }),
+            Bencode::Dictionary(dict) => visitor.visit_map(MapDeserializer {
+                entries: dict.into_iter(),
+                value: None,
+            }),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Bencode::Int(0) => visitor.visit_bool(false),
+            Bencode::Int(1) => visitor.visit_bool(true),
+            other => Err(de::Error::invalid_type(other.unexpected(), &visitor)),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Bencode::ByteString(bytes) => match String::from_utf8(bytes) {
+                Ok(string) => visitor.visit_string(string),
+                Err(err) => Err(de::Error::invalid_value(
+                    Unexpected::Bytes(err.as_bytes()),
+                    &visitor,
+                )),
+            },
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Bencode::ByteString(bytes) => visitor.visit_byte_buf(bytes),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    /// Bencode no tiene valor nulo: si el campo esta presente, es `Some`.
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Las variantes sin datos son strings, y las demas diccionarios de una sola entrada.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            Bencode::ByteString(variant) => visitor.visit_enum(EnumDeserializer {
+                variant,
+                value: None,
+            }),
+            Bencode::Dictionary(mut dict) if dict.len() == 1 => {
+                let (variant, value) = dict.remove(0);
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value: Some(value),
+                })
+            }
+            other => Err(de::Error::invalid_type(other.unexpected(), &visitor)),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char
+        seq tuple tuple_struct map struct identifier
+    }
+}
+
+struct SeqDeserializer {
+    items: std::vec::IntoIter<Bencode>,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        match self.items.next() {
+            Some(item) => seed.deserialize(item).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct MapDeserializer {
+    entries: std::vec::IntoIter<(Vec<u8>, Bencode)>,
+    value: Option<Bencode>,
+}
+
+impl<'de> de::MapAccess<'de> for MapDeserializer {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(Bencode::ByteString(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let value = self.value.take().ok_or(SerdeError::InvalidKeyError)?;
+        seed.deserialize(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct EnumDeserializer {
+    variant: Vec<u8>,
+    value: Option<Bencode>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = SerdeError;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant)> {
+        let variant = seed.deserialize(Bencode::ByteString(self.variant))?;
+        Ok((variant, VariantDeserializer { value: self.value }))
+    }
+}
+
+struct VariantDeserializer {
+    value: Option<Bencode>,
+}
+
+impl VariantDeserializer {
+    fn content(self) -> Result<Bencode> {
+        self.value
+            .ok_or(SerdeError::UnsupportedTypeError("unit variant"))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantDeserializer {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self.content()?)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_seq(self.content()?, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_map(self.content()?, visitor)
+    }
+}
+
+/******************************************************************************************/
+/*                                  BENCODE VALUE                                         */
+/******************************************************************************************/
+
+/// Permite usar `Bencode` como campo de structs serializables, para los valores cuya
+/// estructura no se conoce de antemano.
+impl Serialize for Bencode {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Bencode::Int(num) => serializer.serialize_i64(*num),
+            Bencode::ByteString(bytes) => serializer.serialize_bytes(bytes),
+            Bencode::List(list) => serializer.collect_seq(list),
+            Bencode::Dictionary(dict) => serializer.collect_map(
+                dict.iter()
+                    .map(|(key, value)| (serde_bytes::Bytes::new(key), value)),
+            ),
+        }
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Bencode {
+    fn deserialize<D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(BencodeVisitor)
+    }
+}
+
+struct BencodeVisitor;
+
+impl<'de> Visitor<'de> for BencodeVisitor {
+    type Value = Bencode;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "un valor bencode")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> std::result::Result<Bencode, E> {
+        Ok(Bencode::Int(v as i64))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<Bencode, E> {
+        Ok(Bencode::Int(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<Bencode, E> {
+        let num = i64::try_from(v).or(Err(E::invalid_value(Unexpected::Unsigned(v), &self)))?;
+        Ok(Bencode::Int(num))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Bencode, E> {
+        Ok(Bencode::from(v))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> std::result::Result<Bencode, E> {
+        Ok(Bencode::from(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> std::result::Result<Bencode, E> {
+        Ok(Bencode::ByteString(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> std::result::Result<Bencode, E> {
+        Ok(Bencode::ByteString(v))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> std::result::Result<Bencode, A::Error> {
+        let mut list = vec![];
+        while let Some(item) = seq.next_element()? {
+            list.push(item);
+        }
+        Ok(Bencode::List(list))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> std::result::Result<Bencode, A::Error> {
+        let mut dict = vec![];
+        while let Some((key, value)) = map.next_entry::<serde_bytes::ByteBuf, Bencode>()? {
+            dict.push((key.into_vec(), value));
+        }
+        Ok(Bencode::Dictionary(dict))
+    }
+}
+
+#[cfg(test)]
+mod bencode_serde_should {
+    use super::*;
+    use crate::encoder::errors::DecodingError;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Announce {
+        interval: u32,
+        #[serde(rename = "min interval", default)]
+        min_interval: Option<u32>,
+        #[serde(with = "serde_bytes")]
+        peers: Vec<u8>,
+        #[serde(rename = "tracker id", default)]
+        tracker_id: Option<String>,
+    }
+
+    /// Archivo de resume: que piezas se tienen de cada torrent.
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Resume {
+        #[serde(with = "serde_bytes")]
+        info_hash: Vec<u8>,
+        #[serde(with = "serde_bytes")]
+        bitfield: Vec<u8>,
+        downloaded: u64,
+        paused: bool,
+        files: Vec<String>,
+    }
+
+    /// Mensaje KRPC de la DHT (BEP 5).
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Krpc {
+        #[serde(with = "serde_bytes")]
+        t: Vec<u8>,
+        y: String,
+        #[serde(default)]
+        q: Option<String>,
+        #[serde(default)]
+        a: Option<BTreeMap<String, Bencode>>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Event {
+        Started,
+        Stopped,
+        Completed { downloaded: u64 },
+        Failed(String),
+    }
+
+    #[test]
+    fn serialize_struct_as_sorted_dictionary() {
+        let announce = Announce {
+            interval: 1800,
+            min_interval: None,
+            peers: vec![10, 0, 0, 1, 0x1a, 0xe1],
+            tracker_id: Some("abc".to_string()),
+        };
+        let encoded = to_bytes(&announce).unwrap();
+
+        let mut expected = b"d8:intervali1800e5:peers6:".to_vec();
+        expected.extend([10, 0, 0, 1, 0x1a, 0xe1]);
+        expected.extend(b"10:tracker id3:abce");
+        assert_eq!(encoded, expected);
+        assert_eq!(from_bytes::<Announce>(&encoded).unwrap(), announce);
+    }
+
+    #[test]
+    fn roundtrip_resume_file() {
+        let resume = Resume {
+            info_hash: vec![0xff; 20],
+            bitfield: vec![0b1010_0000],
+            downloaded: 5_000_000_000,
+            paused: true,
+            files: vec!["a.txt".to_string(), "b.txt".to_string()],
+        };
+        let encoded = to_bytes(&resume).unwrap();
+
+        assert_eq!(from_bytes::<Resume>(&encoded).unwrap(), resume);
+        let bencode = DecodingParser.decode_from_u8(encoded).unwrap();
+        assert_eq!(bencode.get("paused"), Some(&Bencode::Int(1)));
+    }
+
+    #[test]
+    fn roundtrip_krpc_message_with_arbitrary_arguments() {
+        let data = b"d1:ad2:id20:abcdefghij01234567896:target20:mnopqrstuvwxyz123456e1:q9:find_node1:t2:aa1:y1:qe";
+        let message: Krpc = from_bytes(data).unwrap();
+
+        assert_eq!(message.t, b"aa");
+        assert_eq!(message.q.as_deref(), Some("find_node"));
+        let arguments = message.a.as_ref().unwrap();
+        assert_eq!(
+            arguments.get("id"),
+            Some(&Bencode::from("abcdefghij0123456789"))
+        );
+        assert_eq!(to_bytes(&message).unwrap(), data.to_vec());
+    }
+
+    #[test]
+    fn serialize_enums() {
+        for event in [
+            Event::Started,
+            Event::Stopped,
+            Event::Completed { downloaded: 10 },
+            Event::Failed("error".to_string()),
+        ] {
+            let encoded = to_bytes(&event).unwrap();
+            assert_eq!(from_bytes::<Event>(&encoded).unwrap(), event);
+        }
+        assert_eq!(to_bytes(&Event::Started).unwrap(), b"7:Started");
+        assert_eq!(
+            to_bytes(&Event::Completed { downloaded: 10 }).unwrap(),
+            b"d9:Completedd10:downloadedi10eee"
+        );
+    }
+
+    #[test]
+    fn skip_missing_optional_fields() {
+        let announce: Announce = from_bytes(b"d8:intervali60e5:peers0:e").unwrap();
+        assert_eq!(announce.min_interval, None);
+        assert_eq!(announce.tracker_id, None);
+    }
+
+    #[test]
+    fn roundtrip_bencode_values() {
+        let data = b"d4:listli1e3:abce4:nulld3:\xff\xfe\xfdi-1eee".to_vec();
+        let bencode: Bencode = from_bytes(&data).unwrap();
+
+        assert_eq!(
+            bencode,
+            DecodingParser.decode_from_u8(data.clone()).unwrap()
+        );
+        assert_eq!(to_bytes(&bencode).unwrap(), data);
+    }
+
+    #[test]
+    fn fail_on_wrong_types() {
+        assert!(matches!(
+            from_bytes::<Announce>(b"d8:interval3:abc5:peers0:e"),
+            Err(SerdeError::CustomError(..))
+        ));
+        assert!(matches!(
+            from_bytes::<Announce>(b"d5:peers0:e"),
+            Err(SerdeError::CustomError(..))
+        ));
+        assert_eq!(
+            from_bytes::<u8>(b"i300e").map_err(|err| err.to_string()),
+            Err("invalid value: integer `300`, expected u8".to_string())
+        );
+    }
+
+    #[test]
+    fn report_decoding_errors_with_position() {
+        assert_eq!(
+            from_bytes::<Announce>(b"d8:intervali60e"),
+            Err(SerdeError::DecodingError(
+                DecodingError::UnexpectedEndError(15)
+            ))
+        );
+    }
+
+    #[test]
+    fn reject_values_without_representation() {
+        assert_eq!(
+            to_bytes(&1.5_f64),
+            Err(SerdeError::UnsupportedTypeError("f64"))
+        );
+        assert_eq!(to_bytes(&u64::MAX), Err(SerdeError::IntegerOverflowError));
+        let mut map = BTreeMap::new();
+        map.insert(vec![1, 2], 1);
+        assert_eq!(to_bytes(&map), Err(SerdeError::InvalidKeyError));
+    }
+}
@@ -0,0 +1,408 @@
+use crate::encoder::errors::DecodingError;
+
+/******************************************************************************************/
+/*                                  BENCODE TOKENIZER                                     */
+/******************************************************************************************/
+
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+pub const DEFAULT_MAX_LENGTH: usize = 64 * 1024 * 1024;
+
+type Result<T> = std::result::Result<T, DecodingError>;
+
+/// Opciones del decodificador: profundidad maxima de anidamiento, largo maximo de la entrada
+/// y si se aceptan diccionarios con claves desordenadas o repetidas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodingOptions {
+    pub max_depth: usize,
+    pub max_length: usize,
+    pub lenient: bool,
+}
+
+impl Default for DecodingOptions {
+    fn default() -> Self {
+        DecodingOptions {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_length: DEFAULT_MAX_LENGTH,
+            lenient: false,
+        }
+    }
+}
+
+/// Token leido de la entrada. Los strings se devuelven prestados de la entrada, sin copiarse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token<'a> {
+    Int(i64),
+    ByteString(&'a [u8]),
+    List,
+    Dictionary,
+    End,
+}
+
+/// Token junto con el rango de bytes [start, end) que ocupa en la entrada.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spanned<'a> {
+    pub token: Token<'a>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Contenedor abierto. En los diccionarios se recuerda si se espera una clave y la ultima clave
+/// leida, para validar el orden.
+#[derive(Debug)]
+enum Frame<'a> {
+    List,
+    Dictionary {
+        expecting_key: bool,
+        last_key: Option<&'a [u8]>,
+    },
+}
+
+/// Decodificador iterativo: recorre la entrada una unica vez devolviendo tokens con sus posiciones.
+/// Los contenedores abiertos se guardan en una pila explicita, por lo que la profundidad
+/// no depende del stack del thread, y cada error indica el byte exacto donde se produjo.
+#[derive(Debug)]
+pub struct Tokenizer<'a> {
+    data: &'a [u8],
+    pos: usize,
+    stack: Vec<Frame<'a>>,
+    options: DecodingOptions,
+    finished: bool,
+    failed: bool,
+}
+
+#[allow(dead_code)]
+impl<'a> Tokenizer<'a> {
+    pub fn new(data: &'a [u8], options: DecodingOptions) -> Self {
+        Tokenizer {
+            data,
+            pos: 0,
+            stack: vec![],
+            options,
+            finished: false,
+            failed: false,
+        }
+    }
+
+    /// Devuelve la profundidad actual, es decir la cantidad de contenedores abiertos.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Devuelve el proximo token, o None cuando se termino de leer el valor y no quedan bytes.
+    pub fn next_token(&mut self) -> Result<Option<Spanned<'a>>> {
+        if self.failed {
+            return Ok(None);
+        }
+        let result = self.read_token();
+        if result.is_err() {
+            self.failed = true;
+        }
+        result
+    }
+
+    fn read_token(&mut self) -> Result<Option<Spanned<'a>>> {
+        if self.pos == 0 && self.data.len() > self.options.max_length {
+            return Err(DecodingError::MaxLengthError(self.options.max_length));
+        }
+        if self.finished {
+            if self.pos != self.data.len() {
+                return Err(DecodingError::TrailingDataError(self.pos));
+            }
+            return Ok(None);
+        }
+        let start = self.pos;
+        let byte = *self
+            .data
+            .get(start)
+            .ok_or(DecodingError::UnexpectedEndError(start))?;
+
+        if let Some(Frame::Dictionary {
+            expecting_key: true,
+            ..
+        }) = self.stack.last()
+        {
+            if !byte.is_ascii_digit() && byte != b'e' {
+                return Err(DecodingError::InvalidTokenError(start));
+            }
+        }
+
+        let token = match byte {
+            b'i' => Token::Int(self.read_int()?),
+            b'0'..=b'9' => Token::ByteString(self.read_string()?),
+            b'l' | b'd' => {
+                if self.stack.len() >= self.options.max_depth {
+                    return Err(DecodingError::MaxDepthError(start));
+                }
+                self.pos += 1;
+                if byte == b'l' {
+                    self.stack.push(Frame::List);
+                    return Ok(Some(Spanned {
+                        token: Token::List,
+                        start,
+                        end: self.pos,
+                    }));
+                }
+                self.stack.push(Frame::Dictionary {
+                    expecting_key: true,
+                    last_key: None,
+                });
+                return Ok(Some(Spanned {
+                    token: Token::Dictionary,
+                    start,
+                    end: self.pos,
+                }));
+            }
+            b'e' => {
+                match self.stack.pop() {
+                    Some(Frame::List)
+                    | Some(Frame::Dictionary {
+                        expecting_key: true,
+                        ..
+                    }) => {}
+                    _ => return Err(DecodingError::InvalidTokenError(start)),
+                }
+                self.pos += 1;
+                Token::End
+            }
+            _ => return Err(DecodingError::InvalidTokenError(start)),
+        };
+        self.complete_value(&token, start)?;
+        Ok(Some(Spanned {
+            token,
+            start,
+            end: self.pos,
+        }))
+    }
+
+    /// Actualiza el contenedor actual luego de leer un valor completo (o una clave).
+    fn complete_value(&mut self, token: &Token<'a>, start: usize) -> Result<()> {
+        let lenient = self.options.lenient;
+        match self.stack.last_mut() {
+            None => self.finished = true,
+            Some(Frame::List) => {}
+            Some(Frame::Dictionary {
+                expecting_key,
+                last_key,
+            }) => {
+                if *expecting_key {
+                    if let Token::ByteString(key) = token {
+                        if let Some(last) = last_key {
+                            if !lenient && *last >= *key {
+                                return Err(DecodingError::UnsortedKeysError(start));
+                            }
+                        }
+                        *last_key = Some(key);
+                    }
+                }
+                *expecting_key = !*expecting_key;
+            }
+        }
+        Ok(())
+    }
+
+    /// Lee un entero de la forma i<numero>e. Se rechazan los ceros a la izquierda y el -0.
+    fn read_int(&mut self) -> Result<i64> {
+        let start = self.pos;
+        let mut pos = start + 1;
+        if self.data.get(pos) == Some(&b'-') {
+            pos += 1;
+        }
+        let digits_start = pos;
+        while let Some(byte) = self.data.get(pos) {
+            if !byte.is_ascii_digit() {
+                break;
+            }
+            pos += 1;
+        }
+        match self.data.get(pos) {
+            None => return Err(DecodingError::UnexpectedEndError(pos)),
+            Some(b'e') => {}
+            Some(_) => return Err(DecodingError::InvalidIntegerError(pos)),
+        }
+        let digits = &self.data[digits_start..pos];
+        let negative = digits_start > start + 1;
+        let canonical = match digits {
+            [] => false,
+            [b'0'] => !negative,
+            [b'0', ..] => false,
+            _ => true,
+        };
+        if !canonical {
+            return Err(DecodingError::InvalidIntegerError(start));
+        }
+        let number = std::str::from_utf8(&self.data[start + 1..pos])
+            .ok()
+            .and_then(|number| number.parse::<i64>().ok())
+            .ok_or(DecodingError::InvalidIntegerError(start))?;
+        self.pos = pos + 1;
+        Ok(number)
+    }
+
+    /// Lee un string de la forma <largo>:<bytes> y devuelve los bytes prestados de la entrada.
+    fn read_string(&mut self) -> Result<&'a [u8]> {
+        let start = self.pos;
+        let mut pos = start;
+        while let Some(byte) = self.data.get(pos) {
+            if !byte.is_ascii_digit() {
+                break;
+            }
+            pos += 1;
+        }
+        match self.data.get(pos) {
+            None => return Err(DecodingError::UnexpectedEndError(pos)),
+            Some(b':') => {}
+            Some(_) => return Err(DecodingError::InvalidTokenError(pos)),
+        }
+        let digits = &self.data[start..pos];
+        if digits.len() > 1 && digits[0] == b'0' {
+            return Err(DecodingError::InvalidIntegerError(start));
+        }
+        let length = std::str::from_utf8(digits)
+            .ok()
+            .and_then(|length| length.parse::<usize>().ok())
+            .ok_or(DecodingError::MaxLengthError(start))?;
+        if length > self.options.max_length {
+            return Err(DecodingError::MaxLengthError(start));
+        }
+        let begin = pos + 1;
+        let end = begin
+            .checked_add(length)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(DecodingError::UnexpectedEndError(self.data.len()))?;
+        self.pos = end;
+        Ok(&self.data[begin..end])
+    }
+
+    /// Saltea el valor que empieza en el proximo token y devuelve el rango de bytes que ocupa.
+    pub fn skip_value(&mut self) -> Result<(usize, usize)> {
+        let first = self
+            .next_token()?
+            .ok_or(DecodingError::UnexpectedEndError(self.pos))?;
+        let depth = match first.token {
+            Token::List | Token::Dictionary => self.depth(),
+            Token::End => return Err(DecodingError::InvalidTokenError(first.start)),
+            _ => return Ok((first.start, first.end)),
+        };
+        loop {
+            let spanned = self
+                .next_token()?
+                .ok_or(DecodingError::UnexpectedEndError(self.pos))?;
+            if spanned.token == Token::End && self.depth() < depth {
+                return Ok((first.start, spanned.end));
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Spanned<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token().transpose()
+    }
+}
+
+/******************************************************************************************/
+/*                                        TESTS                                           */
+/******************************************************************************************/
+
+#[cfg(test)]
+mod tokenizer_should {
+    use super::*;
+
+    fn tokens(data: &[u8]) -> Result<Vec<Spanned<'_>>> {
+        Tokenizer::new(data, DecodingOptions::default()).collect()
+    }
+
+    #[test]
+    fn return_tokens_with_offsets() {
+        let data = b"d3:cowl3:mooi-12eee";
+        let tokens = tokens(data).unwrap();
+        let expected = vec![
+            (Token::Dictionary, 0, 1),
+            (Token::ByteString(b"cow"), 1, 6),
+            (Token::List, 6, 7),
+            (Token::ByteString(b"moo"), 7, 12),
+            (Token::Int(-12), 12, 17),
+            (Token::End, 17, 18),
+            (Token::End, 18, 19),
+        ];
+        let tokens: Vec<(Token, usize, usize)> = tokens
+            .into_iter()
+            .map(|spanned| (spanned.token, spanned.start, spanned.end))
+            .collect();
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn borrow_strings_from_input() {
+        let data = b"4:spam".to_vec();
+        let tokens = tokens(&data).unwrap();
+        if let Token::ByteString(bytes) = tokens[0].token {
+            assert_eq!(bytes.as_ptr(), data[2..].as_ptr());
+        } else {
+            panic!("se esperaba un string");
+        }
+    }
+
+    #[test]
+    fn report_error_positions() {
+        let cases: Vec<(&[u8], Option<usize>)> = vec![
+            (b"l4:spami12", Some(10)),
+            (b"li1ei2xe", Some(6)),
+            (b"d3:cowi1e3:aaai2ee", Some(9)),
+            (b"d3:cowi1eli1eee", Some(9)),
+            (b"i1ei2e", Some(3)),
+            (b"l4:spamx", Some(7)),
+            (b"10:spam", Some(7)),
+            (b"e", Some(0)),
+            (b"d3:cowe", Some(6)),
+        ];
+        for (data, position) in cases {
+            let error = tokens(data).unwrap_err();
+            assert_eq!(error.position(), position, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn enforce_max_depth_without_recursion() {
+        let data = vec![b'l'; 1_000_000];
+        let error = tokens(&data).unwrap_err();
+        assert_eq!(error.position(), Some(DEFAULT_MAX_DEPTH));
+
+        let options = DecodingOptions {
+            max_depth: 2,
+            ..DecodingOptions::default()
+        };
+        let result: Result<Vec<Spanned>> = Tokenizer::new(b"llleee", options).collect();
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Se supero la profundidad maxima de anidamiento (posicion 2)"
+        );
+    }
+
+    #[test]
+    fn enforce_max_length() {
+        let options = DecodingOptions {
+            max_length: 8,
+            ..DecodingOptions::default()
+        };
+        let result: Result<Vec<Spanned>> = Tokenizer::new(b"9:123456789", options).collect();
+        assert!(matches!(result, Err(DecodingError::MaxLengthError(_))));
+
+        let result: Result<Vec<Spanned>> =
+            Tokenizer::new(b"99999999999999999999999:a", DecodingOptions::default()).collect();
+        assert!(matches!(result, Err(DecodingError::MaxLengthError(0))));
+    }
+
+    #[test]
+    fn skip_nested_values() {
+        let data = b"d1:ad1:bli1eee1:ci2ee";
+        let mut tokenizer = Tokenizer::new(data, DecodingOptions::default());
+        tokenizer.next_token().unwrap();
+        tokenizer.next_token().unwrap();
+        assert_eq!(tokenizer.skip_value().unwrap(), (4, 14));
+        tokenizer.next_token().unwrap();
+        assert_eq!(tokenizer.skip_value().unwrap(), (17, 20));
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+use std::num::ParseIntError;
+use std::string::FromUtf8Error;
+
+/******************************************************************************************/
+/*                                  DECODING ERROR                                        */
+/******************************************************************************************/
+
+#[derive(Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum DecodingError {
+    CannotParseError(ParseIntError),
+    InvalidUTF8CharError(FromUtf8Error),
+    InvalidSyntaxError,
+    URLEncodingError,
+    UnexpectedEndError(usize),
+    InvalidTokenError(usize),
+    InvalidIntegerError(usize),
+    UnsortedKeysError(usize),
+    TrailingDataError(usize),
+    MaxDepthError(usize),
+    MaxLengthError(usize),
+}
+
+impl fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodingError::CannotParseError(..) => {
+                write!(f, "El string no puede ser parseado a numero")
+            }
+            DecodingError::InvalidSyntaxError => write!(f, "Sintaxis invalida"),
+            DecodingError::InvalidUTF8CharError(..) => {
+                write!(
+                    f,
+                    "El byte no puede ser convertido a un caracter UTF-8 valido"
+                )
+            }
+            DecodingError::URLEncodingError => {
+                write!(f, "No se pudo encodear en formato URL el valor recibido")
+            }
+            DecodingError::UnexpectedEndError(position) => {
+                write!(
+                    f,
+                    "El bencode termino antes de lo esperado (posicion {})",
+                    position
+                )
+            }
+            DecodingError::InvalidTokenError(position) => {
+                write!(f, "Caracter inesperado en la posicion {}", position)
+            }
+            DecodingError::InvalidIntegerError(position) => {
+                write!(
+                    f,
+                    "El entero no esta en forma canonica (posicion {})",
+                    position
+                )
+            }
+            DecodingError::UnsortedKeysError(position) => {
+                write!(
+                    f,
+                    "Las claves del diccionario no estan ordenadas o estan repetidas (posicion {})",
+                    position
+                )
+            }
+            DecodingError::TrailingDataError(position) => {
+                write!(
+                    f,
+                    "Hay datos de mas despues del bencode (posicion {})",
+                    position
+                )
+            }
+            DecodingError::MaxDepthError(position) => {
+                write!(
+                    f,
+                    "Se supero la profundidad maxima de anidamiento (posicion {})",
+                    position
+                )
+            }
+            DecodingError::MaxLengthError(position) => {
+                write!(
+                    f,
+                    "Se supero el largo maximo permitido (posicion {})",
+                    position
+                )
+            }
+        }
+    }
+}
+
+impl DecodingError {
+    /// Devuelve la posicion del byte donde se detecto el error, si se conoce.
+    pub fn position(&self) -> Option<usize> {
+        match *self {
+            DecodingError::UnexpectedEndError(position)
+            | DecodingError::InvalidTokenError(position)
+            | DecodingError::InvalidIntegerError(position)
+            | DecodingError::UnsortedKeysError(position)
+            | DecodingError::TrailingDataError(position)
+            | DecodingError::MaxDepthError(position)
+            | DecodingError::MaxLengthError(position) => Some(position),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseIntError> for DecodingError {
+    fn from(err: ParseIntError) -> DecodingError {
+        DecodingError::CannotParseError(err)
+    }
+}
+
+impl From<FromUtf8Error> for DecodingError {
+    fn from(err: FromUtf8Error) -> DecodingError {
+        DecodingError::InvalidUTF8CharError(err)
+    }
+}
+
+/******************************************************************************************/
+/*                                    SERDE ERROR                                         */
+/******************************************************************************************/
+
+#[derive(Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum SerdeError {
+    CustomError(String),
+    DecodingError(DecodingError),
+    UnsupportedTypeError(&'static str),
+    InvalidKeyError,
+    IntegerOverflowError,
+}
+
+impl fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SerdeError::CustomError(message) => write!(f, "{}", message),
+            SerdeError::DecodingError(err) => write!(f, "{}", err),
+            SerdeError::UnsupportedTypeError(kind) => {
+                write!(f, "El tipo {} no tiene representacion en bencode", kind)
+            }
+            SerdeError::InvalidKeyError => {
+                write!(f, "Las claves de un diccionario deben ser strings")
+            }
+            SerdeError::IntegerOverflowError => {
+                write!(f, "El entero no entra en un entero de 64 bits con signo")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SerdeError {}
+
+impl serde::ser::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeError::CustomError(msg.to_string())
+    }
+}
+
+impl serde::de::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeError::CustomError(msg.to_string())
+    }
+}
+
+impl From<DecodingError> for SerdeError {
+    fn from(err: DecodingError) -> SerdeError {
+        SerdeError::DecodingError(err)
+    }
+}
@@ -1,4 +1,6 @@
 pub mod bencode_encoder;
 pub mod bencode_parser;
-mod errors;
+pub mod bencode_serde;
+pub mod bencode_tokenizer;
+pub mod errors;
 pub mod url_encoder;
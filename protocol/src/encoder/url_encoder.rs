@@ -23,6 +23,39 @@ impl URLEncoder {
         }
         Ok(encoded)
     }
+
+    /// Realiza la operacion inversa: decodifica los `%xx` a bytes. Los `+` se interpretan
+    /// como espacios, como en los query strings.
+    /// En caso de encontrar un `%` sin dos digitos hexadecimales devuelve URLEncodingError.
+    pub fn urldecode(&self, string: &str) -> Result<Vec<u8>, DecodingError> {
+        let bytes = string.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'%' => {
+                    let hex = bytes
+                        .get(i + 1..i + 3)
+                        .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                        .ok_or(DecodingError::URLEncodingError)?;
+                    let hex = std::str::from_utf8(hex).or(Err(DecodingError::URLEncodingError))?;
+                    let byte =
+                        u8::from_str_radix(hex, 16).or(Err(DecodingError::URLEncodingError))?;
+                    decoded.push(byte);
+                    i += 3;
+                }
+                b'+' => {
+                    decoded.push(b' ');
+                    i += 1;
+                }
+                byte => {
+                    decoded.push(byte);
+                    i += 1;
+                }
+            }
+        }
+        Ok(decoded)
+    }
 }
 
 /******************************************************************************************/
@@ -56,7 +89,7 @@ mod client_should {
     #[test]
     fn urlencode_abc_lower() {
         let encoder = URLEncoder;
-        let expected = String::from("ABCDEFGHIJKLMNOPQRSTUVWXYZ".to_lowercase());
+        let expected = "ABCDEFGHIJKLMNOPQRSTUVWXYZ".to_lowercase();
         let urlencoded_sentence = encoder
             .urlencode(
                 "ABCDEFGHIJKLMNOPQRSTUVWXYZ"
@@ -101,4 +134,23 @@ mod client_should {
             .unwrap();
         assert_eq!(urlencoded_sentence, expected);
     }
+
+    #[test]
+    fn urldecode_info_hash() {
+        let encoder = URLEncoder;
+        let info_hash: Vec<u8> = (0..=255).collect();
+        let encoded = encoder.urlencode(info_hash.clone()).unwrap();
+
+        assert_eq!(encoder.urldecode(&encoded).unwrap(), info_hash);
+        assert_eq!(encoder.urldecode("%2A%2a+a").unwrap(), b"** a");
+    }
+
+    #[test]
+    fn fail_to_urldecode_truncated_escape() {
+        let encoder = URLEncoder;
+
+        assert!(encoder.urldecode("abc%2").is_err());
+        assert!(encoder.urldecode("abc%zz").is_err());
+        assert!(encoder.urldecode("%+1").is_err());
+    }
 }
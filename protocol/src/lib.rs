@@ -0,0 +1,3 @@
+pub mod encoder;
+pub mod peer_protocol;
+pub mod torrent_file;
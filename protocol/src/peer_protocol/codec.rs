@@ -0,0 +1,375 @@
+use super::errors::PeerProtocolError;
+use super::messages::{Message, MessageId};
+use std::io::{ErrorKind, Read, Write};
+
+/******************************************************************************************/
+/*                                    PEER CODEC                                          */
+/******************************************************************************************/
+
+const LEN: usize = 4;
+/// Maximo tamaño de bloque que se acepta en un piece. Los clientes piden bloques de 16 KiB
+/// y ninguno acepta pedidos mayores a 128 KiB.
+const DEFAULT_MAX_BLOCK_LEN: u32 = 1 << 17;
+/// Maximo tamaño del bitfield, alcanza para torrents de hasta 2^20 piezas.
+const DEFAULT_MAX_BITFIELD_LEN: u32 = 1 << 17;
+/// Cantidad de bytes que se intentan leer de la conexion en cada lectura.
+const READ_CHUNK_LEN: usize = 1 << 14;
+
+type Result<T> = std::result::Result<T, PeerProtocolError>;
+
+/// Limites de tamaño de los mensajes de largo variable que acepta el codec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodecLimits {
+    pub max_block_len: u32,
+    pub max_bitfield_len: u32,
+}
+
+impl Default for CodecLimits {
+    fn default() -> Self {
+        CodecLimits {
+            max_block_len: DEFAULT_MAX_BLOCK_LEN,
+            max_bitfield_len: DEFAULT_MAX_BITFIELD_LEN,
+        }
+    }
+}
+
+impl CodecLimits {
+    /// Limites para un torrent con la cantidad de piezas dada: el bitfield debe entrar en
+    /// los bytes necesarios para representarlas.
+    pub fn for_pieces(num_pieces: usize) -> Self {
+        let bitfield_len = num_pieces.div_ceil(8);
+        CodecLimits {
+            max_bitfield_len: bitfield_len.try_into().unwrap_or(u32::MAX),
+            ..CodecLimits::default()
+        }
+    }
+
+    /// Largo maximo que puede tener un mensaje, sin contar el prefijo de largo.
+    fn max_len(&self) -> u32 {
+        self.max_block_len
+            .saturating_add(9)
+            .max(self.max_bitfield_len.saturating_add(1))
+    }
+}
+
+/// Codec de los mensajes del protocolo entre peers. Decodifica los mensajes de a poco a
+/// medida que llegan bytes, sin confiar en el prefijo de largo: antes de esperar el cuerpo
+/// de un mensaje verifica que el largo corresponda con su tipo y no supere los limites.
+/// Los mensajes se codifican sobre un buffer que se reutiliza entre envios.
+#[derive(Debug, Default)]
+pub struct PeerCodec {
+    limits: CodecLimits,
+    input: Vec<u8>,
+    output: Vec<u8>,
+}
+
+impl PeerCodec {
+    pub fn new(limits: CodecLimits) -> Self {
+        PeerCodec {
+            limits,
+            input: vec![],
+            output: vec![],
+        }
+    }
+
+    /// Agrega bytes recibidos al buffer de entrada.
+    pub fn extend(&mut self, bytes: &[u8]) {
+        self.input.extend_from_slice(bytes);
+    }
+
+    /// Cantidad de bytes recibidos que todavia no forman un mensaje completo.
+    pub fn pending(&self) -> usize {
+        self.input.len()
+    }
+
+    /// Intenta decodificar el proximo mensaje del buffer de entrada. Devuelve `None` si
+    /// todavia no llegaron todos sus bytes, o un error si el encabezado es invalido.
+    pub fn decode(&mut self) -> Result<Option<Message>> {
+        if self.input.len() < LEN {
+            return Ok(None);
+        }
+        let len = Message::convert_to_u32(&self.input[..LEN]);
+        if len == 0 {
+            self.input.drain(..LEN);
+            return Message::new(0, vec![]).map(Some);
+        }
+        if len > self.limits.max_len() {
+            return Err(PeerProtocolError::MessageTooLongError);
+        }
+        if self.input.len() == LEN {
+            return Ok(None);
+        }
+        self.validate_header(self.input[LEN], len)?;
+
+        let frame_len = LEN + len as usize;
+        if self.input.len() < frame_len {
+            return Ok(None);
+        }
+        let bytes = self.input[LEN..frame_len].to_vec();
+        self.input.drain(..frame_len);
+        Message::new(len, bytes).map(Some)
+    }
+
+    /// Verifica el largo del mensaje segun su id y los limites del codec.
+    fn validate_header(&self, id: u8, len: u32) -> Result<()> {
+        Message::validate_len(id, len)?;
+        let too_long = match id {
+            5 => len - 1 > self.limits.max_bitfield_len,
+            7 => len - 9 > self.limits.max_block_len,
+            _ => false,
+        };
+        if too_long {
+            return Err(PeerProtocolError::MessageTooLongError);
+        }
+        Ok(())
+    }
+
+    /// Lee de la conexion hasta completar un mensaje. Los bytes que sobren quedan en el
+    /// buffer para el proximo mensaje. Si la conexion tiene timeout de lectura y se agota,
+    /// los bytes ya leidos se conservan y la lectura puede retomarse.
+    pub fn read_message<R: Read>(&mut self, reader: &mut R) -> Result<Message> {
+        loop {
+            if let Some(message) = self.decode()? {
+                return Ok(message);
+            }
+            let filled = self.input.len();
+            self.input.resize(filled + READ_CHUNK_LEN, 0);
+            let read = reader.read(&mut self.input[filled..]);
+            match read {
+                Ok(0) => {
+                    self.input.truncate(filled);
+                    return Err(PeerProtocolError::ConnectionClosedError);
+                }
+                Ok(read) => self.input.truncate(filled + read),
+                Err(error) if error.kind() == ErrorKind::Interrupted => self.input.truncate(filled),
+                Err(error)
+                    if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    self.input.truncate(filled);
+                    return Err(PeerProtocolError::TimeoutError);
+                }
+                Err(_) => {
+                    self.input.truncate(filled);
+                    return Err(PeerProtocolError::ReadConnectionError);
+                }
+            }
+        }
+    }
+
+    /// Codifica el mensaje en el buffer de salida y devuelve sus bytes, prefijo de largo
+    /// incluido.
+    pub fn encode(&mut self, message: &MessageId) -> Result<&[u8]> {
+        self.output.clear();
+        match message {
+            MessageId::KeepAlive => self.output.extend_from_slice(&[0; LEN]),
+            MessageId::Choke => self.encode_frame(0, &[], &[])?,
+            MessageId::Unchoke => self.encode_frame(1, &[], &[])?,
+            MessageId::Interested => self.encode_frame(2, &[], &[])?,
+            MessageId::NotInterested => self.encode_frame(3, &[], &[])?,
+            MessageId::Have(index) => self.encode_frame(4, &[*index], &[])?,
+            MessageId::Bitfield(bitfield) => self.encode_frame(5, &[], bitfield)?,
+            MessageId::Request(index, begin, length) => {
+                self.encode_frame(6, &[*index, *begin, *length], &[])?
+            }
+            MessageId::Piece(index, begin, block) => {
+                self.encode_frame(7, &[*index, *begin], block)?
+            }
+            MessageId::Cancel(index, begin, length) => {
+                self.encode_frame(8, &[*index, *begin, *length], &[])?
+            }
+        }
+        Ok(&self.output)
+    }
+
+    fn encode_frame(&mut self, id: u8, fields: &[u32], data: &[u8]) -> Result<()> {
+        let len: u32 = (1 + fields.len() * 4 + data.len())
+            .try_into()
+            .or(Err(PeerProtocolError::FailToConvertError))?;
+        self.output.extend_from_slice(&len.to_be_bytes());
+        self.output.push(id);
+        for field in fields {
+            self.output.extend_from_slice(&field.to_be_bytes());
+        }
+        self.output.extend_from_slice(data);
+        Ok(())
+    }
+
+    /// Codifica el mensaje y lo escribe en la conexion.
+    pub fn write_message<W: Write>(&mut self, writer: &mut W, message: &MessageId) -> Result<()> {
+        self.encode(message)?;
+        writer
+            .write_all(&self.output)
+            .or(Err(PeerProtocolError::WriteConnectionError))
+    }
+}
+
+#[cfg(test)]
+mod peer_codec_should {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encoded(message: MessageId) -> Vec<u8> {
+        PeerCodec::default().encode(&message).unwrap().to_vec()
+    }
+
+    #[test]
+    fn encode_like_the_message_builders() {
+        let mut bitfield = vec![0xff, 0x80];
+        let mut block = vec![1, 2, 3];
+
+        assert_eq!(encoded(MessageId::KeepAlive), Message::send_keep_alive());
+        assert_eq!(encoded(MessageId::Choke), Message::send_choke());
+        assert_eq!(encoded(MessageId::Unchoke), Message::send_unchoke());
+        assert_eq!(encoded(MessageId::Interested), Message::send_interested());
+        assert_eq!(
+            encoded(MessageId::NotInterested),
+            Message::send_not_interested()
+        );
+        assert_eq!(encoded(MessageId::Have(7)), Message::send_have(7));
+        assert_eq!(
+            encoded(MessageId::Bitfield(bitfield.clone())),
+            Message::send_bitfield(&mut bitfield).unwrap()
+        );
+        assert_eq!(
+            encoded(MessageId::Request(1, 2, 3)),
+            Message::send_request(1, 2, 3)
+        );
+        assert_eq!(
+            encoded(MessageId::Piece(1, 2, block.clone())),
+            Message::send_piece(1, 2, &mut block).unwrap()
+        );
+        assert_eq!(
+            encoded(MessageId::Cancel(1, 2, 3)),
+            Message::send_cancel(1, 2, 3)
+        );
+    }
+
+    #[test]
+    fn decode_messages_split_across_reads() {
+        let mut bytes = encoded(MessageId::Piece(3, 16, vec![9; 20]));
+        bytes.extend(encoded(MessageId::KeepAlive));
+        bytes.extend(encoded(MessageId::Have(5)));
+
+        let mut codec = PeerCodec::default();
+        let mut messages = vec![];
+        for byte in bytes {
+            codec.extend(&[byte]);
+            while let Some(message) = codec.decode().unwrap() {
+                messages.push(message.id);
+            }
+        }
+
+        assert_eq!(
+            messages,
+            vec![
+                MessageId::Piece(3, 16, vec![9; 20]),
+                MessageId::KeepAlive,
+                MessageId::Have(5)
+            ]
+        );
+        assert_eq!(codec.pending(), 0);
+    }
+
+    #[test]
+    fn reject_huge_lengths_before_receiving_the_body() {
+        let mut codec = PeerCodec::default();
+        codec.extend(&[0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(
+            codec.decode().unwrap_err(),
+            PeerProtocolError::MessageTooLongError
+        );
+
+        let mut codec = PeerCodec::new(CodecLimits::for_pieces(16));
+        codec.extend(&[0, 0, 0, 4, 5]);
+        assert_eq!(
+            codec.decode().unwrap_err(),
+            PeerProtocolError::MessageTooLongError
+        );
+
+        let mut codec = PeerCodec::default();
+        codec.extend(&(DEFAULT_MAX_BLOCK_LEN + 10).to_be_bytes());
+        codec.extend(&[7]);
+        assert_eq!(
+            codec.decode().unwrap_err(),
+            PeerProtocolError::MessageTooLongError
+        );
+    }
+
+    #[test]
+    fn reject_lengths_that_do_not_match_the_message() {
+        let mut codec = PeerCodec::default();
+        codec.extend(&[0, 0, 0, 3, 4, 0, 1]);
+        assert_eq!(
+            codec.decode().unwrap_err(),
+            PeerProtocolError::InvalidMessageLengthError
+        );
+
+        let mut codec = PeerCodec::default();
+        codec.extend(&[0, 0, 0, 1, 20]);
+        assert_eq!(
+            codec.decode().unwrap_err(),
+            PeerProtocolError::InvalidMessageFormatError
+        );
+    }
+
+    /// Conexion que entrega un byte por lectura y agota el timeout entre byte y byte.
+    struct SlowStream {
+        bytes: Vec<u8>,
+        timed_out: bool,
+    }
+
+    impl Read for SlowStream {
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            self.timed_out = !self.timed_out;
+            if self.timed_out {
+                return Err(ErrorKind::WouldBlock.into());
+            }
+            if self.bytes.is_empty() {
+                return Ok(0);
+            }
+            buffer[0] = self.bytes.remove(0);
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn resume_reading_after_a_timeout() {
+        let mut stream = SlowStream {
+            bytes: encoded(MessageId::Have(3)),
+            timed_out: false,
+        };
+        let mut codec = PeerCodec::default();
+        let mut timeouts = 0;
+        let message = loop {
+            match codec.read_message(&mut stream) {
+                Ok(message) => break message,
+                Err(PeerProtocolError::TimeoutError) => timeouts += 1,
+                Err(error) => panic!("{}", error),
+            }
+        };
+
+        assert_eq!(message.id, MessageId::Have(3));
+        assert_eq!(timeouts, 9);
+    }
+
+    #[test]
+    fn read_messages_from_a_stream() {
+        let mut bytes = encoded(MessageId::Unchoke);
+        bytes.extend(encoded(MessageId::Request(0, 0, 16384)));
+        let mut stream = Cursor::new(bytes);
+
+        let mut codec = PeerCodec::default();
+        assert_eq!(
+            codec.read_message(&mut stream).unwrap().id,
+            MessageId::Unchoke
+        );
+        assert_eq!(
+            codec.read_message(&mut stream).unwrap().id,
+            MessageId::Request(0, 0, 16384)
+        );
+        assert_eq!(
+            codec.read_message(&mut stream).unwrap_err(),
+            PeerProtocolError::ConnectionClosedError
+        );
+    }
+}
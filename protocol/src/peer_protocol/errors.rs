@@ -4,12 +4,18 @@ use std::fmt;
 /*                                  PeerProtocol ERROR                                    */
 /******************************************************************************************/
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 #[allow(clippy::enum_variant_names)]
 pub enum PeerProtocolError {
     HandshakeInvalidUTF8CharError,
     InvalidMessageFormatError,
     FailToConvertError,
+    InvalidMessageLengthError,
+    MessageTooLongError,
+    ConnectionClosedError,
+    ReadConnectionError,
+    WriteConnectionError,
+    TimeoutError,
 }
 
 impl fmt::Display for PeerProtocolError {
@@ -25,6 +31,24 @@ impl fmt::Display for PeerProtocolError {
             PeerProtocolError::FailToConvertError => {
                 write!(f, "Fallo al intentar convertir un numero desde un string")
             }
+            PeerProtocolError::InvalidMessageLengthError => {
+                write!(f, "El largo del mensaje no corresponde con su tipo")
+            }
+            PeerProtocolError::MessageTooLongError => {
+                write!(f, "El mensaje supera el tamaño maximo permitido")
+            }
+            PeerProtocolError::ConnectionClosedError => {
+                write!(f, "El peer cerro la conexion")
+            }
+            PeerProtocolError::ReadConnectionError => {
+                write!(f, "No se pudo leer en la conexion")
+            }
+            PeerProtocolError::WriteConnectionError => {
+                write!(f, "No se pudo escribir en la conexion")
+            }
+            PeerProtocolError::TimeoutError => {
+                write!(f, "Se agoto el tiempo de espera de la conexion")
+            }
         }
     }
 }
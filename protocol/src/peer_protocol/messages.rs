@@ -30,10 +30,16 @@ pub enum MessageId {
 
 #[allow(dead_code)]
 impl Message {
+    /// Genera el mensaje a partir de su largo y de los bytes que siguen al prefijo de largo.
+    /// Falla si la cantidad de bytes no coincide con el largo o con el tipo del mensaje.
     pub fn new(len: u32, bytes: Vec<u8>) -> Result<Message, PeerProtocolError> {
         if len == 0 {
             return Ok(Self::generate_keep_alive());
         }
+        if bytes.len() != len as usize {
+            return Err(PeerProtocolError::InvalidMessageLengthError);
+        }
+        Self::validate_len(bytes[0], len)?;
         match bytes[0] {
             0 => Ok(Self::generate_choke()),
             1 => Ok(Self::generate_unchoke()),
@@ -48,6 +54,24 @@ impl Message {
         }
     }
 
+    /// Verifica que el largo de un mensaje con el id dado sea valido. Los mensajes de largo
+    /// fijo deben tenerlo exacto, el piece debe traer al menos su indice y su offset.
+    pub fn validate_len(id: u8, len: u32) -> Result<(), PeerProtocolError> {
+        let valid = match id {
+            0..=3 => len == 1,
+            4 => len == 5,
+            5 => len >= 1,
+            6 | 8 => len == 13,
+            7 => len >= 9,
+            _ => return Err(PeerProtocolError::InvalidMessageFormatError),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(PeerProtocolError::InvalidMessageLengthError)
+        }
+    }
+
     /// Genera el mensaje keep alive
     fn generate_keep_alive() -> Message {
         Message {
@@ -287,6 +311,26 @@ mod messages_should {
         );
     }
 
+    #[test]
+    fn fail_if_payload_does_not_match_len() {
+        assert_eq!(
+            Message::new(5, vec![4, 0, 1]).unwrap_err(),
+            PeerProtocolError::InvalidMessageLengthError
+        );
+        assert_eq!(
+            Message::new(3, vec![4, 0, 1]).unwrap_err(),
+            PeerProtocolError::InvalidMessageLengthError
+        );
+        assert_eq!(
+            Message::new(5, vec![7, 0, 0, 0, 1]).unwrap_err(),
+            PeerProtocolError::InvalidMessageLengthError
+        );
+        assert_eq!(
+            Message::new(2, vec![1, 0]).unwrap_err(),
+            PeerProtocolError::InvalidMessageLengthError
+        );
+    }
+
     #[test]
     fn generate_have() {
         let message = Message::generate_have(vec![4, 0, 0, 1, 0]);
@@ -1,3 +1,4 @@
-mod errors;
+pub mod codec;
+pub mod errors;
 pub mod handshake;
 pub mod messages;
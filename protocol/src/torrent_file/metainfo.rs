@@ -1,5 +1,7 @@
-use crate::encoder::bencode_encoder::EncodingParser;
-use crate::encoder::bencode_parser::{Bencode, DecodingParser};
+use crate::encoder::bencode_parser::DecodingParser;
+use crate::encoder::bencode_serde;
+use crate::encoder::bencode_tokenizer::DecodingOptions;
+use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
 use std::fs::File;
 use std::io::Read;
@@ -12,10 +14,12 @@ use super::errors::MetaInfoError;
 
 /// Estructura que representa el campo metainfo del torrent file.
 #[allow(dead_code)]
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MetaInfo {
+    #[serde(default)]
     pub announce: String,
     pub info: Info,
+    #[serde(skip)]
     pub info_hash: Vec<u8>,
 }
 
@@ -23,58 +27,58 @@ type Result<T> = std::result::Result<T, MetaInfoError>;
 
 /// Estructura que representa el campo info del metainfo del torrent file.
 #[allow(dead_code)]
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Info {
+    #[serde(rename = "piece length")]
     pub piece_length: u32,
+    #[serde(with = "pieces")]
     pub pieces: Vec<Vec<u8>>,
+    #[serde(skip)]
     pub num_pieces: usize,
+    #[serde(default)]
     pub name: String,
+    #[serde(default)]
     pub length: u64,
+    /// 1 si el torrent es privado (BEP 27): solo se obtienen peers del tracker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub private: Option<i64>,
 }
+
+impl Info {
+    /// Indica si el torrent es privado.
+    pub fn is_private(&self) -> bool {
+        self.private == Some(1)
+    }
+}
+
 #[allow(dead_code)]
 impl MetaInfo {
     /// Recibe el torrent path, e inicializa la estructura Metainfo.
     pub fn new(torrent_path: &str) -> Result<MetaInfo> {
-        let mut info = Info::new(vec![(String::from(""), Bencode::Int(0))])?;
-        let mut info_hash: Vec<u8> = vec![];
-        let mut announce = String::from("");
-        let bencode = Self::decode_torrent_file(torrent_path)?;
-        if let Bencode::Dictionary(dict) = bencode {
-            for (key, value) in dict {
-                match key.as_str() {
-                    "info" => {
-                        info_hash = Self::hashing(&EncodingParser.encode(value.clone()));
-                        if let Bencode::Dictionary(dict) = value {
-                            info = Info::new(dict)?;
-                        }
-                    }
-                    "announce" => {
-                        if let Bencode::String(string) = value {
-                            announce = string.clone();
-                        }
-                    }
-                    _ => continue,
-                }
-            }
-        }
-        Ok(MetaInfo {
-            announce,
-            info,
-            info_hash,
-        })
-    }
-
-    /// Abre el archivo de torren y lo parsea.
-    fn decode_torrent_file(torrent_path: &str) -> Result<Bencode> {
         let mut file = File::open(torrent_path).or(Err(MetaInfoError::OpenFileError))?;
         let mut data = Vec::new();
         file.read_to_end(&mut data)
             .or(Err(MetaInfoError::ReadFileError))?;
-        let parser = DecodingParser;
-        let result: Bencode = parser
-            .decode_from_u8(data)
+        Self::from_bytes(&data)
+    }
+
+    /// Parsea el contenido de un torrent file. El info hash se calcula sobre los bytes
+    /// originales del campo info y no sobre el diccionario re-encodeado.
+    pub fn from_bytes(data: &[u8]) -> Result<MetaInfo> {
+        let options = DecodingOptions {
+            lenient: true,
+            ..DecodingOptions::default()
+        };
+        let mut metainfo: MetaInfo =
+            bencode_serde::from_bytes_with(data, options).or(Err(MetaInfoError::DecodingError))?;
+        let info_span = DecodingParser
+            .dict_value_span(data, "info")
             .or(Err(MetaInfoError::DecodingError))?;
-        Ok(result)
+        if let Some(span) = info_span {
+            metainfo.info_hash = Self::hashing(&data[span]);
+        }
+        metainfo.info.num_pieces = metainfo.info.pieces.len();
+        Ok(metainfo)
     }
 
     /// Realiza el SHA1 del campo info del torrent file para obtener el hash_info
@@ -86,76 +90,26 @@ impl MetaInfo {
     }
 }
 
-#[allow(dead_code)]
-impl Info {
-    fn new(dict: Vec<(String, Bencode)>) -> Result<Info> {
-        let mut piece_length: u32 = 0;
-        let mut pieces: Vec<Vec<u8>> = vec![vec![]];
-        let mut num_pieces: usize = 0;
-        let mut name: String = String::from("");
-        let mut length: u64 = 0;
+/// Los hashes SHA1 de las piezas vienen concatenados en un unico byte string.
+mod pieces {
+    use serde::de::{Deserializer, Error};
+    use serde::ser::Serializer;
 
-        for (key, value) in dict {
-            match key.as_str() {
-                "piece length" => {
-                    piece_length = Self::get_piece_length(value)?;
-                }
-                "pieces" => {
-                    pieces = Self::get_pieces(value)?;
-                    num_pieces = pieces.len();
-                }
-                "name" => {
-                    if let Bencode::String(string) = value {
-                        name = string.clone();
-                    }
-                }
-                "length" => {
-                    length = Self::get_length(value)?;
-                }
-                _ => continue,
-            }
-        }
-        Ok(Info {
-            piece_length,
-            pieces,
-            num_pieces,
-            name,
-            length,
-        })
+    pub fn serialize<S: Serializer>(pieces: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        serde_bytes::serialize(&pieces.concat(), serializer)
     }
 
-    fn get_piece_length(bencode: Bencode) -> Result<u32> {
-        if let Bencode::Int(num) = bencode {
-            let piece_length = num
-                .try_into()
-                .or(Err(MetaInfoError::IntegerConvertionError))?;
-            return Ok(piece_length);
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        let bytes: Vec<u8> = serde_bytes::deserialize(deserializer)?;
+        if !bytes.len().is_multiple_of(20) {
+            return Err(D::Error::invalid_length(
+                bytes.len(),
+                &"un multiplo de 20 bytes",
+            ));
         }
-        Err(MetaInfoError::DecodingError)
-    }
-
-    fn get_length(bencode: Bencode) -> Result<u64> {
-        if let Bencode::Int(num) = bencode {
-            let length = num
-                .try_into()
-                .or(Err(MetaInfoError::IntegerConvertionError))?;
-            return Ok(length);
-        }
-        Err(MetaInfoError::DecodingError)
-    }
-
-    fn get_pieces(bencode: Bencode) -> Result<Vec<Vec<u8>>> {
-        if let Bencode::ByteString(bytes) = bencode {
-            let mut vec: Vec<Vec<u8>> = vec![];
-            let len = bytes.len();
-            let mut i: usize = 0;
-            while i < len {
-                vec.push(bytes[i..i + 20].to_vec());
-                i += 20;
-            }
-            return Ok(vec);
-        }
-        Err(MetaInfoError::DecodingError)
+        Ok(bytes.chunks(20).map(|hash| hash.to_vec()).collect())
     }
 }
 
@@ -246,6 +200,29 @@ mod metainfo_should {
         assert_eq!(meta.info.num_pieces, num_pieces);
     }
 
+    #[test]
+    fn serialize_info_back_to_same_hash() {
+        let info = b"d6:lengthi20e4:name1:a12:piece lengthi16384e6:pieces20:aaaaabbbbbcccccddddde";
+        let mut data = b"d8:announce14:http://tracker4:info".to_vec();
+        data.extend(info);
+        data.push(b'e');
+        let meta = MetaInfo::from_bytes(&data).unwrap();
+
+        assert_eq!(meta.info.pieces, vec![b"aaaaabbbbbcccccddddd".to_vec()]);
+        assert_eq!(bencode_serde::to_bytes(&meta.info).unwrap(), info.to_vec());
+        assert_eq!(MetaInfo::hashing(info), meta.info_hash);
+    }
+
+    #[test]
+    fn reject_pieces_with_invalid_length() {
+        let data = b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces3:abcee";
+
+        assert!(matches!(
+            MetaInfo::from_bytes(data),
+            Err(MetaInfoError::DecodingError)
+        ));
+    }
+
     #[test]
     fn hashing_parameter() {
         let meta = MetaInfo::new("./torrents/kubuntu-16.04.6-desktop-amd64.iso.torrent").unwrap();